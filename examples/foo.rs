@@ -1,3 +1,5 @@
+#![allow(clippy::single_match)]
+
 extern crate asn1;
 
 // use std::io::Stdin;
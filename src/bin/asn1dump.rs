@@ -0,0 +1,512 @@
+extern crate asn1;
+
+use asn1::identifier::{Class, UniversalTag};
+use asn1::integer;
+use asn1::reading;
+use asn1::time::DateTime;
+use asn1::{Asn1Reader, Identifier, ObjectIdentifier};
+use std::io::Write;
+
+
+const HEX_BYTES_PER_LINE: usize = 16;
+
+
+struct Options {
+    encapsulated: bool,
+}
+
+
+fn usage() -> ! {
+    let _ = writeln!(std::io::stderr(), "usage: asn1dump [-e] [FILE]");
+    let _ = writeln!(std::io::stderr(), "  -e  descend into OCTET STRING and BIT STRING values containing encapsulated DER");
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut options = Options {
+        encapsulated: false,
+    };
+    let mut path = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "-e" | "--encapsulated" => options.encapsulated = true,
+            "-h" | "--help" => usage(),
+            "-" if path.is_none() => path = Some(arg),
+            a if a.starts_with('-') => usage(),
+            _ if path.is_none() => path = Some(arg),
+            _ => usage(),
+        }
+    }
+
+    let mut data = Vec::new();
+    let read = match path {
+        Some(ref p) if p != "-" => std::fs::File::open(p).and_then(|mut f| std::io::Read::read_to_end(&mut f, &mut data)),
+        _ => std::io::Read::read_to_end(&mut std::io::stdin(), &mut data),
+    };
+    if let Err(e) = read {
+        let _ = writeln!(std::io::stderr(), "asn1dump: {}", e);
+        std::process::exit(1);
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    if let Err(e) = dump(&mut out, &options, &data, 0, 0) {
+        if e.kind() != std::io::ErrorKind::BrokenPipe {
+            let _ = writeln!(std::io::stderr(), "asn1dump: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+
+fn dump<W: Write>(out: &mut W, options: &Options, data: &[u8], base: usize, depth: usize) -> std::io::Result<()> {
+    // Untrusted input can nest far deeper than any real structure; show what is left as hex rather than recursing.
+    if depth >= reading::MAX_DEPTH {
+        write_prefix(out, base, None, data.len(), depth)?;
+        writeln!(out, "nesting too deep")?;
+        return write_hex(out, data, depth + 1);
+    }
+    let mut r = Asn1Reader::new(data);
+    loop {
+        let offset = base + r.position();
+        let (identifier, header_len, contents) = match r.next_with_header_len() {
+            Ok(Some(tlv)) => tlv,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                let remaining = &data[offset - base..];
+                write_prefix(out, offset, None, remaining.len(), depth)?;
+                writeln!(out, "malformed data: {}", e)?;
+                return write_hex(out, remaining, depth + 1);
+            }
+        };
+        let contents_offset = offset + header_len;
+        let total_len = base + r.position() - offset;
+        let indefinite = total_len != header_len + contents.len();
+
+        write_prefix(out, offset, Some(header_len), contents.len(), depth)?;
+        if indefinite {
+            write!(out, "(indefinite) ")?;
+        }
+        write!(out, "{}", identifier_name(identifier))?;
+
+        if identifier.is_constructed() {
+            writeln!(out, " {{")?;
+            dump(out, options, contents, contents_offset, depth + 1)?;
+            write_continuation_prefix(out, depth)?;
+            writeln!(out, "}}")?;
+            continue;
+        }
+
+        if identifier.class() != Class::Universal {
+            writeln!(out)?;
+            write_hex(out, contents, depth + 1)?;
+            continue;
+        }
+
+        dump_universal_primitive(out, options, identifier, contents, contents_offset, depth)?;
+    }
+}
+
+fn dump_universal_primitive<W: Write>(out: &mut W, options: &Options, identifier: Identifier, contents: &[u8], offset: usize, depth: usize) -> std::io::Result<()> {
+    let tag = match UniversalTag::from_u64(identifier.tag()) {
+        Some(tag) => tag,
+        None => {
+            writeln!(out)?;
+            return write_hex(out, contents, depth + 1);
+        }
+    };
+
+    match tag {
+        UniversalTag::Boolean => {
+            match contents {
+                [0] => writeln!(out, " FALSE"),
+                [_] => writeln!(out, " TRUE"),
+                _ => {
+                    writeln!(out, " (invalid length)")?;
+                    write_hex(out, contents, depth + 1)
+                }
+            }
+        }
+        UniversalTag::Null => {
+            if contents.is_empty() {
+                writeln!(out)
+            } else {
+                writeln!(out, " (invalid length)")?;
+                write_hex(out, contents, depth + 1)
+            }
+        }
+        UniversalTag::Integer | UniversalTag::Enumerated => {
+            match integer::to_i64(contents) {
+                Ok(v) => writeln!(out, " {}", v),
+                Err(integer::Error::Overflow) => {
+                    writeln!(out)?;
+                    write_hex(out, contents, depth + 1)
+                }
+                Err(e) => {
+                    writeln!(out, " (malformed: {})", e)?;
+                    write_hex(out, contents, depth + 1)
+                }
+            }
+        }
+        UniversalTag::ObjectIdentifier => {
+            match ObjectIdentifier::from_bytes(contents) {
+                Ok(oid) => {
+                    let s = oid.to_string();
+                    match oid_name(&s) {
+                        Some(name) => writeln!(out, " {} ({})", s, name),
+                        None => writeln!(out, " {}", s),
+                    }
+                }
+                Err(e) => {
+                    writeln!(out, " (malformed: {})", e)?;
+                    write_hex(out, contents, depth + 1)
+                }
+            }
+        }
+        UniversalTag::OctetString => {
+            if options.encapsulated && is_encapsulated_der(contents) {
+                writeln!(out, ", encapsulates {{")?;
+                dump(out, options, contents, offset, depth + 1)?;
+                write_continuation_prefix(out, depth)?;
+                return writeln!(out, "}}");
+            }
+            writeln!(out)?;
+            write_hex(out, contents, depth + 1)
+        }
+        UniversalTag::BitString => {
+            let (unused_bits, bits) = match contents.split_first() {
+                Some((&u, bits)) => (u, bits),
+                None => {
+                    writeln!(out, " (invalid length)")?;
+                    return Ok(());
+                }
+            };
+            if options.encapsulated && unused_bits == 0 && is_encapsulated_der(bits) {
+                writeln!(out, ", encapsulates {{")?;
+                dump(out, options, bits, offset + 1, depth + 1)?;
+                write_continuation_prefix(out, depth)?;
+                return writeln!(out, "}}");
+            }
+            if unused_bits != 0 {
+                writeln!(out, " {} unused bits", unused_bits)?;
+            } else {
+                writeln!(out)?;
+            }
+            write_hex(out, bits, depth + 1)
+        }
+        UniversalTag::Utf8String |
+        UniversalTag::NumericString |
+        UniversalTag::PrintableString |
+        UniversalTag::Ia5String |
+        UniversalTag::VisibleString |
+        UniversalTag::GraphicString |
+        UniversalTag::GeneralString |
+        UniversalTag::ObjectDescriptor => {
+            match std::str::from_utf8(contents) {
+                Ok(s) => writeln!(out, " {:?}", s),
+                Err(_) => {
+                    writeln!(out, " (invalid encoding)")?;
+                    write_hex(out, contents, depth + 1)
+                }
+            }
+        }
+        UniversalTag::T61String | UniversalTag::VideotexString => {
+            let s: String = contents.iter().map(|&b| b as char).collect();
+            writeln!(out, " {:?}", s)
+        }
+        UniversalTag::BmpString => {
+            match decode_bmp(contents) {
+                Some(s) => writeln!(out, " {:?}", s),
+                None => {
+                    writeln!(out, " (invalid encoding)")?;
+                    write_hex(out, contents, depth + 1)
+                }
+            }
+        }
+        UniversalTag::UniversalString => {
+            match decode_universal(contents) {
+                Some(s) => writeln!(out, " {:?}", s),
+                None => {
+                    writeln!(out, " (invalid encoding)")?;
+                    write_hex(out, contents, depth + 1)
+                }
+            }
+        }
+        UniversalTag::UtcTime | UniversalTag::GeneralizedTime => {
+            let time = if tag == UniversalTag::UtcTime {
+                DateTime::from_utc_time_bytes(contents)
+            } else {
+                DateTime::from_generalized_time_bytes(contents)
+            };
+            match time {
+                Ok(time) => writeln!(out, " {}", time),
+                Err(e) => {
+                    writeln!(out, " (malformed: {})", e)?;
+                    write_hex(out, contents, depth + 1)
+                }
+            }
+        }
+        _ => {
+            writeln!(out)?;
+            write_hex(out, contents, depth + 1)
+        }
+    }
+}
+
+
+fn write_prefix<W: Write>(out: &mut W, offset: usize, header_len: Option<usize>, contents_len: usize, depth: usize) -> std::io::Result<()> {
+    match header_len {
+        Some(header_len) => write!(out, "{:>6} {:>2} {:>5}: ", offset, header_len, contents_len)?,
+        None => write!(out, "{:>6} {:>2} {:>5}: ", offset, "-", contents_len)?,
+    }
+    write_indent(out, depth)
+}
+
+fn write_continuation_prefix<W: Write>(out: &mut W, depth: usize) -> std::io::Result<()> {
+    write!(out, "{:>6} {:>2} {:>5}  ", "", "", "")?;
+    write_indent(out, depth)
+}
+
+fn write_indent<W: Write>(out: &mut W, depth: usize) -> std::io::Result<()> {
+    for _ in 0..depth {
+        write!(out, "  ")?;
+    }
+    Ok(())
+}
+
+fn write_hex<W: Write>(out: &mut W, data: &[u8], depth: usize) -> std::io::Result<()> {
+    for chunk in data.chunks(HEX_BYTES_PER_LINE) {
+        write_continuation_prefix(out, depth)?;
+        for (i, b) in chunk.iter().enumerate() {
+            if i > 0 {
+                write!(out, " ")?;
+            }
+            write!(out, "{:02X}", b)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+
+fn identifier_name(identifier: Identifier) -> String {
+    match identifier.class() {
+        Class::Universal => {
+            match UniversalTag::from_u64(identifier.tag()) {
+                Some(tag) if identifier.tag() < 31 => universal_tag_name(tag).to_owned(),
+                _ => format!("[UNIVERSAL {}]", identifier.tag()),
+            }
+        }
+        Class::Application => format!("[APPLICATION {}]", identifier.tag()),
+        Class::ContextSpecific => format!("[{}]", identifier.tag()),
+        Class::Private => format!("[PRIVATE {}]", identifier.tag()),
+    }
+}
+
+fn universal_tag_name(tag: UniversalTag) -> &'static str {
+    match tag {
+        UniversalTag::EndOfContent => "EOC",
+        UniversalTag::Boolean => "BOOLEAN",
+        UniversalTag::Integer => "INTEGER",
+        UniversalTag::BitString => "BIT STRING",
+        UniversalTag::OctetString => "OCTET STRING",
+        UniversalTag::Null => "NULL",
+        UniversalTag::ObjectIdentifier => "OBJECT IDENTIFIER",
+        UniversalTag::ObjectDescriptor => "ObjectDescriptor",
+        UniversalTag::External => "EXTERNAL",
+        UniversalTag::Real => "REAL",
+        UniversalTag::Enumerated => "ENUMERATED",
+        UniversalTag::EmbeddedPdv => "EMBEDDED PDV",
+        UniversalTag::Utf8String => "UTF8String",
+        UniversalTag::RelativeOid => "RELATIVE-OID",
+        UniversalTag::Sequence => "SEQUENCE",
+        UniversalTag::Set => "SET",
+        UniversalTag::NumericString => "NumericString",
+        UniversalTag::PrintableString => "PrintableString",
+        UniversalTag::T61String => "TeletexString",
+        UniversalTag::VideotexString => "VideotexString",
+        UniversalTag::Ia5String => "IA5String",
+        UniversalTag::UtcTime => "UTCTime",
+        UniversalTag::GeneralizedTime => "GeneralizedTime",
+        UniversalTag::GraphicString => "GraphicString",
+        UniversalTag::VisibleString => "VisibleString",
+        UniversalTag::GeneralString => "GeneralString",
+        UniversalTag::UniversalString => "UniversalString",
+        UniversalTag::CharacterString => "CHARACTER STRING",
+        UniversalTag::BmpString => "BMPString",
+    }
+}
+
+
+fn is_encapsulated_der(data: &[u8]) -> bool {
+    if data.is_empty() {
+        return false;
+    }
+    let mut r = Asn1Reader::new(data);
+    loop {
+        match r.next() {
+            Ok(Some(_)) => (),
+            Ok(None) => return true,
+            Err(_) => return false,
+        }
+    }
+}
+
+fn decode_bmp(contents: &[u8]) -> Option<String> {
+    if !contents.len().is_multiple_of(2) {
+        return None;
+    }
+    let units: Vec<u16> = contents.chunks(2).map(|c| ((c[0] as u16) << 8) | c[1] as u16).collect();
+    String::from_utf16(&units).ok()
+}
+
+fn decode_universal(contents: &[u8]) -> Option<String> {
+    if !contents.len().is_multiple_of(4) {
+        return None;
+    }
+    contents.chunks(4)
+        .map(|c| std::char::from_u32(((c[0] as u32) << 24) | ((c[1] as u32) << 16) | ((c[2] as u32) << 8) | c[3] as u32))
+        .collect()
+}
+
+
+fn oid_name(oid: &str) -> Option<&'static str> {
+    Some(match oid {
+        "1.2.840.10040.4.1" => "dsa",
+        "1.2.840.10040.4.3" => "dsaWithSha1",
+        "1.2.840.10045.2.1" => "ecPublicKey",
+        "1.2.840.10045.3.1.7" => "prime256v1",
+        "1.2.840.10045.4.1" => "ecdsaWithSHA1",
+        "1.2.840.10045.4.3.2" => "ecdsaWithSHA256",
+        "1.2.840.10045.4.3.3" => "ecdsaWithSHA384",
+        "1.2.840.10045.4.3.4" => "ecdsaWithSHA512",
+        "1.2.840.113549.1.1.1" => "rsaEncryption",
+        "1.2.840.113549.1.1.4" => "md5WithRSAEncryption",
+        "1.2.840.113549.1.1.5" => "sha1WithRSAEncryption",
+        "1.2.840.113549.1.1.10" => "rsassaPss",
+        "1.2.840.113549.1.1.11" => "sha256WithRSAEncryption",
+        "1.2.840.113549.1.1.12" => "sha384WithRSAEncryption",
+        "1.2.840.113549.1.1.13" => "sha512WithRSAEncryption",
+        "1.2.840.113549.1.5.13" => "pkcs5PBES2",
+        "1.2.840.113549.1.5.12" => "pkcs5PBKDF2",
+        "1.2.840.113549.1.7.1" => "data",
+        "1.2.840.113549.1.7.2" => "signedData",
+        "1.2.840.113549.1.7.3" => "envelopedData",
+        "1.2.840.113549.1.7.6" => "encryptedData",
+        "1.2.840.113549.1.9.1" => "emailAddress",
+        "1.2.840.113549.1.9.3" => "contentType",
+        "1.2.840.113549.1.9.4" => "messageDigest",
+        "1.2.840.113549.1.9.5" => "signingTime",
+        "1.2.840.113549.1.9.14" => "extensionRequest",
+        "1.2.840.113549.1.9.20" => "friendlyName",
+        "1.2.840.113549.1.9.21" => "localKeyID",
+        "1.3.6.1.5.5.7.1.1" => "authorityInfoAccess",
+        "1.3.6.1.5.5.7.3.1" => "serverAuth",
+        "1.3.6.1.5.5.7.3.2" => "clientAuth",
+        "1.3.6.1.5.5.7.3.3" => "codeSigning",
+        "1.3.6.1.5.5.7.3.4" => "emailProtection",
+        "1.3.6.1.5.5.7.3.8" => "timeStamping",
+        "1.3.6.1.5.5.7.3.9" => "OCSPSigning",
+        "1.3.6.1.5.5.7.48.1" => "ocsp",
+        "1.3.6.1.5.5.7.48.1.1" => "basicOCSPResponse",
+        "1.3.6.1.5.5.7.48.2" => "caIssuers",
+        "1.3.14.3.2.26" => "sha1",
+        "1.3.101.110" => "X25519",
+        "1.3.101.112" => "Ed25519",
+        "1.3.132.0.34" => "secp384r1",
+        "1.3.132.0.35" => "secp521r1",
+        "2.5.4.3" => "commonName",
+        "2.5.4.4" => "surname",
+        "2.5.4.5" => "serialNumber",
+        "2.5.4.6" => "countryName",
+        "2.5.4.7" => "localityName",
+        "2.5.4.8" => "stateOrProvinceName",
+        "2.5.4.9" => "streetAddress",
+        "2.5.4.10" => "organizationName",
+        "2.5.4.11" => "organizationalUnitName",
+        "2.5.4.12" => "title",
+        "2.5.4.42" => "givenName",
+        "2.5.29.14" => "subjectKeyIdentifier",
+        "2.5.29.15" => "keyUsage",
+        "2.5.29.17" => "subjectAltName",
+        "2.5.29.18" => "issuerAltName",
+        "2.5.29.19" => "basicConstraints",
+        "2.5.29.20" => "cRLNumber",
+        "2.5.29.21" => "cRLReason",
+        "2.5.29.31" => "cRLDistributionPoints",
+        "2.5.29.32" => "certificatePolicies",
+        "2.5.29.35" => "authorityKeyIdentifier",
+        "2.5.29.37" => "extKeyUsage",
+        "2.16.840.1.101.3.4.2.1" => "sha256",
+        "2.16.840.1.101.3.4.2.2" => "sha384",
+        "2.16.840.1.101.3.4.2.3" => "sha512",
+        "0.9.2342.19200300.100.1.25" => "domainComponent",
+        _ => return None,
+    })
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dump_primitives() {
+        let input = [
+            0x17u8, 0x0d, b'1', b'9', b'0', b'1', b'0', b'2', b'0', b'3', b'0', b'4', b'0', b'5', b'Z',
+            0x17, 0x0a, b'1', b'9', b'0', b'1', b'0', b'2', b'0', b'3', b'0', b'4',
+            0x02, 0x02, 0x00, 0x01,
+            0x02, 0x09, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let mut output = Vec::new();
+        dump(&mut output, &Options { encapsulated: false }, &input, 0, 0).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), concat!(
+"     0  2    13: UTCTime 2019-01-02T03:04:05Z\n",
+"    15  2    10: UTCTime (malformed: invalid time format)\n",
+"                   31 39 30 31 30 32 30 33 30 34\n",
+"    27  2     2: INTEGER (malformed: non-minimal integer encoding)\n",
+"                   00 01\n",
+"    31  2     9: INTEGER\n",
+"                   01 00 00 00 00 00 00 00 00\n",
+        ));
+    }
+
+    #[test]
+    fn test_dump_malformed() {
+        let input = [0x30u8, 0x03, 0x02, 0x01, 0xff, 0x04, 0x05, 0x00];
+
+        let mut output = Vec::new();
+        dump(&mut output, &Options { encapsulated: false }, &input, 0, 0).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), concat!(
+"     0  2     3: SEQUENCE {\n",
+"     2  2     1:   INTEGER -1\n",
+"                 }\n",
+"     5  -     3: malformed data: unexpected endofdata\n",
+"                   04 05 00\n",
+        ));
+    }
+
+    #[test]
+    fn test_dump_nesting() {
+        // Nested definite-length SEQUENCEs, built from the innermost outwards with each header reversed.
+        let mut reversed = Vec::new();
+        for _ in 0..10000 {
+            let len = reversed.len();
+            let header = match len {
+                0..=0x7f => vec![0x30, len as u8],
+                0x80..=0xff => vec![0x30, 0x81, len as u8],
+                0x100..=0xffff => vec![0x30, 0x82, (len >> 8) as u8, len as u8],
+                _ => vec![0x30, 0x83, (len >> 16) as u8, (len >> 8) as u8, len as u8],
+            };
+            reversed.extend(header.iter().rev());
+        }
+        let input: Vec<u8> = reversed.into_iter().rev().collect();
+
+        let mut output = Vec::new();
+        dump(&mut output, &Options { encapsulated: false }, &input, 0, 0).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches(" SEQUENCE {").count(), reading::MAX_DEPTH);
+        assert_eq!(output.matches("nesting too deep").count(), 1);
+    }
+}
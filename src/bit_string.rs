@@ -0,0 +1,147 @@
+use std;
+
+
+#[derive(Debug)]
+pub enum Error {
+    Empty,
+    InvalidUnusedBits,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Empty => write!(f, "empty bit string"),
+            Error::InvalidUnusedBits => write!(f, "invalid bit string unused bits"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// A borrowed BIT STRING value: the leading unused-bits octet and the octets holding the bits themselves.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct BitString<'a> {
+    unused_bits: u8,
+    data: &'a [u8],
+}
+
+impl<'a> BitString<'a> {
+    pub fn new(unused_bits: u8, data: &'a [u8]) -> Result<Self> {
+        if unused_bits > 7 || (data.is_empty() && unused_bits != 0) {
+            return Err(Error::InvalidUnusedBits);
+        }
+        Ok(BitString {
+            unused_bits,
+            data,
+        })
+    }
+
+    /// Wraps a whole number of octets, as used for keys and signatures.
+    pub fn from_octets(data: &'a [u8]) -> Self {
+        BitString {
+            unused_bits: 0,
+            data,
+        }
+    }
+
+    pub fn from_bytes(b: &'a [u8]) -> Result<Self> {
+        match b.split_first() {
+            Some((&unused_bits, data)) => BitString::new(unused_bits, data),
+            None => Err(Error::Empty),
+        }
+    }
+
+    pub fn unused_bits(&self) -> u8 {
+        self.unused_bits
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns the contents as whole octets, or `None` if the bit length is not a multiple of eight.
+    pub fn as_octets(&self) -> Option<&'a [u8]> {
+        if self.unused_bits != 0 {
+            return None;
+        }
+        Some(self.data)
+    }
+
+    pub fn bit_len(&self) -> usize {
+        self.data.len() * 8 - self.unused_bits as usize
+    }
+
+    /// Returns bit `i`, counting from the most significant bit of the first octet; bits beyond the end are unset,
+    /// as with named bit lists.
+    pub fn bit(&self, i: usize) -> bool {
+        if i >= self.bit_len() {
+            return false;
+        }
+        self.data[i / 8] & (0x80 >> (i % 8)) != 0
+    }
+
+    #[inline]
+    pub fn bytes_len(&self) -> usize {
+        1 + self.data.len()
+    }
+
+    pub fn write_to<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<usize> {
+        w.write_all(&[self.unused_bits])?;
+        w.write_all(self.data)?;
+        Ok(self.bytes_len())
+    }
+}
+
+/// Encodes a named bit list (such as KeyUsage) in which named bit `n` is `1 << n` in `bits`. The octets follow
+/// X.690 order, with named bit 0 as the most significant bit of the first octet, and trailing zero bits are removed
+/// as DER requires. Returns the unused-bits count and the data octets.
+pub fn named_bits_to_bytes(bits: u64, max_bits: usize) -> (u8, Vec<u8>) {
+    let bit_len = (0..max_bits).rev().find(|&i| bits & (1 << i) != 0).map_or(0, |i| i + 1);
+    let mut data = vec![0u8; bit_len.div_ceil(8)];
+    for i in 0..bit_len {
+        if bits & (1 << i) != 0 {
+            data[i / 8] |= 0x80 >> (i % 8);
+        }
+    }
+    let unused_bits = (data.len() * 8 - bit_len) as u8;
+    (unused_bits, data)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_bytes() {
+        let b = BitString::from_bytes(&[0x07, 0x80]).unwrap();
+        assert_eq!(b.unused_bits(), 7);
+        assert_eq!(b.data(), [0x80u8]);
+        assert_eq!(b.bit_len(), 1);
+        assert!(b.bit(0));
+        assert!(!b.bit(1));
+        assert_eq!(b.as_octets(), None);
+
+        assert_eq!(BitString::from_bytes(&[0x00]).unwrap().bit_len(), 0);
+        assert!(BitString::from_bytes(&[]).is_err());
+        assert!(BitString::from_bytes(&[0x08, 0x00]).is_err());
+        assert!(BitString::from_bytes(&[0x01]).is_err());
+    }
+
+    #[test]
+    fn test_named_bits() {
+        assert_eq!(named_bits_to_bytes(0, 9), (0, vec![]));
+        assert_eq!(named_bits_to_bytes(0b1, 9), (7, vec![0x80]));
+        assert_eq!(named_bits_to_bytes(0b1010_0001, 9), (0, vec![0x85]));
+        assert_eq!(named_bits_to_bytes(0b1_0000_0110, 9), (7, vec![0x60, 0x80]));
+
+        let (unused_bits, data) = named_bits_to_bytes(0b0110, 9);
+        let mut output = Vec::new();
+        BitString::new(unused_bits, &data).unwrap().write_to(&mut output).unwrap();
+        assert_eq!(output, [0x05u8, 0x60]);
+    }
+}
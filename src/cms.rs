@@ -0,0 +1,774 @@
+use identifier::{Class, Identifier, UniversalTag};
+use object_identifier::ObjectIdentifier;
+use reading::{self, Asn1Reader};
+use std;
+use std::borrow::Cow;
+use time::DateTime;
+use writing::der::Writer;
+use x509::{self, extensions, oid as x509_oid, AlgorithmIdentifier, Attribute, Certificate, Name};
+
+
+pub mod oid {
+    pub const DATA: &[u64] = &[1, 2, 840, 113549, 1, 7, 1];
+    pub const SIGNED_DATA: &[u64] = &[1, 2, 840, 113549, 1, 7, 2];
+    pub const ENVELOPED_DATA: &[u64] = &[1, 2, 840, 113549, 1, 7, 3];
+    pub const ENCRYPTED_DATA: &[u64] = &[1, 2, 840, 113549, 1, 7, 6];
+
+    pub const CONTENT_TYPE: &[u64] = &[1, 2, 840, 113549, 1, 9, 3];
+    pub const MESSAGE_DIGEST: &[u64] = &[1, 2, 840, 113549, 1, 9, 4];
+    pub const SIGNING_TIME: &[u64] = &[1, 2, 840, 113549, 1, 9, 5];
+}
+
+
+#[derive(Debug)]
+pub enum Error {
+    Read(reading::Error),
+    InvalidValue,
+    UnexpectedContentType(ObjectIdentifier),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Read(ref e) => e.fmt(f),
+            Error::InvalidValue => write!(f, "invalid value"),
+            Error::UnexpectedContentType(ref oid) => write!(f, "unexpected content type: {}", oid),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Read(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reading::Error> for Error {
+    fn from(e: reading::Error) -> Error {
+        Error::Read(e)
+    }
+}
+
+impl From<x509::Error> for Error {
+    fn from(e: x509::Error) -> Error {
+        match e {
+            x509::Error::Read(e) => Error::Read(e),
+            _ => Error::InvalidValue,
+        }
+    }
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// Returns the contents of an OCTET STRING, or of an implicitly tagged one, given its identifier and contents.
+///
+/// BER allows the value to be constructed from nested OCTET STRING segments, in which case they are concatenated.
+fn octets<'a>(identifier: Identifier, contents: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+    Ok(reading::join_segments(identifier, contents, UniversalTag::OctetString.into())?)
+}
+
+/// Reads an implicitly tagged context-specific value that may be primitive or constructed.
+fn read_optional_implicit<'a>(r: &mut Asn1Reader<'a>, tag: u64) -> Result<Option<(Identifier, &'a [u8])>> {
+    match r.peek()? {
+        Some(i) if i.class() == Class::ContextSpecific && i.tag() == tag => Ok(r.next()?),
+        _ => Ok(None),
+    }
+}
+
+fn read_attributes<'a>(b: &'a [u8]) -> Result<Vec<Attribute<'a>>> {
+    let mut r = Asn1Reader::new(b);
+    let mut attributes = Vec::new();
+    while !r.is_at_end() {
+        attributes.push(Attribute::read(&mut r)?);
+    }
+    Ok(attributes)
+}
+
+/// Re-encodes a BER value in DER: lengths become definite and minimal, constructed strings are joined into
+/// primitive ones and SET elements are sorted.
+fn write_der(w: &mut Writer<Vec<u8>>, identifier: Identifier, contents: &[u8], depth: usize) -> Result<()> {
+    let string_tag = identifier.class() == Class::Universal && matches!(UniversalTag::from_u64(identifier.tag()),
+        Some(UniversalTag::OctetString) | Some(UniversalTag::Utf8String) | Some(UniversalTag::PrintableString) |
+        Some(UniversalTag::Ia5String) | Some(UniversalTag::T61String) | Some(UniversalTag::BmpString) |
+        Some(UniversalTag::UniversalString) | Some(UniversalTag::VisibleString));
+    let write_error = |_| Error::InvalidValue;
+    if !identifier.is_constructed() {
+        w.write_tag_and_data(identifier, contents).map_err(write_error)?;
+    } else if string_tag {
+        let joined = reading::join_segments(identifier, contents, identifier.tag())?;
+        let primitive = Identifier::new(Class::Universal, false, identifier.tag());
+        w.write_tag_and_data(primitive, &joined).map_err(write_error)?;
+    } else {
+        if depth >= reading::MAX_DEPTH {
+            return Err(Error::Read(reading::Error::NestingTooDeep));
+        }
+        let mut elements = Vec::new();
+        let mut r = Asn1Reader::new(contents);
+        while let Some((identifier, contents)) = r.next()? {
+            let mut element = Vec::new();
+            write_der(&mut Writer::new(&mut element), identifier, contents, depth + 1)?;
+            elements.push(element);
+        }
+        if identifier == Identifier::universal(UniversalTag::Set) {
+            elements.sort();
+        }
+        w.write_tag_and_data(identifier, &elements.concat()).map_err(write_error)?;
+    }
+    Ok(())
+}
+
+
+/// A ContentInfo, the outermost CMS structure.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct ContentInfo<'a> {
+    pub content_type: ObjectIdentifier,
+    /// The complete encoding of the explicitly tagged content.
+    pub content: &'a [u8],
+}
+
+impl<'a> ContentInfo<'a> {
+    pub const PEM_LABEL: &'static str = "CMS";
+
+    pub fn from_ber(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = ContentInfo::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let content_type = r.read_object_identifier()?;
+        let mut explicit = Asn1Reader::new(r.read_expected(Identifier::context_specific(true, 0))?);
+        let content = explicit.read_raw()?;
+        explicit.expect_end()?;
+        r.expect_end()?;
+        Ok(ContentInfo {
+            content_type,
+            content,
+        })
+    }
+
+    /// Returns the octets of `data` content.
+    pub fn data(&self) -> Result<Cow<'a, [u8]>> {
+        if self.content_type.components() != oid::DATA {
+            return Err(Error::UnexpectedContentType(self.content_type.clone()));
+        }
+        let mut r = Asn1Reader::new(self.content);
+        let v = r.read_ber_octet_string()?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub fn signed_data(&self) -> Result<SignedData<'a>> {
+        if self.content_type.components() != oid::SIGNED_DATA {
+            return Err(Error::UnexpectedContentType(self.content_type.clone()));
+        }
+        SignedData::from_ber(self.content)
+    }
+
+    pub fn enveloped_data(&self) -> Result<EnvelopedData<'a>> {
+        if self.content_type.components() != oid::ENVELOPED_DATA {
+            return Err(Error::UnexpectedContentType(self.content_type.clone()));
+        }
+        EnvelopedData::from_ber(self.content)
+    }
+
+    pub fn encrypted_data(&self) -> Result<EncryptedData<'a>> {
+        if self.content_type.components() != oid::ENCRYPTED_DATA {
+            return Err(Error::UnexpectedContentType(self.content_type.clone()));
+        }
+        EncryptedData::from_ber(self.content)
+    }
+}
+
+
+/// The CHOICE of IssuerAndSerialNumber or SubjectKeyIdentifier used to identify a signer's or recipient's
+/// certificate.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum SignerIdentifier<'a> {
+    IssuerAndSerialNumber {
+        issuer: Name<'a>,
+        serial_number: &'a [u8],
+    },
+    /// The subjectKeyIdentifier, joined if BER constructed it from segments.
+    SubjectKeyIdentifier(Cow<'a, [u8]>),
+}
+
+pub type RecipientIdentifier<'a> = SignerIdentifier<'a>;
+
+impl<'a> SignerIdentifier<'a> {
+    fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        if let Some((identifier, contents)) = read_optional_implicit(r, 0)? {
+            return Ok(SignerIdentifier::SubjectKeyIdentifier(octets(identifier, contents)?));
+        }
+        let mut r = r.read_sequence()?;
+        let issuer = Name::read(&mut r)?;
+        let serial_number = r.read_integer_bytes()?;
+        r.expect_end()?;
+        Ok(SignerIdentifier::IssuerAndSerialNumber {
+            issuer,
+            serial_number,
+        })
+    }
+
+    /// Returns whether this identifies `certificate`.
+    pub fn matches(&self, certificate: &Certificate) -> bool {
+        let tbs = &certificate.tbs_certificate;
+        match *self {
+            SignerIdentifier::IssuerAndSerialNumber { ref issuer, serial_number } => {
+                serial_number == tbs.serial_number && issuer.matches(&tbs.issuer)
+            }
+            SignerIdentifier::SubjectKeyIdentifier(ref ski) => {
+                match tbs.extension(x509_oid::SUBJECT_KEY_IDENTIFIER) {
+                    Some(e) => extensions::subject_key_identifier(e.extn_value).ok() == Some(&ski[..]),
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct EncapsulatedContentInfo<'a> {
+    pub e_content_type: ObjectIdentifier,
+    /// The content, or `None` for a detached signature.
+    pub e_content: Option<Cow<'a, [u8]>>,
+}
+
+impl<'a> EncapsulatedContentInfo<'a> {
+    fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let e_content_type = r.read_object_identifier()?;
+        let e_content = match r.read_optional(Identifier::context_specific(true, 0))? {
+            Some(b) => {
+                let mut r = Asn1Reader::new(b);
+                let e_content = r.read_ber_octet_string()?;
+                r.expect_end()?;
+                Some(e_content)
+            }
+            None => None,
+        };
+        r.expect_end()?;
+        Ok(EncapsulatedContentInfo {
+            e_content_type,
+            e_content,
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct SignerInfo<'a> {
+    pub version: u64,
+    pub sid: SignerIdentifier<'a>,
+    pub digest_algorithm: AlgorithmIdentifier<'a>,
+    /// The contents of the implicitly tagged signedAttrs, if present.
+    pub signed_attrs_raw: Option<&'a [u8]>,
+    pub signed_attrs: Vec<Attribute<'a>>,
+    pub signature_algorithm: AlgorithmIdentifier<'a>,
+    pub signature: Cow<'a, [u8]>,
+    pub unsigned_attrs: Vec<Attribute<'a>>,
+}
+
+impl<'a> SignerInfo<'a> {
+    fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let version = r.read_u64()?;
+        let sid = SignerIdentifier::read(&mut r)?;
+        let digest_algorithm = AlgorithmIdentifier::read(&mut r)?;
+        let signed_attrs_raw = r.read_optional(Identifier::context_specific(true, 0))?;
+        let signed_attrs = match signed_attrs_raw {
+            Some(b) => read_attributes(b)?,
+            None => Vec::new(),
+        };
+        let signature_algorithm = AlgorithmIdentifier::read(&mut r)?;
+        let signature = r.read_ber_octet_string()?;
+        let unsigned_attrs = match r.read_optional(Identifier::context_specific(true, 1))? {
+            Some(b) => read_attributes(b)?,
+            None => Vec::new(),
+        };
+        r.expect_end()?;
+        Ok(SignerInfo {
+            version,
+            sid,
+            digest_algorithm,
+            signed_attrs_raw,
+            signed_attrs,
+            signature_algorithm,
+            signature,
+            unsigned_attrs,
+        })
+    }
+
+    /// The message that the signature covers when signed attributes are present: their DER encoding with an
+    /// explicit SET OF tag, as RFC 5652 section 5.4 requires. Returns `None` if there are no signed attributes, in
+    /// which case the signature covers the content itself.
+    pub fn signed_attrs_der(&self) -> Result<Option<Vec<u8>>> {
+        let contents = match self.signed_attrs_raw {
+            Some(contents) => contents,
+            None => return Ok(None),
+        };
+        let mut output = Vec::new();
+        write_der(&mut Writer::new(&mut output), Identifier::universal(UniversalTag::Set), contents, 0)?;
+        Ok(Some(output))
+    }
+
+    pub fn signed_attribute(&self, attr_type: &[u64]) -> Option<&Attribute<'a>> {
+        self.signed_attrs.iter().find(|a| a.attr_type.components() == attr_type)
+    }
+
+    /// Returns the single value of a signed attribute as a reader, or `None` if the attribute is absent.
+    fn single_signed_value(&self, attr_type: &[u64]) -> Result<Option<Asn1Reader<'a>>> {
+        match self.signed_attribute(attr_type) {
+            Some(a) if a.values.len() == 1 => Ok(Some(Asn1Reader::new(a.values[0]))),
+            Some(_) => Err(Error::InvalidValue),
+            None => Ok(None),
+        }
+    }
+
+    pub fn content_type(&self) -> Result<Option<ObjectIdentifier>> {
+        match self.single_signed_value(oid::CONTENT_TYPE)? {
+            Some(mut r) => Ok(Some(r.read_object_identifier()?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn message_digest(&self) -> Result<Option<Cow<'a, [u8]>>> {
+        match self.single_signed_value(oid::MESSAGE_DIGEST)? {
+            Some(mut r) => Ok(Some(r.read_ber_octet_string()?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn signing_time(&self) -> Result<Option<DateTime>> {
+        match self.single_signed_value(oid::SIGNING_TIME)? {
+            Some(mut r) => Ok(Some(r.read_time()?)),
+            None => Ok(None),
+        }
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct SignedData<'a> {
+    pub version: u64,
+    pub digest_algorithms: Vec<AlgorithmIdentifier<'a>>,
+    pub encap_content_info: EncapsulatedContentInfo<'a>,
+    /// The complete encodings of the CertificateChoices, most often plain certificates.
+    pub certificates: Vec<&'a [u8]>,
+    /// The complete encodings of the RevocationInfoChoices, most often plain CRLs.
+    pub crls: Vec<&'a [u8]>,
+    pub signer_infos: Vec<SignerInfo<'a>>,
+}
+
+impl<'a> SignedData<'a> {
+    pub fn from_ber(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = SignedData::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let version = r.read_u64()?;
+
+        let mut digest_algorithms = Vec::new();
+        let mut set = r.read_set()?;
+        while !set.is_at_end() {
+            digest_algorithms.push(AlgorithmIdentifier::read(&mut set)?);
+        }
+
+        let encap_content_info = EncapsulatedContentInfo::read(&mut r)?;
+
+        let mut raw_list = |tag| -> Result<Vec<&'a [u8]>> {
+            let mut list = Vec::new();
+            if let Some(b) = r.read_optional(Identifier::context_specific(true, tag))? {
+                let mut r = Asn1Reader::new(b);
+                while !r.is_at_end() {
+                    list.push(r.read_raw()?);
+                }
+            }
+            Ok(list)
+        };
+        let certificates = raw_list(0)?;
+        let crls = raw_list(1)?;
+
+        let mut signer_infos = Vec::new();
+        let mut set = r.read_set()?;
+        while !set.is_at_end() {
+            signer_infos.push(SignerInfo::read(&mut set)?);
+        }
+        r.expect_end()?;
+
+        Ok(SignedData {
+            version,
+            digest_algorithms,
+            encap_content_info,
+            certificates,
+            crls,
+            signer_infos,
+        })
+    }
+
+    /// Finds the certificate that `signer` identifies among those carried in `certificates`.
+    pub fn signer_certificate(&self, signer: &SignerInfo) -> Option<Certificate<'a>> {
+        self.certificates.iter()
+            .filter_map(|b| Certificate::from_der(b).ok())
+            .find(|c| signer.sid.matches(c))
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct KeyTransRecipientInfo<'a> {
+    pub version: u64,
+    pub rid: RecipientIdentifier<'a>,
+    pub key_encryption_algorithm: AlgorithmIdentifier<'a>,
+    pub encrypted_key: Cow<'a, [u8]>,
+}
+
+impl<'a> KeyTransRecipientInfo<'a> {
+    fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let version = r.read_u64()?;
+        let rid = RecipientIdentifier::read(&mut r)?;
+        let key_encryption_algorithm = AlgorithmIdentifier::read(&mut r)?;
+        let encrypted_key = r.read_ber_octet_string()?;
+        r.expect_end()?;
+        Ok(KeyTransRecipientInfo {
+            version,
+            rid,
+            key_encryption_algorithm,
+            encrypted_key,
+        })
+    }
+}
+
+
+/// The RecipientInfo CHOICE. Only key transport is decoded; the other alternatives hold the contents of their
+/// implicitly tagged SEQUENCE.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum RecipientInfo<'a> {
+    KeyTrans(KeyTransRecipientInfo<'a>),
+    KeyAgree(&'a [u8]),
+    Kek(&'a [u8]),
+    Password(&'a [u8]),
+    Other(&'a [u8]),
+}
+
+impl<'a> RecipientInfo<'a> {
+    fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let identifier = match r.peek()? {
+            Some(i) => i,
+            None => return Err(Error::Read(reading::Error::UnexpectedEndOfData)),
+        };
+        if identifier == Identifier::universal(UniversalTag::Sequence) {
+            return Ok(RecipientInfo::KeyTrans(KeyTransRecipientInfo::read(r)?));
+        }
+        if identifier.class() != Class::ContextSpecific || !identifier.is_constructed() {
+            return Err(Error::Read(reading::Error::UnexpectedTag(identifier)));
+        }
+        let make = match identifier.tag() {
+            1 => RecipientInfo::KeyAgree,
+            2 => RecipientInfo::Kek,
+            3 => RecipientInfo::Password,
+            4 => RecipientInfo::Other,
+            _ => return Err(Error::Read(reading::Error::UnexpectedTag(identifier))),
+        };
+        Ok(make(r.read_expected(identifier)?))
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct EncryptedContentInfo<'a> {
+    pub content_type: ObjectIdentifier,
+    pub content_encryption_algorithm: AlgorithmIdentifier<'a>,
+    /// The ciphertext, or `None` if it is conveyed separately.
+    pub encrypted_content: Option<Cow<'a, [u8]>>,
+}
+
+impl<'a> EncryptedContentInfo<'a> {
+    fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let content_type = r.read_object_identifier()?;
+        let content_encryption_algorithm = AlgorithmIdentifier::read(&mut r)?;
+        let encrypted_content = match read_optional_implicit(&mut r, 0)? {
+            Some((identifier, contents)) => Some(octets(identifier, contents)?),
+            None => None,
+        };
+        r.expect_end()?;
+        Ok(EncryptedContentInfo {
+            content_type,
+            content_encryption_algorithm,
+            encrypted_content,
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct EnvelopedData<'a> {
+    pub version: u64,
+    /// The contents of the implicitly tagged originatorInfo, if present.
+    pub originator_info: Option<&'a [u8]>,
+    pub recipient_infos: Vec<RecipientInfo<'a>>,
+    pub encrypted_content_info: EncryptedContentInfo<'a>,
+    pub unprotected_attrs: Vec<Attribute<'a>>,
+}
+
+impl<'a> EnvelopedData<'a> {
+    pub fn from_ber(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = EnvelopedData::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let version = r.read_u64()?;
+        let originator_info = r.read_optional(Identifier::context_specific(true, 0))?;
+
+        let mut recipient_infos = Vec::new();
+        let mut set = r.read_set()?;
+        while !set.is_at_end() {
+            recipient_infos.push(RecipientInfo::read(&mut set)?);
+        }
+        if recipient_infos.is_empty() {
+            return Err(Error::InvalidValue);
+        }
+
+        let encrypted_content_info = EncryptedContentInfo::read(&mut r)?;
+        let unprotected_attrs = match r.read_optional(Identifier::context_specific(true, 1))? {
+            Some(b) => read_attributes(b)?,
+            None => Vec::new(),
+        };
+        r.expect_end()?;
+
+        Ok(EnvelopedData {
+            version,
+            originator_info,
+            recipient_infos,
+            encrypted_content_info,
+            unprotected_attrs,
+        })
+    }
+}
+
+
+/// EncryptedData, content encrypted under a key managed outside of CMS.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct EncryptedData<'a> {
+    pub version: u64,
+    pub encrypted_content_info: EncryptedContentInfo<'a>,
+    pub unprotected_attrs: Vec<Attribute<'a>>,
+}
+
+impl<'a> EncryptedData<'a> {
+    pub fn from_ber(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = EncryptedData::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let version = r.read_u64()?;
+        let encrypted_content_info = EncryptedContentInfo::read(&mut r)?;
+        let unprotected_attrs = match r.read_optional(Identifier::context_specific(true, 1))? {
+            Some(b) => read_attributes(b)?,
+            None => Vec::new(),
+        };
+        r.expect_end()?;
+        Ok(EncryptedData {
+            version,
+            encrypted_content_info,
+            unprotected_attrs,
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pem;
+
+    // `openssl cms -sign -nodetach -nosmimecap -stream -md sha256` over "hello", with the certificate and key from
+    // the x509 tests. The streamed output uses indefinite lengths and a constructed eContent OCTET STRING.
+    const SIGNED_PEM: &str = "\
+-----BEGIN CMS-----
+MIAGCSqGSIb3DQEHAqCAMIACAQExDTALBglghkgBZQMEAgEwgAYJKoZIhvcNAQcB
+oIAkgAQFaGVsbG8AAAAAAACgggIzMIICLzCCAdSgAwIBAgIFAQIDBAUwCgYIKoZI
+zj0EAwIwODELMAkGA1UEBhMCR0IxFDASBgNVBAoMC0V4YW1wbGUgTHRkMRMwEQYD
+VQQDDApFeGFtcGxlIENBMB4XDTI0MDEwMTAwMDAwMFoXDTM0MDEwMTAwMDAwMFow
+ODELMAkGA1UEBhMCR0IxFDASBgNVBAoMC0V4YW1wbGUgTHRkMRMwEQYDVQQDDApF
+eGFtcGxlIENBMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE3mNYI4/xDiQCDij+
+BdgUFABsJ8qvl0mhVE0sP4SKPGCVVs5zzMbgNyFTgL2kOws8DtabNz4qN6uM8U9m
+9R1ce6OByjCBxzAdBgNVHQ4EFgQUB+aj0mwBdQ3uTqDdOh8EVKHDcuowHwYDVR0j
+BBgwFoAUB+aj0mwBdQ3uTqDdOh8EVKHDcuowEgYDVR0TAQH/BAgwBgEB/wIBATAO
+BgNVHQ8BAf8EBAMCAYYwQgYDVR0RBDswOYILZXhhbXBsZS5jb22HBH8AAAGBDmNh
+QGV4YW1wbGUuY29thhRodHRwczovL2V4YW1wbGUuY29tLzAdBgNVHSUEFjAUBggr
+BgEFBQcDAQYIKwYBBQUHAwIwCgYIKoZIzj0EAwIDSQAwRgIhAL5qSITEJnH07E1K
+3rvFaIauRoOkFeY8jP01UslaUKevAiEAqwgndUyX9zb9LdKIVYOoDNOA+9p13IX+
+vbLrZxi3X1sxggEXMIIBEwIBATBBMDgxCzAJBgNVBAYTAkdCMRQwEgYDVQQKDAtF
+eGFtcGxlIEx0ZDETMBEGA1UEAwwKRXhhbXBsZSBDQQIFAQIDBAUwCwYJYIZIAWUD
+BAIBoGkwGAYJKoZIhvcNAQkDMQsGCSqGSIb3DQEHATAcBgkqhkiG9w0BCQUxDxcN
+MjYxMDE4MjEyNTE2WjAvBgkqhkiG9w0BCQQxIgQgLPJNul+wow4m6DsqxbninhsW
+Hlwfp0JecwQzYpOLmCQwCgYIKoZIzj0EAwIERzBFAiEAoRiqGz+dRUWa2wWBNKdL
+dv1Mh59IS9HHaDkdyIZCxWkCIAOllhvT60hRnbJ1bjREHR4/RqetXvPxGYMkEx/8
+myubAAAAAAAA
+-----END CMS-----
+";
+
+    // `openssl cms -encrypt -aes128 -stream` of "hello" to a self-signed RSA certificate with serial number 7.
+    const ENVELOPED_PEM: &str = "\
+-----BEGIN CMS-----
+MIAGCSqGSIb3DQEHA6CAMIACAQAxdTBzAgEAMB0wGDEWMBQGA1UEAwwNUlNBIFJl
+Y2lwaWVudAIBBzANBgkqhkiG9w0BAQEFAARAwDbSBM1M+fp4fjcCvQGTCy4r8zaa
+IR/1DO6iwXGAgrBJ1ZoHN8eMZWBKY7JwGnLY4giHH7LaIdQpRP822DWMnDCABgkq
+hkiG9w0BBwEwHQYJYIZIAWUDBAECBBA+usGCCfr8FPctg455mLMqoIAEEH/DBWV5
+LkUgHe2rNcBg7jsAAAAAAAAAAAAA
+-----END CMS-----
+";
+
+    const HELLO_SHA256: [u8; 32] = [
+        0x2c, 0xf2, 0x4d, 0xba, 0x5f, 0xb0, 0xa3, 0x0e, 0x26, 0xe8, 0x3b, 0x2a, 0xc5, 0xb9, 0xe2, 0x9e,
+        0x1b, 0x16, 0x1e, 0x5c, 0x1f, 0xa7, 0x42, 0x5e, 0x73, 0x04, 0x33, 0x62, 0x93, 0x8b, 0x98, 0x24,
+    ];
+
+    #[test]
+    fn test_signed_data() {
+        let der = pem::parse(SIGNED_PEM, pem::Mode::Strict).unwrap().into_contents();
+        let content_info = ContentInfo::from_ber(&der).unwrap();
+        assert_eq!(content_info.content_type.components(), oid::SIGNED_DATA);
+        assert!(content_info.enveloped_data().is_err());
+
+        let signed_data = content_info.signed_data().unwrap();
+        assert_eq!(signed_data.version, 1);
+        assert_eq!(signed_data.digest_algorithms.len(), 1);
+        assert_eq!(signed_data.digest_algorithms[0].algorithm.components(), [2, 16, 840, 1, 101, 3, 4, 2, 1]);
+        assert_eq!(signed_data.encap_content_info.e_content_type.components(), oid::DATA);
+        assert_eq!(signed_data.encap_content_info.e_content, Some(Cow::Owned(b"hello".to_vec())));
+        assert_eq!(signed_data.certificates, [&x509::test::ca_der()[..]]);
+        assert!(signed_data.crls.is_empty());
+        assert_eq!(signed_data.signer_infos.len(), 1);
+
+        let signer = &signed_data.signer_infos[0];
+        assert_eq!(signer.version, 1);
+        match signer.sid {
+            SignerIdentifier::IssuerAndSerialNumber { ref issuer, serial_number } => {
+                assert_eq!(issuer.to_string(), "CN=Example CA,O=Example Ltd,C=GB");
+                assert_eq!(serial_number, [1, 2, 3, 4, 5]);
+            }
+            _ => panic!("unexpected signer identifier"),
+        }
+        let certificate = signed_data.signer_certificate(signer).unwrap();
+        assert_eq!(certificate.signature_value, Certificate::from_der(signed_data.certificates[0]).unwrap().signature_value);
+        assert!(SignerIdentifier::SubjectKeyIdentifier(Cow::Borrowed(&[
+            0x07, 0xe6, 0xa3, 0xd2, 0x6c, 0x01, 0x75, 0x0d, 0xee, 0x4e, 0xa0, 0xdd, 0x3a, 0x1f, 0x04, 0x54, 0xa1, 0xc3,
+            0x72, 0xea,
+        ])).matches(&certificate));
+
+        assert_eq!(signer.signed_attrs.len(), 3);
+        assert_eq!(signer.content_type().unwrap().unwrap().components(), oid::DATA);
+        assert_eq!(signer.message_digest().unwrap().unwrap(), &HELLO_SHA256[..]);
+        assert_eq!(signer.signing_time().unwrap().unwrap(), DateTime::new(2026, 10, 18, 21, 25, 16).unwrap());
+        assert_eq!(signer.signature_algorithm.algorithm.components(), [1, 2, 840, 10045, 4, 3, 2]);
+        assert_eq!(signer.signature.len(), 71);
+        assert!(signer.unsigned_attrs.is_empty());
+
+        let raw = signer.signed_attrs_raw.unwrap();
+        let signed_attrs = signer.signed_attrs_der().unwrap().unwrap();
+        assert_eq!(signed_attrs[..2], [0x31, raw.len() as u8]);
+        assert_eq!(&signed_attrs[2..], raw);
+    }
+
+    #[test]
+    fn test_signed_attrs_der() {
+        // A signedAttrs with indefinite lengths, a constructed OCTET STRING and unsorted SET elements.
+        let signer_info = [
+            0x30, 0x80,
+            0x02, 0x01, 0x03,
+            0x80, 0x01, 0xaa,
+            0x30, 0x03, 0x06, 0x01, 0x2a,
+            0xa0, 0x80,
+            0x30, 0x80, 0x06, 0x01, 0x2a, 0x31, 0x80, 0x04, 0x01, 0x02, 0x24, 0x80, 0x04, 0x01, 0x01, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00,
+            0x00, 0x00,
+            0x30, 0x03, 0x06, 0x01, 0x2a,
+            0x04, 0x00,
+            0x00, 0x00,
+        ];
+        let mut r = Asn1Reader::new(&signer_info);
+        let signer = SignerInfo::read(&mut r).unwrap();
+        assert!(r.expect_end().is_ok());
+        assert_eq!(signer.sid, SignerIdentifier::SubjectKeyIdentifier(Cow::Borrowed(&[0xaa])));
+
+        let sid = [0xa0, 0x80, 0x04, 0x01, 0xaa, 0x04, 0x01, 0xbb, 0x00, 0x00];
+        let mut r = Asn1Reader::new(&sid);
+        assert_eq!(SignerIdentifier::read(&mut r).unwrap(), SignerIdentifier::SubjectKeyIdentifier(Cow::Owned(vec![0xaa, 0xbb])));
+        assert!(r.expect_end().is_ok());
+        assert_eq!(signer.signed_attrs.len(), 1);
+        assert_eq!(signer.signed_attrs_der().unwrap().unwrap(), [
+            0x31, 0x0d, 0x30, 0x0b, 0x06, 0x01, 0x2a, 0x31, 0x06, 0x04, 0x01, 0x01, 0x04, 0x01, 0x02,
+        ]);
+
+        let mut deep = vec![0x05, 0x00];
+        for _ in 0..1000 {
+            let mut outer = vec![0x30, 0x82, (deep.len() >> 8) as u8, deep.len() as u8];
+            outer.extend_from_slice(&deep);
+            deep = outer;
+        }
+        let mut output = Vec::new();
+        match write_der(&mut Writer::new(&mut output), Identifier::universal(UniversalTag::Set), &deep, 0) {
+            Err(Error::Read(reading::Error::NestingTooDeep)) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_enveloped_data() {
+        let der = pem::parse(ENVELOPED_PEM, pem::Mode::Strict).unwrap().into_contents();
+        let content_info = ContentInfo::from_ber(&der).unwrap();
+        assert!(content_info.signed_data().is_err());
+
+        let enveloped_data = content_info.enveloped_data().unwrap();
+        assert_eq!(enveloped_data.version, 0);
+        assert_eq!(enveloped_data.originator_info, None);
+        assert_eq!(enveloped_data.recipient_infos.len(), 1);
+        match enveloped_data.recipient_infos[0] {
+            RecipientInfo::KeyTrans(ref ktri) => {
+                assert_eq!(ktri.version, 0);
+                match ktri.rid {
+                    RecipientIdentifier::IssuerAndSerialNumber { ref issuer, serial_number } => {
+                        assert_eq!(issuer.to_string(), "CN=RSA Recipient");
+                        assert_eq!(serial_number, [7]);
+                    }
+                    _ => panic!("unexpected recipient identifier"),
+                }
+                assert_eq!(ktri.key_encryption_algorithm.algorithm.components(), [1, 2, 840, 113549, 1, 1, 1]);
+                assert_eq!(ktri.encrypted_key.len(), 64);
+            }
+            _ => panic!("unexpected recipient info"),
+        }
+
+        let eci = &enveloped_data.encrypted_content_info;
+        assert_eq!(eci.content_type.components(), oid::DATA);
+        assert_eq!(eci.content_encryption_algorithm.algorithm.components(), [2, 16, 840, 1, 101, 3, 4, 1, 2]);
+        let encrypted_content = eci.encrypted_content.as_ref().unwrap();
+        assert_eq!(encrypted_content.len(), 16);
+        assert_eq!(encrypted_content[..2], [0x7f, 0xc3]);
+        assert!(enveloped_data.unprotected_attrs.is_empty());
+    }
+}
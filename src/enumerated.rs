@@ -0,0 +1,127 @@
+//! The mapping between ENUMERATED types and Rust enums.
+//!
+//! The contents of an ENUMERATED value are encoded as for INTEGER, so `Asn1Reader::read_enumerated` and
+//! `Writer::write_enumerated` use the codec in `integer` and convert through this trait. `i64` implements it for
+//! callers that want the raw value.
+
+
+/// A Rust type for the values of an ENUMERATED type.
+///
+/// `from_i64` returns `None` for a value that has no variant, which readers reject. For an extensible enumeration,
+/// whose later versions may add values, give the type a variant such as `Unknown(i64)` that holds any other value,
+/// so that `from_i64` never fails and the value is written back unchanged.
+pub trait Enumerated: Sized {
+    fn from_i64(v: i64) -> Option<Self>;
+    fn into_i64(self) -> i64;
+}
+
+impl Enumerated for i64 {
+    fn from_i64(v: i64) -> Option<Self> {
+        Some(v)
+    }
+
+    fn into_i64(self) -> i64 {
+        self
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use reading::{self, Asn1Reader};
+    use writing::der::Writer;
+
+    /// `Color ::= ENUMERATED { red(0), green(1), blue(5), ... }`
+    #[derive(Copy,Clone,Debug,PartialEq,Eq)]
+    enum Color {
+        Red,
+        Green,
+        Blue,
+        Unknown(i64),
+    }
+
+    impl Enumerated for Color {
+        fn from_i64(v: i64) -> Option<Self> {
+            Some(match v {
+                0 => Color::Red,
+                1 => Color::Green,
+                5 => Color::Blue,
+                v => Color::Unknown(v),
+            })
+        }
+
+        fn into_i64(self) -> i64 {
+            match self {
+                Color::Red => 0,
+                Color::Green => 1,
+                Color::Blue => 5,
+                Color::Unknown(v) => v,
+            }
+        }
+    }
+
+    /// `Answer ::= ENUMERATED { no(0), yes(1) }`
+    #[derive(Copy,Clone,Debug,PartialEq,Eq)]
+    enum Answer {
+        No,
+        Yes,
+    }
+
+    impl Enumerated for Answer {
+        fn from_i64(v: i64) -> Option<Self> {
+            match v {
+                0 => Some(Answer::No),
+                1 => Some(Answer::Yes),
+                _ => None,
+            }
+        }
+
+        fn into_i64(self) -> i64 {
+            self as i64
+        }
+    }
+
+    #[test]
+    fn test_enumerated() {
+        let mut output = Vec::new();
+        {
+            let mut w = Writer::new(&mut output);
+            w.write_enumerated(Color::Blue).unwrap();
+            w.write_enumerated(Color::Unknown(-200)).unwrap();
+            w.write_enumerated(Answer::Yes).unwrap();
+            w.write_enumerated(128i64).unwrap();
+            w.write_null().unwrap();
+        }
+        assert_eq!(output, [0x0a, 0x01, 0x05, 0x0a, 0x02, 0xff, 0x38, 0x0a, 0x01, 0x01, 0x0a, 0x02, 0x00, 0x80,
+            0x05, 0x00]);
+
+        let mut r = Asn1Reader::new(&output);
+        assert_eq!(r.read_enumerated::<Color>().unwrap(), Color::Blue);
+        assert_eq!(r.read_enumerated::<Color>().unwrap(), Color::Unknown(-200));
+        assert_eq!(r.read_enumerated::<Answer>().unwrap(), Answer::Yes);
+        assert_eq!(r.read_enumerated::<i64>().unwrap(), 128);
+        r.read_null().unwrap();
+        assert!(r.expect_end().is_ok());
+
+        let input = [0x0a, 0x01, 0x02, 0x0a, 0x02, 0x00, 0x01, 0x02, 0x01, 0x01, 0x05, 0x01, 0x00];
+        let mut r = Asn1Reader::new(&input);
+        match r.read_enumerated::<Answer>() {
+            Err(reading::Error::UnknownEnumerated(2)) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+        match r.read_enumerated::<i64>() {
+            Err(reading::Error::Integer(_)) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+        match r.read_enumerated::<i64>() {
+            Err(reading::Error::UnexpectedTag(_)) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+        let mut r = Asn1Reader::new(&input[10..]);
+        match r.read_null() {
+            Err(reading::Error::InvalidNull) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+    }
+}
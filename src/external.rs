@@ -0,0 +1,521 @@
+//! The EXTERNAL, EMBEDDED PDV and CHARACTER STRING types (X.680 clauses 37, 36 and 44), which carry a value of
+//! another abstract syntax along with how to identify it.
+//!
+//! EXTERNAL is encoded in the form X.690 8.18 gives, with a direct reference OID, an indirect reference
+//! presentation context or both, and the value as a single ASN.1 type, as octets or as bits. EMBEDDED PDV and
+//! CHARACTER STRING share the `Identification` CHOICE and are encoded as their associated SEQUENCE types with
+//! automatic tags, in which the data-value-descriptor is always absent.
+
+use bit_string::BitString;
+use identifier::{Class, Identifier, UniversalTag};
+use integer;
+use object_identifier::ObjectIdentifier;
+use reading::{self, Asn1Reader};
+use std;
+use std::borrow::Cow;
+use writing::der::{self, Writer};
+
+
+#[derive(Debug)]
+pub enum Error {
+    Read(reading::Error),
+    InvalidValue,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Read(ref e) => e.fmt(f),
+            Error::InvalidValue => write!(f, "invalid value"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Read(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reading::Error> for Error {
+    fn from(e: reading::Error) -> Error {
+        Error::Read(e)
+    }
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// The identification CHOICE of EMBEDDED PDV and CHARACTER STRING.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Identification {
+    Syntaxes {
+        abstract_syntax: ObjectIdentifier,
+        transfer_syntax: ObjectIdentifier,
+    },
+    Syntax(ObjectIdentifier),
+    PresentationContextId(i64),
+    ContextNegotiation {
+        presentation_context_id: i64,
+        transfer_syntax: ObjectIdentifier,
+    },
+    TransferSyntax(ObjectIdentifier),
+    /// The abstract and transfer syntaxes are fixed by the designer of the containing type.
+    Fixed,
+}
+
+impl Identification {
+    fn read(r: &mut Asn1Reader) -> Result<Self> {
+        let (identifier, b) = r.next()?.ok_or(reading::Error::UnexpectedEndOfData)?;
+        let v = if identifier == Identifier::context_specific(true, 0) {
+            let mut r = Asn1Reader::new(b);
+            let abstract_syntax = read_oid(r.read_expected(Identifier::context_specific(false, 0))?)?;
+            let transfer_syntax = read_oid(r.read_expected(Identifier::context_specific(false, 1))?)?;
+            r.expect_end()?;
+            Identification::Syntaxes {
+                abstract_syntax,
+                transfer_syntax,
+            }
+        } else if identifier == Identifier::context_specific(false, 1) {
+            Identification::Syntax(read_oid(b)?)
+        } else if identifier == Identifier::context_specific(false, 2) {
+            Identification::PresentationContextId(read_i64(b)?)
+        } else if identifier == Identifier::context_specific(true, 3) {
+            let mut r = Asn1Reader::new(b);
+            let presentation_context_id = read_i64(r.read_expected(Identifier::context_specific(false, 0))?)?;
+            let transfer_syntax = read_oid(r.read_expected(Identifier::context_specific(false, 1))?)?;
+            r.expect_end()?;
+            Identification::ContextNegotiation {
+                presentation_context_id,
+                transfer_syntax,
+            }
+        } else if identifier == Identifier::context_specific(false, 4) {
+            Identification::TransferSyntax(read_oid(b)?)
+        } else if identifier == Identifier::context_specific(false, 5) && b.is_empty() {
+            Identification::Fixed
+        } else {
+            return Err(Error::InvalidValue);
+        };
+        Ok(v)
+    }
+
+    fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        match *self {
+            Identification::Syntaxes { ref abstract_syntax, ref transfer_syntax } => {
+                w.write_constructed(Identifier::context_specific(true, 0), |w| {
+                    w.write_implicit_object_identifier(0, abstract_syntax)?;
+                    w.write_implicit_object_identifier(1, transfer_syntax)?;
+                    Ok(())
+                })
+            }
+            Identification::Syntax(ref oid) => {
+                w.write_implicit_object_identifier(1, oid)
+            }
+            Identification::PresentationContextId(id) => {
+                w.write_tag_and_data(Identifier::context_specific(false, 2), &integer::from_i64(id))
+            }
+            Identification::ContextNegotiation { presentation_context_id, ref transfer_syntax } => {
+                w.write_constructed(Identifier::context_specific(true, 3), |w| {
+                    let id = integer::from_i64(presentation_context_id);
+                    w.write_tag_and_data(Identifier::context_specific(false, 0), &id)?;
+                    w.write_implicit_object_identifier(1, transfer_syntax)?;
+                    Ok(())
+                })
+            }
+            Identification::TransferSyntax(ref oid) => {
+                w.write_implicit_object_identifier(4, oid)
+            }
+            Identification::Fixed => w.write_tag_and_data(Identifier::context_specific(false, 5), &[]),
+        }
+    }
+}
+
+
+/// The encoding CHOICE of EXTERNAL.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum ExternalEncoding<'a> {
+    /// The complete encoding of a single value, including its tag and length.
+    SingleAsn1Type(&'a [u8]),
+    /// The value as octets, joined if BER constructed it from segments.
+    OctetAligned(Cow<'a, [u8]>),
+    /// The value as bits, held as the contents octets of a BIT STRING (the unused-bits octet, then the bits) and
+    /// joined if BER constructed it from segments. `BitString::from_bytes` decodes them.
+    Arbitrary(Cow<'a, [u8]>),
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct External<'a> {
+    pub direct_reference: Option<ObjectIdentifier>,
+    pub indirect_reference: Option<i64>,
+    pub data_value_descriptor: Option<&'a str>,
+    pub encoding: ExternalEncoding<'a>,
+}
+
+impl<'a> External<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = External::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = Asn1Reader::new(r.read_expected(Identifier::universal(UniversalTag::External))?);
+        let direct_reference = match r.peek()? {
+            Some(i) if i == Identifier::universal(UniversalTag::ObjectIdentifier) => Some(r.read_object_identifier()?),
+            _ => None,
+        };
+        let indirect_reference = match r.peek()? {
+            Some(i) if i == Identifier::universal(UniversalTag::Integer) => Some(r.read_i64()?),
+            _ => None,
+        };
+        let data_value_descriptor = match r.read_optional(Identifier::universal(UniversalTag::ObjectDescriptor))? {
+            Some(b) => Some(read_descriptor(b)?),
+            None => None,
+        };
+        let encoding = match r.next()? {
+            Some((i, b)) if i == Identifier::context_specific(true, 0) => {
+                let mut r = Asn1Reader::new(b);
+                let v = r.read_raw()?;
+                r.expect_end()?;
+                ExternalEncoding::SingleAsn1Type(v)
+            }
+            Some((i, b)) if i.class() == Class::ContextSpecific && i.tag() == 1 => {
+                ExternalEncoding::OctetAligned(reading::join_segments(i, b, UniversalTag::OctetString.into())?)
+            }
+            Some((i, b)) if i.class() == Class::ContextSpecific && i.tag() == 2 => {
+                let bits = reading::join_bit_string_segments(i, b)?;
+                BitString::from_bytes(&bits).map_err(reading::Error::from)?;
+                ExternalEncoding::Arbitrary(bits)
+            }
+            _ => return Err(Error::InvalidValue),
+        };
+        r.expect_end()?;
+        Ok(External {
+            direct_reference,
+            indirect_reference,
+            data_value_descriptor,
+            encoding,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_constructed(Identifier::universal(UniversalTag::External), |w| {
+            if let Some(ref oid) = self.direct_reference {
+                w.write_object_identifier(oid)?;
+            }
+            if let Some(id) = self.indirect_reference {
+                w.write_i64(id)?;
+            }
+            if let Some(descriptor) = self.data_value_descriptor {
+                w.write_tag_and_data(Identifier::universal(UniversalTag::ObjectDescriptor), descriptor.as_bytes())?;
+            }
+            match self.encoding {
+                ExternalEncoding::SingleAsn1Type(encoded) => w.write_explicit(0, |w| {
+                    w.write_raw(encoded)?;
+                    Ok(())
+                })?,
+                ExternalEncoding::OctetAligned(ref b) => w.write_tag_and_data(Identifier::context_specific(false, 1), b)?,
+                ExternalEncoding::Arbitrary(ref bits) => {
+                    BitString::from_bytes(bits).map_err(|_| der::Error::InvalidValue)?;
+                    w.write_tag_and_data(Identifier::context_specific(false, 2), bits)?
+                }
+            };
+            Ok(())
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+
+    /// The identification the references amount to, as X.680 maps EXTERNAL onto its associated type: a direct
+    /// reference alone is the abstract syntax, an indirect reference alone the presentation context, and both
+    /// together a context negotiation. Returns `None` if there is neither.
+    pub fn identification(&self) -> Option<Identification> {
+        match (&self.direct_reference, self.indirect_reference) {
+            (Some(oid), None) => Some(Identification::Syntax(oid.clone())),
+            (None, Some(id)) => Some(Identification::PresentationContextId(id)),
+            (Some(oid), Some(id)) => Some(Identification::ContextNegotiation {
+                presentation_context_id: id,
+                transfer_syntax: oid.clone(),
+            }),
+            (None, None) => None,
+        }
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct EmbeddedPdv<'a> {
+    pub identification: Identification,
+    /// The data value, joined if BER constructed it from segments.
+    pub data_value: Cow<'a, [u8]>,
+}
+
+impl<'a> EmbeddedPdv<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = EmbeddedPdv::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let (identification, data_value) = read_identified(r, UniversalTag::EmbeddedPdv)?;
+        Ok(EmbeddedPdv {
+            identification,
+            data_value,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        write_identified(w, UniversalTag::EmbeddedPdv, &self.identification, &self.data_value)
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+/// A CHARACTER STRING, whose string value is encoded in the character abstract and transfer syntaxes that its
+/// identification gives.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct CharacterString<'a> {
+    pub identification: Identification,
+    /// The string value, joined if BER constructed it from segments.
+    pub string_value: Cow<'a, [u8]>,
+}
+
+impl<'a> CharacterString<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = CharacterString::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let (identification, string_value) = read_identified(r, UniversalTag::CharacterString)?;
+        Ok(CharacterString {
+            identification,
+            string_value,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        write_identified(w, UniversalTag::CharacterString, &self.identification, &self.string_value)
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+/// Reads the `[0]` identification and `[2]` value of EMBEDDED PDV or CHARACTER STRING. The `[1]`
+/// data-value-descriptor between them must be absent.
+fn read_identified<'a>(r: &mut Asn1Reader<'a>, tag: UniversalTag) -> Result<(Identification, Cow<'a, [u8]>)> {
+    let mut r = Asn1Reader::new(r.read_expected(Identifier::universal(tag))?);
+    let mut identification = Asn1Reader::new(r.read_expected(Identifier::context_specific(true, 0))?);
+    let v = Identification::read(&mut identification)?;
+    identification.expect_end()?;
+    let value = match r.next()? {
+        Some((i, b)) if i.class() == Class::ContextSpecific && i.tag() == 2 => {
+            reading::join_segments(i, b, UniversalTag::OctetString.into())?
+        }
+        _ => return Err(Error::InvalidValue),
+    };
+    r.expect_end()?;
+    Ok((v, value))
+}
+
+fn write_identified<W: std::io::Write>(w: &mut Writer<W>, tag: UniversalTag, identification: &Identification,
+    value: &[u8]) -> der::Result<usize>
+{
+    w.write_constructed(Identifier::universal(tag), |w| {
+        w.write_explicit(0, |w| {
+            identification.write(w)?;
+            Ok(())
+        })?;
+        w.write_tag_and_data(Identifier::context_specific(false, 2), value)?;
+        Ok(())
+    })
+}
+
+fn read_oid(b: &[u8]) -> Result<ObjectIdentifier> {
+    Ok(ObjectIdentifier::from_bytes(b).map_err(reading::Error::from)?)
+}
+
+fn read_i64(b: &[u8]) -> Result<i64> {
+    Ok(integer::to_i64(b).map_err(reading::Error::from)?)
+}
+
+/// An ObjectDescriptor is a GraphicString, so only printable ASCII and space are accepted.
+fn read_descriptor(b: &[u8]) -> Result<&str> {
+    if !b.iter().all(|c| (0x20..0x7f).contains(c)) {
+        return Err(Error::InvalidValue);
+    }
+    Ok(std::str::from_utf8(b).unwrap())
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_external() {
+        let b = [0x28, 0x0c, 0x06, 0x03, 0x2a, 0x03, 0x04, 0xa0, 0x05, 0x30, 0x03, 0x02, 0x01, 0x07];
+        let v = External::from_der(&b).unwrap();
+        assert_eq!(v, External {
+            direct_reference: Some(ObjectIdentifier::from_components(&[1, 2, 3, 4])),
+            indirect_reference: None,
+            data_value_descriptor: None,
+            encoding: ExternalEncoding::SingleAsn1Type(&b[9..]),
+        });
+        assert_eq!(v.identification(), Some(Identification::Syntax(ObjectIdentifier::from_components(&[1, 2, 3, 4]))));
+        assert_eq!(v.to_der().unwrap(), b);
+
+        let b = [0x28, 0x0d, 0x02, 0x01, 0x03, 0x07, 0x04, b'd', b'e', b's', b'c', 0x81, 0x02, 0xca, 0xfe];
+        let v = External::from_der(&b).unwrap();
+        assert_eq!(v.indirect_reference, Some(3));
+        assert_eq!(v.data_value_descriptor, Some("desc"));
+        assert_eq!(v.encoding, ExternalEncoding::OctetAligned(Cow::Borrowed(&[0xca, 0xfe])));
+        assert_eq!(v.identification(), Some(Identification::PresentationContextId(3)));
+        assert_eq!(v.to_der().unwrap(), b);
+
+        // BER may split the octet-aligned value into OCTET STRING segments.
+        let ber = [0x28, 0x80, 0x02, 0x01, 0x03, 0xa1, 0x80, 0x04, 0x01, 0xca, 0x04, 0x01, 0xfe, 0x00, 0x00, 0x00, 0x00];
+        let v = External::from_der(&ber).unwrap();
+        assert_eq!(v.encoding, ExternalEncoding::OctetAligned(Cow::Owned(vec![0xca, 0xfe])));
+        assert_eq!(v.to_der().unwrap(), [0x28, 0x07, 0x02, 0x01, 0x03, 0x81, 0x02, 0xca, 0xfe]);
+
+        let v = External {
+            direct_reference: Some(ObjectIdentifier::from_components(&[2, 1, 1])),
+            indirect_reference: Some(1),
+            data_value_descriptor: None,
+            encoding: ExternalEncoding::Arbitrary(Cow::Borrowed(&[0x04, 0xf0])),
+        };
+        let b = v.to_der().unwrap();
+        assert_eq!(b, [0x28, 0x0b, 0x06, 0x02, 0x51, 0x01, 0x02, 0x01, 0x01, 0x82, 0x02, 0x04, 0xf0]);
+        assert_eq!(External::from_der(&b).unwrap(), v);
+        assert_eq!(v.identification(), Some(Identification::ContextNegotiation {
+            presentation_context_id: 1,
+            transfer_syntax: ObjectIdentifier::from_components(&[2, 1, 1]),
+        }));
+
+        // BER may split the arbitrary value into BIT STRING segments, of which only the last has unused bits.
+        let ber = [0x28, 0x80, 0x02, 0x01, 0x01, 0xa2, 0x80, 0x03, 0x02, 0x00, 0xaa, 0x03, 0x02, 0x04, 0xf0, 0x00, 0x00,
+            0x00, 0x00];
+        let v = External::from_der(&ber).unwrap();
+        assert_eq!(v.encoding, ExternalEncoding::Arbitrary(Cow::Owned(vec![0x04, 0xaa, 0xf0])));
+        assert_eq!(BitString::from_bytes(&[0x04, 0xaa, 0xf0]).unwrap().bit_len(), 12);
+        assert_eq!(v.to_der().unwrap(), [0x28, 0x08, 0x02, 0x01, 0x01, 0x82, 0x03, 0x04, 0xaa, 0xf0]);
+        assert!(External::from_der(&[0x28, 0x0c, 0x02, 0x01, 0x01, 0xa2, 0x08, 0x03, 0x02, 0x04, 0xa0, 0x03, 0x02,
+            0x00, 0xf0]).is_err());
+        let v = External {
+            direct_reference: None,
+            indirect_reference: Some(1),
+            data_value_descriptor: None,
+            encoding: ExternalEncoding::Arbitrary(Cow::Borrowed(&[0x08, 0xff])),
+        };
+        assert!(v.to_der().is_err());
+
+        let cases: &[&[u8]] = &[
+            // No encoding, and an unknown one.
+            &[0x28, 0x05, 0x06, 0x03, 0x2a, 0x03, 0x04],
+            &[0x28, 0x04, 0x83, 0x02, 0xca, 0xfe],
+            // Two values in single-ASN1-type.
+            &[0x28, 0x08, 0xa0, 0x06, 0x05, 0x00, 0x05, 0x00, 0x05, 0x00],
+            // A descriptor with a control character.
+            &[0x28, 0x07, 0x07, 0x01, 0x0a, 0x81, 0x02, 0xca, 0xfe],
+            // Components out of order.
+            &[0x28, 0x0b, 0x02, 0x01, 0x03, 0x06, 0x02, 0x51, 0x01, 0x81, 0x02, 0xca, 0xfe],
+        ];
+        for b in cases {
+            assert!(External::from_der(b).is_err(), "{:02x?}", b);
+        }
+    }
+
+    #[test]
+    fn test_embedded_pdv() {
+        let b = [
+            0x2b, 0x12, 0xa0, 0x0c, 0xa0, 0x0a, 0x80, 0x03, 0x2a, 0x03, 0x04, 0x81, 0x03, 0x2a, 0x03, 0x05,
+            0x82, 0x02, 0x01, 0x02,
+        ];
+        let v = EmbeddedPdv::from_der(&b).unwrap();
+        assert_eq!(v, EmbeddedPdv {
+            identification: Identification::Syntaxes {
+                abstract_syntax: ObjectIdentifier::from_components(&[1, 2, 3, 4]),
+                transfer_syntax: ObjectIdentifier::from_components(&[1, 2, 3, 5]),
+            },
+            data_value: Cow::Borrowed(&[0x01, 0x02]),
+        });
+        assert_eq!(v.to_der().unwrap(), b);
+
+        // A BER constructed data value.
+        let ber = [0x2b, 0x80, 0xa0, 0x02, 0x85, 0x00, 0xa2, 0x80, 0x04, 0x01, 0x01, 0x04, 0x01, 0x02, 0x00, 0x00, 0x00,
+            0x00];
+        assert_eq!(*EmbeddedPdv::from_der(&ber).unwrap().data_value, [0x01, 0x02]);
+
+        let identifications = [
+            Identification::Syntax(ObjectIdentifier::from_components(&[1, 2, 3])),
+            Identification::PresentationContextId(-1),
+            Identification::ContextNegotiation {
+                presentation_context_id: 300,
+                transfer_syntax: ObjectIdentifier::from_components(&[2, 1, 1]),
+            },
+            Identification::TransferSyntax(ObjectIdentifier::from_components(&[2, 1, 1])),
+            Identification::Fixed,
+        ];
+        for identification in &identifications {
+            let v = EmbeddedPdv {
+                identification: identification.clone(),
+                data_value: Cow::Borrowed(&[]),
+            };
+            assert_eq!(EmbeddedPdv::from_der(&v.to_der().unwrap()).unwrap(), v);
+        }
+
+        let cases: &[&[u8]] = &[
+            // A data-value-descriptor, which must be absent.
+            &[0x2b, 0x08, 0xa0, 0x02, 0x85, 0x00, 0x81, 0x00, 0x82, 0x00],
+            // A non-empty fixed, and an unknown alternative.
+            &[0x2b, 0x07, 0xa0, 0x03, 0x85, 0x01, 0x00, 0x82, 0x00],
+            &[0x2b, 0x06, 0xa0, 0x02, 0x86, 0x00, 0x82, 0x00],
+            // Two identifications.
+            &[0x2b, 0x08, 0xa0, 0x04, 0x85, 0x00, 0x85, 0x00, 0x82, 0x00],
+            // The CHARACTER STRING tag.
+            &[0x3d, 0x06, 0xa0, 0x02, 0x85, 0x00, 0x82, 0x00],
+        ];
+        for b in cases {
+            assert!(EmbeddedPdv::from_der(b).is_err(), "{:02x?}", b);
+        }
+    }
+
+    #[test]
+    fn test_character_string() {
+        let b = [0x3d, 0x08, 0xa0, 0x02, 0x85, 0x00, 0x82, 0x02, b'h', b'i'];
+        let v = CharacterString::from_der(&b).unwrap();
+        assert_eq!(v, CharacterString {
+            identification: Identification::Fixed,
+            string_value: Cow::Borrowed(b"hi"),
+        });
+        assert_eq!(v.to_der().unwrap(), b);
+        let ber = [0x3d, 0x0c, 0xa0, 0x02, 0x85, 0x00, 0xa2, 0x06, 0x04, 0x01, b'h', 0x04, 0x01, b'i'];
+        assert_eq!(CharacterString::from_der(&ber).unwrap(), v);
+        assert!(CharacterString::from_der(&[0x2b, 0x06, 0xa0, 0x02, 0x85, 0x00, 0x82, 0x00]).is_err());
+    }
+}
@@ -53,7 +53,7 @@ pub enum UniversalTag {
     External, // 0b01000
     Real, // 0b01001
     Enumerated, // 0b01010
-    EmbeddedPov, // 0b01011
+    EmbeddedPdv, // 0b01011
     Utf8String, // 0b01100
     RelativeOid, // 0b01101
     // 0b01110 reserved
@@ -77,6 +77,10 @@ pub enum UniversalTag {
 }
 
 impl UniversalTag {
+    #[deprecated(note = "renamed to `EmbeddedPdv`")]
+    #[allow(non_upper_case_globals)]
+    pub const EmbeddedPov: UniversalTag = UniversalTag::EmbeddedPdv;
+
     pub fn into_u8(self) -> u8 {
         match self {
             UniversalTag::EndOfContent => 0b00000,
@@ -90,7 +94,7 @@ impl UniversalTag {
             UniversalTag::External => 0b01000,
             UniversalTag::Real => 0b01001,
             UniversalTag::Enumerated => 0b01010,
-            UniversalTag::EmbeddedPov => 0b01011,
+            UniversalTag::EmbeddedPdv => 0b01011,
             UniversalTag::Utf8String => 0b01100,
             UniversalTag::RelativeOid => 0b01101,
             // 0b01110 reserved
@@ -126,7 +130,7 @@ impl UniversalTag {
             0b01000 => UniversalTag::External,
             0b01001 => UniversalTag::Real,
             0b01010 => UniversalTag::Enumerated,
-            0b01011 => UniversalTag::EmbeddedPov,
+            0b01011 => UniversalTag::EmbeddedPdv,
             0b01100 => UniversalTag::Utf8String,
             0b01101 => UniversalTag::RelativeOid,
             0b01110 => return None,
@@ -166,7 +170,7 @@ impl From<UniversalTag> for u8 {
             UniversalTag::External => 0b01000,
             UniversalTag::Real => 0b01001,
             UniversalTag::Enumerated => 0b01010,
-            UniversalTag::EmbeddedPov => 0b01011,
+            UniversalTag::EmbeddedPdv => 0b01011,
             UniversalTag::Utf8String => 0b01100,
             UniversalTag::RelativeOid => 0b01101,
             // 0b01110 reserved
@@ -209,6 +213,25 @@ impl Identifier {
         Identifier(klass, constructed, tag)
     }
 
+    /// A universal class identifier, constructed if DER requires values of `tag` to be constructed.
+    pub fn universal(tag: UniversalTag) -> Self {
+        let constructed = matches!(tag,
+            UniversalTag::Sequence |
+            UniversalTag::Set |
+            UniversalTag::External |
+            UniversalTag::EmbeddedPdv |
+            UniversalTag::CharacterString);
+        Identifier(Class::Universal, constructed, tag.into())
+    }
+
+    pub fn application(constructed: bool, tag: u64) -> Self {
+        Identifier(Class::Application, constructed, tag)
+    }
+
+    pub fn context_specific(constructed: bool, tag: u64) -> Self {
+        Identifier(Class::ContextSpecific, constructed, tag)
+    }
+
     pub fn from_u8(v: u8) -> Option<Self> {
         let class = Class::from_u8(v & 0xc0);
         let constructed = (v & 0x20) != 0;
@@ -242,16 +265,8 @@ impl Identifier {
         if tag < 31 {
             return 1;
         }
-        let mut tag = tag;
-        let mut len = 1;
-        loop {
-            if tag == 0 {
-                break;
-            }
-            len += 1;
-            tag = tag / 127;
-        }
-        len
+        let bits = 64 - tag.leading_zeros() as usize;
+        1 + bits.div_ceil(7)
     }
 
     #[inline]
@@ -262,15 +277,24 @@ impl Identifier {
         v
     }
 
+    /// Writes the identifier octets, using the high-tag-number form (base 128, most significant group first) for
+    /// tags of 31 and above.
     pub fn write_to<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<usize> {
         let tag = self.tag();
+        let class = self.0.into_u8();
+        let constructed = if self.1 { 0x20 } else { 0 };
         if tag < 31 {
-            let class = self.0.into_u8();
-            let constructed = if self.1 { 0x20 } else { 0 };
             let b: u8 = class | constructed | (tag as u8);
             return w.write(&[b]);
         }
-        unimplemented!()
+        let len = self.bytes_len();
+        let mut b = vec![class | constructed | 0x1f];
+        for i in (0..len - 1).rev() {
+            let continuation = if i == 0 { 0 } else { 0x80 };
+            b.push(((tag >> (7 * i)) & 0x7f) as u8 | continuation);
+        }
+        w.write_all(&b)?;
+        Ok(len)
     }
 }
 
@@ -307,4 +331,26 @@ mod test {
             assert_eq!(i.to_bytes(), &[v]);
         }
     }
+
+    #[test]
+    fn test_high_tag_number() {
+        let cases: [(Identifier, &[u8]); 5] = [
+            (Identifier::application(true, 31), &[0x7f, 0x1f]),
+            (Identifier::context_specific(false, 127), &[0x9f, 0x7f]),
+            (Identifier::application(false, 128), &[0x5f, 0x81, 0x00]),
+            (Identifier::new(Class::Private, true, 16383), &[0xff, 0xff, 0x7f]),
+            (Identifier::new(Class::Universal, false, u64::MAX), &[0x1f, 0x81, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]),
+        ];
+        for &(i, bytes) in &cases {
+            assert_eq!(i.bytes_len(), bytes.len());
+            assert_eq!(i.to_bytes(), bytes);
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_embedded_pov_alias() {
+        assert_eq!(UniversalTag::EmbeddedPov, UniversalTag::EmbeddedPdv);
+        assert_eq!(u8::from(UniversalTag::EmbeddedPov), 0b01011);
+    }
 }
@@ -0,0 +1,242 @@
+use std;
+
+
+#[derive(Debug)]
+pub enum Error {
+    Empty,
+    NonMinimal,
+    Overflow,
+    Negative,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Empty => write!(f, "empty integer"),
+            Error::NonMinimal => write!(f, "non-minimal integer encoding"),
+            Error::Overflow => write!(f, "integer overflow"),
+            Error::Negative => write!(f, "negative integer"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// Checks that `b` is a non-empty, minimally encoded two's complement integer.
+pub fn validate(b: &[u8]) -> Result<()> {
+    match b {
+        [] => Err(Error::Empty),
+        [0x00, next, ..] if next & 0x80 == 0 => Err(Error::NonMinimal),
+        [0xff, next, ..] if next & 0x80 != 0 => Err(Error::NonMinimal),
+        _ => Ok(()),
+    }
+}
+
+pub fn is_negative(b: &[u8]) -> bool {
+    b.first().is_some_and(|b| b & 0x80 != 0)
+}
+
+pub fn to_i64(b: &[u8]) -> Result<i64> {
+    validate(b)?;
+    if b.len() > 8 {
+        return Err(Error::Overflow);
+    }
+    let mut v: i64 = if is_negative(b) { -1 } else { 0 };
+    for &byte in b {
+        v = (v << 8) | byte as i64;
+    }
+    Ok(v)
+}
+
+pub fn to_u64(b: &[u8]) -> Result<u64> {
+    let b = to_unsigned_bytes(b)?;
+    if b.len() > 8 {
+        return Err(Error::Overflow);
+    }
+    Ok(b.iter().fold(0u64, |v, &byte| (v << 8) | byte as u64))
+}
+
+/// Returns the magnitude of a non-negative integer as big-endian bytes with any sign octet removed.
+pub fn to_unsigned_bytes(b: &[u8]) -> Result<&[u8]> {
+    validate(b)?;
+    if is_negative(b) {
+        return Err(Error::Negative);
+    }
+    match b {
+        [0x00, rest @ ..] if !rest.is_empty() => Ok(rest),
+        _ => Ok(b),
+    }
+}
+
+pub fn from_i64(v: i64) -> Vec<u8> {
+    let bytes = v.to_be_bytes();
+    let mut start = 0;
+    while start < bytes.len() - 1 {
+        let redundant = (bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0) ||
+            (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0);
+        if !redundant {
+            break;
+        }
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+pub fn from_u64(v: u64) -> Vec<u8> {
+    from_unsigned_bytes(&v.to_be_bytes())
+}
+
+/// Encodes a big-endian magnitude as a non-negative integer, stripping leading zeros and adding a sign octet if
+/// required.
+pub fn from_unsigned_bytes(b: &[u8]) -> Vec<u8> {
+    let start = b.iter().position(|&byte| byte != 0).unwrap_or(b.len());
+    let b = &b[start..];
+    let mut v = Vec::with_capacity(b.len() + 1);
+    if b.first().is_none_or(|byte| byte & 0x80 != 0) {
+        v.push(0);
+    }
+    v.extend_from_slice(b);
+    v
+}
+
+/// Formats a two's complement integer of any size in decimal.
+pub fn to_decimal(b: &[u8]) -> Result<String> {
+    validate(b)?;
+    let negative = is_negative(b);
+    let mut magnitude = b.to_vec();
+    if negative {
+        negate(&mut magnitude);
+    }
+    let mut digits = Vec::new();
+    while magnitude.iter().any(|&byte| byte != 0) {
+        let mut remainder = 0u32;
+        for byte in magnitude.iter_mut() {
+            let v = (remainder << 8) | *byte as u32;
+            *byte = (v / 10) as u8;
+            remainder = v % 10;
+        }
+        digits.push(b'0' + remainder as u8);
+    }
+    if digits.is_empty() {
+        digits.push(b'0');
+    }
+    if negative {
+        digits.push(b'-');
+    }
+    digits.reverse();
+    Ok(String::from_utf8(digits).unwrap())
+}
+
+/// Parses a decimal integer of any size, with an optional leading minus sign, into minimal two's complement octets.
+pub fn from_decimal(s: &str) -> Option<Vec<u8>> {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut magnitude = vec![0u8];
+    for digit in digits.bytes() {
+        let mut carry = (digit - b'0') as u32;
+        for byte in magnitude.iter_mut().rev() {
+            let v = *byte as u32 * 10 + carry;
+            *byte = v as u8;
+            carry = v >> 8;
+        }
+        if carry != 0 {
+            magnitude.insert(0, carry as u8);
+        }
+    }
+    let mut v = from_unsigned_bytes(&magnitude);
+    if negative {
+        negate(&mut v);
+        let redundant = v.windows(2).take_while(|w| w[0] == 0xff && w[1] & 0x80 != 0).count();
+        v.drain(..redundant);
+    }
+    Some(v)
+}
+
+/// Negates a two's complement integer in place, keeping its width.
+fn negate(b: &mut [u8]) {
+    let mut carry = true;
+    for byte in b.iter_mut().rev() {
+        let (v, c) = (!*byte).overflowing_add(carry as u8);
+        *byte = v;
+        carry = c;
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_i64_roundtrip() {
+        let cases: &[(i64, &[u8])] = &[
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (127, &[0x7f]),
+            (128, &[0x00, 0x80]),
+            (256, &[0x01, 0x00]),
+            (-1, &[0xff]),
+            (-128, &[0x80]),
+            (-129, &[0xff, 0x7f]),
+            (i64::MAX, &[0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+            (i64::MIN, &[0x80, 0, 0, 0, 0, 0, 0, 0]),
+        ];
+        for &(v, b) in cases {
+            assert_eq!(from_i64(v), b);
+            assert_eq!(to_i64(b).unwrap(), v);
+        }
+    }
+
+    #[test]
+    fn test_unsigned() {
+        assert_eq!(from_u64(0), [0x00]);
+        assert_eq!(from_u64(0x80), [0x00, 0x80]);
+        assert_eq!(from_u64(u64::MAX), [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(to_u64(&[0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).unwrap(), u64::MAX);
+        assert_eq!(from_unsigned_bytes(&[0x00, 0x00, 0x01]), [0x01]);
+        assert_eq!(to_unsigned_bytes(&[0x00, 0x80]).unwrap(), [0x80]);
+        assert_eq!(to_u64(&[0xff]).unwrap_err().to_string(), "negative integer");
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(to_i64(&[]).unwrap_err().to_string(), "empty integer");
+        assert_eq!(to_i64(&[0x00, 0x7f]).unwrap_err().to_string(), "non-minimal integer encoding");
+        assert_eq!(to_i64(&[0xff, 0x80]).unwrap_err().to_string(), "non-minimal integer encoding");
+        assert_eq!(to_i64(&[0x01, 0, 0, 0, 0, 0, 0, 0, 0]).unwrap_err().to_string(), "integer overflow");
+    }
+
+    #[test]
+    fn test_decimal() {
+        let cases: &[(&str, &[u8])] = &[
+            ("0", &[0x00]),
+            ("127", &[0x7f]),
+            ("128", &[0x00, 0x80]),
+            ("-1", &[0xff]),
+            ("-128", &[0x80]),
+            ("-129", &[0xff, 0x7f]),
+            ("-256", &[0xff, 0x00]),
+            ("18446744073709551616", &[0x01, 0, 0, 0, 0, 0, 0, 0, 0]),
+            ("-18446744073709551616", &[0xff, 0, 0, 0, 0, 0, 0, 0, 0]),
+        ];
+        for &(s, b) in cases {
+            assert_eq!(from_decimal(s).unwrap(), b);
+            assert_eq!(to_decimal(b).unwrap(), s);
+        }
+        assert_eq!(from_decimal("-0").unwrap(), [0x00]);
+        assert_eq!(from_decimal("007").unwrap(), [0x07]);
+        assert!(from_decimal("").is_none());
+        assert!(from_decimal("-").is_none());
+        assert!(from_decimal("1e3").is_none());
+        assert!(to_decimal(&[0x00, 0x01]).is_err());
+    }
+}
@@ -0,0 +1,765 @@
+//! JSON Encoding Rules (X.697).
+//!
+//! `Json` is a minimal JSON document model with a parser and a compact printer. Types with a schema convert
+//! to and from it through `ToJer` and `FromJer`, implemented here for the primitive types and by record types for
+//! themselves, usually as a JSON object with one member for each present component. The helpers below give the
+//! X.697 forms of the types that have no direct Rust counterpart: OCTET STRING as a hexadecimal string and BIT
+//! STRING as `{"value": hex, "length": bits}`, or as a plain hexadecimal string when its size is fixed.
+//!
+//! Without a schema, `from_value` converts a `value::Value` tree, choosing each representation from the value's
+//! universal tag. `to_value` converts back, but since JSON does not say which ASN.1 type a value has it picks one
+//! for each kind of JSON value: strings become UTF8String, numbers INTEGER or, with a fraction or exponent, REAL,
+//! arrays SEQUENCE OF and objects SEQUENCE values whose components are `Value::Named` with the member names.
+
+use identifier::{Identifier, UniversalTag};
+use integer;
+use object_identifier::ObjectIdentifier;
+use real;
+use std;
+use std::fmt::Write;
+use time::DateTime;
+use value::{self, Value};
+
+
+#[derive(Debug)]
+pub enum Error {
+    /// The input is not valid JSON, at the given byte offset.
+    Syntax(usize),
+    NestingTooDeep,
+    /// A JSON value does not have the form its ASN.1 type needs.
+    InvalidValue,
+    MissingMember(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Syntax(pos) => write!(f, "JSON syntax error at offset {}", pos),
+            Error::NestingTooDeep => write!(f, "nesting too deep"),
+            Error::InvalidValue => write!(f, "invalid value"),
+            Error::MissingMember(ref name) => write!(f, "missing member \"{}\"", name),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// A JSON value. Numbers keep their text so that INTEGER values of any size survive, and object members keep
+/// their order.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Json {
+    Null,
+    Boolean(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(s: &str) -> Result<Json> {
+        let mut p = Parser {
+            b: s.as_bytes(),
+            pos: 0,
+        };
+        let v = p.parse_value(0)?;
+        p.skip_whitespace();
+        if p.pos != s.len() {
+            return Err(Error::Syntax(p.pos));
+        }
+        Ok(v)
+    }
+
+    /// The member `name` of an object.
+    pub fn get(&self, name: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref members) => members.iter().find(|m| m.0 == name).map(|m| &m.1),
+            _ => None,
+        }
+    }
+
+    /// The member `name` of an object, which must be present.
+    pub fn require(&self, name: &str) -> Result<&Json> {
+        self.get(name).ok_or_else(|| Error::MissingMember(name.to_string()))
+    }
+
+    /// Converts the member `name` of an object, which must be present.
+    pub fn member<T: FromJer>(&self, name: &str) -> Result<T> {
+        T::from_jer(self.require(name)?)
+    }
+
+    /// Converts the member `name` of an object, as for an OPTIONAL component.
+    pub fn optional_member<T: FromJer>(&self, name: &str) -> Result<Option<T>> {
+        self.get(name).map(T::from_jer).transpose()
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Json::Null => write!(f, "null"),
+            Json::Boolean(b) => write!(f, "{}", b),
+            Json::Number(ref n) => write!(f, "{}", n),
+            Json::String(ref s) => write_string(f, s),
+            Json::Array(ref items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    item.fmt(f)?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(ref members) => {
+                f.write_char('{')?;
+                for (i, (name, v)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, name)?;
+                    f.write_char(':')?;
+                    v.fmt(f)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string<W: Write>(f: &mut W, s: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+
+/// Conversion of a value of an ASN.1 type to its JER form.
+pub trait ToJer {
+    fn to_jer(&self) -> Json;
+}
+
+/// Conversion of the JER form of an ASN.1 value back to the value.
+pub trait FromJer: Sized {
+    fn from_jer(j: &Json) -> Result<Self>;
+}
+
+impl ToJer for bool {
+    fn to_jer(&self) -> Json {
+        Json::Boolean(*self)
+    }
+}
+
+impl FromJer for bool {
+    fn from_jer(j: &Json) -> Result<Self> {
+        match *j {
+            Json::Boolean(b) => Ok(b),
+            _ => Err(Error::InvalidValue),
+        }
+    }
+}
+
+macro_rules! jer_integer {
+    ($($t:ty),*) => {$(
+        impl ToJer for $t {
+            fn to_jer(&self) -> Json {
+                Json::Number(self.to_string())
+            }
+        }
+
+        impl FromJer for $t {
+            fn from_jer(j: &Json) -> Result<Self> {
+                match *j {
+                    Json::Number(ref n) => n.parse().map_err(|_| Error::InvalidValue),
+                    _ => Err(Error::InvalidValue),
+                }
+            }
+        }
+    )*}
+}
+
+jer_integer!(i8, i16, i32, i64, u8, u16, u32, u64, usize);
+
+impl ToJer for str {
+    fn to_jer(&self) -> Json {
+        Json::String(self.to_string())
+    }
+}
+
+impl ToJer for String {
+    fn to_jer(&self) -> Json {
+        Json::String(self.clone())
+    }
+}
+
+impl FromJer for String {
+    fn from_jer(j: &Json) -> Result<Self> {
+        j.as_str().map(str::to_string).ok_or(Error::InvalidValue)
+    }
+}
+
+/// SEQUENCE OF and SET OF are arrays.
+impl<T: ToJer> ToJer for Vec<T> {
+    fn to_jer(&self) -> Json {
+        Json::Array(self.iter().map(ToJer::to_jer).collect())
+    }
+}
+
+impl<T: FromJer> FromJer for Vec<T> {
+    fn from_jer(j: &Json) -> Result<Self> {
+        match *j {
+            Json::Array(ref items) => items.iter().map(T::from_jer).collect(),
+            _ => Err(Error::InvalidValue),
+        }
+    }
+}
+
+impl ToJer for ObjectIdentifier {
+    fn to_jer(&self) -> Json {
+        Json::String(self.to_string())
+    }
+}
+
+impl FromJer for ObjectIdentifier {
+    fn from_jer(j: &Json) -> Result<Self> {
+        j.as_str().and_then(|s| s.parse().ok()).ok_or(Error::InvalidValue)
+    }
+}
+
+/// Times are GeneralizedTime strings.
+impl ToJer for DateTime {
+    fn to_jer(&self) -> Json {
+        Json::String(String::from_utf8(self.to_generalized_time_bytes()).unwrap())
+    }
+}
+
+impl FromJer for DateTime {
+    fn from_jer(j: &Json) -> Result<Self> {
+        j.as_str().and_then(|s| DateTime::from_generalized_time_bytes(s.as_bytes()).ok()).ok_or(Error::InvalidValue)
+    }
+}
+
+impl ToJer for Value {
+    fn to_jer(&self) -> Json {
+        from_value(self)
+    }
+}
+
+
+pub fn octet_string(b: &[u8]) -> Json {
+    Json::String(to_hex(b))
+}
+
+pub fn read_octet_string(j: &Json) -> Result<Vec<u8>> {
+    j.as_str().and_then(from_hex).ok_or(Error::InvalidValue)
+}
+
+/// The form of a BIT STRING whose size is not fixed: its octets in hexadecimal and its length in bits.
+///
+/// Fails with `InvalidValue` if `data` holds fewer than `bit_len` bits.
+pub fn bit_string(data: &[u8], bit_len: usize) -> Result<Json> {
+    check_bit_len(data, bit_len)?;
+    Ok(to_bit_string(data, bit_len))
+}
+
+fn to_bit_string(data: &[u8], bit_len: usize) -> Json {
+    Json::Object(vec![
+        ("value".to_string(), to_fixed_bit_string(data, bit_len)),
+        ("length".to_string(), Json::Number(bit_len.to_string())),
+    ])
+}
+
+pub fn read_bit_string(j: &Json) -> Result<(Vec<u8>, usize)> {
+    let bit_len: usize = j.member("length")?;
+    let data = read_fixed_bit_string(j.require("value")?, bit_len)?;
+    Ok((data, bit_len))
+}
+
+/// The form of a BIT STRING whose size is fixed, as a hexadecimal string.
+///
+/// Fails with `InvalidValue` if `data` holds fewer than `bit_len` bits.
+pub fn fixed_bit_string(data: &[u8], bit_len: usize) -> Result<Json> {
+    check_bit_len(data, bit_len)?;
+    Ok(to_fixed_bit_string(data, bit_len))
+}
+
+fn check_bit_len(data: &[u8], bit_len: usize) -> Result<()> {
+    if bit_len > data.len() * 8 {
+        return Err(Error::InvalidValue);
+    }
+    Ok(())
+}
+
+fn to_fixed_bit_string(data: &[u8], bit_len: usize) -> Json {
+    let mut octets = data[..bit_len.div_ceil(8)].to_vec();
+    let unused = octets.len() * 8 - bit_len;
+    if let Some(last) = octets.last_mut() {
+        *last &= 0xff << unused;
+    }
+    octet_string(&octets)
+}
+
+/// Reads a BIT STRING of `bit_len` bits in the fixed-size form. The unused bits of the last octet must be zero.
+pub fn read_fixed_bit_string(j: &Json, bit_len: usize) -> Result<Vec<u8>> {
+    let data = read_octet_string(j)?;
+    if data.len() != bit_len.div_ceil(8) || data.last().is_some_and(|b| b & !(0xff << (data.len() * 8 - bit_len)) != 0) {
+        return Err(Error::InvalidValue);
+    }
+    Ok(data)
+}
+
+
+/// Converts a value tree without a schema. SEQUENCE and SET values become objects if every component is
+/// `Value::Named` and arrays otherwise, explicitly tagged values become the value inside them, finite REAL values
+/// become numbers and values of unknown types become the hexadecimal form of their contents.
+pub fn from_value(v: &Value) -> Json {
+    match *v {
+        Value::Boolean(b) => Json::Boolean(b),
+        Value::Integer(ref b) | Value::Enumerated(ref b) => Json::Number(integer::to_decimal(b).unwrap_or_default()),
+        Value::BitString(ref data, bit_len) => to_bit_string(data, std::cmp::min(bit_len, data.len() * 8)),
+        Value::Primitive(identifier, ref b) if identifier == Identifier::universal(UniversalTag::Real) => {
+            match real::to_f64(b) {
+                Ok(v) if v.is_finite() => Json::Number(format!("{:?}", v)),
+                _ => octet_string(b),
+            }
+        }
+        Value::OctetString(ref b) | Value::Primitive(_, ref b) => octet_string(b),
+        Value::Null => Json::Null,
+        Value::ObjectIdentifier(ref oid) => oid.to_jer(),
+        Value::String(_, ref s) => Json::String(s.clone()),
+        Value::Sequence(ref children) | Value::Set(ref children) => from_values(children),
+        Value::Constructed(_, ref children) if children.len() == 1 => from_value(&children[0]),
+        Value::Constructed(_, ref children) => from_values(children),
+        Value::Named(ref name, ref v) => Json::Object(vec![(name.clone(), from_value(v))]),
+    }
+}
+
+fn from_values(children: &[Value]) -> Json {
+    let all_named = !children.is_empty() && children.iter().all(|c| matches!(*c, Value::Named(..)));
+    if !all_named {
+        return Json::Array(children.iter().map(from_value).collect());
+    }
+    Json::Object(children.iter().map(|c| match *c {
+        Value::Named(ref name, ref v) => (name.clone(), from_value(v)),
+        _ => unreachable!(),
+    }).collect())
+}
+
+
+/// Converts a JSON value without a schema, as the module documentation describes. Booleans and `null` become
+/// BOOLEAN and NULL values.
+pub fn to_value(j: &Json) -> Result<Value> {
+    to_value_at(j, 0)
+}
+
+fn to_value_at(j: &Json, depth: usize) -> Result<Value> {
+    if depth >= value::MAX_DEPTH {
+        return Err(Error::NestingTooDeep);
+    }
+    let v = match *j {
+        Json::Null => Value::Null,
+        Json::Boolean(b) => Value::Boolean(b),
+        Json::Number(ref n) if n.contains(['.', 'e', 'E']) => {
+            let v: f64 = n.parse().map_err(|_| Error::InvalidValue)?;
+            if !v.is_finite() {
+                return Err(Error::InvalidValue);
+            }
+            Value::Primitive(Identifier::universal(UniversalTag::Real), real::from_f64(v))
+        }
+        Json::Number(ref n) => Value::Integer(integer::from_decimal(n).ok_or(Error::InvalidValue)?),
+        Json::String(ref s) => Value::String(UniversalTag::Utf8String, s.clone()),
+        Json::Array(ref items) => {
+            Value::Sequence(items.iter().map(|j| to_value_at(j, depth + 1)).collect::<Result<_>>()?)
+        }
+        Json::Object(ref members) => {
+            Value::Sequence(members.iter()
+                .map(|m| Ok(Value::named(m.0.clone(), to_value_at(&m.1, depth + 1)?)))
+                .collect::<Result<_>>()?)
+        }
+    };
+    Ok(v)
+}
+
+
+fn to_hex(b: &[u8]) -> String {
+    b.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    s.as_bytes().chunks(2)
+        .map(|pair| std::str::from_utf8(pair).ok().and_then(|p| u8::from_str_radix(p, 16).ok()))
+        .collect()
+}
+
+
+struct Parser<'a> {
+    b: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn syntax_error(&self) -> Error {
+        Error::Syntax(self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.b.get(self.pos).is_some_and(|b| matches!(*b, b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, s: &[u8]) -> bool {
+        if self.b[self.pos..].starts_with(s) {
+            self.pos += s.len();
+            return true;
+        }
+        false
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<Json> {
+        if depth >= value::MAX_DEPTH {
+            return Err(Error::NestingTooDeep);
+        }
+        self.skip_whitespace();
+        match self.b.get(self.pos) {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.eat(b"}") {
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let name = self.parse_string()?;
+                    self.skip_whitespace();
+                    if !self.eat(b":") {
+                        return Err(self.syntax_error());
+                    }
+                    members.push((name, self.parse_value(depth + 1)?));
+                    self.skip_whitespace();
+                    if self.eat(b"}") {
+                        return Ok(Json::Object(members));
+                    }
+                    if !self.eat(b",") {
+                        return Err(self.syntax_error());
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.eat(b"]") {
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.parse_value(depth + 1)?);
+                    self.skip_whitespace();
+                    if self.eat(b"]") {
+                        return Ok(Json::Array(items));
+                    }
+                    if !self.eat(b",") {
+                        return Err(self.syntax_error());
+                    }
+                }
+            }
+            Some(b'"') => Ok(Json::String(self.parse_string()?)),
+            Some(b'-') | Some(b'0'..=b'9') => self.parse_number(),
+            _ if self.eat(b"true") => Ok(Json::Boolean(true)),
+            _ if self.eat(b"false") => Ok(Json::Boolean(false)),
+            _ if self.eat(b"null") => Ok(Json::Null),
+            _ => Err(self.syntax_error()),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Json> {
+        let start = self.pos;
+        self.eat(b"-");
+        let digits = |p: &mut Parser| {
+            let start = p.pos;
+            while p.b.get(p.pos).is_some_and(u8::is_ascii_digit) {
+                p.pos += 1;
+            }
+            p.pos - start
+        };
+        let int_start = self.pos;
+        let int_len = digits(self);
+        if int_len == 0 || (int_len > 1 && self.b[int_start] == b'0') {
+            return Err(self.syntax_error());
+        }
+        if self.eat(b".") && digits(self) == 0 {
+            return Err(self.syntax_error());
+        }
+        if self.eat(b"e") || self.eat(b"E") {
+            let _ = self.eat(b"+") || self.eat(b"-");
+            if digits(self) == 0 {
+                return Err(self.syntax_error());
+            }
+        }
+        Ok(Json::Number(std::str::from_utf8(&self.b[start..self.pos]).unwrap().to_string()))
+    }
+
+    fn parse_string(&mut self) -> Result<String> {
+        if !self.eat(b"\"") {
+            return Err(self.syntax_error());
+        }
+        let mut s = String::new();
+        loop {
+            let start = self.pos;
+            while self.b.get(self.pos).is_some_and(|b| *b != b'"' && *b != b'\\' && *b >= 0x20) {
+                self.pos += 1;
+            }
+            s.push_str(std::str::from_utf8(&self.b[start..self.pos]).unwrap());
+            match self.b.get(self.pos) {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let c = match self.b.get(self.pos) {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            self.pos += 1;
+                            let unit = self.parse_hex4()?;
+                            let code = if (0xd800..0xdc00).contains(&unit) {
+                                if !self.eat(b"\\u") {
+                                    return Err(self.syntax_error());
+                                }
+                                let low = self.parse_hex4()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(self.syntax_error());
+                                }
+                                0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00)
+                            } else {
+                                unit
+                            };
+                            s.push(std::char::from_u32(code).ok_or_else(|| self.syntax_error())?);
+                            continue;
+                        }
+                        _ => return Err(self.syntax_error()),
+                    };
+                    self.pos += 1;
+                    s.push(c);
+                }
+                _ => return Err(self.syntax_error()),
+            }
+        }
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32> {
+        let digits = self.b.get(self.pos..self.pos + 4).ok_or_else(|| self.syntax_error())?;
+        let v = std::str::from_utf8(digits).ok()
+            .filter(|d| d.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+            .ok_or_else(|| self.syntax_error())?;
+        self.pos += 4;
+        Ok(v)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A record type as it would convert itself: `Sample ::= SEQUENCE { id INTEGER, name UTF8String OPTIONAL,
+    /// flags BIT STRING, digest OCTET STRING (SIZE (4)), algorithm OBJECT IDENTIFIER, issued GeneralizedTime,
+    /// values SEQUENCE OF INTEGER }`.
+    #[derive(Debug,PartialEq)]
+    struct Sample {
+        id: i64,
+        name: Option<String>,
+        flags: (Vec<u8>, usize),
+        digest: Vec<u8>,
+        algorithm: ObjectIdentifier,
+        issued: DateTime,
+        values: Vec<u32>,
+    }
+
+    impl ToJer for Sample {
+        fn to_jer(&self) -> Json {
+            let mut members = vec![("id".to_string(), self.id.to_jer())];
+            if let Some(ref name) = self.name {
+                members.push(("name".to_string(), name.to_jer()));
+            }
+            members.push(("flags".to_string(), bit_string(&self.flags.0, self.flags.1).unwrap()));
+            members.push(("digest".to_string(), octet_string(&self.digest)));
+            members.push(("algorithm".to_string(), self.algorithm.to_jer()));
+            members.push(("issued".to_string(), self.issued.to_jer()));
+            members.push(("values".to_string(), self.values.to_jer()));
+            Json::Object(members)
+        }
+    }
+
+    impl FromJer for Sample {
+        fn from_jer(j: &Json) -> Result<Self> {
+            Ok(Sample {
+                id: j.member("id")?,
+                name: j.optional_member("name")?,
+                flags: read_bit_string(j.require("flags")?)?,
+                digest: read_octet_string(j.require("digest")?)?,
+                algorithm: j.member("algorithm")?,
+                issued: j.member("issued")?,
+                values: j.member("values")?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_derived() {
+        let v = Sample {
+            id: -5,
+            name: Some("caf\u{e9} \"1\"\n".to_string()),
+            flags: (vec![0xa0], 3),
+            digest: vec![0xde, 0xad, 0xbe, 0xef],
+            algorithm: "1.2.840.113549.1.1.11".parse().unwrap(),
+            issued: DateTime::new(2025, 1, 2, 3, 4, 5).unwrap(),
+            values: vec![1, 2],
+        };
+        let s = v.to_jer().to_string();
+        assert_eq!(s, "{\"id\":-5,\"name\":\"caf\u{e9} \\\"1\\\"\\n\",\"flags\":{\"value\":\"A0\",\"length\":3},\
+            \"digest\":\"DEADBEEF\",\"algorithm\":\"1.2.840.113549.1.1.11\",\"issued\":\"20250102030405Z\",\
+            \"values\":[1,2]}");
+        assert_eq!(Sample::from_jer(&Json::parse(&s).unwrap()).unwrap(), v);
+
+        let j = Json::parse(" { \"id\" : 7, \"flags\": {\"length\": 0, \"value\": \"\"}, \"digest\": \"00ff00ff\", \
+            \"algorithm\": \"2.5.4.3\", \"issued\": \"20250102030405Z\", \"values\": [] } ").unwrap();
+        let parsed = Sample::from_jer(&j).unwrap();
+        assert_eq!(parsed.name, None);
+        assert_eq!(parsed.flags, (vec![], 0));
+        assert_eq!(parsed.digest, [0x00, 0xff, 0x00, 0xff]);
+
+        match Sample::from_jer(&Json::parse("{\"id\": 1}").unwrap()) {
+            Err(Error::MissingMember(ref name)) if name == "flags" || name == "digest" => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+        assert!(read_bit_string(&Json::parse("{\"value\": \"A1\", \"length\": 3}").unwrap()).is_err());
+        assert!(read_octet_string(&Json::String("+f+f".to_string())).is_err());
+        assert!(read_octet_string(&Json::String("0g".to_string())).is_err());
+        assert_eq!(read_octet_string(&Json::String("0aF0".to_string())).unwrap(), [0x0a, 0xf0]);
+        assert!(i32::from_jer(&Json::parse("1.5").unwrap()).is_err());
+        assert!(u8::from_jer(&Json::parse("256").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_bit_string_too_long() {
+        assert!(fixed_bit_string(&[0xff], 9).is_err());
+        assert!(bit_string(&[], 1).is_err());
+        assert_eq!(fixed_bit_string(&[0xff], 3).unwrap(), Json::String("E0".to_string()));
+    }
+
+    #[test]
+    fn test_from_value() {
+        let v = Value::Sequence(vec![
+            Value::Boolean(true),
+            Value::Integer(integer::from_decimal("-18446744073709551616").unwrap()),
+            Value::BitString(vec![0xa0], 3),
+            Value::OctetString(vec![0x01, 0xab]),
+            Value::Null,
+            Value::ObjectIdentifier("2.5.4.3".parse().unwrap()),
+            Value::String(UniversalTag::UtcTime, "250101120000Z".to_string()),
+            Value::Constructed(Identifier::context_specific(true, 0), vec![Value::integer(2)]),
+            Value::Primitive(Identifier::context_specific(false, 1), vec![0xca, 0xfe]),
+            Value::Set(vec![]),
+        ]);
+        assert_eq!(from_value(&v).to_string(), "[true,-18446744073709551616,{\"value\":\"A0\",\"length\":3},\"01AB\",\
+            null,\"2.5.4.3\",\"250101120000Z\",2,\"CAFE\",[]]");
+
+        let named = Value::Sequence(vec![
+            Value::named("version", Value::integer(1)),
+            Value::named("subject", Value::Sequence(vec![Value::String(UniversalTag::Utf8String, "x".to_string())])),
+        ]);
+        assert_eq!(named.to_jer().to_string(), "{\"version\":1,\"subject\":[\"x\"]}");
+        assert_eq!(from_value(&Value::named("choice", Value::Null)).to_string(), "{\"choice\":null}");
+    }
+
+    #[test]
+    fn test_to_value() {
+        let j = Json::parse("{\"version\": 2, \"big\": -18446744073709551616, \"ratio\": -0.5e+3, \"name\": \"x\", \
+            \"ok\": true, \"none\": null, \"items\": [1, \"a\", []], \"empty\": {}}").unwrap();
+        let v = to_value(&j).unwrap();
+        assert_eq!(v, Value::Sequence(vec![
+            Value::named("version", Value::integer(2)),
+            Value::named("big", Value::Integer(integer::from_decimal("-18446744073709551616").unwrap())),
+            Value::named("ratio", Value::Primitive(Identifier::universal(UniversalTag::Real), real::from_f64(-500.0))),
+            Value::named("name", Value::String(UniversalTag::Utf8String, "x".to_string())),
+            Value::named("ok", Value::Boolean(true)),
+            Value::named("none", Value::Null),
+            Value::named("items", Value::Sequence(vec![
+                Value::integer(1),
+                Value::String(UniversalTag::Utf8String, "a".to_string()),
+                Value::Sequence(vec![]),
+            ])),
+            Value::named("empty", Value::Sequence(vec![])),
+        ]));
+        assert_eq!(from_value(&v).to_string(), "{\"version\":2,\"big\":-18446744073709551616,\"ratio\":-500.0,\
+            \"name\":\"x\",\"ok\":true,\"none\":null,\"items\":[1,\"a\",[]],\"empty\":[]}");
+        assert_eq!(to_value(&from_value(&v)).unwrap(), v);
+        assert!(v.to_der().is_ok());
+
+        assert!(to_value(&Json::Number("1e999".to_string())).is_err());
+        let mut deep = Json::Null;
+        for _ in 0..value::MAX_DEPTH {
+            deep = Json::Array(vec![deep]);
+        }
+        match to_value(&deep) {
+            Err(Error::NestingTooDeep) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Json::parse("[1, -0.5e+3, \"\\u00e9\\ud83d\\ude00\\/\", {}, [], null, false]").unwrap(),
+            Json::Array(vec![
+                Json::Number("1".to_string()),
+                Json::Number("-0.5e+3".to_string()),
+                Json::String("\u{e9}\u{1f600}/".to_string()),
+                Json::Object(vec![]),
+                Json::Array(vec![]),
+                Json::Null,
+                Json::Boolean(false),
+            ]));
+        let cases = ["", "[1,]", "{\"a\" 1}", "01", "1.", "-", "\"\\ud800\"", "\"a", "\"\u{1}\"", "[1] x", "tru"];
+        for s in &cases {
+            assert!(Json::parse(s).is_err(), "{}", s);
+        }
+        match Json::parse(&"[".repeat(100)) {
+            Err(Error::NestingTooDeep) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+    }
+}
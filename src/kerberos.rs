@@ -0,0 +1,1090 @@
+//! Kerberos V5 messages (RFC 4120).
+//!
+//! Every message is an APPLICATION-tagged SEQUENCE whose components carry EXPLICIT context tags. Encrypted parts
+//! are left as `EncryptedData`; decrypting them and decoding their plaintext is up to the caller.
+
+use identifier::{Class, Identifier, UniversalTag};
+use reading::{self, Asn1Reader};
+use std;
+use time::DateTime;
+use writing::der::{self, Writer};
+
+
+/// The protocol version number carried as `pvno` and `tkt-vno`.
+pub const PVNO: i64 = 5;
+
+
+/// Principal name types (RFC 4120 section 6.2).
+pub mod name_type {
+    pub const NT_UNKNOWN: i32 = 0;
+    pub const NT_PRINCIPAL: i32 = 1;
+    pub const NT_SRV_INST: i32 = 2;
+    pub const NT_SRV_HST: i32 = 3;
+    pub const NT_SRV_XHST: i32 = 4;
+    pub const NT_UID: i32 = 5;
+    pub const NT_X500_PRINCIPAL: i32 = 6;
+    pub const NT_SMTP_NAME: i32 = 7;
+    pub const NT_ENTERPRISE: i32 = 10;
+}
+
+/// Pre-authentication data types.
+pub mod pa_type {
+    pub const PA_TGS_REQ: i32 = 1;
+    pub const PA_ENC_TIMESTAMP: i32 = 2;
+    pub const PA_PW_SALT: i32 = 3;
+    pub const PA_ETYPE_INFO: i32 = 11;
+    pub const PA_ETYPE_INFO2: i32 = 19;
+}
+
+/// Encryption types (RFC 3961 and RFC 3962).
+pub mod etype {
+    pub const DES_CBC_MD5: i32 = 3;
+    pub const DES3_CBC_SHA1_KD: i32 = 16;
+    pub const AES128_CTS_HMAC_SHA1_96: i32 = 17;
+    pub const AES256_CTS_HMAC_SHA1_96: i32 = 18;
+    pub const AES128_CTS_HMAC_SHA256_128: i32 = 19;
+    pub const AES256_CTS_HMAC_SHA384_192: i32 = 20;
+    pub const RC4_HMAC: i32 = 23;
+}
+
+/// Error codes carried in KRB-ERROR (RFC 4120 section 7.5.9).
+pub mod error_code {
+    pub const KDC_ERR_NONE: i32 = 0;
+    pub const KDC_ERR_NAME_EXP: i32 = 1;
+    pub const KDC_ERR_C_PRINCIPAL_UNKNOWN: i32 = 6;
+    pub const KDC_ERR_S_PRINCIPAL_UNKNOWN: i32 = 7;
+    pub const KDC_ERR_ETYPE_NOSUPP: i32 = 14;
+    pub const KDC_ERR_CLIENT_REVOKED: i32 = 18;
+    pub const KDC_ERR_KEY_EXPIRED: i32 = 23;
+    pub const KDC_ERR_PREAUTH_FAILED: i32 = 24;
+    pub const KDC_ERR_PREAUTH_REQUIRED: i32 = 25;
+    pub const KRB_AP_ERR_BAD_INTEGRITY: i32 = 31;
+    pub const KRB_AP_ERR_TKT_EXPIRED: i32 = 32;
+    pub const KRB_AP_ERR_TKT_NYV: i32 = 33;
+    pub const KRB_AP_ERR_REPEAT: i32 = 34;
+    pub const KRB_AP_ERR_SKEW: i32 = 37;
+    pub const KRB_AP_ERR_MODIFIED: i32 = 41;
+    pub const KRB_ERR_RESPONSE_TOO_BIG: i32 = 52;
+    pub const KRB_ERR_GENERIC: i32 = 60;
+}
+
+
+#[derive(Debug)]
+pub enum Error {
+    Read(reading::Error),
+    InvalidVersion,
+    InvalidValue,
+    UnexpectedMessageType(i64),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Read(ref e) => e.fmt(f),
+            Error::InvalidVersion => write!(f, "invalid version"),
+            Error::InvalidValue => write!(f, "invalid value"),
+            Error::UnexpectedMessageType(t) => write!(f, "unexpected message type: {}", t),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Read(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reading::Error> for Error {
+    fn from(e: reading::Error) -> Error {
+        Error::Read(e)
+    }
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// Reads `[tag] EXPLICIT` and decodes the single value inside it with `f`.
+fn read_explicit<'a, T, F>(r: &mut Asn1Reader<'a>, tag: u64, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Asn1Reader<'a>) -> Result<T>,
+{
+    let mut inner = Asn1Reader::new(r.read_expected(Identifier::context_specific(true, tag))?);
+    let v = f(&mut inner)?;
+    inner.expect_end()?;
+    Ok(v)
+}
+
+fn read_optional_explicit<'a, T, F>(r: &mut Asn1Reader<'a>, tag: u64, f: F) -> Result<Option<T>>
+    where
+        F: FnOnce(&mut Asn1Reader<'a>) -> Result<T>,
+{
+    if r.peek()? != Some(Identifier::context_specific(true, tag)) {
+        return Ok(None);
+    }
+    read_explicit(r, tag, f).map(Some)
+}
+
+fn read_i32(r: &mut Asn1Reader) -> Result<i32> {
+    let v = r.read_i64()?;
+    if v < i32::MIN as i64 || v > i32::MAX as i64 {
+        return Err(Error::InvalidValue);
+    }
+    Ok(v as i32)
+}
+
+fn read_u32(r: &mut Asn1Reader) -> Result<u32> {
+    let v = r.read_u64()?;
+    if v > u32::MAX as u64 {
+        return Err(Error::InvalidValue);
+    }
+    Ok(v as u32)
+}
+
+fn read_microseconds(r: &mut Asn1Reader) -> Result<u32> {
+    let v = read_u32(r)?;
+    if v > 999_999 {
+        return Err(Error::InvalidValue);
+    }
+    Ok(v)
+}
+
+fn read_kerberos_string<'a>(r: &mut Asn1Reader<'a>) -> Result<&'a str> {
+    let b = r.read_expected(Identifier::universal(UniversalTag::GeneralString))?;
+    std::str::from_utf8(b).map_err(|_| Error::InvalidValue)
+}
+
+fn write_kerberos_string<W: std::io::Write>(w: &mut Writer<W>, s: &str) -> der::Result<usize> {
+    w.write_tag_and_data(Identifier::universal(UniversalTag::GeneralString), s.as_bytes())
+}
+
+fn read_pvno(r: &mut Asn1Reader, tag: u64) -> Result<()> {
+    if read_explicit(r, tag, |r| Ok(r.read_i64()?))? != PVNO {
+        return Err(Error::InvalidVersion);
+    }
+    Ok(())
+}
+
+fn read_msg_type(r: &mut Asn1Reader, tag: u64, expected: u64) -> Result<()> {
+    let msg_type = read_explicit(r, tag, |r| Ok(r.read_i64()?))?;
+    if msg_type != expected as i64 {
+        return Err(Error::UnexpectedMessageType(msg_type));
+    }
+    Ok(())
+}
+
+fn read_list<'a, T, F>(r: &mut Asn1Reader<'a>, mut f: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&mut Asn1Reader<'a>) -> Result<T>,
+{
+    let mut r = r.read_sequence()?;
+    let mut list = Vec::new();
+    while !r.is_at_end() {
+        list.push(f(&mut r)?);
+    }
+    Ok(list)
+}
+
+
+/// A KerberosTime: a GeneralizedTime in UTC with no fractional seconds.
+#[derive(Copy,Clone,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub struct KerberosTime(DateTime);
+
+impl KerberosTime {
+    /// Fails if `date_time` has fractional seconds, which KerberosTime cannot carry.
+    pub fn new(date_time: DateTime) -> Result<Self> {
+        if date_time.nanosecond() != 0 {
+            return Err(Error::InvalidValue);
+        }
+        Ok(KerberosTime(date_time))
+    }
+
+    pub fn date_time(&self) -> DateTime {
+        self.0
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader) -> Result<Self> {
+        KerberosTime::new(r.read_generalized_time()?)
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_generalized_time(self.0)
+    }
+}
+
+
+/// A KerberosFlags BIT STRING, such as KDCOptions or APOptions. Bit `n` of the value is named bit `n`.
+///
+/// Unlike named bit lists elsewhere, Kerberos always encodes at least 32 bits, so trailing zero bits are kept.
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct KerberosFlags(pub u32);
+
+impl KerberosFlags {
+    pub fn contains(&self, bits: u32) -> bool {
+        self.0 & bits == bits
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader) -> Result<Self> {
+        let bits = r.read_bit_string()?;
+        let mut v = 0u32;
+        for i in 0..std::cmp::min(bits.bit_len(), 32) {
+            if bits.bit(i) {
+                v |= 1 << i;
+            }
+        }
+        Ok(KerberosFlags(v))
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        let mut b = [0u8; 5];
+        for i in 0..32 {
+            if self.0 & (1 << i) != 0 {
+                b[1 + i / 8] |= 0x80 >> (i % 8);
+            }
+        }
+        w.write_tag_and_data(Identifier::universal(UniversalTag::BitString), &b)
+    }
+}
+
+
+/// Named bits of KDCOptions.
+pub mod kdc_options {
+    pub const FORWARDABLE: u32 = 1 << 1;
+    pub const FORWARDED: u32 = 1 << 2;
+    pub const PROXIABLE: u32 = 1 << 3;
+    pub const PROXY: u32 = 1 << 4;
+    pub const ALLOW_POSTDATE: u32 = 1 << 5;
+    pub const POSTDATED: u32 = 1 << 6;
+    pub const RENEWABLE: u32 = 1 << 8;
+    pub const CANONICALIZE: u32 = 1 << 15;
+    pub const DISABLE_TRANSITED_CHECK: u32 = 1 << 26;
+    pub const RENEWABLE_OK: u32 = 1 << 27;
+    pub const ENC_TKT_IN_SKEY: u32 = 1 << 28;
+    pub const RENEW: u32 = 1 << 30;
+    pub const VALIDATE: u32 = 1 << 31;
+}
+
+/// Named bits of APOptions.
+pub mod ap_options {
+    pub const USE_SESSION_KEY: u32 = 1 << 1;
+    pub const MUTUAL_REQUIRED: u32 = 1 << 2;
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct PrincipalName<'a> {
+    pub name_type: i32,
+    pub name_string: Vec<&'a str>,
+}
+
+impl<'a> PrincipalName<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let name_type = read_explicit(&mut r, 0, read_i32)?;
+        let name_string = read_explicit(&mut r, 1, |r| read_list(r, read_kerberos_string))?;
+        r.expect_end()?;
+        Ok(PrincipalName {
+            name_type,
+            name_string,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_explicit(0, |w| { w.write_i64(self.name_type as i64)?; Ok(()) })?;
+            w.write_explicit(1, |w| {
+                w.write_sequence(|w| {
+                    for s in &self.name_string {
+                        write_kerberos_string(w, s)?;
+                    }
+                    Ok(())
+                })?;
+                Ok(())
+            })?;
+            Ok(())
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct HostAddress<'a> {
+    pub addr_type: i32,
+    pub address: &'a [u8],
+}
+
+impl<'a> HostAddress<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let addr_type = read_explicit(&mut r, 0, read_i32)?;
+        let address = read_explicit(&mut r, 1, |r| Ok(r.read_octet_string()?))?;
+        r.expect_end()?;
+        Ok(HostAddress {
+            addr_type,
+            address,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_explicit(0, |w| { w.write_i64(self.addr_type as i64)?; Ok(()) })?;
+            w.write_explicit(1, |w| { w.write_octet_string(self.address)?; Ok(()) })?;
+            Ok(())
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct EncryptedData<'a> {
+    pub etype: i32,
+    pub kvno: Option<u32>,
+    pub cipher: &'a [u8],
+}
+
+impl<'a> EncryptedData<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = EncryptedData::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let etype = read_explicit(&mut r, 0, read_i32)?;
+        let kvno = read_optional_explicit(&mut r, 1, read_u32)?;
+        let cipher = read_explicit(&mut r, 2, |r| Ok(r.read_octet_string()?))?;
+        r.expect_end()?;
+        Ok(EncryptedData {
+            etype,
+            kvno,
+            cipher,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_explicit(0, |w| { w.write_i64(self.etype as i64)?; Ok(()) })?;
+            if let Some(kvno) = self.kvno {
+                w.write_explicit(1, |w| { w.write_u64(kvno as u64)?; Ok(()) })?;
+            }
+            w.write_explicit(2, |w| { w.write_octet_string(self.cipher)?; Ok(()) })?;
+            Ok(())
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct PaData<'a> {
+    pub padata_type: i32,
+    pub padata_value: &'a [u8],
+}
+
+impl<'a> PaData<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let padata_type = read_explicit(&mut r, 1, read_i32)?;
+        let padata_value = read_explicit(&mut r, 2, |r| Ok(r.read_octet_string()?))?;
+        r.expect_end()?;
+        Ok(PaData {
+            padata_type,
+            padata_value,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_explicit(1, |w| { w.write_i64(self.padata_type as i64)?; Ok(()) })?;
+            w.write_explicit(2, |w| { w.write_octet_string(self.padata_value)?; Ok(()) })?;
+            Ok(())
+        })
+    }
+
+    fn write_optional_list<W: std::io::Write>(w: &mut Writer<W>, tag: u64, list: &Option<Vec<PaData>>) -> der::Result<()> {
+        if let Some(ref list) = *list {
+            w.write_explicit(tag, |w| {
+                w.write_sequence(|w| {
+                    for padata in list {
+                        padata.write(w)?;
+                    }
+                    Ok(())
+                })?;
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Ticket<'a> {
+    pub realm: &'a str,
+    pub sname: PrincipalName<'a>,
+    pub enc_part: EncryptedData<'a>,
+}
+
+impl<'a> Ticket<'a> {
+    pub const TAG: u64 = 1;
+
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = Ticket::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = Asn1Reader::new(r.read_expected(Identifier::application(true, Ticket::TAG))?);
+        let mut r = r.read_sequence()?;
+        read_pvno(&mut r, 0)?;
+        let realm = read_explicit(&mut r, 1, read_kerberos_string)?;
+        let sname = read_explicit(&mut r, 2, PrincipalName::read)?;
+        let enc_part = read_explicit(&mut r, 3, EncryptedData::read)?;
+        r.expect_end()?;
+        Ok(Ticket {
+            realm,
+            sname,
+            enc_part,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_constructed(Identifier::application(true, Ticket::TAG), |w| {
+            w.write_sequence(|w| {
+                w.write_explicit(0, |w| { w.write_i64(PVNO)?; Ok(()) })?;
+                w.write_explicit(1, |w| { write_kerberos_string(w, self.realm)?; Ok(()) })?;
+                w.write_explicit(2, |w| { self.sname.write(w)?; Ok(()) })?;
+                w.write_explicit(3, |w| { self.enc_part.write(w)?; Ok(()) })?;
+                Ok(())
+            })?;
+            Ok(())
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct KdcReqBody<'a> {
+    pub kdc_options: KerberosFlags,
+    pub cname: Option<PrincipalName<'a>>,
+    pub realm: &'a str,
+    pub sname: Option<PrincipalName<'a>>,
+    pub from: Option<KerberosTime>,
+    pub till: KerberosTime,
+    pub rtime: Option<KerberosTime>,
+    pub nonce: u32,
+    /// The encryption types the client supports, in order of preference.
+    pub etype: Vec<i32>,
+    pub addresses: Option<Vec<HostAddress<'a>>>,
+    pub enc_authorization_data: Option<EncryptedData<'a>>,
+    pub additional_tickets: Option<Vec<Ticket<'a>>>,
+}
+
+impl<'a> KdcReqBody<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let kdc_options = read_explicit(&mut r, 0, KerberosFlags::read)?;
+        let cname = read_optional_explicit(&mut r, 1, PrincipalName::read)?;
+        let realm = read_explicit(&mut r, 2, read_kerberos_string)?;
+        let sname = read_optional_explicit(&mut r, 3, PrincipalName::read)?;
+        let from = read_optional_explicit(&mut r, 4, KerberosTime::read)?;
+        let till = read_explicit(&mut r, 5, KerberosTime::read)?;
+        let rtime = read_optional_explicit(&mut r, 6, KerberosTime::read)?;
+        let nonce = read_explicit(&mut r, 7, read_u32)?;
+        let etype = read_explicit(&mut r, 8, |r| read_list(r, read_i32))?;
+        let addresses = read_optional_explicit(&mut r, 9, |r| read_list(r, HostAddress::read))?;
+        let enc_authorization_data = read_optional_explicit(&mut r, 10, EncryptedData::read)?;
+        let additional_tickets = read_optional_explicit(&mut r, 11, |r| read_list(r, Ticket::read))?;
+        r.expect_end()?;
+        Ok(KdcReqBody {
+            kdc_options,
+            cname,
+            realm,
+            sname,
+            from,
+            till,
+            rtime,
+            nonce,
+            etype,
+            addresses,
+            enc_authorization_data,
+            additional_tickets,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_explicit(0, |w| { self.kdc_options.write(w)?; Ok(()) })?;
+            if let Some(ref cname) = self.cname {
+                w.write_explicit(1, |w| { cname.write(w)?; Ok(()) })?;
+            }
+            w.write_explicit(2, |w| { write_kerberos_string(w, self.realm)?; Ok(()) })?;
+            if let Some(ref sname) = self.sname {
+                w.write_explicit(3, |w| { sname.write(w)?; Ok(()) })?;
+            }
+            if let Some(from) = self.from {
+                w.write_explicit(4, |w| { from.write(w)?; Ok(()) })?;
+            }
+            w.write_explicit(5, |w| { self.till.write(w)?; Ok(()) })?;
+            if let Some(rtime) = self.rtime {
+                w.write_explicit(6, |w| { rtime.write(w)?; Ok(()) })?;
+            }
+            w.write_explicit(7, |w| { w.write_u64(self.nonce as u64)?; Ok(()) })?;
+            w.write_explicit(8, |w| {
+                w.write_sequence(|w| {
+                    for etype in &self.etype {
+                        w.write_i64(*etype as i64)?;
+                    }
+                    Ok(())
+                })?;
+                Ok(())
+            })?;
+            if let Some(ref addresses) = self.addresses {
+                w.write_explicit(9, |w| {
+                    w.write_sequence(|w| {
+                        for address in addresses {
+                            address.write(w)?;
+                        }
+                        Ok(())
+                    })?;
+                    Ok(())
+                })?;
+            }
+            if let Some(ref data) = self.enc_authorization_data {
+                w.write_explicit(10, |w| { data.write(w)?; Ok(()) })?;
+            }
+            if let Some(ref tickets) = self.additional_tickets {
+                w.write_explicit(11, |w| {
+                    w.write_sequence(|w| {
+                        for ticket in tickets {
+                            ticket.write(w)?;
+                        }
+                        Ok(())
+                    })?;
+                    Ok(())
+                })?;
+            }
+            Ok(())
+        })
+    }
+
+    /// The DER encoding of the body, over which the checksum in a TGS-REQ authenticator is computed.
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+/// A KDC-REQ, the body of both AS-REQ and TGS-REQ.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct KdcReq<'a> {
+    pub padata: Option<Vec<PaData<'a>>>,
+    pub req_body: KdcReqBody<'a>,
+}
+
+impl<'a> KdcReq<'a> {
+    fn read_contents(b: &'a [u8], msg_type: u64) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let mut r = r.read_sequence()?;
+        read_pvno(&mut r, 1)?;
+        read_msg_type(&mut r, 2, msg_type)?;
+        let padata = read_optional_explicit(&mut r, 3, |r| read_list(r, PaData::read))?;
+        let req_body = read_explicit(&mut r, 4, KdcReqBody::read)?;
+        r.expect_end()?;
+        Ok(KdcReq {
+            padata,
+            req_body,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>, msg_type: u64) -> der::Result<()> {
+        w.write_sequence(|w| {
+            w.write_explicit(1, |w| { w.write_i64(PVNO)?; Ok(()) })?;
+            w.write_explicit(2, |w| { w.write_u64(msg_type)?; Ok(()) })?;
+            PaData::write_optional_list(w, 3, &self.padata)?;
+            w.write_explicit(4, |w| { self.req_body.write(w)?; Ok(()) })?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+
+/// A KDC-REP, the body of both AS-REP and TGS-REP.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct KdcRep<'a> {
+    pub padata: Option<Vec<PaData<'a>>>,
+    pub crealm: &'a str,
+    pub cname: PrincipalName<'a>,
+    pub ticket: Ticket<'a>,
+    pub enc_part: EncryptedData<'a>,
+}
+
+impl<'a> KdcRep<'a> {
+    fn read_contents(b: &'a [u8], msg_type: u64) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let mut r = r.read_sequence()?;
+        read_pvno(&mut r, 0)?;
+        read_msg_type(&mut r, 1, msg_type)?;
+        let padata = read_optional_explicit(&mut r, 2, |r| read_list(r, PaData::read))?;
+        let crealm = read_explicit(&mut r, 3, read_kerberos_string)?;
+        let cname = read_explicit(&mut r, 4, PrincipalName::read)?;
+        let ticket = read_explicit(&mut r, 5, Ticket::read)?;
+        let enc_part = read_explicit(&mut r, 6, EncryptedData::read)?;
+        r.expect_end()?;
+        Ok(KdcRep {
+            padata,
+            crealm,
+            cname,
+            ticket,
+            enc_part,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>, msg_type: u64) -> der::Result<()> {
+        w.write_sequence(|w| {
+            w.write_explicit(0, |w| { w.write_i64(PVNO)?; Ok(()) })?;
+            w.write_explicit(1, |w| { w.write_u64(msg_type)?; Ok(()) })?;
+            PaData::write_optional_list(w, 2, &self.padata)?;
+            w.write_explicit(3, |w| { write_kerberos_string(w, self.crealm)?; Ok(()) })?;
+            w.write_explicit(4, |w| { self.cname.write(w)?; Ok(()) })?;
+            w.write_explicit(5, |w| { self.ticket.write(w)?; Ok(()) })?;
+            w.write_explicit(6, |w| { self.enc_part.write(w)?; Ok(()) })?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct ApReq<'a> {
+    pub ap_options: KerberosFlags,
+    pub ticket: Ticket<'a>,
+    pub authenticator: EncryptedData<'a>,
+}
+
+impl<'a> ApReq<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let mut r = r.read_sequence()?;
+        read_pvno(&mut r, 0)?;
+        read_msg_type(&mut r, 1, Message::AP_REQ)?;
+        let ap_options = read_explicit(&mut r, 2, KerberosFlags::read)?;
+        let ticket = read_explicit(&mut r, 3, Ticket::read)?;
+        let authenticator = read_explicit(&mut r, 4, EncryptedData::read)?;
+        r.expect_end()?;
+        Ok(ApReq {
+            ap_options,
+            ticket,
+            authenticator,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_sequence(|w| {
+            w.write_explicit(0, |w| { w.write_i64(PVNO)?; Ok(()) })?;
+            w.write_explicit(1, |w| { w.write_u64(Message::AP_REQ)?; Ok(()) })?;
+            w.write_explicit(2, |w| { self.ap_options.write(w)?; Ok(()) })?;
+            w.write_explicit(3, |w| { self.ticket.write(w)?; Ok(()) })?;
+            w.write_explicit(4, |w| { self.authenticator.write(w)?; Ok(()) })?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct ApRep<'a> {
+    pub enc_part: EncryptedData<'a>,
+}
+
+impl<'a> ApRep<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let mut r = r.read_sequence()?;
+        read_pvno(&mut r, 0)?;
+        read_msg_type(&mut r, 1, Message::AP_REP)?;
+        let enc_part = read_explicit(&mut r, 2, EncryptedData::read)?;
+        r.expect_end()?;
+        Ok(ApRep {
+            enc_part,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_sequence(|w| {
+            w.write_explicit(0, |w| { w.write_i64(PVNO)?; Ok(()) })?;
+            w.write_explicit(1, |w| { w.write_u64(Message::AP_REP)?; Ok(()) })?;
+            w.write_explicit(2, |w| { self.enc_part.write(w)?; Ok(()) })?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct KrbError<'a> {
+    pub ctime: Option<KerberosTime>,
+    pub cusec: Option<u32>,
+    pub stime: KerberosTime,
+    pub susec: u32,
+    /// One of the `error_code` constants.
+    pub error_code: i32,
+    pub crealm: Option<&'a str>,
+    pub cname: Option<PrincipalName<'a>>,
+    pub realm: &'a str,
+    pub sname: PrincipalName<'a>,
+    pub e_text: Option<&'a str>,
+    pub e_data: Option<&'a [u8]>,
+}
+
+impl<'a> KrbError<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let mut r = r.read_sequence()?;
+        read_pvno(&mut r, 0)?;
+        read_msg_type(&mut r, 1, Message::KRB_ERROR)?;
+        let ctime = read_optional_explicit(&mut r, 2, KerberosTime::read)?;
+        let cusec = read_optional_explicit(&mut r, 3, read_microseconds)?;
+        let stime = read_explicit(&mut r, 4, KerberosTime::read)?;
+        let susec = read_explicit(&mut r, 5, read_microseconds)?;
+        let error_code = read_explicit(&mut r, 6, read_i32)?;
+        let crealm = read_optional_explicit(&mut r, 7, read_kerberos_string)?;
+        let cname = read_optional_explicit(&mut r, 8, PrincipalName::read)?;
+        let realm = read_explicit(&mut r, 9, read_kerberos_string)?;
+        let sname = read_explicit(&mut r, 10, PrincipalName::read)?;
+        let e_text = read_optional_explicit(&mut r, 11, read_kerberos_string)?;
+        let e_data = read_optional_explicit(&mut r, 12, |r| Ok(r.read_octet_string()?))?;
+        r.expect_end()?;
+        Ok(KrbError {
+            ctime,
+            cusec,
+            stime,
+            susec,
+            error_code,
+            crealm,
+            cname,
+            realm,
+            sname,
+            e_text,
+            e_data,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_sequence(|w| {
+            w.write_explicit(0, |w| { w.write_i64(PVNO)?; Ok(()) })?;
+            w.write_explicit(1, |w| { w.write_u64(Message::KRB_ERROR)?; Ok(()) })?;
+            if let Some(ctime) = self.ctime {
+                w.write_explicit(2, |w| { ctime.write(w)?; Ok(()) })?;
+            }
+            if let Some(cusec) = self.cusec {
+                w.write_explicit(3, |w| { w.write_u64(cusec as u64)?; Ok(()) })?;
+            }
+            w.write_explicit(4, |w| { self.stime.write(w)?; Ok(()) })?;
+            w.write_explicit(5, |w| { w.write_u64(self.susec as u64)?; Ok(()) })?;
+            w.write_explicit(6, |w| { w.write_i64(self.error_code as i64)?; Ok(()) })?;
+            if let Some(crealm) = self.crealm {
+                w.write_explicit(7, |w| { write_kerberos_string(w, crealm)?; Ok(()) })?;
+            }
+            if let Some(ref cname) = self.cname {
+                w.write_explicit(8, |w| { cname.write(w)?; Ok(()) })?;
+            }
+            w.write_explicit(9, |w| { write_kerberos_string(w, self.realm)?; Ok(()) })?;
+            w.write_explicit(10, |w| { self.sname.write(w)?; Ok(()) })?;
+            if let Some(e_text) = self.e_text {
+                w.write_explicit(11, |w| { write_kerberos_string(w, e_text)?; Ok(()) })?;
+            }
+            if let Some(e_data) = self.e_data {
+                w.write_explicit(12, |w| { w.write_octet_string(e_data)?; Ok(()) })?;
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+
+/// A Kerberos message, identified by its APPLICATION tag, which is also its `msg-type`.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Message<'a> {
+    AsReq(KdcReq<'a>),
+    AsRep(KdcRep<'a>),
+    TgsReq(KdcReq<'a>),
+    TgsRep(KdcRep<'a>),
+    ApReq(ApReq<'a>),
+    ApRep(ApRep<'a>),
+    KrbError(KrbError<'a>),
+}
+
+impl<'a> Message<'a> {
+    pub const AS_REQ: u64 = 10;
+    pub const AS_REP: u64 = 11;
+    pub const TGS_REQ: u64 = 12;
+    pub const TGS_REP: u64 = 13;
+    pub const AP_REQ: u64 = 14;
+    pub const AP_REP: u64 = 15;
+    pub const KRB_ERROR: u64 = 30;
+
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = Message::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let (identifier, b) = match r.next()? {
+            Some(v) => v,
+            None => return Err(Error::Read(reading::Error::UnexpectedEndOfData)),
+        };
+        if identifier.class() != Class::Application || !identifier.is_constructed() {
+            return Err(Error::Read(reading::Error::UnexpectedTag(identifier)));
+        }
+        let message = match identifier.tag() {
+            Message::AS_REQ => Message::AsReq(KdcReq::read_contents(b, Message::AS_REQ)?),
+            Message::AS_REP => Message::AsRep(KdcRep::read_contents(b, Message::AS_REP)?),
+            Message::TGS_REQ => Message::TgsReq(KdcReq::read_contents(b, Message::TGS_REQ)?),
+            Message::TGS_REP => Message::TgsRep(KdcRep::read_contents(b, Message::TGS_REP)?),
+            Message::AP_REQ => Message::ApReq(ApReq::read_contents(b)?),
+            Message::AP_REP => Message::ApRep(ApRep::read_contents(b)?),
+            Message::KRB_ERROR => Message::KrbError(KrbError::read_contents(b)?),
+            _ => return Err(Error::Read(reading::Error::UnexpectedTag(identifier))),
+        };
+        Ok(message)
+    }
+
+    /// The `msg-type`, which is also the APPLICATION tag number.
+    pub fn msg_type(&self) -> u64 {
+        match *self {
+            Message::AsReq(_) => Message::AS_REQ,
+            Message::AsRep(_) => Message::AS_REP,
+            Message::TgsReq(_) => Message::TGS_REQ,
+            Message::TgsRep(_) => Message::TGS_REP,
+            Message::ApReq(_) => Message::AP_REQ,
+            Message::ApRep(_) => Message::AP_REP,
+            Message::KrbError(_) => Message::KRB_ERROR,
+        }
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        let msg_type = self.msg_type();
+        w.write_constructed(Identifier::application(true, msg_type), |w| {
+            match *self {
+                Message::AsReq(ref m) | Message::TgsReq(ref m) => m.write_contents(w, msg_type),
+                Message::AsRep(ref m) | Message::TgsRep(ref m) => m.write_contents(w, msg_type),
+                Message::ApReq(ref m) => m.write_contents(w),
+                Message::ApRep(ref m) => m.write_contents(w),
+                Message::KrbError(ref m) => m.write_contents(w),
+            }
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn time(y: u16, mo: u8, d: u8, h: u8, mi: u8, s: u8) -> KerberosTime {
+        KerberosTime::new(DateTime::new(y, mo, d, h, mi, s).unwrap()).unwrap()
+    }
+
+    fn principal<'a>(name_type: i32, name_string: &[&'a str]) -> PrincipalName<'a> {
+        PrincipalName {
+            name_type,
+            name_string: name_string.to_vec(),
+        }
+    }
+
+    fn ticket() -> Ticket<'static> {
+        Ticket {
+            realm: "EXAMPLE.COM",
+            sname: principal(name_type::NT_SRV_INST, &["krbtgt", "EXAMPLE.COM"]),
+            enc_part: EncryptedData { etype: etype::AES256_CTS_HMAC_SHA1_96, kvno: Some(2), cipher: &[0xaa; 16] },
+        }
+    }
+
+    #[test]
+    fn test_as_req() {
+        let req_body = KdcReqBody {
+            kdc_options: KerberosFlags(kdc_options::FORWARDABLE | kdc_options::RENEWABLE | kdc_options::CANONICALIZE |
+                kdc_options::RENEWABLE_OK),
+            cname: Some(principal(name_type::NT_PRINCIPAL, &["alice"])),
+            realm: "EXAMPLE.COM",
+            sname: Some(principal(name_type::NT_SRV_INST, &["krbtgt", "EXAMPLE.COM"])),
+            from: None,
+            till: time(2026, 10, 19, 12, 0, 0),
+            rtime: Some(time(2026, 10, 25, 12, 0, 0)),
+            nonce: 0x5f3b_91c2,
+            etype: vec![etype::AES256_CTS_HMAC_SHA1_96, etype::AES128_CTS_HMAC_SHA1_96],
+            addresses: Some(vec![HostAddress { addr_type: 2, address: &[192, 0, 2, 10] }]),
+            enc_authorization_data: None,
+            additional_tickets: None,
+        };
+        let message = Message::AsReq(KdcReq {
+            padata: Some(vec![PaData { padata_type: 149, padata_value: &[] }]),
+            req_body: req_body.clone(),
+        });
+        let der = message.to_der().unwrap();
+        assert_eq!(der[0], 0x6a);
+        assert_eq!(Message::from_der(&der).unwrap(), message);
+
+        let find = |needle: &[u8]| der.windows(needle.len()).any(|w| w == needle);
+        // pvno, msg-type and the 32-bit options kinit typically sends.
+        assert!(find(&[0xa1, 0x03, 0x02, 0x01, 0x05, 0xa2, 0x03, 0x02, 0x01, 0x0a]));
+        assert!(find(&[0xa0, 0x07, 0x03, 0x05, 0x00, 0x40, 0x81, 0x00, 0x10]));
+        assert!(find(b"\xa5\x11\x18\x0f20261019120000Z"));
+        assert!(find(&[0xa7, 0x06, 0x02, 0x04, 0x5f, 0x3b, 0x91, 0xc2]));
+        assert!(find(&[0xa8, 0x08, 0x30, 0x06, 0x02, 0x01, 0x12, 0x02, 0x01, 0x11]));
+
+        let body = req_body.to_der().unwrap();
+        assert!(find(&body));
+
+        let tgs = Message::TgsReq(KdcReq {
+            padata: None,
+            req_body: KdcReqBody {
+                kdc_options: KerberosFlags(kdc_options::FORWARDABLE | kdc_options::ENC_TKT_IN_SKEY),
+                cname: None,
+                sname: Some(principal(name_type::NT_SRV_HST, &["host", "server.example.com"])),
+                from: Some(time(2026, 10, 18, 12, 0, 0)),
+                rtime: None,
+                addresses: None,
+                enc_authorization_data: Some(EncryptedData { etype: etype::AES256_CTS_HMAC_SHA1_96, kvno: None, cipher: &[1, 2, 3] }),
+                additional_tickets: Some(vec![ticket()]),
+                ..req_body
+            },
+        });
+        let der = tgs.to_der().unwrap();
+        assert_eq!(der[0], 0x6c);
+        assert_eq!(Message::from_der(&der).unwrap(), tgs);
+    }
+
+    #[test]
+    fn test_ticket_and_ap_messages() {
+        let der = ticket().to_der().unwrap();
+        assert_eq!(&der[..6], [0x61, 0x5a, 0x30, 0x58, 0xa0, 0x03]);
+        assert!(der.windows(13).any(|w| w == b"\x1b\x0bEXAMPLE.COM"));
+        assert_eq!(Ticket::from_der(&der).unwrap(), ticket());
+
+        let ap_req = Message::ApReq(ApReq {
+            ap_options: KerberosFlags(ap_options::MUTUAL_REQUIRED),
+            ticket: ticket(),
+            authenticator: EncryptedData { etype: etype::AES256_CTS_HMAC_SHA1_96, kvno: None, cipher: &[0xbb; 8] },
+        });
+        let der = ap_req.to_der().unwrap();
+        assert_eq!(der[0], 0x6e);
+        assert!(der.windows(9).any(|w| w == [0xa2, 0x07, 0x03, 0x05, 0x00, 0x20, 0x00, 0x00, 0x00]));
+        assert_eq!(Message::from_der(&der).unwrap(), ap_req);
+
+        let ap_rep = Message::ApRep(ApRep {
+            enc_part: EncryptedData { etype: etype::AES256_CTS_HMAC_SHA1_96, kvno: None, cipher: &[0xcc; 4] },
+        });
+        let der = ap_rep.to_der().unwrap();
+        assert_eq!(der, [
+            0x6f, 0x1d, 0x30, 0x1b,
+                0xa0, 0x03, 0x02, 0x01, 0x05,
+                0xa1, 0x03, 0x02, 0x01, 0x0f,
+                0xa2, 0x0f, 0x30, 0x0d,
+                    0xa0, 0x03, 0x02, 0x01, 0x12,
+                    0xa2, 0x06, 0x04, 0x04, 0xcc, 0xcc, 0xcc, 0xcc,
+        ]);
+        assert_eq!(Message::from_der(&der).unwrap(), ap_rep);
+
+        // The msg-type must agree with the APPLICATION tag, and pvno must be 5.
+        let mut wrong_type = der.clone();
+        wrong_type[13] = 0x0e;
+        match Message::from_der(&wrong_type) {
+            Err(Error::UnexpectedMessageType(14)) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+        let mut wrong_pvno = der.clone();
+        wrong_pvno[8] = 0x04;
+        match Message::from_der(&wrong_pvno) {
+            Err(Error::InvalidVersion) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_as_rep_and_error() {
+        let as_rep = Message::AsRep(KdcRep {
+            padata: Some(vec![PaData { padata_type: pa_type::PA_ETYPE_INFO2, padata_value: &[0x30, 0x00] }]),
+            crealm: "EXAMPLE.COM",
+            cname: principal(name_type::NT_PRINCIPAL, &["alice"]),
+            ticket: ticket(),
+            enc_part: EncryptedData { etype: etype::AES256_CTS_HMAC_SHA1_96, kvno: Some(1), cipher: &[0xdd; 32] },
+        });
+        let der = as_rep.to_der().unwrap();
+        assert_eq!(der[0], 0x6b);
+        assert_eq!(Message::from_der(&der).unwrap(), as_rep);
+
+        let tgs_rep = match as_rep {
+            Message::AsRep(rep) => Message::TgsRep(KdcRep { padata: None, ..rep }),
+            _ => unreachable!(),
+        };
+        let der = tgs_rep.to_der().unwrap();
+        assert_eq!(der[0], 0x6d);
+        assert_eq!(Message::from_der(&der).unwrap(), tgs_rep);
+
+        let error = Message::KrbError(KrbError {
+            ctime: None,
+            cusec: None,
+            stime: time(2026, 10, 18, 9, 30, 15),
+            susec: 999_999,
+            error_code: error_code::KDC_ERR_PREAUTH_REQUIRED,
+            crealm: None,
+            cname: Some(principal(name_type::NT_PRINCIPAL, &["alice"])),
+            realm: "EXAMPLE.COM",
+            sname: principal(name_type::NT_SRV_INST, &["krbtgt", "EXAMPLE.COM"]),
+            e_text: Some("Additional pre-authentication required"),
+            e_data: Some(&[0x30, 0x00]),
+        });
+        let der = error.to_der().unwrap();
+        assert_eq!(&der[..2], [0x7e, 0x81]);
+        assert!(der.windows(5).any(|w| w == [0xa6, 0x03, 0x02, 0x01, 0x19]));
+        assert_eq!(Message::from_der(&der).unwrap(), error);
+    }
+
+    #[test]
+    fn test_kerberos_time() {
+        let fractional = DateTime::new(2026, 10, 18, 0, 0, 0).unwrap().with_nanosecond(5).unwrap();
+        assert!(KerberosTime::new(fractional).is_err());
+
+        let mut r = Asn1Reader::new(b"\x18\x11\x32\x30\x32\x36\x31\x30\x31\x38\x30\x30\x30\x30\x30\x30\x2e\x35\x5a");
+        assert!(KerberosTime::read(&mut r).is_err());
+
+        let mut output = Vec::new();
+        time(2026, 10, 18, 0, 0, 0).write(&mut Writer::new(&mut output)).unwrap();
+        assert_eq!(output, b"\x18\x0f20261018000000Z");
+    }
+}
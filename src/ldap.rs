@@ -0,0 +1,1516 @@
+//! LDAP messages (RFC 4511).
+//!
+//! Decoding accepts BER, including indefinite lengths; encoding uses definite lengths and keeps SET OF elements in
+//! the order given. `LdapMessage::from_buffer` frames messages arriving incrementally, such as from a socket; as RFC
+//! 4511 section 5.1 requires, it only accepts the definite length form for the message itself.
+
+use enumerated::Enumerated;
+use identifier::{Class, Identifier, UniversalTag};
+use integer;
+use reading::{self, Asn1Reader};
+use std;
+use writing::der::{self, Writer};
+
+
+/// The deepest nesting of `and`, `or` and `not` filters that will be decoded, which bounds the recursion of
+/// `Filter::read`.
+pub const MAX_FILTER_DEPTH: usize = 64;
+
+/// The largest complete message that `LdapMessage::from_buffer` will wait for, so that a hostile length cannot make
+/// the caller buffer gigabytes.
+pub const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+
+#[derive(Debug)]
+pub enum Error {
+    Read(reading::Error),
+    InvalidValue,
+    NestingTooDeep,
+    IndefiniteLength,
+    MessageTooLarge,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Read(ref e) => e.fmt(f),
+            Error::InvalidValue => write!(f, "invalid value"),
+            Error::NestingTooDeep => write!(f, "filter nesting too deep"),
+            Error::IndefiniteLength => write!(f, "indefinite length message"),
+            Error::MessageTooLarge => write!(f, "message too large"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Read(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reading::Error> for Error {
+    fn from(e: reading::Error) -> Error {
+        Error::Read(e)
+    }
+}
+
+impl From<integer::Error> for Error {
+    fn from(e: integer::Error) -> Error {
+        Error::Read(reading::Error::from(e))
+    }
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// Returns the length of the complete message starting at `b` from its SEQUENCE header alone, or `None` if `b` does
+/// not yet hold the whole header.
+fn message_len(b: &[u8]) -> Result<Option<usize>> {
+    if b.len() < 2 {
+        return Ok(None);
+    }
+    if b[0] != 0x30 {
+        return Err(Error::InvalidValue);
+    }
+    let (header_len, len) = match b[1] {
+        0x80 => return Err(Error::IndefiniteLength),
+        len if len < 0x80 => (2, len as usize),
+        len_len => {
+            let header_len = 2 + (len_len & 0x7f) as usize;
+            if b.len() < header_len {
+                return Ok(None);
+            }
+            let mut len = 0usize;
+            for &byte in &b[2..header_len] {
+                if len > MAX_MESSAGE_LEN / 256 {
+                    return Err(Error::MessageTooLarge);
+                }
+                len = len * 256 + byte as usize;
+            }
+            (header_len, len)
+        }
+    };
+    if len > MAX_MESSAGE_LEN - header_len {
+        return Err(Error::MessageTooLarge);
+    }
+    Ok(Some(header_len + len))
+}
+
+
+fn to_str(b: &[u8]) -> Result<&str> {
+    std::str::from_utf8(b).map_err(|_| Error::InvalidValue)
+}
+
+/// Reads an LDAPString, LDAPDN, LDAPOID or other UTF-8 OCTET STRING.
+fn read_string<'a>(r: &mut Asn1Reader<'a>) -> Result<&'a str> {
+    to_str(r.read_octet_string()?)
+}
+
+fn read_optional_string<'a>(r: &mut Asn1Reader<'a>, tag: u64) -> Result<Option<&'a str>> {
+    match r.read_optional(Identifier::context_specific(false, tag))? {
+        Some(b) => to_str(b).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Converts the contents of an `INTEGER (0 .. maxInt)`.
+fn to_max_int(b: &[u8]) -> Result<i32> {
+    let v = integer::to_i64(b)?;
+    if v < 0 || v > i32::MAX as i64 {
+        return Err(Error::InvalidValue);
+    }
+    Ok(v as i32)
+}
+
+fn read_max_int(r: &mut Asn1Reader) -> Result<i32> {
+    to_max_int(r.read_integer_bytes()?)
+}
+
+fn read_string_list<'a>(mut r: Asn1Reader<'a>) -> Result<Vec<&'a str>> {
+    let mut list = Vec::new();
+    while !r.is_at_end() {
+        list.push(read_string(&mut r)?);
+    }
+    Ok(list)
+}
+
+fn write_string_list<W: std::io::Write>(w: &mut Writer<W>, tag: Identifier, list: &[&str]) -> der::Result<usize> {
+    w.write_constructed(tag, |w| {
+        for s in list {
+            w.write_octet_string(s.as_bytes())?;
+        }
+        Ok(())
+    })
+}
+
+
+/// An LDAP result code. Codes are open-ended, so any value is retained.
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct ResultCode(pub u32);
+
+impl ResultCode {
+    pub const SUCCESS: ResultCode = ResultCode(0);
+    pub const OPERATIONS_ERROR: ResultCode = ResultCode(1);
+    pub const PROTOCOL_ERROR: ResultCode = ResultCode(2);
+    pub const TIME_LIMIT_EXCEEDED: ResultCode = ResultCode(3);
+    pub const SIZE_LIMIT_EXCEEDED: ResultCode = ResultCode(4);
+    pub const COMPARE_FALSE: ResultCode = ResultCode(5);
+    pub const COMPARE_TRUE: ResultCode = ResultCode(6);
+    pub const AUTH_METHOD_NOT_SUPPORTED: ResultCode = ResultCode(7);
+    pub const STRONGER_AUTH_REQUIRED: ResultCode = ResultCode(8);
+    pub const REFERRAL: ResultCode = ResultCode(10);
+    pub const ADMIN_LIMIT_EXCEEDED: ResultCode = ResultCode(11);
+    pub const UNAVAILABLE_CRITICAL_EXTENSION: ResultCode = ResultCode(12);
+    pub const CONFIDENTIALITY_REQUIRED: ResultCode = ResultCode(13);
+    pub const SASL_BIND_IN_PROGRESS: ResultCode = ResultCode(14);
+    pub const NO_SUCH_ATTRIBUTE: ResultCode = ResultCode(16);
+    pub const UNDEFINED_ATTRIBUTE_TYPE: ResultCode = ResultCode(17);
+    pub const INAPPROPRIATE_MATCHING: ResultCode = ResultCode(18);
+    pub const CONSTRAINT_VIOLATION: ResultCode = ResultCode(19);
+    pub const ATTRIBUTE_OR_VALUE_EXISTS: ResultCode = ResultCode(20);
+    pub const INVALID_ATTRIBUTE_SYNTAX: ResultCode = ResultCode(21);
+    pub const NO_SUCH_OBJECT: ResultCode = ResultCode(32);
+    pub const ALIAS_PROBLEM: ResultCode = ResultCode(33);
+    pub const INVALID_DN_SYNTAX: ResultCode = ResultCode(34);
+    pub const ALIAS_DEREFERENCING_PROBLEM: ResultCode = ResultCode(36);
+    pub const INAPPROPRIATE_AUTHENTICATION: ResultCode = ResultCode(48);
+    pub const INVALID_CREDENTIALS: ResultCode = ResultCode(49);
+    pub const INSUFFICIENT_ACCESS_RIGHTS: ResultCode = ResultCode(50);
+    pub const BUSY: ResultCode = ResultCode(51);
+    pub const UNAVAILABLE: ResultCode = ResultCode(52);
+    pub const UNWILLING_TO_PERFORM: ResultCode = ResultCode(53);
+    pub const LOOP_DETECT: ResultCode = ResultCode(54);
+    pub const NAMING_VIOLATION: ResultCode = ResultCode(64);
+    pub const OBJECT_CLASS_VIOLATION: ResultCode = ResultCode(65);
+    pub const NOT_ALLOWED_ON_NON_LEAF: ResultCode = ResultCode(66);
+    pub const NOT_ALLOWED_ON_RDN: ResultCode = ResultCode(67);
+    pub const ENTRY_ALREADY_EXISTS: ResultCode = ResultCode(68);
+    pub const OBJECT_CLASS_MODS_PROHIBITED: ResultCode = ResultCode(69);
+    pub const AFFECTS_MULTIPLE_DSAS: ResultCode = ResultCode(71);
+    pub const OTHER: ResultCode = ResultCode(80);
+}
+
+/// ENUMERATED values outside the range of `u32` are rejected.
+impl Enumerated for ResultCode {
+    fn from_i64(v: i64) -> Option<Self> {
+        if v < 0 || v > u32::MAX as i64 {
+            return None;
+        }
+        Some(ResultCode(v as u32))
+    }
+
+    fn into_i64(self) -> i64 {
+        self.0 as i64
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct LdapResult<'a> {
+    pub result_code: ResultCode,
+    pub matched_dn: &'a str,
+    pub diagnostic_message: &'a str,
+    /// The `[3]` referral URIs.
+    pub referral: Option<Vec<&'a str>>,
+}
+
+impl<'a> LdapResult<'a> {
+    /// Reads the LDAPResult components, which other responses extend with fields of their own.
+    fn read_components(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let result_code = r.read_enumerated()?;
+        let matched_dn = read_string(r)?;
+        let diagnostic_message = read_string(r)?;
+        let referral = match r.read_optional(Identifier::context_specific(true, 3))? {
+            Some(b) => Some(read_string_list(Asn1Reader::new(b))?),
+            None => None,
+        };
+        Ok(LdapResult {
+            result_code,
+            matched_dn,
+            diagnostic_message,
+            referral,
+        })
+    }
+
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = LdapResult::read_components(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    fn write_components<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_enumerated(self.result_code)?;
+        w.write_octet_string(self.matched_dn.as_bytes())?;
+        w.write_octet_string(self.diagnostic_message.as_bytes())?;
+        if let Some(ref referral) = self.referral {
+            write_string_list(w, Identifier::context_specific(true, 3), referral)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Control<'a> {
+    pub control_type: &'a str,
+    pub criticality: bool,
+    pub control_value: Option<&'a [u8]>,
+}
+
+impl<'a> Control<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let control_type = read_string(&mut r)?;
+        let criticality = match r.peek()? {
+            Some(i) if i == Identifier::universal(UniversalTag::Boolean) => r.read_boolean()?,
+            _ => false,
+        };
+        let control_value = match r.peek()? {
+            Some(_) => Some(r.read_octet_string()?),
+            None => None,
+        };
+        r.expect_end()?;
+        Ok(Control {
+            control_type,
+            criticality,
+            control_value,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_octet_string(self.control_type.as_bytes())?;
+            if self.criticality {
+                w.write_boolean(true)?;
+            }
+            if let Some(value) = self.control_value {
+                w.write_octet_string(value)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Authentication<'a> {
+    Simple(&'a [u8]),
+    Sasl {
+        mechanism: &'a str,
+        credentials: Option<&'a [u8]>,
+    },
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct BindRequest<'a> {
+    pub version: u8,
+    pub name: &'a str,
+    pub authentication: Authentication<'a>,
+}
+
+impl<'a> BindRequest<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let version = r.read_i64()?;
+        if !(1..=127).contains(&version) {
+            return Err(Error::InvalidValue);
+        }
+        let name = read_string(&mut r)?;
+        let authentication = match r.next()? {
+            Some((i, b)) if i == Identifier::context_specific(false, 0) => Authentication::Simple(b),
+            Some((i, b)) if i == Identifier::context_specific(true, 3) => {
+                let mut r = Asn1Reader::new(b);
+                let mechanism = read_string(&mut r)?;
+                let credentials = match r.peek()? {
+                    Some(_) => Some(r.read_octet_string()?),
+                    None => None,
+                };
+                r.expect_end()?;
+                Authentication::Sasl {
+                    mechanism,
+                    credentials,
+                }
+            }
+            Some((i, _)) => return Err(Error::Read(reading::Error::UnexpectedTag(i))),
+            None => return Err(Error::Read(reading::Error::UnexpectedEndOfData)),
+        };
+        r.expect_end()?;
+        Ok(BindRequest {
+            version: version as u8,
+            name,
+            authentication,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_i64(self.version as i64)?;
+        w.write_octet_string(self.name.as_bytes())?;
+        match self.authentication {
+            Authentication::Simple(password) => {
+                w.write_tag_and_data(Identifier::context_specific(false, 0), password)?;
+            }
+            Authentication::Sasl { mechanism, credentials } => {
+                w.write_constructed(Identifier::context_specific(true, 3), |w| {
+                    w.write_octet_string(mechanism.as_bytes())?;
+                    if let Some(credentials) = credentials {
+                        w.write_octet_string(credentials)?;
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct BindResponse<'a> {
+    pub result: LdapResult<'a>,
+    pub server_sasl_creds: Option<&'a [u8]>,
+}
+
+impl<'a> BindResponse<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let result = LdapResult::read_components(&mut r)?;
+        let server_sasl_creds = r.read_optional(Identifier::context_specific(false, 7))?;
+        r.expect_end()?;
+        Ok(BindResponse {
+            result,
+            server_sasl_creds,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        self.result.write_components(w)?;
+        if let Some(creds) = self.server_sasl_creds {
+            w.write_tag_and_data(Identifier::context_specific(false, 7), creds)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum Scope {
+    BaseObject,
+    SingleLevel,
+    WholeSubtree,
+}
+
+impl Enumerated for Scope {
+    fn from_i64(v: i64) -> Option<Self> {
+        match v {
+            0 => Some(Scope::BaseObject),
+            1 => Some(Scope::SingleLevel),
+            2 => Some(Scope::WholeSubtree),
+            _ => None,
+        }
+    }
+
+    fn into_i64(self) -> i64 {
+        self as i64
+    }
+}
+
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum DerefAliases {
+    NeverDerefAliases,
+    DerefInSearching,
+    DerefFindingBaseObj,
+    DerefAlways,
+}
+
+impl Enumerated for DerefAliases {
+    fn from_i64(v: i64) -> Option<Self> {
+        match v {
+            0 => Some(DerefAliases::NeverDerefAliases),
+            1 => Some(DerefAliases::DerefInSearching),
+            2 => Some(DerefAliases::DerefFindingBaseObj),
+            3 => Some(DerefAliases::DerefAlways),
+            _ => None,
+        }
+    }
+
+    fn into_i64(self) -> i64 {
+        self as i64
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct AttributeValueAssertion<'a> {
+    pub attribute_desc: &'a str,
+    pub assertion_value: &'a [u8],
+}
+
+impl<'a> AttributeValueAssertion<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let attribute_desc = read_string(&mut r)?;
+        let assertion_value = r.read_octet_string()?;
+        r.expect_end()?;
+        Ok(AttributeValueAssertion {
+            attribute_desc,
+            assertion_value,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_octet_string(self.attribute_desc.as_bytes())?;
+        w.write_octet_string(self.assertion_value)?;
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Substring<'a> {
+    Initial(&'a [u8]),
+    Any(&'a [u8]),
+    Final(&'a [u8]),
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct SubstringFilter<'a> {
+    pub attribute_type: &'a str,
+    pub substrings: Vec<Substring<'a>>,
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct MatchingRuleAssertion<'a> {
+    pub matching_rule: Option<&'a str>,
+    pub attribute_type: Option<&'a str>,
+    pub match_value: &'a [u8],
+    pub dn_attributes: bool,
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Filter<'a> {
+    And(Vec<Filter<'a>>),
+    Or(Vec<Filter<'a>>),
+    Not(Box<Filter<'a>>),
+    EqualityMatch(AttributeValueAssertion<'a>),
+    Substrings(SubstringFilter<'a>),
+    GreaterOrEqual(AttributeValueAssertion<'a>),
+    LessOrEqual(AttributeValueAssertion<'a>),
+    Present(&'a str),
+    ApproxMatch(AttributeValueAssertion<'a>),
+    ExtensibleMatch(MatchingRuleAssertion<'a>),
+}
+
+impl<'a> Filter<'a> {
+    pub fn from_ber(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = Filter::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        Filter::read_nested(r, 0)
+    }
+
+    fn read_nested(r: &mut Asn1Reader<'a>, depth: usize) -> Result<Self> {
+        let (identifier, contents) = match r.next()? {
+            Some(v) => v,
+            None => return Err(Error::Read(reading::Error::UnexpectedEndOfData)),
+        };
+        if identifier.class() != Class::ContextSpecific {
+            return Err(Error::Read(reading::Error::UnexpectedTag(identifier)));
+        }
+        let filter = match (identifier.tag(), identifier.is_constructed()) {
+            (0, true) | (1, true) | (2, true) => {
+                if depth >= MAX_FILTER_DEPTH {
+                    return Err(Error::NestingTooDeep);
+                }
+                let mut r = Asn1Reader::new(contents);
+                let mut filters = Vec::new();
+                while !r.is_at_end() {
+                    filters.push(Filter::read_nested(&mut r, depth + 1)?);
+                }
+                match identifier.tag() {
+                    0 => Filter::And(filters),
+                    1 => Filter::Or(filters),
+                    _ if filters.len() == 1 => Filter::Not(Box::new(filters.remove(0))),
+                    _ => return Err(Error::InvalidValue),
+                }
+            }
+            (3, true) => Filter::EqualityMatch(AttributeValueAssertion::read_contents(contents)?),
+            (4, true) => {
+                let mut r = Asn1Reader::new(contents);
+                let attribute_type = read_string(&mut r)?;
+                let mut substrings = Vec::new();
+                for item in r.read_sequence()? {
+                    let (i, b) = item?;
+                    if i.class() != Class::ContextSpecific || i.is_constructed() {
+                        return Err(Error::Read(reading::Error::UnexpectedTag(i)));
+                    }
+                    let substring = match i.tag() {
+                        0 => Substring::Initial(b),
+                        1 => Substring::Any(b),
+                        2 => Substring::Final(b),
+                        _ => return Err(Error::Read(reading::Error::UnexpectedTag(i))),
+                    };
+                    substrings.push(substring);
+                }
+                r.expect_end()?;
+                if substrings.is_empty() {
+                    return Err(Error::InvalidValue);
+                }
+                Filter::Substrings(SubstringFilter {
+                    attribute_type,
+                    substrings,
+                })
+            }
+            (5, true) => Filter::GreaterOrEqual(AttributeValueAssertion::read_contents(contents)?),
+            (6, true) => Filter::LessOrEqual(AttributeValueAssertion::read_contents(contents)?),
+            (7, false) => Filter::Present(to_str(contents)?),
+            (8, true) => Filter::ApproxMatch(AttributeValueAssertion::read_contents(contents)?),
+            (9, true) => {
+                let mut r = Asn1Reader::new(contents);
+                let matching_rule = read_optional_string(&mut r, 1)?;
+                let attribute_type = read_optional_string(&mut r, 2)?;
+                let match_value = r.read_expected(Identifier::context_specific(false, 3))?;
+                let dn_attributes = match r.read_optional(Identifier::context_specific(false, 4))? {
+                    Some([0]) => false,
+                    Some([_]) => true,
+                    Some(_) => return Err(Error::Read(reading::Error::InvalidBoolean)),
+                    None => false,
+                };
+                r.expect_end()?;
+                Filter::ExtensibleMatch(MatchingRuleAssertion {
+                    matching_rule,
+                    attribute_type,
+                    match_value,
+                    dn_attributes,
+                })
+            }
+            _ => return Err(Error::Read(reading::Error::UnexpectedTag(identifier))),
+        };
+        Ok(filter)
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        match *self {
+            Filter::And(ref filters) | Filter::Or(ref filters) => {
+                let tag = if let Filter::And(_) = *self { 0 } else { 1 };
+                w.write_constructed(Identifier::context_specific(true, tag), |w| {
+                    for filter in filters {
+                        filter.write(w)?;
+                    }
+                    Ok(())
+                })
+            }
+            Filter::Not(ref filter) => w.write_constructed(Identifier::context_specific(true, 2), |w| {
+                filter.write(w)?;
+                Ok(())
+            }),
+            Filter::EqualityMatch(ref ava) => w.write_constructed(Identifier::context_specific(true, 3), |w| ava.write_contents(w)),
+            Filter::Substrings(ref substrings) => w.write_constructed(Identifier::context_specific(true, 4), |w| {
+                w.write_octet_string(substrings.attribute_type.as_bytes())?;
+                w.write_sequence(|w| {
+                    for substring in &substrings.substrings {
+                        let (tag, b) = match *substring {
+                            Substring::Initial(b) => (0, b),
+                            Substring::Any(b) => (1, b),
+                            Substring::Final(b) => (2, b),
+                        };
+                        w.write_tag_and_data(Identifier::context_specific(false, tag), b)?;
+                    }
+                    Ok(())
+                })?;
+                Ok(())
+            }),
+            Filter::GreaterOrEqual(ref ava) => w.write_constructed(Identifier::context_specific(true, 5), |w| ava.write_contents(w)),
+            Filter::LessOrEqual(ref ava) => w.write_constructed(Identifier::context_specific(true, 6), |w| ava.write_contents(w)),
+            Filter::Present(attribute_desc) => w.write_tag_and_data(Identifier::context_specific(false, 7), attribute_desc.as_bytes()),
+            Filter::ApproxMatch(ref ava) => w.write_constructed(Identifier::context_specific(true, 8), |w| ava.write_contents(w)),
+            Filter::ExtensibleMatch(ref mra) => w.write_constructed(Identifier::context_specific(true, 9), |w| {
+                if let Some(matching_rule) = mra.matching_rule {
+                    w.write_tag_and_data(Identifier::context_specific(false, 1), matching_rule.as_bytes())?;
+                }
+                if let Some(attribute_type) = mra.attribute_type {
+                    w.write_tag_and_data(Identifier::context_specific(false, 2), attribute_type.as_bytes())?;
+                }
+                w.write_tag_and_data(Identifier::context_specific(false, 3), mra.match_value)?;
+                if mra.dn_attributes {
+                    w.write_tag_and_data(Identifier::context_specific(false, 4), &[0xff])?;
+                }
+                Ok(())
+            }),
+        }
+    }
+
+    pub fn to_ber(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct SearchRequest<'a> {
+    pub base_object: &'a str,
+    pub scope: Scope,
+    pub deref_aliases: DerefAliases,
+    pub size_limit: i32,
+    pub time_limit: i32,
+    pub types_only: bool,
+    pub filter: Filter<'a>,
+    pub attributes: Vec<&'a str>,
+}
+
+impl<'a> SearchRequest<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let base_object = read_string(&mut r)?;
+        let scope = r.read_enumerated()?;
+        let deref_aliases = r.read_enumerated()?;
+        let size_limit = read_max_int(&mut r)?;
+        let time_limit = read_max_int(&mut r)?;
+        let types_only = r.read_boolean()?;
+        let filter = Filter::read(&mut r)?;
+        let attributes = read_string_list(r.read_sequence()?)?;
+        r.expect_end()?;
+        Ok(SearchRequest {
+            base_object,
+            scope,
+            deref_aliases,
+            size_limit,
+            time_limit,
+            types_only,
+            filter,
+            attributes,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_octet_string(self.base_object.as_bytes())?;
+        w.write_enumerated(self.scope)?;
+        w.write_enumerated(self.deref_aliases)?;
+        w.write_i64(self.size_limit as i64)?;
+        w.write_i64(self.time_limit as i64)?;
+        w.write_boolean(self.types_only)?;
+        self.filter.write(w)?;
+        write_string_list(w, Identifier::universal(UniversalTag::Sequence), &self.attributes)?;
+        Ok(())
+    }
+}
+
+
+/// An attribute and its values. An `Attribute` in RFC 4511 is the same structure with at least one value.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct PartialAttribute<'a> {
+    pub attribute_type: &'a str,
+    pub vals: Vec<&'a [u8]>,
+}
+
+impl<'a> PartialAttribute<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let attribute_type = read_string(&mut r)?;
+        let mut vals = Vec::new();
+        let mut set = r.read_set()?;
+        while !set.is_at_end() {
+            vals.push(set.read_octet_string()?);
+        }
+        r.expect_end()?;
+        Ok(PartialAttribute {
+            attribute_type,
+            vals,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_octet_string(self.attribute_type.as_bytes())?;
+            w.write_constructed(Identifier::universal(UniversalTag::Set), |w| {
+                for val in &self.vals {
+                    w.write_octet_string(val)?;
+                }
+                Ok(())
+            })?;
+            Ok(())
+        })
+    }
+
+    pub(crate) fn read_list(r: &mut Asn1Reader<'a>) -> Result<Vec<Self>> {
+        let mut r = r.read_sequence()?;
+        let mut list = Vec::new();
+        while !r.is_at_end() {
+            list.push(PartialAttribute::read(&mut r)?);
+        }
+        Ok(list)
+    }
+
+    pub fn write_list<W: std::io::Write>(w: &mut Writer<W>, list: &[PartialAttribute]) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            for attribute in list {
+                attribute.write(w)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct SearchResultEntry<'a> {
+    pub object_name: &'a str,
+    pub attributes: Vec<PartialAttribute<'a>>,
+}
+
+impl<'a> SearchResultEntry<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let object_name = read_string(&mut r)?;
+        let attributes = PartialAttribute::read_list(&mut r)?;
+        r.expect_end()?;
+        Ok(SearchResultEntry {
+            object_name,
+            attributes,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_octet_string(self.object_name.as_bytes())?;
+        PartialAttribute::write_list(w, &self.attributes)?;
+        Ok(())
+    }
+}
+
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum ModifyOperation {
+    Add,
+    Delete,
+    Replace,
+    /// The increment operation of RFC 4525.
+    Increment,
+}
+
+impl Enumerated for ModifyOperation {
+    fn from_i64(v: i64) -> Option<Self> {
+        match v {
+            0 => Some(ModifyOperation::Add),
+            1 => Some(ModifyOperation::Delete),
+            2 => Some(ModifyOperation::Replace),
+            3 => Some(ModifyOperation::Increment),
+            _ => None,
+        }
+    }
+
+    fn into_i64(self) -> i64 {
+        self as i64
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Change<'a> {
+    pub operation: ModifyOperation,
+    pub modification: PartialAttribute<'a>,
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct ModifyRequest<'a> {
+    pub object: &'a str,
+    pub changes: Vec<Change<'a>>,
+}
+
+impl<'a> ModifyRequest<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let object = read_string(&mut r)?;
+        let mut changes = Vec::new();
+        let mut seq = r.read_sequence()?;
+        while !seq.is_at_end() {
+            let mut change = seq.read_sequence()?;
+            let operation = change.read_enumerated()?;
+            let modification = PartialAttribute::read(&mut change)?;
+            change.expect_end()?;
+            changes.push(Change {
+                operation,
+                modification,
+            });
+        }
+        r.expect_end()?;
+        Ok(ModifyRequest {
+            object,
+            changes,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_octet_string(self.object.as_bytes())?;
+        w.write_sequence(|w| {
+            for change in &self.changes {
+                w.write_sequence(|w| {
+                    w.write_enumerated(change.operation)?;
+                    change.modification.write(w)?;
+                    Ok(())
+                })?;
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct AddRequest<'a> {
+    pub entry: &'a str,
+    pub attributes: Vec<PartialAttribute<'a>>,
+}
+
+impl<'a> AddRequest<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let entry = read_string(&mut r)?;
+        let attributes = PartialAttribute::read_list(&mut r)?;
+        r.expect_end()?;
+        Ok(AddRequest {
+            entry,
+            attributes,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_octet_string(self.entry.as_bytes())?;
+        PartialAttribute::write_list(w, &self.attributes)?;
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct ModifyDnRequest<'a> {
+    pub entry: &'a str,
+    pub new_rdn: &'a str,
+    pub delete_old_rdn: bool,
+    pub new_superior: Option<&'a str>,
+}
+
+impl<'a> ModifyDnRequest<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let entry = read_string(&mut r)?;
+        let new_rdn = read_string(&mut r)?;
+        let delete_old_rdn = r.read_boolean()?;
+        let new_superior = read_optional_string(&mut r, 0)?;
+        r.expect_end()?;
+        Ok(ModifyDnRequest {
+            entry,
+            new_rdn,
+            delete_old_rdn,
+            new_superior,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_octet_string(self.entry.as_bytes())?;
+        w.write_octet_string(self.new_rdn.as_bytes())?;
+        w.write_boolean(self.delete_old_rdn)?;
+        if let Some(new_superior) = self.new_superior {
+            w.write_tag_and_data(Identifier::context_specific(false, 0), new_superior.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct CompareRequest<'a> {
+    pub entry: &'a str,
+    pub ava: AttributeValueAssertion<'a>,
+}
+
+impl<'a> CompareRequest<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let entry = read_string(&mut r)?;
+        let ava = AttributeValueAssertion::read_contents(r.read_expected(Identifier::universal(UniversalTag::Sequence))?)?;
+        r.expect_end()?;
+        Ok(CompareRequest {
+            entry,
+            ava,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_octet_string(self.entry.as_bytes())?;
+        w.write_sequence(|w| self.ava.write_contents(w))?;
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct ExtendedRequest<'a> {
+    pub request_name: &'a str,
+    pub request_value: Option<&'a [u8]>,
+}
+
+impl<'a> ExtendedRequest<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let request_name = to_str(r.read_expected(Identifier::context_specific(false, 0))?)?;
+        let request_value = r.read_optional(Identifier::context_specific(false, 1))?;
+        r.expect_end()?;
+        Ok(ExtendedRequest {
+            request_name,
+            request_value,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_tag_and_data(Identifier::context_specific(false, 0), self.request_name.as_bytes())?;
+        if let Some(value) = self.request_value {
+            w.write_tag_and_data(Identifier::context_specific(false, 1), value)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct ExtendedResponse<'a> {
+    pub result: LdapResult<'a>,
+    pub response_name: Option<&'a str>,
+    pub response_value: Option<&'a [u8]>,
+}
+
+impl<'a> ExtendedResponse<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let result = LdapResult::read_components(&mut r)?;
+        let response_name = read_optional_string(&mut r, 10)?;
+        let response_value = r.read_optional(Identifier::context_specific(false, 11))?;
+        r.expect_end()?;
+        Ok(ExtendedResponse {
+            result,
+            response_name,
+            response_value,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        self.result.write_components(w)?;
+        if let Some(name) = self.response_name {
+            w.write_tag_and_data(Identifier::context_specific(false, 10), name.as_bytes())?;
+        }
+        if let Some(value) = self.response_value {
+            w.write_tag_and_data(Identifier::context_specific(false, 11), value)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct IntermediateResponse<'a> {
+    pub response_name: Option<&'a str>,
+    pub response_value: Option<&'a [u8]>,
+}
+
+impl<'a> IntermediateResponse<'a> {
+    fn read_contents(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let response_name = read_optional_string(&mut r, 0)?;
+        let response_value = r.read_optional(Identifier::context_specific(false, 1))?;
+        r.expect_end()?;
+        Ok(IntermediateResponse {
+            response_name,
+            response_value,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        if let Some(name) = self.response_name {
+            w.write_tag_and_data(Identifier::context_specific(false, 0), name.as_bytes())?;
+        }
+        if let Some(value) = self.response_value {
+            w.write_tag_and_data(Identifier::context_specific(false, 1), value)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// The protocolOp CHOICE, each alternative carried under its application tag.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum ProtocolOp<'a> {
+    BindRequest(BindRequest<'a>),
+    BindResponse(BindResponse<'a>),
+    UnbindRequest,
+    SearchRequest(SearchRequest<'a>),
+    SearchResultEntry(SearchResultEntry<'a>),
+    SearchResultDone(LdapResult<'a>),
+    SearchResultReference(Vec<&'a str>),
+    ModifyRequest(ModifyRequest<'a>),
+    ModifyResponse(LdapResult<'a>),
+    AddRequest(AddRequest<'a>),
+    AddResponse(LdapResult<'a>),
+    DelRequest(&'a str),
+    DelResponse(LdapResult<'a>),
+    ModifyDnRequest(ModifyDnRequest<'a>),
+    ModifyDnResponse(LdapResult<'a>),
+    CompareRequest(CompareRequest<'a>),
+    CompareResponse(LdapResult<'a>),
+    AbandonRequest(i32),
+    ExtendedRequest(ExtendedRequest<'a>),
+    ExtendedResponse(ExtendedResponse<'a>),
+    IntermediateResponse(IntermediateResponse<'a>),
+}
+
+impl<'a> ProtocolOp<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let (identifier, b) = match r.next()? {
+            Some(v) => v,
+            None => return Err(Error::Read(reading::Error::UnexpectedEndOfData)),
+        };
+        if identifier.class() != Class::Application {
+            return Err(Error::Read(reading::Error::UnexpectedTag(identifier)));
+        }
+        let op = match (identifier.tag(), identifier.is_constructed()) {
+            (0, true) => ProtocolOp::BindRequest(BindRequest::read_contents(b)?),
+            (1, true) => ProtocolOp::BindResponse(BindResponse::read_contents(b)?),
+            (2, false) if b.is_empty() => ProtocolOp::UnbindRequest,
+            (3, true) => ProtocolOp::SearchRequest(SearchRequest::read_contents(b)?),
+            (4, true) => ProtocolOp::SearchResultEntry(SearchResultEntry::read_contents(b)?),
+            (5, true) => ProtocolOp::SearchResultDone(LdapResult::read_contents(b)?),
+            (6, true) => ProtocolOp::ModifyRequest(ModifyRequest::read_contents(b)?),
+            (7, true) => ProtocolOp::ModifyResponse(LdapResult::read_contents(b)?),
+            (8, true) => ProtocolOp::AddRequest(AddRequest::read_contents(b)?),
+            (9, true) => ProtocolOp::AddResponse(LdapResult::read_contents(b)?),
+            (10, false) => ProtocolOp::DelRequest(to_str(b)?),
+            (11, true) => ProtocolOp::DelResponse(LdapResult::read_contents(b)?),
+            (12, true) => ProtocolOp::ModifyDnRequest(ModifyDnRequest::read_contents(b)?),
+            (13, true) => ProtocolOp::ModifyDnResponse(LdapResult::read_contents(b)?),
+            (14, true) => ProtocolOp::CompareRequest(CompareRequest::read_contents(b)?),
+            (15, true) => ProtocolOp::CompareResponse(LdapResult::read_contents(b)?),
+            (16, false) => ProtocolOp::AbandonRequest(to_max_int(b)?),
+            (19, true) => {
+                let uris = read_string_list(Asn1Reader::new(b))?;
+                if uris.is_empty() {
+                    return Err(Error::InvalidValue);
+                }
+                ProtocolOp::SearchResultReference(uris)
+            }
+            (23, true) => ProtocolOp::ExtendedRequest(ExtendedRequest::read_contents(b)?),
+            (24, true) => ProtocolOp::ExtendedResponse(ExtendedResponse::read_contents(b)?),
+            (25, true) => ProtocolOp::IntermediateResponse(IntermediateResponse::read_contents(b)?),
+            _ => return Err(Error::Read(reading::Error::UnexpectedTag(identifier))),
+        };
+        Ok(op)
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        fn app(tag: u64) -> Identifier {
+            Identifier::application(true, tag)
+        }
+        match *self {
+            ProtocolOp::BindRequest(ref v) => w.write_constructed(app(0), |w| v.write_contents(w)),
+            ProtocolOp::BindResponse(ref v) => w.write_constructed(app(1), |w| v.write_contents(w)),
+            ProtocolOp::UnbindRequest => w.write_tag_and_data(Identifier::application(false, 2), &[]),
+            ProtocolOp::SearchRequest(ref v) => w.write_constructed(app(3), |w| v.write_contents(w)),
+            ProtocolOp::SearchResultEntry(ref v) => w.write_constructed(app(4), |w| v.write_contents(w)),
+            ProtocolOp::SearchResultDone(ref v) => w.write_constructed(app(5), |w| v.write_components(w)),
+            ProtocolOp::ModifyRequest(ref v) => w.write_constructed(app(6), |w| v.write_contents(w)),
+            ProtocolOp::ModifyResponse(ref v) => w.write_constructed(app(7), |w| v.write_components(w)),
+            ProtocolOp::AddRequest(ref v) => w.write_constructed(app(8), |w| v.write_contents(w)),
+            ProtocolOp::AddResponse(ref v) => w.write_constructed(app(9), |w| v.write_components(w)),
+            ProtocolOp::DelRequest(dn) => w.write_tag_and_data(Identifier::application(false, 10), dn.as_bytes()),
+            ProtocolOp::DelResponse(ref v) => w.write_constructed(app(11), |w| v.write_components(w)),
+            ProtocolOp::ModifyDnRequest(ref v) => w.write_constructed(app(12), |w| v.write_contents(w)),
+            ProtocolOp::ModifyDnResponse(ref v) => w.write_constructed(app(13), |w| v.write_components(w)),
+            ProtocolOp::CompareRequest(ref v) => w.write_constructed(app(14), |w| v.write_contents(w)),
+            ProtocolOp::CompareResponse(ref v) => w.write_constructed(app(15), |w| v.write_components(w)),
+            ProtocolOp::AbandonRequest(id) => w.write_tag_and_data(Identifier::application(false, 16), &integer::from_i64(id as i64)),
+            ProtocolOp::SearchResultReference(ref uris) => write_string_list(w, app(19), uris),
+            ProtocolOp::ExtendedRequest(ref v) => w.write_constructed(app(23), |w| v.write_contents(w)),
+            ProtocolOp::ExtendedResponse(ref v) => w.write_constructed(app(24), |w| v.write_contents(w)),
+            ProtocolOp::IntermediateResponse(ref v) => w.write_constructed(app(25), |w| v.write_contents(w)),
+        }
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct LdapMessage<'a> {
+    pub message_id: i32,
+    pub protocol_op: ProtocolOp<'a>,
+    /// The `[0]` controls, empty when absent.
+    pub controls: Vec<Control<'a>>,
+}
+
+impl<'a> LdapMessage<'a> {
+    pub fn from_ber(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = LdapMessage::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    /// Decodes the first message in `b`, which may hold a partial message or several messages, as when reading
+    /// from a stream.
+    ///
+    /// Returns `None` if `b` does not yet hold a complete message, or the message and the number of bytes it took
+    /// up, which the caller should then discard from the front of its buffer. A message longer than
+    /// `MAX_MESSAGE_LEN` fails as soon as its header arrives.
+    pub fn from_buffer(b: &'a [u8]) -> Result<Option<(Self, usize)>> {
+        match message_len(b)? {
+            Some(len) if len <= b.len() => Ok(Some((LdapMessage::from_ber(&b[..len])?, len))),
+            _ => Ok(None),
+        }
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let message_id = read_max_int(&mut r)?;
+        let protocol_op = ProtocolOp::read(&mut r)?;
+        let mut controls = Vec::new();
+        if let Some(b) = r.read_optional(Identifier::context_specific(true, 0))? {
+            let mut r = Asn1Reader::new(b);
+            while !r.is_at_end() {
+                controls.push(Control::read(&mut r)?);
+            }
+        }
+        r.expect_end()?;
+        Ok(LdapMessage {
+            message_id,
+            protocol_op,
+            controls,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_i64(self.message_id as i64)?;
+            self.protocol_op.write(w)?;
+            if !self.controls.is_empty() {
+                w.write_constructed(Identifier::context_specific(true, 0), |w| {
+                    for control in &self.controls {
+                        control.write(w)?;
+                    }
+                    Ok(())
+                })?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn to_ber(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+
+    /// Returns the control of type `control_type`, if present.
+    pub fn control(&self, control_type: &str) -> Option<&Control<'a>> {
+        self.controls.iter().find(|c| c.control_type == control_type)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn result(result_code: ResultCode) -> LdapResult<'static> {
+        LdapResult {
+            result_code,
+            matched_dn: "",
+            diagnostic_message: "",
+            referral: None,
+        }
+    }
+
+    fn roundtrip(message: &LdapMessage) -> Vec<u8> {
+        let ber = message.to_ber().unwrap();
+        assert_eq!(&LdapMessage::from_ber(&ber).unwrap(), message);
+        ber
+    }
+
+    #[test]
+    fn test_simple_bind() {
+        let mut expected = vec![0x30, 0x2c, 0x02, 0x01, 0x01, 0x60, 0x27, 0x02, 0x01, 0x03, 0x04, 0x1a];
+        expected.extend_from_slice(b"cn=admin,dc=example,dc=com");
+        expected.extend_from_slice(&[0x80, 0x06]);
+        expected.extend_from_slice(b"secret");
+
+        let message = LdapMessage::from_ber(&expected).unwrap();
+        assert_eq!(message, LdapMessage {
+            message_id: 1,
+            protocol_op: ProtocolOp::BindRequest(BindRequest {
+                version: 3,
+                name: "cn=admin,dc=example,dc=com",
+                authentication: Authentication::Simple(b"secret"),
+            }),
+            controls: Vec::new(),
+        });
+        assert_eq!(message.to_ber().unwrap(), expected);
+
+        let sasl = LdapMessage {
+            message_id: 2,
+            protocol_op: ProtocolOp::BindRequest(BindRequest {
+                version: 3,
+                name: "",
+                authentication: Authentication::Sasl { mechanism: "EXTERNAL", credentials: None },
+            }),
+            controls: Vec::new(),
+        };
+        assert_eq!(&roundtrip(&sasl)[5..12], [0x60, 0x11, 0x02, 0x01, 0x03, 0x04, 0x00]);
+
+        let response = LdapMessage {
+            message_id: 2,
+            protocol_op: ProtocolOp::BindResponse(BindResponse {
+                result: result(ResultCode::SASL_BIND_IN_PROGRESS),
+                server_sasl_creds: Some(b"challenge"),
+            }),
+            controls: Vec::new(),
+        };
+        assert!(roundtrip(&response).ends_with(b"\x87\x09challenge"));
+    }
+
+    #[test]
+    fn test_search_filter() {
+        // (&(objectClass=person)(|(cn=J*o*n)(!(uid=admin)))(mail=*)(cn:caseExactMatch:=Jon)(age>=21)(age<=65)(sn~=smith))
+        let filter = Filter::And(vec![
+            Filter::EqualityMatch(AttributeValueAssertion { attribute_desc: "objectClass", assertion_value: b"person" }),
+            Filter::Or(vec![
+                Filter::Substrings(SubstringFilter {
+                    attribute_type: "cn",
+                    substrings: vec![Substring::Initial(b"J"), Substring::Any(b"o"), Substring::Final(b"n")],
+                }),
+                Filter::Not(Box::new(Filter::EqualityMatch(AttributeValueAssertion {
+                    attribute_desc: "uid",
+                    assertion_value: b"admin",
+                }))),
+            ]),
+            Filter::Present("mail"),
+            Filter::ExtensibleMatch(MatchingRuleAssertion {
+                matching_rule: Some("caseExactMatch"),
+                attribute_type: Some("cn"),
+                match_value: b"Jon",
+                dn_attributes: true,
+            }),
+            Filter::GreaterOrEqual(AttributeValueAssertion { attribute_desc: "age", assertion_value: b"21" }),
+            Filter::LessOrEqual(AttributeValueAssertion { attribute_desc: "age", assertion_value: b"65" }),
+            Filter::ApproxMatch(AttributeValueAssertion { attribute_desc: "sn", assertion_value: b"smith" }),
+        ]);
+        let ber = filter.to_ber().unwrap();
+        assert_eq!(Filter::from_ber(&ber).unwrap(), filter);
+        assert_eq!(ber[0], 0xa0);
+        let find = |needle: &[u8]| ber.windows(needle.len()).any(|w| w == needle);
+        assert!(find(&[0xa4, 0x0f, 0x04, 0x02, b'c', b'n', 0x30, 0x09, 0x80, 0x01, b'J', 0x81, 0x01, b'o', 0x82, 0x01, b'n']));
+        assert!(find(&[0xa2, 0x0e, 0xa3, 0x0c]));
+        assert!(find(&[0x87, 0x04, b'm', b'a', b'i', b'l']));
+        assert!(find(&[0x83, 0x03, b'J', b'o', b'n', 0x84, 0x01, 0xff]));
+        assert!(find(&[0xa5, 0x09]));
+        assert!(find(&[0xa6, 0x09]));
+        assert!(find(&[0xa8, 0x0b]));
+
+        let message = LdapMessage {
+            message_id: 5,
+            protocol_op: ProtocolOp::SearchRequest(SearchRequest {
+                base_object: "dc=example,dc=com",
+                scope: Scope::WholeSubtree,
+                deref_aliases: DerefAliases::NeverDerefAliases,
+                size_limit: 0,
+                time_limit: 30,
+                types_only: false,
+                filter,
+                attributes: vec!["cn", "mail"],
+            }),
+            controls: Vec::new(),
+        };
+        let ber = roundtrip(&message);
+        assert!(ber.ends_with(&[0x30, 0x0a, 0x04, 0x02, b'c', b'n', 0x04, 0x04, b'm', b'a', b'i', b'l']));
+
+        // A not filter must hold exactly one filter.
+        assert!(Filter::from_ber(&[0xa2, 0x00]).is_err());
+        assert!(Filter::from_ber(&[0xa2, 0x06, 0x87, 0x01, b'a', 0x87, 0x01, b'b']).is_err());
+    }
+
+    #[test]
+    fn test_filter_depth() {
+        fn nest(depth: usize) -> Vec<u8> {
+            let mut ber = vec![0x87, 0x01, b'a'];
+            for _ in 0..depth {
+                let len = ber.len();
+                let mut outer = vec![0xa2];
+                if len < 0x80 {
+                    outer.push(len as u8);
+                } else {
+                    outer.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]);
+                }
+                outer.extend_from_slice(&ber);
+                ber = outer;
+            }
+            ber
+        }
+        assert!(Filter::from_ber(&nest(MAX_FILTER_DEPTH)).is_ok());
+        match Filter::from_ber(&nest(MAX_FILTER_DEPTH + 1)) {
+            Err(Error::NestingTooDeep) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+        match Filter::from_ber(&nest(10000)) {
+            Err(Error::NestingTooDeep) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_controls() {
+        let message = LdapMessage {
+            message_id: 9,
+            protocol_op: ProtocolOp::DelRequest("cn=old,dc=example,dc=com"),
+            controls: vec![
+                Control { control_type: "1.2.840.113556.1.4.805", criticality: true, control_value: None },
+                Control { control_type: "1.2.840.113556.1.4.319", criticality: false, control_value: Some(&[0x30, 0x05, 0x02, 0x01, 0x64, 0x04, 0x00]) },
+            ],
+        };
+        let ber = roundtrip(&message);
+        assert_eq!(&ber[5..7], [0x4a, 0x18]);
+        let controls = &ber[31..];
+        assert_eq!(&controls[..4], [0xa0, 0x40, 0x30, 0x1b]);
+        assert_eq!(&controls[28..32], [0x01, 0x01, 0xff, 0x30]);
+        assert!(message.control("1.2.840.113556.1.4.319").is_some());
+
+        // An explicitly encoded default criticality is accepted.
+        let control = [0x30, 0x08, 0x04, 0x03, b'1', b'.', b'2', 0x01, 0x01, 0x00];
+        let decoded = Control::read(&mut Asn1Reader::new(&control)).unwrap();
+        assert_eq!(decoded, Control { control_type: "1.2", criticality: false, control_value: None });
+    }
+
+    #[test]
+    fn test_operations() {
+        let messages = vec![
+            ProtocolOp::UnbindRequest,
+            ProtocolOp::AbandonRequest(5),
+            ProtocolOp::SearchResultEntry(SearchResultEntry {
+                object_name: "cn=Jon,dc=example,dc=com",
+                attributes: vec![
+                    PartialAttribute { attribute_type: "mail", vals: vec![b"jon@example.com", b"j@example.com"] },
+                    PartialAttribute { attribute_type: "jpegPhoto", vals: vec![] },
+                ],
+            }),
+            ProtocolOp::SearchResultReference(vec!["ldap://other.example.com/dc=example,dc=com??sub"]),
+            ProtocolOp::SearchResultDone(LdapResult {
+                result_code: ResultCode::REFERRAL,
+                matched_dn: "dc=example,dc=com",
+                diagnostic_message: "see elsewhere",
+                referral: Some(vec!["ldap://other.example.com/"]),
+            }),
+            ProtocolOp::ModifyRequest(ModifyRequest {
+                object: "cn=Jon,dc=example,dc=com",
+                changes: vec![
+                    Change { operation: ModifyOperation::Replace, modification: PartialAttribute { attribute_type: "sn", vals: vec![b"Doe"] } },
+                    Change { operation: ModifyOperation::Increment, modification: PartialAttribute { attribute_type: "uidNumber", vals: vec![b"1"] } },
+                ],
+            }),
+            ProtocolOp::ModifyResponse(result(ResultCode::SUCCESS)),
+            ProtocolOp::AddRequest(AddRequest {
+                entry: "cn=New,dc=example,dc=com",
+                attributes: vec![PartialAttribute { attribute_type: "objectClass", vals: vec![b"top", b"person"] }],
+            }),
+            ProtocolOp::AddResponse(result(ResultCode::ENTRY_ALREADY_EXISTS)),
+            ProtocolOp::DelResponse(result(ResultCode(4096))),
+            ProtocolOp::ModifyDnRequest(ModifyDnRequest {
+                entry: "cn=New,dc=example,dc=com",
+                new_rdn: "cn=Newer",
+                delete_old_rdn: true,
+                new_superior: Some("ou=People,dc=example,dc=com"),
+            }),
+            ProtocolOp::ModifyDnResponse(result(ResultCode::SUCCESS)),
+            ProtocolOp::CompareRequest(CompareRequest {
+                entry: "cn=Jon,dc=example,dc=com",
+                ava: AttributeValueAssertion { attribute_desc: "sn", assertion_value: b"Doe" },
+            }),
+            ProtocolOp::CompareResponse(result(ResultCode::COMPARE_TRUE)),
+            ProtocolOp::ExtendedRequest(ExtendedRequest { request_name: "1.3.6.1.4.1.1466.20037", request_value: None }),
+            ProtocolOp::ExtendedResponse(ExtendedResponse {
+                result: result(ResultCode::SUCCESS),
+                response_name: Some("1.3.6.1.4.1.1466.20037"),
+                response_value: Some(b"\x01"),
+            }),
+            ProtocolOp::IntermediateResponse(IntermediateResponse { response_name: None, response_value: Some(b"sync") }),
+        ];
+        let mut tags = Vec::new();
+        for protocol_op in messages {
+            let ber = roundtrip(&LdapMessage { message_id: 3, protocol_op, controls: Vec::new() });
+            tags.push(ber[5]);
+        }
+        assert_eq!(tags, [
+            0x42, 0x50, 0x64, 0x73, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6b, 0x6c, 0x6d, 0x6e, 0x6f, 0x77, 0x78, 0x79,
+        ]);
+
+        let unbind = LdapMessage { message_id: 3, protocol_op: ProtocolOp::UnbindRequest, controls: Vec::new() };
+        assert_eq!(unbind.to_ber().unwrap(), [0x30, 0x05, 0x02, 0x01, 0x03, 0x42, 0x00]);
+
+        // A message ID outside 0..maxInt.
+        assert!(LdapMessage::from_ber(&[0x30, 0x05, 0x02, 0x01, 0xff, 0x42, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_from_buffer() {
+        let entry = LdapMessage {
+            message_id: 2,
+            protocol_op: ProtocolOp::SearchResultEntry(SearchResultEntry {
+                object_name: "dc=example,dc=com",
+                attributes: vec![PartialAttribute { attribute_type: "dc", vals: vec![b"example"] }],
+            }),
+            controls: Vec::new(),
+        };
+        let done = LdapMessage { message_id: 2, protocol_op: ProtocolOp::SearchResultDone(result(ResultCode::SUCCESS)), controls: Vec::new() };
+        let mut stream = entry.to_ber().unwrap();
+        let entry_len = stream.len();
+        stream.extend_from_slice(&done.to_ber().unwrap());
+
+        // Feed the stream a few bytes at a time, as a socket might deliver it.
+        let mut buffer = Vec::new();
+        let mut decoded = Vec::new();
+        for chunk in stream.chunks(3) {
+            buffer.extend_from_slice(chunk);
+            while let Some((message, len)) = LdapMessage::from_buffer(&buffer).unwrap() {
+                decoded.push((message.message_id, message.to_ber().unwrap()));
+                buffer.drain(..len);
+            }
+        }
+        assert!(buffer.is_empty());
+        assert_eq!(decoded, [(2, stream[..entry_len].to_vec()), (2, stream[entry_len..].to_vec())]);
+
+        // Malformed headers are reported rather than waited on.
+        assert!(LdapMessage::from_buffer(&[0x30, 0x84, 0x00]).unwrap().is_none());
+        assert!(LdapMessage::from_buffer(&[0x30, 0x03, 0x02, 0x01, 0x01]).is_err());
+        assert!(LdapMessage::from_buffer(&[0x04, 0x00]).is_err());
+        match LdapMessage::from_buffer(&[0x30, 0x80, 0x02, 0x01, 0x01]) {
+            Err(Error::IndefiniteLength) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+        match LdapMessage::from_buffer(&[0x30, 0x84, 0xff, 0xff, 0xff, 0xff]) {
+            Err(Error::MessageTooLarge) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+        match LdapMessage::from_buffer(&[0x30, 0x89, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]) {
+            Err(Error::MessageTooLarge) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+    }
+}
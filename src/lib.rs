@@ -2,6 +2,30 @@ pub mod identifier;
 pub mod reading;
 pub mod writing;
 pub mod object_identifier;
+pub mod integer;
+pub mod real;
+pub mod enumerated;
+pub mod bit_string;
+pub mod time;
+pub mod external;
+pub mod pem;
+pub mod x509;
+pub mod cms;
+pub mod ocsp;
+pub mod tsp;
+pub mod pkcs1;
+pub mod pkcs8;
+pub mod pkcs12;
+pub mod sec1;
+pub mod snmp;
+pub mod ldap;
+pub mod kerberos;
+pub mod per;
+pub mod oer;
+pub mod value;
+pub mod xer;
+pub mod jer;
+pub mod notation;
 
 pub use identifier::Identifier;
 pub use object_identifier::ObjectIdentifier;
@@ -22,11 +46,25 @@ mod test {
             let mut buf = Vec::with_capacity(4);
             {
                 let mut w = Writer::new(&mut buf);
-                w.write_indefinite_tag(Identifier::new(Class::Universal, false, UniversalTag::Boolean.into()), |w| {
+                assert!(w.write_indefinite_tag(Identifier::new(Class::Universal, false, UniversalTag::Boolean.into()), |w| {
                     w.write(&[0xffu8])
+                }).is_err());
+            }
+            assert!(buf.is_empty());
+
+            // X.690 only allows the indefinite form for constructed values, which the reader also enforces.
+            let buf = [0x01u8, 0x80, 0xff, 0, 0];
+            assert!(Asn1Reader::new(&buf).next().is_err());
+        }
+        {
+            let mut buf = Vec::with_capacity(7);
+            {
+                let mut w = Writer::new(&mut buf);
+                w.write_indefinite_tag(Identifier::new(Class::Universal, true, UniversalTag::Sequence.into()), |w| {
+                    w.write(&[0x01u8, 0x01, 0xff])
                 }).unwrap();
             }
-            assert_eq!(buf, [0x01u8, 0x80, 0xff, 0, 0]);
+            assert_eq!(buf, [0x30u8, 0x80, 0x01, 0x01, 0xff, 0, 0]);
 
             let (t, v) = {
                 let mut r = Asn1Reader::new(&buf);
@@ -34,8 +72,8 @@ mod test {
                 assert!(r.is_at_end());
                 tv
             }.unwrap();
-            assert_eq!(t, Identifier::new(Class::Universal, false, UniversalTag::Boolean.into()));
-            assert_eq!(v, [0xffu8]);
+            assert_eq!(t, Identifier::new(Class::Universal, true, UniversalTag::Sequence.into()));
+            assert_eq!(v, [0x01u8, 0x01, 0xff]);
         }
     }
 }
@@ -0,0 +1,596 @@
+//! ASN.1 value notation (X.680), for `value::Value` trees.
+//!
+//! Without a schema, the notation of a value has to say which type it is. Most values do so by their form:
+//! `TRUE` and `FALSE`, integers such as `-42`, `'0101'B` for a BIT STRING, `'0AFF'H` for an OCTET STRING, `NULL`,
+//! `"text"` for a UTF8String, `{ 1 2 840 113549 }` or `{ iso(1) member-body(2) 840 }` for an OBJECT IDENTIFIER and
+//! `{ a, b }` for a SEQUENCE, whose components may be preceded by their names, as in `{ version 2, serial 42 }`.
+//! Any other type is given before the value as in an open type, for example `SET : { 1, 2 }`,
+//! `ENUMERATED : 3`, `IA5String : "text"`, `UTCTime : "250101120000Z"` or `BIT STRING : 'A0'H`. Tagged values
+//! are written `[0] : value` when explicitly tagged, and `[APPLICATION 3] IMPLICIT : '0102'H` or
+//! `[3] IMPLICIT : { a, b }` when implicitly tagged, with the contents octets or components of the value.
+//! A named CHOICE alternative is written `name : value`.
+//!
+//! Strings keep everything between their quotes verbatim, except that `""` stands for one quote. Comments start
+//! with `--` and end at the end of the line or at the next `--`, or are enclosed in `/*` and `*/`.
+
+use identifier::{Class, Identifier, UniversalTag};
+use integer;
+use object_identifier::ObjectIdentifier;
+use std;
+use std::fmt::Write;
+use value::{self, Value};
+use writing::der;
+use xer;
+
+
+#[derive(Debug)]
+pub enum Error {
+    /// The input is not valid value notation, at the given byte offset.
+    Syntax(usize),
+    /// The value at the given byte offset is not a valid value of its type.
+    InvalidValue(usize),
+    NestingTooDeep,
+    Encode(der::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Syntax(pos) => write!(f, "syntax error at offset {}", pos),
+            Error::InvalidValue(pos) => write!(f, "invalid value at offset {}", pos),
+            Error::NestingTooDeep => write!(f, "nesting too deep"),
+            Error::Encode(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Encode(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<der::Error> for Error {
+    fn from(e: der::Error) -> Error {
+        Error::Encode(e)
+    }
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// Renders `v` in value notation, with the components of SEQUENCE and SET values on their own lines and indented.
+pub fn to_string(v: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, v, 0).unwrap();
+    out
+}
+
+/// Parses a value and encodes it in DER.
+pub fn to_der(s: &str) -> Result<Vec<u8>> {
+    Ok(from_str(s)?.to_der()?)
+}
+
+/// Parses a value, which must be the only thing in `s` other than whitespace and comments.
+pub fn from_str(s: &str) -> Result<Value> {
+    let mut p = Parser {
+        tokens: tokenize(s)?,
+        index: 0,
+    };
+    let v = p.parse_value(0)?;
+    match p.next() {
+        (_, Token::End) => Ok(v),
+        (pos, _) => Err(Error::Syntax(pos)),
+    }
+}
+
+
+/// The notation of a universal type, such as `OCTET STRING` or `IA5String`.
+fn type_name(tag: UniversalTag) -> String {
+    xer::type_name(tag).replace('_', " ")
+}
+
+fn write_value(out: &mut String, v: &Value, depth: usize) -> std::fmt::Result {
+    match *v {
+        Value::Boolean(b) => out.write_str(if b { "TRUE" } else { "FALSE" }),
+        Value::Integer(ref b) => out.write_str(&integer::to_decimal(b).unwrap_or_default()),
+        Value::Enumerated(ref b) => write!(out, "ENUMERATED : {}", integer::to_decimal(b).unwrap_or_default()),
+        Value::BitString(ref data, bit_len) if bit_len > 0 && bit_len.is_multiple_of(8) => {
+            write!(out, "BIT STRING : '{}'H", to_hex(data))
+        }
+        Value::BitString(ref data, bit_len) => {
+            out.write_char('\'')?;
+            for i in 0..std::cmp::min(bit_len, data.len() * 8) {
+                out.write_char(if data[i / 8] & (0x80 >> (i % 8)) != 0 { '1' } else { '0' })?;
+            }
+            out.write_str("'B")
+        }
+        Value::OctetString(ref b) => write!(out, "'{}'H", to_hex(b)),
+        Value::Null => out.write_str("NULL"),
+        Value::ObjectIdentifier(ref oid) => {
+            out.write_str("{")?;
+            for c in oid.components() {
+                write!(out, " {}", c)?;
+            }
+            out.write_str(" }")
+        }
+        Value::String(tag, ref s) => {
+            if tag != UniversalTag::Utf8String {
+                write!(out, "{} : ", type_name(tag))?;
+            }
+            write!(out, "\"{}\"", s.replace('"', "\"\""))
+        }
+        Value::Sequence(ref children) => write_components(out, children, depth),
+        Value::Set(ref children) => {
+            out.write_str("SET : ")?;
+            write_components(out, children, depth)
+        }
+        Value::Constructed(identifier, ref children) => {
+            write_tag(out, identifier)?;
+            if children.len() == 1 {
+                out.write_str(" : ")?;
+                return write_value(out, &children[0], depth);
+            }
+            out.write_str(" IMPLICIT : ")?;
+            write_components(out, children, depth)
+        }
+        Value::Primitive(identifier, ref b) => {
+            write_tag(out, identifier)?;
+            write!(out, " IMPLICIT : '{}'H", to_hex(b))
+        }
+        Value::Named(ref name, ref v) => {
+            write!(out, "{} : ", name)?;
+            write_value(out, v, depth)
+        }
+    }
+}
+
+fn write_tag(out: &mut String, identifier: Identifier) -> std::fmt::Result {
+    match identifier.class() {
+        Class::Universal => write!(out, "[UNIVERSAL {}]", identifier.tag()),
+        Class::Application => write!(out, "[APPLICATION {}]", identifier.tag()),
+        Class::ContextSpecific => write!(out, "[{}]", identifier.tag()),
+        Class::Private => write!(out, "[PRIVATE {}]", identifier.tag()),
+    }
+}
+
+fn write_components(out: &mut String, children: &[Value], depth: usize) -> std::fmt::Result {
+    if children.is_empty() {
+        return out.write_str("{}");
+    }
+    let indent = "  ".repeat(depth + 1);
+    out.write_str("{\n")?;
+    for (i, child) in children.iter().enumerate() {
+        out.write_str(&indent)?;
+        match *child {
+            Value::Named(ref name, ref v) => {
+                write!(out, "{} ", name)?;
+                write_value(out, v, depth + 1)?;
+            }
+            ref v => write_value(out, v, depth + 1)?,
+        }
+        out.write_str(if i + 1 < children.len() { ",\n" } else { "\n" })?;
+    }
+    write!(out, "{}}}", "  ".repeat(depth))
+}
+
+fn to_hex(b: &[u8]) -> String {
+    b.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+enum Token {
+    /// A word such as `version`, `TRUE` or `OCTET`, which may contain hyphens.
+    Identifier(String),
+    /// Decimal digits, with a leading `-` if negative.
+    Number(String),
+    /// The bits of a `'...'B` string.
+    BString(Vec<bool>),
+    HString(Vec<u8>),
+    CString(String),
+    Punctuation(u8),
+    End,
+}
+
+fn tokenize(s: &str) -> Result<Vec<(usize, Token)>> {
+    let b = s.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    loop {
+        while b.get(pos).is_some_and(u8::is_ascii_whitespace) {
+            pos += 1;
+        }
+        let start = pos;
+        let token = match b.get(pos) {
+            None => {
+                tokens.push((pos, Token::End));
+                return Ok(tokens);
+            }
+            Some(b'-') if b.get(pos + 1) == Some(&b'-') => {
+                pos += 2;
+                while pos < b.len() && b[pos] != b'\n' && !b[pos..].starts_with(b"--") {
+                    pos += 1;
+                }
+                if b[pos..].starts_with(b"--") {
+                    pos += 2;
+                }
+                continue;
+            }
+            Some(b'/') if b.get(pos + 1) == Some(&b'*') => {
+                let mut nesting = 0;
+                loop {
+                    if b[pos..].starts_with(b"/*") {
+                        nesting += 1;
+                        pos += 2;
+                    } else if b[pos..].starts_with(b"*/") {
+                        nesting -= 1;
+                        pos += 2;
+                        if nesting == 0 {
+                            break;
+                        }
+                    } else if pos < b.len() {
+                        pos += 1;
+                    } else {
+                        return Err(Error::Syntax(start));
+                    }
+                }
+                continue;
+            }
+            Some(b'-') | Some(b'0'..=b'9') => {
+                pos += 1;
+                while b.get(pos).is_some_and(u8::is_ascii_digit) {
+                    pos += 1;
+                }
+                let n = &s[start..pos];
+                let digits = n.trim_start_matches('-');
+                if digits.is_empty() || (digits.len() > 1 && digits.starts_with('0')) {
+                    return Err(Error::Syntax(start));
+                }
+                Token::Number(n.to_string())
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                while b.get(pos).is_some_and(|c| c.is_ascii_alphanumeric() || *c == b'-') {
+                    if b[pos] == b'-' && !b.get(pos + 1).is_some_and(u8::is_ascii_alphanumeric) {
+                        break;
+                    }
+                    pos += 1;
+                }
+                Token::Identifier(s[start..pos].to_string())
+            }
+            Some(b'\'') => {
+                let end = start + 1 + s[start + 1..].find('\'').ok_or(Error::Syntax(start))?;
+                let text: Vec<u8> = b[start + 1..end].iter().cloned().filter(|c| !c.is_ascii_whitespace()).collect();
+                pos = end + 2;
+                match b.get(end + 1) {
+                    Some(b'B') if text.iter().all(|c| *c == b'0' || *c == b'1') => {
+                        Token::BString(text.iter().map(|c| *c == b'1').collect())
+                    }
+                    Some(b'H') if text.len().is_multiple_of(2) => {
+                        let octets = text.chunks(2)
+                            .map(|pair| std::str::from_utf8(pair).ok().and_then(|p| u8::from_str_radix(p, 16).ok()))
+                            .collect::<Option<Vec<u8>>>();
+                        Token::HString(octets.ok_or(Error::Syntax(start))?)
+                    }
+                    _ => return Err(Error::Syntax(start)),
+                }
+            }
+            Some(b'"') => {
+                let mut text = String::new();
+                pos += 1;
+                loop {
+                    let end = pos + s[pos..].find('"').ok_or(Error::Syntax(start))?;
+                    text.push_str(&s[pos..end]);
+                    pos = end + 1;
+                    if b.get(pos) != Some(&b'"') {
+                        break;
+                    }
+                    text.push('"');
+                    pos += 1;
+                }
+                Token::CString(text)
+            }
+            Some(&c) if b"{}[](),:".contains(&c) => {
+                pos += 1;
+                Token::Punctuation(c)
+            }
+            Some(_) => return Err(Error::Syntax(start)),
+        };
+        tokens.push((start, token));
+    }
+}
+
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self, offset: usize) -> &Token {
+        self.tokens.get(self.index + offset).map_or(&Token::End, |t| &t.1)
+    }
+
+    fn next(&mut self) -> (usize, Token) {
+        let t = self.tokens[self.index].clone();
+        if t.1 != Token::End {
+            self.index += 1;
+        }
+        t
+    }
+
+    fn expect(&mut self, c: u8) -> Result<()> {
+        match self.next() {
+            (_, Token::Punctuation(p)) if p == c => Ok(()),
+            (pos, _) => Err(Error::Syntax(pos)),
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(*self.peek(0), Token::Identifier(ref s) if s == keyword) {
+            self.index += 1;
+            return true;
+        }
+        false
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<Value> {
+        if depth >= value::MAX_DEPTH {
+            return Err(Error::NestingTooDeep);
+        }
+        let (pos, token) = self.next();
+        let v = match token {
+            Token::Punctuation(b'{') => {
+                if self.is_object_identifier() {
+                    self.parse_object_identifier(pos)?
+                } else {
+                    Value::Sequence(self.parse_components(depth)?)
+                }
+            }
+            Token::Punctuation(b'[') => return self.parse_tagged(pos, depth),
+            Token::Number(n) => Value::Integer(integer::from_decimal(&n).ok_or(Error::InvalidValue(pos))?),
+            Token::BString(bits) => bit_string(&bits),
+            Token::HString(b) => Value::OctetString(b),
+            Token::CString(s) => Value::String(UniversalTag::Utf8String, s),
+            Token::Identifier(ref s) if s == "TRUE" => Value::Boolean(true),
+            Token::Identifier(ref s) if s == "FALSE" => Value::Boolean(false),
+            Token::Identifier(ref s) if s == "NULL" => Value::Null,
+            Token::Identifier(s) => {
+                if s.starts_with(|c: char| c.is_ascii_lowercase()) {
+                    self.expect(b':')?;
+                    return Ok(Value::Named(s, Box::new(self.parse_value(depth + 1)?)));
+                }
+                let mut name = s;
+                if matches!(name.as_str(), "BIT" | "OCTET" | "OBJECT" | "EMBEDDED" | "CHARACTER") {
+                    if let Token::Identifier(ref second) = *self.peek(0) {
+                        name = format!("{} {}", name, second);
+                        self.index += 1;
+                    }
+                }
+                let tag = (0..31).filter_map(UniversalTag::from_u64).find(|t| type_name(*t) == name)
+                    .ok_or(Error::Syntax(pos))?;
+                self.expect(b':')?;
+                return self.parse_typed(tag, depth);
+            }
+            _ => return Err(Error::Syntax(pos)),
+        };
+        Ok(v)
+    }
+
+    /// Parses the value after `Type :`.
+    fn parse_typed(&mut self, tag: UniversalTag, depth: usize) -> Result<Value> {
+        let (pos, token) = self.next();
+        let v = match (tag, token) {
+            (UniversalTag::Boolean, Token::Identifier(ref s)) if s == "TRUE" => Value::Boolean(true),
+            (UniversalTag::Boolean, Token::Identifier(ref s)) if s == "FALSE" => Value::Boolean(false),
+            (UniversalTag::Integer, Token::Number(n)) => {
+                Value::Integer(integer::from_decimal(&n).ok_or(Error::InvalidValue(pos))?)
+            }
+            (UniversalTag::Enumerated, Token::Number(n)) => {
+                Value::Enumerated(integer::from_decimal(&n).ok_or(Error::InvalidValue(pos))?)
+            }
+            (UniversalTag::BitString, Token::BString(bits)) => bit_string(&bits),
+            (UniversalTag::BitString, Token::HString(b)) => {
+                let bit_len = b.len() * 8;
+                Value::BitString(b, bit_len)
+            }
+            (UniversalTag::OctetString, Token::HString(b)) => Value::OctetString(b),
+            (UniversalTag::OctetString, Token::BString(bits)) => match bit_string(&bits) {
+                Value::BitString(b, _) => Value::OctetString(b),
+                _ => unreachable!(),
+            },
+            (UniversalTag::Null, Token::Identifier(ref s)) if s == "NULL" => Value::Null,
+            (UniversalTag::ObjectIdentifier, Token::Punctuation(b'{')) => self.parse_object_identifier(pos)?,
+            (UniversalTag::Sequence, Token::Punctuation(b'{')) => Value::Sequence(self.parse_components(depth)?),
+            (UniversalTag::Set, Token::Punctuation(b'{')) => Value::Set(self.parse_components(depth)?),
+            (tag, Token::CString(s)) if value::is_string_type(tag) => Value::String(tag, s),
+            _ => return Err(Error::InvalidValue(pos)),
+        };
+        Ok(v)
+    }
+
+    /// Parses a value after its opening `[`.
+    fn parse_tagged(&mut self, pos: usize, depth: usize) -> Result<Value> {
+        let class = if self.eat_keyword("UNIVERSAL") {
+            Class::Universal
+        } else if self.eat_keyword("APPLICATION") {
+            Class::Application
+        } else if self.eat_keyword("PRIVATE") {
+            Class::Private
+        } else {
+            Class::ContextSpecific
+        };
+        let tag = match self.next() {
+            (_, Token::Number(ref n)) if !n.starts_with('-') => n.parse().map_err(|_| Error::InvalidValue(pos))?,
+            (pos, _) => return Err(Error::Syntax(pos)),
+        };
+        self.expect(b']')?;
+        let implicit = self.eat_keyword("IMPLICIT");
+        self.expect(b':')?;
+        if !implicit {
+            let v = self.parse_value(depth + 1)?;
+            return Ok(Value::Constructed(Identifier::new(class, true, tag), vec![v]));
+        }
+        match self.next() {
+            (_, Token::HString(b)) => Ok(Value::Primitive(Identifier::new(class, false, tag), b)),
+            (_, Token::Punctuation(b'{')) => {
+                Ok(Value::Constructed(Identifier::new(class, true, tag), self.parse_components(depth)?))
+            }
+            (pos, _) => Err(Error::InvalidValue(pos)),
+        }
+    }
+
+    /// Whether the tokens after an opening `{` are the components of an OBJECT IDENTIFIER, which are numbers or
+    /// `name(number)` forms not separated by commas.
+    fn is_object_identifier(&self) -> bool {
+        let mut offset = 0;
+        let mut arcs = 0;
+        loop {
+            match *self.peek(offset) {
+                Token::Number(_) => offset += 1,
+                Token::Identifier(_) if *self.peek(offset + 1) == Token::Punctuation(b'(') => offset += 4,
+                Token::Punctuation(b'}') => return arcs >= 2,
+                _ => return false,
+            }
+            arcs += 1;
+        }
+    }
+
+    fn parse_object_identifier(&mut self, pos: usize) -> Result<Value> {
+        let mut components = Vec::new();
+        loop {
+            let n = match self.next() {
+                (_, Token::Punctuation(b'}')) => break,
+                (_, Token::Number(n)) => n,
+                (_, Token::Identifier(_)) => {
+                    self.expect(b'(')?;
+                    let n = match self.next() {
+                        (_, Token::Number(n)) => n,
+                        (pos, _) => return Err(Error::Syntax(pos)),
+                    };
+                    self.expect(b')')?;
+                    n
+                }
+                (pos, _) => return Err(Error::Syntax(pos)),
+            };
+            components.push(n.parse::<u64>().map_err(|_| Error::InvalidValue(pos))?);
+        }
+        let oid = ObjectIdentifier::from_components(&components);
+        if !oid.is_valid() {
+            return Err(Error::InvalidValue(pos));
+        }
+        Ok(Value::ObjectIdentifier(oid))
+    }
+
+    /// Parses comma separated components, each optionally preceded by its name, up to the closing `}`.
+    fn parse_components(&mut self, depth: usize) -> Result<Vec<Value>> {
+        let mut children = Vec::new();
+        if *self.peek(0) == Token::Punctuation(b'}') {
+            self.index += 1;
+            return Ok(children);
+        }
+        loop {
+            let name = match (self.peek(0).clone(), self.peek(1)) {
+                (Token::Identifier(ref s), next) if s.starts_with(|c: char| c.is_ascii_lowercase()) &&
+                    *next != Token::Punctuation(b':') => Some(s.clone()),
+                _ => None,
+            };
+            let v = match name {
+                Some(name) => {
+                    self.index += 1;
+                    Value::Named(name, Box::new(self.parse_value(depth + 1)?))
+                }
+                None => self.parse_value(depth + 1)?,
+            };
+            children.push(v);
+            match self.next() {
+                (_, Token::Punctuation(b',')) => (),
+                (_, Token::Punctuation(b'}')) => return Ok(children),
+                (pos, _) => return Err(Error::Syntax(pos)),
+            }
+        }
+    }
+}
+
+fn bit_string(bits: &[bool]) -> Value {
+    let mut data = vec![0; bits.len().div_ceil(8)];
+    for (i, _) in bits.iter().enumerate().filter(|b| *b.1) {
+        data[i / 8] |= 0x80 >> (i % 8);
+    }
+    Value::BitString(data, bits.len())
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use value::test::sample;
+
+    #[test]
+    fn test_roundtrip() {
+        let v = Value::from_der(&sample()).unwrap();
+        let s = to_string(&v);
+        assert_eq!(from_str(&s).unwrap(), v);
+        assert_eq!(to_der(&s).unwrap(), sample());
+
+        let v = Value::Sequence(vec![
+            Value::named("version", Value::integer(2)),
+            Value::named("flags", Value::BitString(vec![0xa0], 3)),
+            Value::named("choice", Value::named("alt", Value::Null)),
+            Value::Constructed(Identifier::context_specific(true, 1), vec![]),
+            Value::String(UniversalTag::Ia5String, "say \"hi\"".to_string()),
+        ]);
+        let s = to_string(&v);
+        assert_eq!(s, "{\n  version 2,\n  flags '101'B,\n  choice alt : NULL,\n  [1] IMPLICIT : {},\n  \
+            IA5String : \"say \"\"hi\"\"\"\n}");
+        assert_eq!(from_str(&s).unwrap(), v);
+    }
+
+    #[test]
+    fn test_fixture() {
+        let s = "-- An AlgorithmIdentifier in a SEQUENCE.
+            { version 2, serial 42, /* sha256WithRSAEncryption */ algo { iso(1) member-body(2) 840 113549 1 1 11 },
+              params NULL, key BIT STRING : '0102'H, ext [3] : { critical TRUE }, tag [APPLICATION 1] IMPLICIT : ''H }";
+        let v = from_str(s).unwrap();
+        assert_eq!(v, Value::Sequence(vec![
+            Value::named("version", Value::integer(2)),
+            Value::named("serial", Value::integer(42)),
+            Value::named("algo", Value::ObjectIdentifier("1.2.840.113549.1.1.11".parse().unwrap())),
+            Value::named("params", Value::Null),
+            Value::named("key", Value::BitString(vec![0x01, 0x02], 16)),
+            Value::named("ext", Value::Constructed(Identifier::context_specific(true, 3),
+                vec![Value::Sequence(vec![Value::named("critical", Value::Boolean(true))])])),
+            Value::named("tag", Value::Primitive(Identifier::application(false, 1), vec![])),
+        ]));
+        assert_eq!(to_der(s).unwrap(), [
+            0x30, 0x21, 0x02, 0x01, 0x02, 0x02, 0x01, 0x2a, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01,
+            0x01, 0x0b, 0x05, 0x00, 0x03, 0x03, 0x00, 0x01, 0x02, 0xa3, 0x05, 0x30, 0x03, 0x01, 0x01, 0xff, 0x41,
+            0x00,
+        ][..]);
+        assert_eq!(from_str("{ 1, 2 }").unwrap(), Value::Sequence(vec![Value::integer(1), Value::integer(2)]));
+        assert_eq!(from_str("{ 5 }").unwrap(), Value::Sequence(vec![Value::integer(5)]));
+        assert_eq!(from_str("OBJECT IDENTIFIER : { joint-iso-itu-t(2) example(999) 1 }").unwrap(),
+            Value::ObjectIdentifier(ObjectIdentifier::from_components(&[2, 999, 1])));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let cases = ["", "{ 1, }", "{ 1 2", "'012'B", "'0G'H", "\"abc", "[0]", "[0] IMPLICIT : 5", "Foo : 1",
+            "INTEGER : \"1\"", "{ 3 1 }", "{ 1 40 }", "version", "007", "1 2", "/* x", "IA5String : 'FF'H"];
+        for s in &cases {
+            assert!(from_str(s).is_err(), "{}", s);
+        }
+        match to_der("IA5String : \"caf\u{e9}\"") {
+            Err(Error::Encode(der::Error::InvalidValue)) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+        match from_str(&"{".repeat(100)) {
+            Err(Error::NestingTooDeep) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+    }
+}
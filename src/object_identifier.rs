@@ -7,9 +7,11 @@ use std::io::Read;
 pub enum Error {
     UnexpectedEndOfData,
     ComponentOverflow,
+    InvalidFormat,
 }
 
 impl std::fmt::Display for Error {
+    #[allow(deprecated)]
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", std::error::Error::description(self))
     }
@@ -17,9 +19,10 @@ impl std::fmt::Display for Error {
 
 impl std::error::Error for Error {
     fn description(&self) -> &str {
-        match self {
-            &Error::UnexpectedEndOfData => "unexpected endofdata",
-            &Error::ComponentOverflow => "oid component overflow",
+        match *self {
+            Error::UnexpectedEndOfData => "unexpected endofdata",
+            Error::ComponentOverflow => "oid component overflow",
+            Error::InvalidFormat => "invalid oid string",
         }
     }
 }
@@ -28,28 +31,26 @@ impl std::error::Error for Error {
 pub type Result<T> = std::result::Result<T, Error>;
 
 
-#[derive(Debug)]
+#[derive(Clone,Debug,PartialEq,Eq,Hash)]
 pub struct ObjectIdentifier {
     components: Vec<u64>,
 }
 
 impl ObjectIdentifier {
+    pub fn from_components(components: &[u64]) -> ObjectIdentifier {
+        ObjectIdentifier {
+            components: components.to_vec(),
+        }
+    }
+
     pub fn from_bytes(b: &[u8]) -> Result<ObjectIdentifier> {
-        if b.len() < 1 {
+        if b.is_empty() {
             return Err(Error::UnexpectedEndOfData);
         }
 
         let mut components = Vec::<u64>::with_capacity(b.len() + 1);
         let c = std::io::Cursor::new(b);
-        let mut cb = c.bytes();
-        let b = match cb.next() {
-            Some(Ok(b)) => b,
-            Some(Err(_)) => return Err(Error::UnexpectedEndOfData),
-            None => return Err(Error::UnexpectedEndOfData),
-        };
-
-        components.push((b / 40) as u64);
-        components.push((b % 40) as u64);
+        let cb = c.bytes();
 
         let mut expect_continuation = false;
         let mut accumulator = 0u64;
@@ -57,7 +58,7 @@ impl ObjectIdentifier {
             Ok(b) => {
                 accumulator |= (b & 0x7f) as u64;
                 if b & 0x80 != 0 {
-                    if accumulator > u64::max_value() / 128 {
+                    if accumulator > u64::MAX / 128 {
                          return Err(Error::ComponentOverflow);
                     }
                     accumulator *= 128;
@@ -75,8 +76,17 @@ impl ObjectIdentifier {
             return Err(Error::UnexpectedEndOfData);
         }
 
+        // The first subidentifier packs the first two arcs as 40 * first + second (X.690 8.19.4).
+        let (first, second) = match components[0] {
+            v @ 0..=39 => (0, v),
+            v @ 40..=79 => (1, v - 40),
+            v => (2, v - 80),
+        };
+        components[0] = second;
+        components.insert(0, first);
+
         Ok(ObjectIdentifier {
-            components: components,
+            components,
         })
     }
 
@@ -84,27 +94,31 @@ impl ObjectIdentifier {
         self.components.as_ref()
     }
 
-    #[inline]
-    pub fn bytes_len(&self) -> usize {
-        if self.components.len() < 2 {
-            panic!();
-        }
-
-        let mut it = self.components.iter();
+    /// Whether the OID can be encoded: it needs at least two arcs, a first arc of 0, 1 or 2 and, under 0 and 1, a
+    /// second arc below 40.
+    pub fn is_valid(&self) -> bool {
+        self.first_subidentifier().is_some()
+    }
 
-        let first_byte_hi = it.next().unwrap();
-        if *first_byte_hi > 256/40 {
-            panic!();
+    fn first_subidentifier(&self) -> Option<u64> {
+        match *self.components.as_slice() {
+            [first @ 0..=1, second, ..] if second < 40 => Some(first * 40 + second),
+            [2, second, ..] => second.checked_add(80),
+            _ => None,
         }
+    }
 
-        let first_byte_lo = it.next().unwrap();
-        if *first_byte_lo >= 40 {
-            panic!();
-        }
+    /// Panics if the OID is not valid.
+    #[inline]
+    pub fn bytes_len(&self) -> usize {
+        let first = match self.first_subidentifier() {
+            Some(v) => v,
+            None => panic!("invalid object identifier"),
+        };
 
-        let mut len = 1;
+        let mut len = byte_len_for_component(first);
 
-        for component in it {
+        for component in &self.components[2..] {
             len += byte_len_for_component(*component);
         }
 
@@ -119,26 +133,17 @@ impl ObjectIdentifier {
         v
     }
 
+    /// Panics if the OID is not valid.
+    #[allow(deprecated)]
     pub fn write_to<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<usize> {
-        if self.components.len() < 2 {
-            panic!();
-        }
+        let first = match self.first_subidentifier() {
+            Some(v) => v,
+            None => panic!("invalid object identifier"),
+        };
 
         let mut len = 0;
 
-        let mut it = self.components.iter();
-
-        let first_byte_hi = it.next().unwrap();
-        if *first_byte_hi > 256/40 {
-            panic!();
-        }
-
-        let first_byte_lo = it.next().unwrap();
-        if *first_byte_lo >= 40 {
-            panic!();
-        }
-
-        len += try!(w.write(&[ (first_byte_hi * 40 + first_byte_lo) as u8 ]));
+        let it = std::iter::once(&first).chain(&self.components[2..]);
 
         for component in it {
             let remainder_bytes_len = 9;
@@ -147,7 +152,7 @@ impl ObjectIdentifier {
             let mut is_last_byte = true;
 
             let mut component = *component;
-            while component > 0 {
+            loop {
                 let component_sevenbits: u8 = (component % 128) as u8;
                 component /= 128;
                 let continuation_overlay: u8 = if is_last_byte {
@@ -159,19 +164,58 @@ impl ObjectIdentifier {
                 remainder_bytes[remainder_bytes_len - remainder_bytes_used - 1] = component_sevenbits | continuation_overlay;
                 remainder_bytes_used += 1;
                 is_last_byte = false;
+                if component == 0 {
+                    break;
+                }
             }
 
-            len += try!(w.write(&remainder_bytes[(remainder_bytes_len - remainder_bytes_used)..remainder_bytes_len]));
+            try!(w.write_all(&remainder_bytes[(remainder_bytes_len - remainder_bytes_used)..remainder_bytes_len]));
+            len += remainder_bytes_used;
         }
 
         Ok(len)
     }
 }
 
+impl std::fmt::Display for ObjectIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, c) in self.components.iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the dotted decimal form, e.g. `2.5.4.3`.
+impl std::str::FromStr for ObjectIdentifier {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ObjectIdentifier> {
+        let mut components = Vec::new();
+        for c in s.split('.') {
+            if c.is_empty() || !c.bytes().all(|b| b.is_ascii_digit()) || (c.len() > 1 && c.starts_with('0')) {
+                return Err(Error::InvalidFormat);
+            }
+            components.push(c.parse().map_err(|_| Error::ComponentOverflow)?);
+        }
+        let oid = ObjectIdentifier {
+            components,
+        };
+        if !oid.is_valid() {
+            return Err(Error::InvalidFormat);
+        }
+        Ok(oid)
+    }
+}
+
+
 #[inline]
 fn byte_len_for_component(mut c: u64) -> usize {
-    let mut len = 0;
-    while c > 0 {
+    let mut len = 1;
+    while c >= 128 {
         len += 1;
         c /= 128;
     }
@@ -191,6 +235,7 @@ mod test {
             let oid = ObjectIdentifier::from_bytes(&input).unwrap();
 
             assert_eq!(oid.components(), &[ 1, 2, 840, 113549, 1, 7, 2]);
+            assert_eq!(oid.to_string(), "1.2.840.113549.1.7.2");
         }
     }
 
@@ -209,4 +254,43 @@ mod test {
             assert_eq!(oid_bytes, input);
         }
     }
+
+    #[test]
+    fn test_oid_from_str() {
+        let oid: ObjectIdentifier = "1.2.840.113549.1.7.2".parse().unwrap();
+        assert_eq!(oid.components(), &[1, 2, 840, 113549, 1, 7, 2]);
+
+        let oid: ObjectIdentifier = "2.5.4.0".parse().unwrap();
+        assert_eq!(oid.to_bytes(), [0x55, 0x04, 0x00]);
+        assert_eq!(oid.bytes_len(), 3);
+
+        assert!("1".parse::<ObjectIdentifier>().is_err());
+        assert!("1..2".parse::<ObjectIdentifier>().is_err());
+        assert!("1.02".parse::<ObjectIdentifier>().is_err());
+        assert!("3.1".parse::<ObjectIdentifier>().is_err());
+        assert!("0.40".parse::<ObjectIdentifier>().is_err());
+        assert!("1.40".parse::<ObjectIdentifier>().is_err());
+
+        let oid: ObjectIdentifier = "2.999.1".parse().unwrap();
+        assert_eq!(oid.components(), &[2, 999, 1]);
+        assert_eq!(oid.to_bytes(), [0x88, 0x37, 0x01]);
+        assert!("1.2.x".parse::<ObjectIdentifier>().is_err());
+    }
+
+    #[test]
+    fn test_oid_joint_iso_itu_t() {
+        // Under joint-iso-itu-t the second arc may be 40 or more, so the first subidentifier spans several octets.
+        let oid = ObjectIdentifier::from_components(&[2, 999, 3]);
+        assert!(oid.is_valid());
+        assert_eq!(oid.to_bytes(), [0x88, 0x37, 0x03]);
+        assert_eq!(oid.bytes_len(), 3);
+        assert_eq!(ObjectIdentifier::from_bytes(&[0x88, 0x37, 0x03]).unwrap(), oid);
+        assert_eq!(ObjectIdentifier::from_bytes(&[0x50]).unwrap().components(), &[2, 0]);
+        assert_eq!(ObjectIdentifier::from_bytes(&[0x4f]).unwrap().components(), &[1, 39]);
+
+        assert!(!ObjectIdentifier::from_components(&[1]).is_valid());
+        assert!(!ObjectIdentifier::from_components(&[1, 40]).is_valid());
+        assert!(!ObjectIdentifier::from_components(&[3, 1]).is_valid());
+        assert!(!ObjectIdentifier::from_components(&[2, u64::MAX]).is_valid());
+    }
 }
@@ -0,0 +1,901 @@
+use bit_string::BitString;
+use enumerated::Enumerated;
+use identifier::Identifier;
+use object_identifier::ObjectIdentifier;
+use reading::{self, Asn1Reader};
+use std;
+use time::DateTime;
+use writing::der::{self, Writer};
+use x509::{self, extensions::{CrlReason, GeneralName}, AlgorithmIdentifier, Extension, Name};
+
+
+pub mod oid {
+    pub const PKIX_OCSP_BASIC: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 48, 1, 1];
+    pub const PKIX_OCSP_NONCE: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 48, 1, 2];
+}
+
+
+#[derive(Debug)]
+pub enum Error {
+    Read(reading::Error),
+    InvalidVersion,
+    InvalidValue,
+    UnexpectedResponseType(ObjectIdentifier),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Read(ref e) => e.fmt(f),
+            Error::InvalidVersion => write!(f, "invalid version"),
+            Error::InvalidValue => write!(f, "invalid value"),
+            Error::UnexpectedResponseType(ref oid) => write!(f, "unexpected response type: {}", oid),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Read(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reading::Error> for Error {
+    fn from(e: reading::Error) -> Error {
+        Error::Read(e)
+    }
+}
+
+impl From<x509::Error> for Error {
+    fn from(e: x509::Error) -> Error {
+        match e {
+            x509::Error::Read(e) => Error::Read(e),
+            x509::Error::InvalidVersion => Error::InvalidVersion,
+            _ => Error::InvalidValue,
+        }
+    }
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// Reads `[tag] EXPLICIT Version DEFAULT v1`. OCSP only defines v1.
+fn read_version(r: &mut Asn1Reader, tag: u64) -> Result<()> {
+    if let Some(b) = r.read_optional(Identifier::context_specific(true, tag))? {
+        let mut r = Asn1Reader::new(b);
+        let v = r.read_i64()?;
+        r.expect_end()?;
+        if v != 0 {
+            return Err(Error::InvalidVersion);
+        }
+    }
+    Ok(())
+}
+
+fn read_optional_extensions<'a>(r: &mut Asn1Reader<'a>, tag: u64) -> Result<Vec<Extension<'a>>> {
+    match r.read_optional(Identifier::context_specific(true, tag))? {
+        Some(b) => {
+            let mut r = Asn1Reader::new(b);
+            let extensions = Extension::read_list(&mut r)?;
+            r.expect_end()?;
+            Ok(extensions)
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+fn write_optional_extensions<W: std::io::Write>(w: &mut Writer<W>, tag: u64, extensions: &[Extension]) -> der::Result<()> {
+    if !extensions.is_empty() {
+        w.write_explicit(tag, |w| {
+            Extension::write_list(w, extensions)?;
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+/// Reads `[0] EXPLICIT SEQUENCE OF Certificate OPTIONAL`, returning the complete encoding of each certificate.
+fn read_optional_certs<'a>(r: &mut Asn1Reader<'a>) -> Result<Vec<&'a [u8]>> {
+    let mut certs = Vec::new();
+    if let Some(b) = r.read_optional(Identifier::context_specific(true, 0))? {
+        let mut r = Asn1Reader::new(b);
+        let mut seq = r.read_sequence()?;
+        r.expect_end()?;
+        while !seq.is_at_end() {
+            certs.push(seq.read_raw()?);
+        }
+    }
+    Ok(certs)
+}
+
+fn write_optional_certs<W: std::io::Write>(w: &mut Writer<W>, certs: &[&[u8]]) -> der::Result<()> {
+    if !certs.is_empty() {
+        w.write_explicit(0, |w| {
+            w.write_sequence(|w| {
+                for cert in certs {
+                    w.write_raw(cert)?;
+                }
+                Ok(())
+            })?;
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct CertId<'a> {
+    pub hash_algorithm: AlgorithmIdentifier<'a>,
+    pub issuer_name_hash: &'a [u8],
+    pub issuer_key_hash: &'a [u8],
+    /// The validated contents of the serialNumber INTEGER.
+    pub serial_number: &'a [u8],
+}
+
+impl<'a> CertId<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = CertId::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let hash_algorithm = AlgorithmIdentifier::read(&mut r)?;
+        let issuer_name_hash = r.read_octet_string()?;
+        let issuer_key_hash = r.read_octet_string()?;
+        let serial_number = r.read_integer_bytes()?;
+        r.expect_end()?;
+        Ok(CertId {
+            hash_algorithm,
+            issuer_name_hash,
+            issuer_key_hash,
+            serial_number,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            self.hash_algorithm.write(w)?;
+            w.write_octet_string(self.issuer_name_hash)?;
+            w.write_octet_string(self.issuer_key_hash)?;
+            w.write_integer_bytes(self.serial_number)?;
+            Ok(())
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Request<'a> {
+    pub req_cert: CertId<'a>,
+    pub single_request_extensions: Vec<Extension<'a>>,
+}
+
+impl<'a> Request<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let req_cert = CertId::read(&mut r)?;
+        let single_request_extensions = read_optional_extensions(&mut r, 0)?;
+        r.expect_end()?;
+        Ok(Request {
+            req_cert,
+            single_request_extensions,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            self.req_cert.write(w)?;
+            write_optional_extensions(w, 0, &self.single_request_extensions)
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct TbsRequest<'a> {
+    pub requestor_name: Option<GeneralName<'a>>,
+    pub request_list: Vec<Request<'a>>,
+    pub request_extensions: Vec<Extension<'a>>,
+}
+
+impl<'a> TbsRequest<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        read_version(&mut r, 0)?;
+        let requestor_name = match r.read_optional(Identifier::context_specific(true, 1))? {
+            Some(b) => {
+                let mut r = Asn1Reader::new(b);
+                let v = GeneralName::read(&mut r)?;
+                r.expect_end()?;
+                Some(v)
+            }
+            None => None,
+        };
+        let mut request_list = Vec::new();
+        {
+            let mut seq = r.read_sequence()?;
+            while !seq.is_at_end() {
+                request_list.push(Request::read(&mut seq)?);
+            }
+        }
+        let request_extensions = read_optional_extensions(&mut r, 2)?;
+        r.expect_end()?;
+        Ok(TbsRequest {
+            requestor_name,
+            request_list,
+            request_extensions,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            if let Some(ref requestor_name) = self.requestor_name {
+                w.write_explicit(1, |w| {
+                    requestor_name.write(w)?;
+                    Ok(())
+                })?;
+            }
+            w.write_sequence(|w| {
+                for request in &self.request_list {
+                    request.write(w)?;
+                }
+                Ok(())
+            })?;
+            write_optional_extensions(w, 2, &self.request_extensions)
+        })
+    }
+
+    /// The DER encoding of the TBSRequest, over which the optional signature is computed.
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Signature<'a> {
+    pub signature_algorithm: AlgorithmIdentifier<'a>,
+    pub signature: BitString<'a>,
+    /// The complete encoding of each certificate.
+    pub certs: Vec<&'a [u8]>,
+}
+
+impl<'a> Signature<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let signature_algorithm = AlgorithmIdentifier::read(&mut r)?;
+        let signature = r.read_bit_string()?;
+        let certs = read_optional_certs(&mut r)?;
+        r.expect_end()?;
+        Ok(Signature {
+            signature_algorithm,
+            signature,
+            certs,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            self.signature_algorithm.write(w)?;
+            w.write_bit_string(self.signature)?;
+            write_optional_certs(w, &self.certs)
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct OcspRequest<'a> {
+    pub tbs_request: TbsRequest<'a>,
+    pub optional_signature: Option<Signature<'a>>,
+}
+
+impl<'a> OcspRequest<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = OcspRequest::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let tbs_request = TbsRequest::read(&mut r)?;
+        let optional_signature = match r.read_optional(Identifier::context_specific(true, 0))? {
+            Some(b) => {
+                let mut r = Asn1Reader::new(b);
+                let v = Signature::read(&mut r)?;
+                r.expect_end()?;
+                Some(v)
+            }
+            None => None,
+        };
+        r.expect_end()?;
+        Ok(OcspRequest {
+            tbs_request,
+            optional_signature,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            self.tbs_request.write(w)?;
+            if let Some(ref signature) = self.optional_signature {
+                w.write_explicit(0, |w| {
+                    signature.write(w)?;
+                    Ok(())
+                })?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum OcspResponseStatus {
+    Successful,
+    MalformedRequest,
+    InternalError,
+    TryLater,
+    SigRequired,
+    Unauthorized,
+}
+
+impl Enumerated for OcspResponseStatus {
+    fn from_i64(v: i64) -> Option<Self> {
+        Some(match v {
+            0 => OcspResponseStatus::Successful,
+            1 => OcspResponseStatus::MalformedRequest,
+            2 => OcspResponseStatus::InternalError,
+            3 => OcspResponseStatus::TryLater,
+            5 => OcspResponseStatus::SigRequired,
+            6 => OcspResponseStatus::Unauthorized,
+            _ => return None,
+        })
+    }
+
+    fn into_i64(self) -> i64 {
+        match self {
+            OcspResponseStatus::Successful => 0,
+            OcspResponseStatus::MalformedRequest => 1,
+            OcspResponseStatus::InternalError => 2,
+            OcspResponseStatus::TryLater => 3,
+            OcspResponseStatus::SigRequired => 5,
+            OcspResponseStatus::Unauthorized => 6,
+        }
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct ResponseBytes<'a> {
+    pub response_type: ObjectIdentifier,
+    /// The contents of the response OCTET STRING, i.e. the DER encoding of the response.
+    pub response: &'a [u8],
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct OcspResponse<'a> {
+    pub response_status: OcspResponseStatus,
+    pub response_bytes: Option<ResponseBytes<'a>>,
+}
+
+impl<'a> OcspResponse<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = OcspResponse::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let response_status = r.read_enumerated()?;
+        let response_bytes = match r.read_optional(Identifier::context_specific(true, 0))? {
+            Some(b) => {
+                let mut r = Asn1Reader::new(b);
+                let mut seq = r.read_sequence()?;
+                r.expect_end()?;
+                let response_type = seq.read_object_identifier()?;
+                let response = seq.read_octet_string()?;
+                seq.expect_end()?;
+                Some(ResponseBytes {
+                    response_type,
+                    response,
+                })
+            }
+            None => None,
+        };
+        r.expect_end()?;
+        Ok(OcspResponse {
+            response_status,
+            response_bytes,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_enumerated(self.response_status)?;
+            if let Some(ref response_bytes) = self.response_bytes {
+                w.write_explicit(0, |w| {
+                    w.write_sequence(|w| {
+                        w.write_object_identifier(&response_bytes.response_type)?;
+                        w.write_octet_string(response_bytes.response)?;
+                        Ok(())
+                    })?;
+                    Ok(())
+                })?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+
+    /// Decodes the response bytes as a BasicOCSPResponse, if present.
+    pub fn basic_response(&self) -> Result<Option<BasicOcspResponse<'a>>> {
+        match self.response_bytes {
+            Some(ref b) if b.response_type.components() == oid::PKIX_OCSP_BASIC => Ok(Some(BasicOcspResponse::from_der(b.response)?)),
+            Some(ref b) => Err(Error::UnexpectedResponseType(b.response_type.clone())),
+            None => Ok(None),
+        }
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum ResponderId<'a> {
+    ByName(Name<'a>),
+    /// The SHA-1 hash of the responder's public key.
+    ByKey(&'a [u8]),
+}
+
+impl<'a> ResponderId<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        if let Some(b) = r.read_optional(Identifier::context_specific(true, 1))? {
+            let mut r = Asn1Reader::new(b);
+            let v = Name::read(&mut r)?;
+            r.expect_end()?;
+            return Ok(ResponderId::ByName(v));
+        }
+        let b = r.read_expected(Identifier::context_specific(true, 2))?;
+        let mut r = Asn1Reader::new(b);
+        let v = r.read_octet_string()?;
+        r.expect_end()?;
+        Ok(ResponderId::ByKey(v))
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        match *self {
+            ResponderId::ByName(ref name) => w.write_explicit(1, |w| {
+                name.write(w)?;
+                Ok(())
+            }),
+            ResponderId::ByKey(key_hash) => w.write_explicit(2, |w| {
+                w.write_octet_string(key_hash)?;
+                Ok(())
+            }),
+        }
+    }
+}
+
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct RevokedInfo {
+    pub revocation_time: DateTime,
+    pub revocation_reason: Option<CrlReason>,
+}
+
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum CertStatus {
+    Good,
+    Revoked(RevokedInfo),
+    Unknown,
+}
+
+impl CertStatus {
+    pub(crate) fn read(r: &mut Asn1Reader) -> Result<Self> {
+        if let Some(b) = r.read_optional(Identifier::context_specific(false, 0))? {
+            if !b.is_empty() {
+                return Err(Error::InvalidValue);
+            }
+            return Ok(CertStatus::Good);
+        }
+        if let Some(b) = r.read_optional(Identifier::context_specific(true, 1))? {
+            let mut r = Asn1Reader::new(b);
+            let revocation_time = r.read_generalized_time()?;
+            let revocation_reason = match r.read_optional(Identifier::context_specific(true, 0))? {
+                Some(b) => Some(CrlReason::from_der(b)?),
+                None => None,
+            };
+            r.expect_end()?;
+            return Ok(CertStatus::Revoked(RevokedInfo {
+                revocation_time,
+                revocation_reason,
+            }));
+        }
+        let b = r.read_expected(Identifier::context_specific(false, 2))?;
+        if !b.is_empty() {
+            return Err(Error::InvalidValue);
+        }
+        Ok(CertStatus::Unknown)
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        match *self {
+            CertStatus::Good => w.write_tag_and_data(Identifier::context_specific(false, 0), &[]),
+            CertStatus::Revoked(ref info) => w.write_constructed(Identifier::context_specific(true, 1), |w| {
+                w.write_generalized_time(info.revocation_time)?;
+                if let Some(reason) = info.revocation_reason {
+                    w.write_explicit(0, |w| {
+                        reason.write(w)?;
+                        Ok(())
+                    })?;
+                }
+                Ok(())
+            }),
+            CertStatus::Unknown => w.write_tag_and_data(Identifier::context_specific(false, 2), &[]),
+        }
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct SingleResponse<'a> {
+    pub cert_id: CertId<'a>,
+    pub cert_status: CertStatus,
+    pub this_update: DateTime,
+    pub next_update: Option<DateTime>,
+    pub single_extensions: Vec<Extension<'a>>,
+}
+
+impl<'a> SingleResponse<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let cert_id = CertId::read(&mut r)?;
+        let cert_status = CertStatus::read(&mut r)?;
+        let this_update = r.read_generalized_time()?;
+        let next_update = match r.read_optional(Identifier::context_specific(true, 0))? {
+            Some(b) => {
+                let mut r = Asn1Reader::new(b);
+                let v = r.read_generalized_time()?;
+                r.expect_end()?;
+                Some(v)
+            }
+            None => None,
+        };
+        let single_extensions = read_optional_extensions(&mut r, 1)?;
+        r.expect_end()?;
+        Ok(SingleResponse {
+            cert_id,
+            cert_status,
+            this_update,
+            next_update,
+            single_extensions,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            self.cert_id.write(w)?;
+            self.cert_status.write(w)?;
+            w.write_generalized_time(self.this_update)?;
+            if let Some(next_update) = self.next_update {
+                w.write_explicit(0, |w| {
+                    w.write_generalized_time(next_update)?;
+                    Ok(())
+                })?;
+            }
+            write_optional_extensions(w, 1, &self.single_extensions)
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct ResponseData<'a> {
+    pub responder_id: ResponderId<'a>,
+    pub produced_at: DateTime,
+    pub responses: Vec<SingleResponse<'a>>,
+    pub response_extensions: Vec<Extension<'a>>,
+}
+
+impl<'a> ResponseData<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        read_version(&mut r, 0)?;
+        let responder_id = ResponderId::read(&mut r)?;
+        let produced_at = r.read_generalized_time()?;
+        let mut responses = Vec::new();
+        {
+            let mut seq = r.read_sequence()?;
+            while !seq.is_at_end() {
+                responses.push(SingleResponse::read(&mut seq)?);
+            }
+        }
+        let response_extensions = read_optional_extensions(&mut r, 1)?;
+        r.expect_end()?;
+        Ok(ResponseData {
+            responder_id,
+            produced_at,
+            responses,
+            response_extensions,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            self.responder_id.write(w)?;
+            w.write_generalized_time(self.produced_at)?;
+            w.write_sequence(|w| {
+                for response in &self.responses {
+                    response.write(w)?;
+                }
+                Ok(())
+            })?;
+            write_optional_extensions(w, 1, &self.response_extensions)
+        })
+    }
+
+    /// The DER encoding of the ResponseData, over which the response signature is computed.
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+
+    pub fn extension(&self, extn_id: &[u64]) -> Option<&Extension<'a>> {
+        self.response_extensions.iter().find(|e| e.extn_id.components() == extn_id)
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct BasicOcspResponse<'a> {
+    pub tbs_response_data: ResponseData<'a>,
+    pub signature_algorithm: AlgorithmIdentifier<'a>,
+    pub signature: BitString<'a>,
+    /// The complete encoding of each certificate.
+    pub certs: Vec<&'a [u8]>,
+}
+
+impl<'a> BasicOcspResponse<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = BasicOcspResponse::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let tbs_response_data = ResponseData::read(&mut r)?;
+        let signature_algorithm = AlgorithmIdentifier::read(&mut r)?;
+        let signature = r.read_bit_string()?;
+        let certs = read_optional_certs(&mut r)?;
+        r.expect_end()?;
+        Ok(BasicOcspResponse {
+            tbs_response_data,
+            signature_algorithm,
+            signature,
+            certs,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            self.tbs_response_data.write(w)?;
+            self.signature_algorithm.write(w)?;
+            w.write_bit_string(self.signature)?;
+            write_optional_certs(w, &self.certs)
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pem;
+
+    const REQUEST: &str = "\
+MIGAMH4wfDA8MDowCQYFKw4DAhoFAAQUgjeAcyae9yAs+zqQkTsYc213LcoEFAfm
+o9JsAXUN7k6g3TofBFShw3LqAgEHMDwwOjAJBgUrDgMCGgUABBSCN4BzJp73ICz7
+OpCROxhzbXctygQUB+aj0mwBdQ3uTqDdOh8EVKHDcuoCAQg=";
+
+    const RESPONSE: &str = "\
+MIIBgQoBAKCCAXowggF2BgkrBgEFBQcwAQEEggFnMIIBYzCCAQqiFgQUB+aj0mwB
+dQ3uTqDdOh8EVKHDcuoYDzIwMjYxMDE4MjEzMDE3WjCB3jB4MDowCQYFKw4DAhoF
+AAQUgjeAcyae9yAs+zqQkTsYc213LcoEFAfmo9JsAXUN7k6g3TofBFShw3LqAgEH
+oRYYDzIwMjUwMzAzMTIwMDAwWqADCgEBGA8yMDI2MTAxODIxMzAxN1qgERgPMjAy
+NjEwMjUyMTMwMTdaMGIwOjAJBgUrDgMCGgUABBSCN4BzJp73ICz7OpCROxhzbXct
+ygQUB+aj0mwBdQ3uTqDdOh8EVKHDcuoCAQiCABgPMjAyNjEwMTgyMTMwMTdaoBEY
+DzIwMjYxMDI1MjEzMDE3WjAKBggqhkjOPQQDAgNHADBEAiBUo5scH4Hpsgl0yT22
+qjRvg9WicZalS3E4edbh0JafDgIgNVlln8AUs89k0ALqqjqDJYGtCDD8yyHikjoM
+udQAp1E=";
+
+    const ISSUER_NAME_HASH: [u8; 20] = [
+        0x82, 0x37, 0x80, 0x73, 0x26, 0x9e, 0xf7, 0x20, 0x2c, 0xfb, 0x3a, 0x90, 0x91, 0x3b, 0x18, 0x73, 0x6d, 0x77, 0x2d, 0xca,
+    ];
+    const ISSUER_KEY_HASH: [u8; 20] = [
+        0x07, 0xe6, 0xa3, 0xd2, 0x6c, 0x01, 0x75, 0x0d, 0xee, 0x4e, 0xa0, 0xdd, 0x3a, 0x1f, 0x04, 0x54, 0xa1, 0xc3, 0x72, 0xea,
+    ];
+
+    fn decode(b64: &str) -> Vec<u8> {
+        let pem = format!("-----BEGIN X-----\n{}\n-----END X-----\n", b64);
+        pem::parse(&pem, pem::Mode::Strict).unwrap().into_contents()
+    }
+
+    fn cert_id(serial_number: &'static [u8]) -> CertId<'static> {
+        CertId {
+            hash_algorithm: AlgorithmIdentifier::from_der(&[0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00]).unwrap(),
+            issuer_name_hash: &ISSUER_NAME_HASH,
+            issuer_key_hash: &ISSUER_KEY_HASH,
+            serial_number,
+        }
+    }
+
+    #[test]
+    fn test_request() {
+        let der = decode(REQUEST);
+        let request = OcspRequest::from_der(&der).unwrap();
+        assert_eq!(request.optional_signature, None);
+        assert_eq!(request.tbs_request.requestor_name, None);
+        assert!(request.tbs_request.request_extensions.is_empty());
+        assert_eq!(request.tbs_request.request_list.len(), 2);
+        assert_eq!(request.tbs_request.request_list[0].req_cert, cert_id(&[7]));
+        assert_eq!(request.tbs_request.request_list[1].req_cert, cert_id(&[8]));
+
+        let built = OcspRequest {
+            tbs_request: TbsRequest {
+                requestor_name: None,
+                request_list: vec![
+                    Request { req_cert: cert_id(&[7]), single_request_extensions: Vec::new() },
+                    Request { req_cert: cert_id(&[8]), single_request_extensions: Vec::new() },
+                ],
+                request_extensions: Vec::new(),
+            },
+            optional_signature: None,
+        };
+        assert_eq!(built.to_der().unwrap(), der);
+    }
+
+    #[test]
+    fn test_request_tagging() {
+        let request = OcspRequest {
+            tbs_request: TbsRequest {
+                requestor_name: Some(GeneralName::DnsName("a")),
+                request_list: Vec::new(),
+                request_extensions: vec![Extension {
+                    extn_id: ObjectIdentifier::from_components(oid::PKIX_OCSP_NONCE),
+                    critical: false,
+                    extn_value: &[0x04, 0x01, 0xaa],
+                }],
+            },
+            optional_signature: Some(Signature {
+                signature_algorithm: AlgorithmIdentifier::from_der(&[0x30, 0x03, 0x06, 0x01, 0x2a]).unwrap(),
+                signature: BitString::from_bytes(&[0x00, 0xbb]).unwrap(),
+                certs: vec![&[0x30, 0x00]],
+            }),
+        };
+        let der = request.to_der().unwrap();
+        assert_eq!(der, [
+            0x30, 0x32,
+                0x30, 0x1d,
+                    0xa1, 0x03, 0x82, 0x01, 0x61,
+                    0x30, 0x00,
+                    0xa2, 0x14, 0x30, 0x12, 0x30, 0x10,
+                        0x06, 0x09, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x02,
+                        0x04, 0x03, 0x04, 0x01, 0xaa,
+                0xa0, 0x11, 0x30, 0x0f,
+                    0x30, 0x03, 0x06, 0x01, 0x2a,
+                    0x03, 0x02, 0x00, 0xbb,
+                    0xa0, 0x04, 0x30, 0x02, 0x30, 0x00,
+        ][..]);
+        assert_eq!(OcspRequest::from_der(&der).unwrap(), request);
+    }
+
+    #[test]
+    fn test_response() {
+        let der = decode(RESPONSE);
+        let response = OcspResponse::from_der(&der).unwrap();
+        assert_eq!(response.response_status, OcspResponseStatus::Successful);
+        assert_eq!(response.to_der().unwrap(), der);
+
+        let basic = response.basic_response().unwrap().unwrap();
+        assert_eq!(basic.signature_algorithm.algorithm.components(), [1, 2, 840, 10045, 4, 3, 2]);
+        assert!(basic.certs.is_empty());
+        assert_eq!(basic.to_der().unwrap(), response.response_bytes.as_ref().unwrap().response);
+
+        let data = &basic.tbs_response_data;
+        assert_eq!(data.responder_id, ResponderId::ByKey(&ISSUER_KEY_HASH));
+        assert_eq!(data.produced_at, DateTime::new(2026, 10, 18, 21, 30, 17).unwrap());
+        assert!(data.response_extensions.is_empty());
+        assert_eq!(data.responses.len(), 2);
+
+        let revoked = &data.responses[0];
+        assert_eq!(revoked.cert_id, cert_id(&[7]));
+        assert_eq!(revoked.cert_status, CertStatus::Revoked(RevokedInfo {
+            revocation_time: DateTime::new(2025, 3, 3, 12, 0, 0).unwrap(),
+            revocation_reason: Some(CrlReason::KeyCompromise),
+        }));
+        assert_eq!(revoked.this_update, DateTime::new(2026, 10, 18, 21, 30, 17).unwrap());
+        assert_eq!(revoked.next_update, Some(DateTime::new(2026, 10, 25, 21, 30, 17).unwrap()));
+
+        let unknown = &data.responses[1];
+        assert_eq!(unknown.cert_id, cert_id(&[8]));
+        assert_eq!(unknown.cert_status, CertStatus::Unknown);
+    }
+
+    #[test]
+    fn test_response_status_only() {
+        let response = OcspResponse {
+            response_status: OcspResponseStatus::TryLater,
+            response_bytes: None,
+        };
+        let der = response.to_der().unwrap();
+        assert_eq!(der, [0x30, 0x03, 0x0a, 0x01, 0x03]);
+        let decoded = OcspResponse::from_der(&der).unwrap();
+        assert_eq!(decoded, response);
+        assert_eq!(decoded.basic_response().unwrap(), None);
+
+        assert!(OcspResponse::from_der(&[0x30, 0x03, 0x0a, 0x01, 0x04]).is_err());
+    }
+
+    #[test]
+    fn test_single_response_by_name() {
+        let name = "CN=Example CA".parse::<Name>().unwrap();
+        let data = ResponseData {
+            responder_id: ResponderId::ByName(name),
+            produced_at: DateTime::new(2026, 1, 1, 0, 0, 0).unwrap(),
+            responses: vec![SingleResponse {
+                cert_id: cert_id(&[9]),
+                cert_status: CertStatus::Good,
+                this_update: DateTime::new(2026, 1, 1, 0, 0, 0).unwrap(),
+                next_update: None,
+                single_extensions: Vec::new(),
+            }],
+            response_extensions: Vec::new(),
+        };
+        let der = data.to_der().unwrap();
+        assert_eq!(&der[2..4], [0xa1, 0x17]);
+        let decoded = ResponseData::read(&mut Asn1Reader::new(&der)).unwrap();
+        assert_eq!(decoded, data);
+        assert_eq!(decoded.responses[0].cert_status, CertStatus::Good);
+    }
+}
@@ -0,0 +1,370 @@
+use identifier::{Class, Identifier};
+use integer;
+use object_identifier::ObjectIdentifier;
+use oer::{self, Error, IntegerConstraint, Result, SizeConstraint};
+use std;
+
+
+/// Reads OER encodings from a byte slice, in the order they were written.
+#[derive(Clone,Debug)]
+pub struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    canonical: bool,
+}
+
+impl<'a> Decoder<'a> {
+    /// A decoder for basic OER.
+    pub fn new(b: &'a [u8]) -> Self {
+        Decoder {
+            buf: b,
+            pos: 0,
+            canonical: false,
+        }
+    }
+
+    /// A decoder that rejects encodings which are not canonical (COER) with `Error::NonCanonical`.
+    pub fn canonical(b: &'a [u8]) -> Self {
+        Decoder {
+            buf: b,
+            pos: 0,
+            canonical: true,
+        }
+    }
+
+    pub fn is_canonical(&self) -> bool {
+        self.canonical
+    }
+
+    /// The number of octets read so far.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.pos == self.buf.len()
+    }
+
+    /// Reads `len` octets, for encodings not covered by the methods here.
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.buf.len() - self.pos {
+            return Err(Error::UnexpectedEndOfData);
+        }
+        let v = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(v)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn check_canonical(&self, canonical: bool) -> Result<()> {
+        if self.canonical && !canonical {
+            return Err(Error::NonCanonical);
+        }
+        Ok(())
+    }
+
+    pub fn decode_boolean(&mut self) -> Result<bool> {
+        let v = self.read_u8()?;
+        self.check_canonical(v == 0x00 || v == 0xff)?;
+        Ok(v != 0)
+    }
+
+    pub fn decode_length(&mut self) -> Result<usize> {
+        let first = self.read_u8()?;
+        if first < 0x80 {
+            return Ok(first as usize);
+        }
+        let octets = self.read_bytes((first & 0x7f) as usize)?;
+        self.check_canonical(octets.first().is_some_and(|b| *b != 0))?;
+        let len = unsigned_value(octets)?;
+        self.check_canonical(len >= 128)?;
+        if len > usize::MAX as u64 {
+            return Err(Error::UnexpectedEndOfData);
+        }
+        Ok(len as usize)
+    }
+
+    pub fn decode_integer(&mut self, constraint: &IntegerConstraint) -> Result<i64> {
+        let v = match oer::integer_width(constraint) {
+            Some((width, signed)) => {
+                let octets = self.read_bytes(width)?;
+                let mut v: i64 = if signed && octets[0] & 0x80 != 0 { -1 } else { 0 };
+                for &byte in octets {
+                    v = (v << 8) | byte as i64;
+                }
+                if !signed && width == 8 && v < 0 {
+                    return Err(Error::ValueOutOfRange);
+                }
+                v
+            }
+            None if !constraint.extensible && constraint.lower.is_some_and(|lower| lower >= 0) => {
+                let len = self.decode_length()?;
+                let octets = self.read_bytes(len)?;
+                self.check_canonical(octets.len() == 1 || octets.first().is_some_and(|b| *b != 0))?;
+                let v = unsigned_value(octets)?;
+                if v > i64::MAX as u64 {
+                    return Err(Error::ValueOutOfRange);
+                }
+                v as i64
+            }
+            None => {
+                let len = self.decode_length()?;
+                integer::to_i64(self.read_bytes(len)?)?
+            }
+        };
+        if !constraint.extensible && !constraint.contains(v) {
+            return Err(Error::ValueOutOfRange);
+        }
+        Ok(v)
+    }
+
+    /// Decodes the value of an ENUMERATED item; see `Encoder::encode_enumerated`.
+    pub fn decode_enumerated(&mut self) -> Result<i64> {
+        let first = self.read_u8()?;
+        if first < 0x80 {
+            return Ok(first as i64);
+        }
+        let v = integer::to_i64(self.read_bytes((first & 0x7f) as usize)?)?;
+        self.check_canonical(!(0..128).contains(&v))?;
+        Ok(v)
+    }
+
+    /// Decodes a BIT STRING, returning its bits packed into octets and its length in bits.
+    pub fn decode_bit_string(&mut self, constraint: &SizeConstraint) -> Result<(Vec<u8>, usize)> {
+        if let Some(size) = oer::fixed_size(constraint) {
+            let octets = self.read_bytes(size.div_ceil(8))?;
+            return Ok((self.bits_with_unused(octets, size.div_ceil(8) * 8 - size)?, size));
+        }
+        let len = self.decode_length()?;
+        if len == 0 {
+            return Err(Error::UnexpectedEndOfData);
+        }
+        let unused = self.read_u8()? as usize;
+        let octets = self.read_bytes(len - 1)?;
+        if unused > 7 || (octets.is_empty() && unused != 0) {
+            return Err(Error::ValueOutOfRange);
+        }
+        let bit_len = octets.len() * 8 - unused;
+        if !constraint.extensible && !constraint.contains(bit_len) {
+            return Err(Error::ValueOutOfRange);
+        }
+        Ok((self.bits_with_unused(octets, unused)?, bit_len))
+    }
+
+    fn bits_with_unused(&self, octets: &[u8], unused: usize) -> Result<Vec<u8>> {
+        let mut octets = octets.to_vec();
+        if let Some(last) = octets.last_mut() {
+            let mask = !(0xffu8 << unused);
+            self.check_canonical(*last & mask == 0)?;
+            *last &= !mask;
+        }
+        Ok(octets)
+    }
+
+    pub fn decode_octet_string(&mut self, constraint: &SizeConstraint) -> Result<&'a [u8]> {
+        let len = match oer::fixed_size(constraint) {
+            Some(size) => size,
+            None => self.decode_length()?,
+        };
+        if !constraint.extensible && !constraint.contains(len) {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.read_bytes(len)
+    }
+
+    pub fn decode_object_identifier(&mut self) -> Result<ObjectIdentifier> {
+        let len = self.decode_length()?;
+        Ok(ObjectIdentifier::from_bytes(self.read_bytes(len)?)?)
+    }
+
+    /// Decodes a SEQUENCE or SET preamble, returning whether extension additions are present and which of the
+    /// `optional_count` OPTIONAL or DEFAULT components are.
+    pub fn decode_sequence_preamble(&mut self, extensible: bool, optional_count: usize) -> Result<(bool, Vec<bool>)> {
+        let bit_len = extensible as usize + optional_count;
+        let octets = self.read_bytes(bit_len.div_ceil(8))?;
+        let bits = self.bits_with_unused(octets, octets.len() * 8 - bit_len)?;
+        let mut bits = (0..bit_len).map(|i| bits[i / 8] & (0x80 >> (i % 8)) != 0);
+        let extended = extensible && bits.next() == Some(true);
+        Ok((extended, bits.collect()))
+    }
+
+    /// Decodes the bitmap of present extension additions. It may be longer than the additions the decoder knows
+    /// about; the open types of unknown additions can be skipped with `decode_open_type`.
+    pub fn decode_extension_presence(&mut self) -> Result<Vec<bool>> {
+        let (bits, bit_len) = self.decode_bit_string(&SizeConstraint::range(1, usize::MAX))?;
+        Ok((0..bit_len).map(|i| bits[i / 8] & (0x80 >> (i % 8)) != 0).collect())
+    }
+
+    pub fn decode_quantity(&mut self) -> Result<usize> {
+        let len = self.read_u8()? as usize;
+        let octets = self.read_bytes(len)?;
+        self.check_canonical(octets.first().is_some_and(|b| *b != 0) || octets == [0])?;
+        let count = unsigned_value(octets)?;
+        if count > usize::MAX as u64 {
+            return Err(Error::UnexpectedEndOfData);
+        }
+        Ok(count as usize)
+    }
+
+    /// Decodes a SEQUENCE OF or SET OF, reading each component with `f`.
+    pub fn decode_sequence_of<T, F>(&mut self, constraint: &SizeConstraint, mut f: F) -> Result<Vec<T>>
+        where F: FnMut(&mut Decoder<'a>) -> Result<T>
+    {
+        let count = self.decode_quantity()?;
+        if !constraint.extensible && !constraint.contains(count) {
+            return Err(Error::ValueOutOfRange);
+        }
+        let mut items = Vec::with_capacity(std::cmp::min(count, self.buf.len() - self.pos));
+        for _ in 0..count {
+            items.push(f(self)?);
+        }
+        Ok(items)
+    }
+
+    /// Decodes the tag of a CHOICE alternative, returned as a primitive `Identifier`.
+    pub fn decode_choice_tag(&mut self) -> Result<Identifier> {
+        let first = self.read_u8()?;
+        let class = Class::from_u8(first);
+        if first & 0x3f != 0x3f {
+            return Ok(Identifier::new(class, false, (first & 0x3f) as u64));
+        }
+        let mut number = 0u64;
+        loop {
+            let b = self.read_u8()?;
+            if number == 0 && b == 0x80 {
+                self.check_canonical(false)?;
+            }
+            number = number.checked_mul(128).ok_or(Error::ValueOutOfRange)? | (b & 0x7f) as u64;
+            if b & 0x80 == 0 {
+                break;
+            }
+        }
+        self.check_canonical(number >= 63)?;
+        Ok(Identifier::new(class, false, number))
+    }
+
+    /// Decodes an open type, returning the complete encoding it carries for a separate `Decoder`.
+    pub fn decode_open_type(&mut self) -> Result<&'a [u8]> {
+        let len = self.decode_length()?;
+        self.read_bytes(len)
+    }
+}
+
+
+fn unsigned_value(octets: &[u8]) -> Result<u64> {
+    let start = octets.iter().position(|b| *b != 0).unwrap_or(octets.len());
+    if octets.is_empty() || octets.len() - start > 8 {
+        return Err(Error::ValueOutOfRange);
+    }
+    Ok(octets[start..].iter().fold(0u64, |v, b| (v << 8) | *b as u64))
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use oer::Encoder;
+    use oer::encoder::test::UNSECURED_DATA;
+
+    #[test]
+    fn test_roundtrip() {
+        let constraints = [
+            IntegerConstraint::unconstrained(),
+            IntegerConstraint::range(-5, 1000),
+            IntegerConstraint::range(0, 65535),
+            IntegerConstraint::range(i64::MIN, i64::MAX),
+            IntegerConstraint::range(0, i64::MAX),
+            IntegerConstraint::semi_constrained(-10),
+            IntegerConstraint::semi_constrained(0),
+            IntegerConstraint::range(0, 15).extensible(),
+        ];
+        let values = [-10, -5, 0, 1, 15, 16, 127, 128, 1000, i64::MAX];
+
+        let mut e = Encoder::new();
+        for c in &constraints {
+            for v in values.iter().filter(|v| c.extensible || c.contains(**v)) {
+                e.encode_integer(*v, c).unwrap();
+            }
+        }
+        e.encode_boolean(false).unwrap();
+        e.encode_enumerated(-300).unwrap();
+        e.encode_bit_string(&[0xde, 0xad, 0xbe, 0xef], 27, &SizeConstraint::range(0, 100)).unwrap();
+        e.encode_bit_string(&[0xc0], 2, &SizeConstraint::fixed(2)).unwrap();
+        e.encode_octet_string(&[0x55; 300], &SizeConstraint::range(1, 1000)).unwrap();
+        e.encode_sequence_preamble(Some(true), &[false, true]).unwrap();
+        e.encode_extension_presence(&[true; 10]).unwrap();
+        e.encode_object_identifier(&ObjectIdentifier::from_components(&[1, 2, 840, 10045])).unwrap();
+        e.encode_sequence_of(&[true, false], &SizeConstraint::range(1, 2), |e, v| e.encode_boolean(*v)).unwrap();
+        e.encode_choice_tag(Identifier::new(Class::Private, false, 1000)).unwrap();
+        e.encode_open_type(&[0x80]).unwrap();
+        let b = e.into_bytes();
+
+        let mut d = Decoder::canonical(&b);
+        for c in &constraints {
+            for v in values.iter().filter(|v| c.extensible || c.contains(**v)) {
+                assert_eq!(d.decode_integer(c).unwrap(), *v);
+            }
+        }
+        assert!(!d.decode_boolean().unwrap());
+        assert_eq!(d.decode_enumerated().unwrap(), -300);
+        assert_eq!(d.decode_bit_string(&SizeConstraint::range(0, 100)).unwrap(), (vec![0xde, 0xad, 0xbe, 0xe0], 27));
+        assert_eq!(d.decode_bit_string(&SizeConstraint::fixed(2)).unwrap(), (vec![0xc0], 2));
+        assert_eq!(d.decode_octet_string(&SizeConstraint::range(1, 1000)).unwrap(), &[0x55; 300][..]);
+        assert_eq!(d.decode_sequence_preamble(true, 2).unwrap(), (true, vec![false, true]));
+        assert_eq!(d.decode_extension_presence().unwrap(), vec![true; 10]);
+        assert_eq!(d.decode_object_identifier().unwrap().components(), [1, 2, 840, 10045]);
+        assert_eq!(d.decode_sequence_of(&SizeConstraint::range(1, 2), |d| d.decode_boolean()).unwrap(), [true, false]);
+        assert_eq!(d.decode_choice_tag().unwrap(), Identifier::new(Class::Private, false, 1000));
+        assert_eq!(d.decode_open_type().unwrap(), [0x80]);
+        assert!(d.is_at_end());
+    }
+
+    #[test]
+    fn test_ieee1609dot2() {
+        let mut d = Decoder::canonical(UNSECURED_DATA);
+        assert_eq!(d.decode_integer(&IntegerConstraint::range(3, 3)).unwrap(), 3);
+        assert_eq!(d.decode_choice_tag().unwrap(), Identifier::context_specific(false, 0));
+        assert_eq!(d.decode_octet_string(&SizeConstraint::unconstrained()).unwrap(), b"Hello");
+        assert!(d.is_at_end());
+    }
+
+    #[test]
+    fn test_canonical() {
+        // Each of these is valid basic OER but not canonical.
+        type Decode = fn(&mut Decoder) -> Result<()>;
+        let cases: &[(&[u8], Decode)] = &[
+            (&[0x01], |d| d.decode_boolean().map(|_| ())),
+            (&[0x81, 0x05], |d| d.decode_length().map(|_| ())),
+            (&[0x82, 0x00, 0x80], |d| d.decode_length().map(|_| ())),
+            (&[0x81, 0x05], |d| d.decode_enumerated().map(|_| ())),
+            (&[0x02, 0x00, 0x05], |d| d.decode_integer(&IntegerConstraint::semi_constrained(0)).map(|_| ())),
+            (&[0x02, 0x01, 0xff], |d| d.decode_bit_string(&SizeConstraint::unconstrained()).map(|_| ())),
+            (&[0x50 | 0x01], |d| d.decode_sequence_preamble(false, 4).map(|_| ())),
+            (&[0x02, 0x00, 0x03], |d| d.decode_quantity().map(|_| ())),
+            (&[0xbf, 0x05], |d| d.decode_choice_tag().map(|_| ())),
+        ];
+        for (b, f) in cases {
+            assert!(f(&mut Decoder::new(b)).is_ok());
+            match f(&mut Decoder::canonical(b)) {
+                Err(Error::NonCanonical) => (),
+                v => panic!("unexpected result {:?} for {:?}", v, b),
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Decoder::new(&[0x01]).decode_integer(&IntegerConstraint::range(2, 5)).is_err());
+        assert!(Decoder::new(&[0x05, 0x00]).decode_octet_string(&SizeConstraint::unconstrained()).is_err());
+        assert!(Decoder::new(&[0x89, 1, 0, 0, 0, 0, 0, 0, 0, 0]).decode_length().is_err());
+        assert!(Decoder::new(&[0x02, 0x08, 0xff]).decode_bit_string(&SizeConstraint::unconstrained()).is_err());
+        assert!(Decoder::new(&[0x00]).decode_bit_string(&SizeConstraint::unconstrained()).is_err());
+        assert!(Decoder::new(&[0x01, 0x05]).decode_sequence_of(&SizeConstraint::range(1, 4), |d| d.decode_boolean())
+            .is_err());
+        assert!(Decoder::new(&[0x3f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]).decode_choice_tag()
+            .is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use identifier::Identifier;
+use integer;
+use object_identifier::ObjectIdentifier;
+use oer::{self, Error, IntegerConstraint, Result, SizeConstraint};
+
+
+/// Writes the canonical OER encodings into an octet buffer.
+#[derive(Clone,Debug,Default)]
+pub struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Encoder::default()
+    }
+
+    /// The underlying octets, for encodings not covered by the methods here.
+    pub fn bytes(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn encode_boolean(&mut self, v: bool) -> Result<()> {
+        self.buf.push(if v { 0xff } else { 0x00 });
+        Ok(())
+    }
+
+    /// Encodes a length determinant in octets, in the short form below 128 and otherwise in the long form with
+    /// as few length octets as possible (X.696 8.6).
+    pub fn encode_length(&mut self, len: usize) -> Result<()> {
+        if len < 128 {
+            self.buf.push(len as u8);
+            return Ok(());
+        }
+        let octets = oer::unsigned_octets(len as u64);
+        self.buf.push(0x80 | octets.len() as u8);
+        self.buf.extend_from_slice(&octets);
+        Ok(())
+    }
+
+    /// Encodes an INTEGER in the fixed-size field its constraint allows, or else preceded by a length determinant,
+    /// as an unsigned number if the constraint excludes negative values (X.696 10).
+    pub fn encode_integer(&mut self, v: i64, constraint: &IntegerConstraint) -> Result<()> {
+        if !constraint.extensible && !constraint.contains(v) {
+            return Err(Error::ValueOutOfRange);
+        }
+        match oer::integer_width(constraint) {
+            Some((width, _)) => {
+                self.buf.extend_from_slice(&v.to_be_bytes()[8 - width..]);
+                Ok(())
+            }
+            None if !constraint.extensible && constraint.lower.is_some_and(|lower| lower >= 0) => {
+                self.encode_octets_with_length(&oer::unsigned_octets(v as u64))
+            }
+            None => self.encode_octets_with_length(&integer::from_i64(v)),
+        }
+    }
+
+    fn encode_octets_with_length(&mut self, octets: &[u8]) -> Result<()> {
+        self.encode_length(octets.len())?;
+        self.buf.extend_from_slice(octets);
+        Ok(())
+    }
+
+    /// Encodes the value (not the index) of an ENUMERATED item: a single octet from 0 to 127, and otherwise its
+    /// two's complement octets preceded by their count (X.696 11).
+    pub fn encode_enumerated(&mut self, v: i64) -> Result<()> {
+        if (0..128).contains(&v) {
+            self.buf.push(v as u8);
+            return Ok(());
+        }
+        let octets = integer::from_i64(v);
+        self.buf.push(0x80 | octets.len() as u8);
+        self.buf.extend_from_slice(&octets);
+        Ok(())
+    }
+
+    /// Encodes the first `bit_len` bits of `data` as a BIT STRING. Unless its size is fixed, the bits are preceded
+    /// by a length determinant and the number of unused bits in the last octet.
+    pub fn encode_bit_string(&mut self, data: &[u8], bit_len: usize, constraint: &SizeConstraint) -> Result<()> {
+        if bit_len > data.len() * 8 || (!constraint.extensible && !constraint.contains(bit_len)) {
+            return Err(Error::ValueOutOfRange);
+        }
+        let mut octets = data[..bit_len.div_ceil(8)].to_vec();
+        let unused = octets.len() * 8 - bit_len;
+        if let Some(last) = octets.last_mut() {
+            *last &= 0xff << unused;
+        }
+        if oer::fixed_size(constraint).is_none() {
+            self.encode_length(octets.len() + 1)?;
+            self.buf.push(unused as u8);
+        }
+        self.buf.extend_from_slice(&octets);
+        Ok(())
+    }
+
+    pub fn encode_octet_string(&mut self, data: &[u8], constraint: &SizeConstraint) -> Result<()> {
+        if !constraint.extensible && !constraint.contains(data.len()) {
+            return Err(Error::ValueOutOfRange);
+        }
+        if oer::fixed_size(constraint).is_none() {
+            self.encode_length(data.len())?;
+        }
+        self.buf.extend_from_slice(data);
+        Ok(())
+    }
+
+    /// Encodes an OBJECT IDENTIFIER as its BER contents preceded by a length determinant.
+    pub fn encode_object_identifier(&mut self, v: &ObjectIdentifier) -> Result<()> {
+        self.encode_octets_with_length(&v.to_bytes())
+    }
+
+    /// Encodes the preamble of a SEQUENCE or SET: the extension bit, if the type is extensible, then one bit for
+    /// each OPTIONAL or DEFAULT component saying whether it is present, padded to whole octets.
+    pub fn encode_sequence_preamble(&mut self, extensions_present: Option<bool>, optional_present: &[bool])
+        -> Result<()>
+    {
+        let bits: Vec<bool> = extensions_present.into_iter().chain(optional_present.iter().cloned()).collect();
+        self.buf.extend_from_slice(&pack_bits(&bits));
+        Ok(())
+    }
+
+    /// Encodes the bitmap saying which extension additions of a SEQUENCE are present, as a BIT STRING of
+    /// unconstrained size (X.696 16.4). Each present addition then follows as an open type.
+    pub fn encode_extension_presence(&mut self, present: &[bool]) -> Result<()> {
+        if present.is_empty() {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.encode_bit_string(&pack_bits(present), present.len(), &SizeConstraint::unconstrained())
+    }
+
+    /// Encodes the number of components of a SEQUENCE OF or SET OF, preceded by the number of octets it takes
+    /// (X.696 8.7).
+    pub fn encode_quantity(&mut self, count: usize) -> Result<()> {
+        let octets = oer::unsigned_octets(count as u64);
+        self.buf.push(octets.len() as u8);
+        self.buf.extend_from_slice(&octets);
+        Ok(())
+    }
+
+    /// Encodes a SEQUENCE OF or SET OF: the quantity, then each component as written by `f`.
+    pub fn encode_sequence_of<T, F>(&mut self, items: &[T], constraint: &SizeConstraint, mut f: F) -> Result<()>
+        where F: FnMut(&mut Encoder, &T) -> Result<()>
+    {
+        if !constraint.extensible && !constraint.contains(items.len()) {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.encode_quantity(items.len())?;
+        items.iter().try_for_each(|item| f(self, item))
+    }
+
+    /// Encodes the tag of the chosen CHOICE alternative: its class in the top two bits, then its number in the
+    /// remaining six or, from 63 on, in subsequent octets of seven bits each (X.696 8.7). Whether `tag` is
+    /// constructed is ignored. The value of an extension addition must follow as an open type.
+    pub fn encode_choice_tag(&mut self, tag: Identifier) -> Result<()> {
+        let class = u8::from(tag.class());
+        let number = tag.tag();
+        if number < 63 {
+            self.buf.push(class | number as u8);
+            return Ok(());
+        }
+        self.buf.push(class | 0x3f);
+        let groups = (64 - number.leading_zeros() as usize).div_ceil(7);
+        for i in (0..groups).rev() {
+            let more = if i > 0 { 0x80 } else { 0x00 };
+            self.buf.push(more | ((number >> (i * 7)) & 0x7f) as u8);
+        }
+        Ok(())
+    }
+
+    /// Encodes an open type: the complete encoding of a value, which can be produced by a separate `Encoder`,
+    /// preceded by its length.
+    pub fn encode_open_type(&mut self, encoding: &[u8]) -> Result<()> {
+        self.encode_octets_with_length(encoding)
+    }
+}
+
+
+/// Packs bits into octets, first bit most significant, padding the last octet with zero bits.
+fn pack_bits(bits: &[bool]) -> Vec<u8> {
+    let mut octets = vec![0u8; bits.len().div_ceil(8)];
+    for (i, _) in bits.iter().enumerate().filter(|(_, bit)| **bit) {
+        octets[i / 8] |= 0x80 >> (i % 8);
+    }
+    octets
+}
+
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use identifier::Class;
+
+    fn encode<F: FnOnce(&mut Encoder) -> Result<()>>(f: F) -> Vec<u8> {
+        let mut e = Encoder::new();
+        f(&mut e).unwrap();
+        e.into_bytes()
+    }
+
+    /// An IEEE 1609.2 Ieee1609Dot2Data carrying unsecuredData.
+    pub(crate) const UNSECURED_DATA: &[u8] = &[0x03, 0x80, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f];
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(encode(|e| e.encode_integer(5, &IntegerConstraint::range(0, 255))), [0x05]);
+        assert_eq!(encode(|e| e.encode_integer(256, &IntegerConstraint::range(0, 65535))), [0x01, 0x00]);
+        assert_eq!(encode(|e| e.encode_integer(1, &IntegerConstraint::range(0, 4294967295))), [0, 0, 0, 1]);
+        assert_eq!(encode(|e| e.encode_integer(-1, &IntegerConstraint::range(-1, 4000))), [0xff, 0xff]);
+        assert_eq!(encode(|e| e.encode_integer(-2, &IntegerConstraint::range(-100, 100))), [0xfe]);
+        assert_eq!(encode(|e| e.encode_integer(i64::MIN, &IntegerConstraint::range(i64::MIN, 0))),
+            [0x80, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(encode(|e| e.encode_integer(300, &IntegerConstraint::semi_constrained(0))), [0x02, 0x01, 0x2c]);
+        assert_eq!(encode(|e| e.encode_integer(128, &IntegerConstraint::semi_constrained(1))), [0x01, 0x80]);
+        assert_eq!(encode(|e| e.encode_integer(128, &IntegerConstraint::unconstrained())), [0x02, 0x00, 0x80]);
+        // Extensible constraints are not OER-visible.
+        assert_eq!(encode(|e| e.encode_integer(300, &IntegerConstraint::range(0, 255).extensible())),
+            [0x02, 0x01, 0x2c]);
+        assert!(Encoder::new().encode_integer(256, &IntegerConstraint::range(0, 255)).is_err());
+    }
+
+    #[test]
+    fn test_fields() {
+        assert_eq!(encode(|e| e.encode_length(127)), [0x7f]);
+        assert_eq!(encode(|e| e.encode_length(200)), [0x81, 0xc8]);
+        assert_eq!(encode(|e| e.encode_length(256)), [0x82, 0x01, 0x00]);
+        assert_eq!(encode(|e| e.encode_boolean(true)), [0xff]);
+        assert_eq!(encode(|e| e.encode_enumerated(5)), [0x05]);
+        assert_eq!(encode(|e| e.encode_enumerated(128)), [0x82, 0x00, 0x80]);
+        assert_eq!(encode(|e| e.encode_enumerated(-1)), [0x81, 0xff]);
+
+        assert_eq!(encode(|e| e.encode_octet_string(b"ab", &SizeConstraint::fixed(2))), b"ab");
+        assert_eq!(encode(|e| e.encode_octet_string(b"ab", &SizeConstraint::range(0, 4))), b"\x02ab");
+        assert_eq!(encode(|e| e.encode_bit_string(&[0xff], 3, &SizeConstraint::fixed(3))), [0xe0]);
+        assert_eq!(encode(|e| e.encode_bit_string(&[0xff, 0xff], 10, &SizeConstraint::unconstrained())),
+            [0x03, 0x06, 0xff, 0xc0]);
+        assert_eq!(encode(|e| e.encode_bit_string(&[], 0, &SizeConstraint::unconstrained())), [0x01, 0x00]);
+        assert_eq!(encode(|e| e.encode_object_identifier(&ObjectIdentifier::from_components(&[1, 3, 6, 1]))),
+            [0x03, 0x2b, 0x06, 0x01]);
+    }
+
+    #[test]
+    fn test_structures() {
+        assert_eq!(encode(|e| e.encode_sequence_preamble(Some(false), &[true, false, true])), [0x50]);
+        assert_eq!(encode(|e| e.encode_sequence_preamble(None, &[true; 9])), [0xff, 0x80]);
+        assert_eq!(encode(|e| e.encode_sequence_preamble(None, &[])), []);
+        assert_eq!(encode(|e| e.encode_extension_presence(&[true, false, true])), [0x02, 0x05, 0xa0]);
+        assert_eq!(encode(|e| {
+            e.encode_sequence_of(&[1, 2, 3], &SizeConstraint::unconstrained(), |e, v| {
+                e.encode_integer(*v, &IntegerConstraint::range(0, 255))
+            })
+        }), [0x01, 0x03, 0x01, 0x02, 0x03]);
+        assert_eq!(encode(|e| e.encode_quantity(300)), [0x02, 0x01, 0x2c]);
+
+        assert_eq!(encode(|e| e.encode_choice_tag(Identifier::context_specific(true, 1))), [0x81]);
+        assert_eq!(encode(|e| e.encode_choice_tag(Identifier::new(Class::Private, false, 62))), [0xfe]);
+        assert_eq!(encode(|e| e.encode_choice_tag(Identifier::application(false, 63))), [0x7f, 0x3f]);
+        assert_eq!(encode(|e| e.encode_choice_tag(Identifier::context_specific(false, 200))), [0xbf, 0x81, 0x48]);
+
+        // Ieee1609Dot2Data ::= SEQUENCE { protocolVersion Uint8 (3), content Ieee1609Dot2Content }, with content
+        // the unsecuredData alternative [0] Opaque.
+        assert_eq!(encode(|e| {
+            e.encode_integer(3, &IntegerConstraint::range(3, 3))?;
+            e.encode_choice_tag(Identifier::context_specific(false, 0))?;
+            e.encode_octet_string(b"Hello", &SizeConstraint::unconstrained())
+        }), UNSECURED_DATA);
+    }
+}
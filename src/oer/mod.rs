@@ -0,0 +1,120 @@
+//! Octet Encoding Rules (X.696), in the basic (OER) and canonical (COER) variants.
+//!
+//! Like PER, OER carries no tags outside CHOICE and is driven by the type's constraints, but every field is a
+//! whole number of octets: constrained integers take a fixed width of 1, 2, 4 or 8 octets, variable fields are
+//! preceded by a length determinant and the OPTIONAL components of a SEQUENCE are announced by a presence bitmap.
+//! The constraint types are shared with `per`.
+//!
+//! `Encoder` always produces the canonical encoding, which is also valid basic OER. `Decoder::new` accepts basic
+//! OER, while `Decoder::canonical` additionally rejects any encoding that is not canonical, as needed when
+//! verifying signatures over IEEE 1609.2 and ETSI ITS structures.
+
+use integer;
+use object_identifier;
+use std;
+
+pub mod decoder;
+pub mod encoder;
+
+pub use self::decoder::Decoder;
+pub use self::encoder::Encoder;
+pub use per::{IntegerConstraint, SizeConstraint};
+
+
+#[derive(Debug)]
+pub enum Error {
+    UnexpectedEndOfData,
+    /// A value lies outside a constraint that has no extension marker, or decodes to one that does.
+    ValueOutOfRange,
+    /// The encoding is valid basic OER but not canonical.
+    NonCanonical,
+    Integer(integer::Error),
+    ObjectIdentifier(object_identifier::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::UnexpectedEndOfData => write!(f, "unexpected end of data"),
+            Error::ValueOutOfRange => write!(f, "value out of range"),
+            Error::NonCanonical => write!(f, "non-canonical encoding"),
+            Error::Integer(ref e) => e.fmt(f),
+            Error::ObjectIdentifier(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Integer(ref e) => Some(e),
+            Error::ObjectIdentifier(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<integer::Error> for Error {
+    fn from(e: integer::Error) -> Error {
+        Error::Integer(e)
+    }
+}
+
+impl From<object_identifier::Error> for Error {
+    fn from(e: object_identifier::Error) -> Error {
+        Error::ObjectIdentifier(e)
+    }
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// The width in octets of a fixed-size integer field, and whether it is signed, for an INTEGER type whose
+/// constraint allows one (X.696 10.2 and 10.3). Extensible constraints are not OER-visible.
+fn integer_width(constraint: &IntegerConstraint) -> Option<(usize, bool)> {
+    if constraint.extensible {
+        return None;
+    }
+    let (lower, upper) = match (constraint.lower, constraint.upper) {
+        (Some(lower), Some(upper)) => (lower, upper),
+        _ => return None,
+    };
+    if lower >= 0 {
+        let width = if upper <= u8::MAX as i64 {
+            1
+        } else if upper <= u16::MAX as i64 {
+            2
+        } else if upper <= u32::MAX as i64 {
+            4
+        } else {
+            8
+        };
+        return Some((width, false));
+    }
+    let width = if lower >= i8::MIN as i64 && upper <= i8::MAX as i64 {
+        1
+    } else if lower >= i16::MIN as i64 && upper <= i16::MAX as i64 {
+        2
+    } else if lower >= i32::MIN as i64 && upper <= i32::MAX as i64 {
+        4
+    } else {
+        8
+    };
+    Some((width, true))
+}
+
+/// The size of a string type without a length determinant, if its constraint fixes one.
+fn fixed_size(constraint: &SizeConstraint) -> Option<usize> {
+    match constraint.upper {
+        Some(upper) if upper == constraint.lower && !constraint.extensible => Some(upper),
+        _ => None,
+    }
+}
+
+/// The minimal octets of a non-negative-binary-integer, at least one octet long.
+fn unsigned_octets(v: u64) -> Vec<u8> {
+    let bytes = v.to_be_bytes();
+    let skip = std::cmp::min(v.leading_zeros() as usize / 8, 7);
+    bytes[skip..].to_vec()
+}
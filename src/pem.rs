@@ -0,0 +1,439 @@
+use reading::Asn1Reader;
+use std;
+
+
+const LINE_LEN: usize = 64;
+const LINE_BYTES: usize = LINE_LEN / 4 * 3;
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+
+#[derive(Debug)]
+pub enum Error {
+    MissingBeginLine,
+    MissingEndLine,
+    InvalidLabel,
+    LabelMismatch,
+    InvalidLineLength,
+    InvalidBase64,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::MissingBeginLine => write!(f, "missing begin line"),
+            Error::MissingEndLine => write!(f, "missing end line"),
+            Error::InvalidLabel => write!(f, "invalid label"),
+            Error::LabelMismatch => write!(f, "label mismatch"),
+            Error::InvalidLineLength => write!(f, "invalid line length"),
+            Error::InvalidBase64 => write!(f, "invalid base64"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// How strictly the textual encoding is checked, per RFC 7468 section 3.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum Mode {
+    /// `stricttextualmsg`: base64 lines must be exactly 64 characters (the last may be shorter) and padding is
+    /// required.
+    Strict,
+    /// `laxtextualmsg`: whitespace may appear anywhere in the base64 text, lines may be any length and padding is
+    /// optional.
+    Lax,
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Pem {
+    label: String,
+    contents: Vec<u8>,
+}
+
+impl Pem {
+    pub fn new<S: Into<String>>(label: S, contents: Vec<u8>) -> Self {
+        Pem {
+            label: label.into(),
+            contents,
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn contents(&self) -> &[u8] {
+        &self.contents
+    }
+
+    pub fn into_contents(self) -> Vec<u8> {
+        self.contents
+    }
+
+    pub fn reader(&self) -> Asn1Reader<'_> {
+        Asn1Reader::new(&self.contents)
+    }
+}
+
+impl std::fmt::Display for Pem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&encode(&self.label, &self.contents))
+    }
+}
+
+
+/// Parses the first encapsulated block in `input`, ignoring any explanatory text around it.
+pub fn parse(input: &str, mode: Mode) -> Result<Pem> {
+    let mut lines = input.lines();
+    match parse_block(&mut lines, mode)? {
+        Some(pem) => Ok(pem),
+        None => Err(Error::MissingBeginLine),
+    }
+}
+
+/// Parses every encapsulated block in `input`, ignoring any explanatory text before, between or after them.
+pub fn parse_many(input: &str, mode: Mode) -> Result<Vec<Pem>> {
+    let mut lines = input.lines();
+    let mut pems = Vec::new();
+    while let Some(pem) = parse_block(&mut lines, mode)? {
+        pems.push(pem);
+    }
+    Ok(pems)
+}
+
+fn parse_block<'a, I: Iterator<Item=&'a str>>(lines: &mut I, mode: Mode) -> Result<Option<Pem>> {
+    let label = loop {
+        let line = match lines.next() {
+            Some(line) => line,
+            None => return Ok(None),
+        };
+        let line = match mode {
+            Mode::Strict => line.trim_end(),
+            Mode::Lax => line.trim(),
+        };
+        if let Some(label) = encapsulation_boundary_label(line, "BEGIN") {
+            break label?;
+        }
+    };
+
+    let mut text = String::new();
+    let mut short_line_seen = false;
+    for line in lines {
+        let line = match mode {
+            Mode::Strict => line.trim_end(),
+            Mode::Lax => line.trim(),
+        };
+        if let Some(end_label) = encapsulation_boundary_label(line, "END") {
+            if end_label? != label {
+                return Err(Error::LabelMismatch);
+            }
+            let contents = base64_decode(&text, mode == Mode::Lax)?;
+            return Ok(Some(Pem::new(label, contents)));
+        }
+        match mode {
+            Mode::Strict => {
+                if short_line_seen || line.is_empty() || line.len() > LINE_LEN {
+                    return Err(Error::InvalidLineLength);
+                }
+                short_line_seen = line.len() < LINE_LEN;
+                text.push_str(line);
+            }
+            Mode::Lax => text.extend(line.chars().filter(|c| !c.is_whitespace())),
+        }
+    }
+    Err(Error::MissingEndLine)
+}
+
+fn encapsulation_boundary_label<'a>(line: &'a str, kind: &str) -> Option<Result<&'a str>> {
+    let rest = line.strip_prefix("-----")?.strip_prefix(kind)?;
+    let label = match rest.strip_suffix("-----") {
+        Some(label) => label,
+        None => return Some(Err(Error::InvalidLabel)),
+    };
+    let label = match label.strip_prefix(' ') {
+        Some(label) => label,
+        None if label.is_empty() => return Some(Ok(label)),
+        None => return Some(Err(Error::InvalidLabel)),
+    };
+    if !is_valid_label(label) {
+        return Some(Err(Error::InvalidLabel));
+    }
+    Some(Ok(label))
+}
+
+/// Checks a label against the `label` production of RFC 7468: printable characters other than `-`, optionally
+/// separated by single spaces or hyphens.
+fn is_valid_label(label: &str) -> bool {
+    if label.is_empty() {
+        return true;
+    }
+    let mut previous_was_separator = true;
+    for b in label.bytes() {
+        match b {
+            b' ' | b'-' => {
+                if previous_was_separator {
+                    return false;
+                }
+                previous_was_separator = true;
+            }
+            0x21..=0x7e => previous_was_separator = false,
+            _ => return false,
+        }
+    }
+    !previous_was_separator
+}
+
+
+pub fn encode(label: &str, contents: &[u8]) -> String {
+    let mut output = Vec::with_capacity(label.len() * 2 + 32 + contents.len() * 4 / 3 + contents.len() / LINE_BYTES + 4);
+    write_to(&mut output, label, contents).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+pub fn write_to<W: std::io::Write>(w: &mut W, label: &str, contents: &[u8]) -> std::io::Result<()> {
+    let mut encoder = Encoder::new(w, label)?;
+    std::io::Write::write_all(&mut encoder, contents)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+
+/// Streams base64-armored output to an underlying writer, so that it can be handed to `der::Writer` directly.
+///
+/// The begin line is written on construction; `finish` must be called to flush the final line and write the end
+/// line.
+pub struct Encoder<W: std::io::Write> {
+    w: W,
+    label: String,
+    pending: Vec<u8>,
+}
+
+impl<W: std::io::Write> Encoder<W> {
+    pub fn new(mut w: W, label: &str) -> std::io::Result<Self> {
+        if !is_valid_label(label) {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, Error::InvalidLabel));
+        }
+        writeln!(w, "-----BEGIN {}-----", label)?;
+        Ok(Encoder {
+            w,
+            label: label.to_owned(),
+            pending: Vec::with_capacity(LINE_BYTES),
+        })
+    }
+
+    pub fn finish(mut self) -> std::io::Result<W> {
+        if !self.pending.is_empty() {
+            let line = base64_encode(&self.pending);
+            self.w.write_all(line.as_bytes())?;
+            self.w.write_all(b"\n")?;
+        }
+        writeln!(self.w, "-----END {}-----", self.label)?;
+        Ok(self.w)
+    }
+}
+
+impl<W: std::io::Write> std::io::Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut buf = buf;
+        let len = buf.len();
+        while !buf.is_empty() {
+            let take = std::cmp::min(LINE_BYTES - self.pending.len(), buf.len());
+            self.pending.extend_from_slice(&buf[..take]);
+            buf = &buf[take..];
+            if self.pending.len() == LINE_BYTES {
+                let line = base64_encode(&self.pending);
+                self.w.write_all(line.as_bytes())?;
+                self.w.write_all(b"\n")?;
+                self.pending.clear();
+            }
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.w.flush()
+    }
+}
+
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut s = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b0 = chunk[0] as u32;
+        let b1 = chunk.get(1).cloned().unwrap_or(0) as u32;
+        let b2 = chunk.get(2).cloned().unwrap_or(0) as u32;
+        let v = (b0 << 16) | (b1 << 8) | b2;
+        s.push(BASE64_ALPHABET[(v >> 18) as usize & 0x3f] as char);
+        s.push(BASE64_ALPHABET[(v >> 12) as usize & 0x3f] as char);
+        if chunk.len() > 1 {
+            s.push(BASE64_ALPHABET[(v >> 6) as usize & 0x3f] as char);
+        } else {
+            s.push('=');
+        }
+        if chunk.len() > 2 {
+            s.push(BASE64_ALPHABET[v as usize & 0x3f] as char);
+        } else {
+            s.push('=');
+        }
+    }
+    s
+}
+
+fn base64_value(c: u8) -> Option<u32> {
+    Some(match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' => 62,
+        b'/' => 63,
+        _ => return None,
+    } as u32)
+}
+
+fn base64_decode(text: &str, padding_optional: bool) -> Result<Vec<u8>> {
+    let bytes = text.as_bytes();
+    let data_len = bytes.iter().rposition(|&b| b != b'=').map_or(0, |p| p + 1);
+    let padding_len = bytes.len() - data_len;
+    if padding_len > 2 {
+        return Err(Error::InvalidBase64);
+    }
+    if (padding_len > 0 || !padding_optional) && !bytes.len().is_multiple_of(4) {
+        return Err(Error::InvalidBase64);
+    }
+    if data_len % 4 == 1 {
+        return Err(Error::InvalidBase64);
+    }
+
+    let mut output = Vec::with_capacity(data_len * 3 / 4);
+    for chunk in bytes[..data_len].chunks(4) {
+        let mut v = 0u32;
+        for &c in chunk {
+            v = (v << 6) | base64_value(c).ok_or(Error::InvalidBase64)?;
+        }
+        match chunk.len() {
+            4 => {
+                output.push((v >> 16) as u8);
+                output.push((v >> 8) as u8);
+                output.push(v as u8);
+            }
+            3 => {
+                if v & 0x3 != 0 {
+                    return Err(Error::InvalidBase64);
+                }
+                output.push((v >> 10) as u8);
+                output.push((v >> 2) as u8);
+            }
+            2 => {
+                if v & 0xf != 0 {
+                    return Err(Error::InvalidBase64);
+                }
+                output.push((v >> 4) as u8);
+            }
+            _ => unreachable!(),
+        }
+    }
+    Ok(output)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use identifier::{Identifier, Class, UniversalTag};
+    use writing::der::Writer;
+
+    #[test]
+    fn test_base64_roundtrip() {
+        for len in 0..10 {
+            let data: Vec<u8> = (0..len).map(|v| (v * 37 + 11) as u8).collect();
+            let text = base64_encode(&data);
+            assert_eq!(base64_decode(&text, false).unwrap(), data);
+            assert_eq!(base64_decode(text.trim_end_matches('='), true).unwrap(), data);
+        }
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64_encode(b"fooba"), "Zm9vYmE=");
+        assert!(base64_decode("Zm9vYmE", false).is_err());
+        assert!(base64_decode("Zm9vYm!=", false).is_err());
+        assert!(base64_decode("Zm9vYmF=", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_strict() {
+        let input = "-----BEGIN TEST-----\nMAYBAQABAf8=\n-----END TEST-----\n";
+
+        let pem = parse(input, Mode::Strict).unwrap();
+        assert_eq!(pem.label(), "TEST");
+        assert_eq!(pem.contents(), [0x30u8, 0x06, 0x01, 0x01, 0x00, 0x01, 0x01, 0xff]);
+
+        let (t, _) = pem.reader().next().unwrap().unwrap();
+        assert_eq!(t, Identifier::new(Class::Universal, true, UniversalTag::Sequence.into()));
+
+        assert_eq!(parse("-----BEGIN TEST-----\nMAYB\nAQABAf8=\n-----END TEST-----\n", Mode::Strict).unwrap_err().to_string(), "invalid line length");
+        assert_eq!(parse("-----BEGIN TEST-----\nMAYBAQ ABAf8=\n-----END TEST-----\n", Mode::Strict).unwrap_err().to_string(), "invalid base64");
+        assert_eq!(parse("-----BEGIN TEST-----\nMAYBAQABAf8=\n-----END OTHER-----\n", Mode::Strict).unwrap_err().to_string(), "label mismatch");
+        assert_eq!(parse("-----BEGIN TEST-----\nMAYBAQABAf8=\n", Mode::Strict).unwrap_err().to_string(), "missing end line");
+        assert_eq!(parse("MAYBAQABAf8=\n", Mode::Strict).unwrap_err().to_string(), "missing begin line");
+    }
+
+    #[test]
+    fn test_parse_lax_many() {
+        let input = concat!(
+            "Subject: first\r\n",
+            "  -----BEGIN TEST-----  \r\n",
+            "MAYB AQAB\r\n",
+            "\tAf8\r\n",
+            "-----END TEST-----\r\n",
+            "\r\n",
+            "Subject: second\n",
+            "-----BEGIN X509 CRL-----\n",
+            "AQH/\n",
+            "-----END X509 CRL-----\n",
+            "trailing text\n",
+        );
+
+        let pems = parse_many(input, Mode::Lax).unwrap();
+        assert_eq!(pems, [
+            Pem::new("TEST", vec![0x30u8, 0x06, 0x01, 0x01, 0x00, 0x01, 0x01, 0xff]),
+            Pem::new("X509 CRL", vec![0x01u8, 0x01, 0xff]),
+        ]);
+
+        let pems = parse_many(input, Mode::Strict).unwrap();
+        assert_eq!(pems.len(), 1);
+        assert_eq!(pems[0].label(), "X509 CRL");
+    }
+
+    #[test]
+    fn test_encode_wraps_at_64_columns() {
+        let contents: Vec<u8> = (0..100u8).collect();
+
+        let encoded = encode("DATA", &contents);
+        let lines: Vec<_> = encoded.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "-----BEGIN DATA-----");
+        assert_eq!(lines[1].len(), 64);
+        assert_eq!(lines[2].len(), 64);
+        assert_eq!(lines[3].len(), 8);
+        assert_eq!(lines[4], "-----END DATA-----");
+
+        assert_eq!(parse(&encoded, Mode::Strict).unwrap().contents(), &contents[..]);
+    }
+
+    #[test]
+    fn test_encoder_with_der_writer() {
+        let mut output = Vec::new();
+        {
+            let mut encoder = Encoder::new(&mut output, "TEST").unwrap();
+            {
+                let mut w = Writer::new(&mut encoder);
+                w.write_boolean(true).unwrap();
+            }
+            encoder.finish().unwrap();
+        }
+        assert_eq!(String::from_utf8(output).unwrap(), "-----BEGIN TEST-----\nAQH/\n-----END TEST-----\n");
+    }
+}
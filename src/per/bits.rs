@@ -0,0 +1,200 @@
+use per::{Error, Result};
+
+
+/// Accumulates bits, most significant bit of each octet first.
+#[derive(Clone,Debug,Default)]
+pub struct BitWriter {
+    buf: Vec<u8>,
+    bit_len: usize,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        BitWriter::default()
+    }
+
+    /// The number of bits written so far.
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    pub fn write_bit(&mut self, v: bool) {
+        if self.bit_len.is_multiple_of(8) {
+            self.buf.push(0);
+        }
+        if v {
+            let last = self.buf.len() - 1;
+            self.buf[last] |= 0x80 >> (self.bit_len % 8);
+        }
+        self.bit_len += 1;
+    }
+
+    /// Writes the low `n` bits of `v`, most significant first.
+    pub fn write_bits(&mut self, v: u64, n: usize) {
+        debug_assert!(n <= 64);
+        for i in (0..n).rev() {
+            self.write_bit((v >> i) & 1 != 0);
+        }
+    }
+
+    /// Writes the first `bit_len` bits of `data`.
+    pub fn write_bits_from(&mut self, data: &[u8], bit_len: usize) {
+        debug_assert!(bit_len <= data.len() * 8);
+        if self.bit_len.is_multiple_of(8) && bit_len.is_multiple_of(8) {
+            self.buf.extend_from_slice(&data[..bit_len / 8]);
+            self.bit_len += bit_len;
+            return;
+        }
+        for i in 0..bit_len {
+            self.write_bit(data[i / 8] & (0x80 >> (i % 8)) != 0);
+        }
+    }
+
+    pub fn write_bytes(&mut self, data: &[u8]) {
+        self.write_bits_from(data, data.len() * 8);
+    }
+
+    /// Pads with zero bits up to the next octet boundary.
+    pub fn align(&mut self) {
+        self.bit_len = self.buf.len() * 8;
+    }
+
+    /// Returns the bits written, with the final octet padded with zero bits.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+
+/// Reads bits from a byte slice, most significant bit of each octet first.
+#[derive(Clone,Debug)]
+pub struct BitReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        BitReader {
+            buf,
+            pos: 0,
+        }
+    }
+
+    /// The number of bits read so far.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buf.len() * 8 - self.pos
+    }
+
+    /// Skips the padding bits up to the next octet boundary.
+    pub fn align(&mut self) -> Result<()> {
+        let padding = (8 - self.pos % 8) % 8;
+        if padding > self.remaining() {
+            return Err(Error::UnexpectedEndOfData);
+        }
+        self.pos += padding;
+        Ok(())
+    }
+
+    pub fn read_bit(&mut self) -> Result<bool> {
+        if self.remaining() == 0 {
+            return Err(Error::UnexpectedEndOfData);
+        }
+        let v = self.buf[self.pos / 8] & (0x80 >> (self.pos % 8)) != 0;
+        self.pos += 1;
+        Ok(v)
+    }
+
+    /// Reads `n` bits as an unsigned number, most significant first.
+    pub fn read_bits(&mut self, n: usize) -> Result<u64> {
+        debug_assert!(n <= 64);
+        if n > self.remaining() {
+            return Err(Error::UnexpectedEndOfData);
+        }
+        let mut v = 0u64;
+        for _ in 0..n {
+            v = (v << 1) | self.read_bit()? as u64;
+        }
+        Ok(v)
+    }
+
+    /// Reads `bit_len` bits into octets, the last of which is padded with zero bits.
+    pub fn read_bits_to_vec(&mut self, bit_len: usize) -> Result<Vec<u8>> {
+        if bit_len > self.remaining() {
+            return Err(Error::UnexpectedEndOfData);
+        }
+        if self.pos.is_multiple_of(8) && bit_len.is_multiple_of(8) {
+            let start = self.pos / 8;
+            self.pos += bit_len;
+            return Ok(self.buf[start..start + bit_len / 8].to_vec());
+        }
+        let mut v = vec![0u8; bit_len.div_ceil(8)];
+        for i in 0..bit_len {
+            if self.read_bit()? {
+                v[i / 8] |= 0x80 >> (i % 8);
+            }
+        }
+        Ok(v)
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        match len.checked_mul(8) {
+            Some(bit_len) => self.read_bits_to_vec(bit_len),
+            None => Err(Error::UnexpectedEndOfData),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut w = BitWriter::new();
+        w.write_bit(true);
+        w.write_bits(0b0110, 4);
+        w.write_bytes(&[0xa5, 0x3c]);
+        w.write_bits_from(&[0xf0], 3);
+        assert_eq!(w.bit_len(), 24);
+        let b = w.into_bytes();
+        assert_eq!(b, [0xb5, 0x29, 0xe7]);
+
+        let mut r = BitReader::new(&b);
+        assert!(r.read_bit().unwrap());
+        assert_eq!(r.read_bits(4).unwrap(), 0b0110);
+        assert_eq!(r.read_bytes(2).unwrap(), [0xa5, 0x3c]);
+        assert_eq!(r.read_bits_to_vec(3).unwrap(), [0xe0]);
+        assert_eq!(r.remaining(), 0);
+        assert!(r.read_bit().is_err());
+        assert!(BitReader::new(&[0]).read_bytes(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_align() {
+        let mut w = BitWriter::new();
+        w.align();
+        w.write_bits(0b101, 3);
+        w.align();
+        w.align();
+        w.write_bit(true);
+        assert_eq!(w.bit_len(), 9);
+        let b = w.into_bytes();
+        assert_eq!(b, [0xa0, 0x80]);
+
+        let mut r = BitReader::new(&b);
+        r.align().unwrap();
+        assert_eq!(r.read_bits(3).unwrap(), 0b101);
+        r.align().unwrap();
+        assert_eq!(r.position(), 8);
+        assert!(r.read_bit().unwrap());
+        r.align().unwrap();
+        assert!(r.align().is_ok());
+        assert_eq!(r.remaining(), 0);
+    }
+}
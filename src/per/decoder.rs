@@ -0,0 +1,455 @@
+use integer;
+use per::{self, BitReader, BitWriter, Error, IntegerConstraint, Result, SizeConstraint};
+use std;
+
+
+/// Reads the primitive PER encodings from a byte slice, in the order they were written.
+#[derive(Clone,Debug)]
+pub struct Decoder<'a> {
+    r: BitReader<'a>,
+    aligned: bool,
+}
+
+impl<'a> Decoder<'a> {
+    /// A decoder for the unaligned variant (UPER).
+    pub fn new(b: &'a [u8]) -> Self {
+        Decoder {
+            r: BitReader::new(b),
+            aligned: false,
+        }
+    }
+
+    /// A decoder for the aligned variant (APER).
+    pub fn aligned(b: &'a [u8]) -> Self {
+        Decoder {
+            r: BitReader::new(b),
+            aligned: true,
+        }
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        self.aligned
+    }
+
+    /// The underlying bits, for encodings not covered by the methods here.
+    pub fn bits(&mut self) -> &mut BitReader<'a> {
+        &mut self.r
+    }
+
+    fn align(&mut self) -> Result<()> {
+        if self.aligned {
+            self.r.align()?;
+        }
+        Ok(())
+    }
+
+    pub fn decode_boolean(&mut self) -> Result<bool> {
+        self.r.read_bit()
+    }
+
+    pub fn decode_constrained_whole_number(&mut self, lower: i64, upper: i64) -> Result<i64> {
+        if lower > upper {
+            return Err(Error::ValueOutOfRange);
+        }
+        let range = (upper as i128 - lower as i128 + 1) as u128;
+        let offset = if !self.aligned || range <= 255 {
+            self.r.read_bits(per::range_bits(range))?
+        } else if range <= 65536 {
+            self.r.align()?;
+            self.r.read_bits(if range == 256 { 8 } else { 16 })?
+        } else {
+            let max_octets = per::unsigned_octets((range - 1) as u64).len();
+            let len = self.decode_constrained_whole_number(1, max_octets as i64)?;
+            self.r.align()?;
+            unsigned_value(&self.r.read_bytes(len as usize)?)?
+        };
+        let v = lower as i128 + offset as i128;
+        if v > upper as i128 {
+            return Err(Error::ValueOutOfRange);
+        }
+        Ok(v as i64)
+    }
+
+    pub fn decode_normally_small_number(&mut self) -> Result<u64> {
+        if !self.r.read_bit()? {
+            return self.r.read_bits(6);
+        }
+        let octets = self.decode_octets_with_length()?;
+        unsigned_value(&octets)
+    }
+
+    pub fn decode_integer(&mut self, constraint: &IntegerConstraint) -> Result<i64> {
+        if constraint.extensible && self.r.read_bit()? {
+            return Ok(integer::to_i64(&self.decode_octets_with_length()?)?);
+        }
+        let v = match (constraint.lower, constraint.upper) {
+            (Some(lower), Some(upper)) => return self.decode_constrained_whole_number(lower, upper),
+            (Some(lower), None) => {
+                let offset = unsigned_value(&self.decode_octets_with_length()?)?;
+                let v = lower as i128 + offset as i128;
+                if v > i64::MAX as i128 {
+                    return Err(Error::ValueOutOfRange);
+                }
+                v as i64
+            }
+            _ => integer::to_i64(&self.decode_octets_with_length()?)?,
+        };
+        if !constraint.contains(v) {
+            return Err(Error::ValueOutOfRange);
+        }
+        Ok(v)
+    }
+
+    pub fn decode_length(&mut self, constraint: &SizeConstraint) -> Result<usize> {
+        if constraint.extensible && self.r.read_bit()? {
+            return self.decode_unconstrained_length();
+        }
+        let len = match constraint.upper {
+            Some(upper) if constraint.is_small() => {
+                self.decode_constrained_whole_number(constraint.lower as i64, upper as i64)? as usize
+            }
+            _ => self.decode_unconstrained_length()?,
+        };
+        if !constraint.contains(len) {
+            return Err(Error::ValueOutOfRange);
+        }
+        Ok(len)
+    }
+
+    fn decode_unconstrained_length(&mut self) -> Result<usize> {
+        match self.decode_length_part()? {
+            (len, false) => Ok(len),
+            (_, true) => Err(Error::LengthTooLarge),
+        }
+    }
+
+    /// Decodes an unconstrained length determinant, returning the count and whether it is a fragment that more
+    /// length determinants follow.
+    fn decode_length_part(&mut self) -> Result<(usize, bool)> {
+        self.align()?;
+        if !self.r.read_bit()? {
+            return Ok((self.r.read_bits(7)? as usize, false));
+        }
+        if !self.r.read_bit()? {
+            return Ok((self.r.read_bits(14)? as usize, false));
+        }
+        match self.r.read_bits(6)? {
+            multiplier @ 1..=4 => Ok((multiplier as usize * per::MAX_UNFRAGMENTED_LENGTH, true)),
+            _ => Err(Error::ValueOutOfRange),
+        }
+    }
+
+    /// Decodes a length determinant and the units it counts, which `read_units` reads `count` at a time, following
+    /// any fragments. Returns the total number of units. See `Encoder::encode_fragmented`.
+    fn decode_fragmented<F>(&mut self, constraint: &SizeConstraint, unit_bits: usize, mut read_units: F)
+        -> Result<usize>
+        where F: FnMut(&mut Self, usize) -> Result<()>
+    {
+        let extended = constraint.extensible && self.r.read_bit()?;
+        if !extended && constraint.is_small() {
+            let upper = constraint.upper.unwrap_or(constraint.lower);
+            let len = self.decode_constrained_whole_number(constraint.lower as i64, upper as i64)? as usize;
+            let fixed = constraint.lower == upper;
+            if unit_bits > 0 && if fixed { len * unit_bits > 16 } else { len > 0 } {
+                self.align()?;
+            }
+            read_units(self, len)?;
+            return Ok(len);
+        }
+        let mut len = 0usize;
+        loop {
+            let (count, more) = self.decode_length_part()?;
+            read_units(self, count)?;
+            len = len.checked_add(count).ok_or(Error::LengthTooLarge)?;
+            if !more {
+                break;
+            }
+        }
+        if !extended && !constraint.contains(len) {
+            return Err(Error::ValueOutOfRange);
+        }
+        Ok(len)
+    }
+
+    fn decode_octets_with_length(&mut self) -> Result<Vec<u8>> {
+        let len = self.decode_unconstrained_length()?;
+        self.r.read_bytes(len)
+    }
+
+    pub fn decode_enumerated(&mut self, root_count: usize, extensible: bool) -> Result<usize> {
+        self.decode_index(root_count, extensible)
+    }
+
+    pub fn decode_choice_index(&mut self, root_count: usize, extensible: bool) -> Result<usize> {
+        self.decode_index(root_count, extensible)
+    }
+
+    fn decode_index(&mut self, root_count: usize, extensible: bool) -> Result<usize> {
+        if extensible && self.r.read_bit()? {
+            let addition = self.decode_normally_small_number()?;
+            return (root_count as u64).checked_add(addition)
+                .filter(|v| *v <= usize::MAX as u64)
+                .map(|v| v as usize)
+                .ok_or(Error::ValueOutOfRange);
+        }
+        if root_count == 0 {
+            return Err(Error::ValueOutOfRange);
+        }
+        Ok(self.decode_constrained_whole_number(0, root_count as i64 - 1)? as usize)
+    }
+
+    /// Decodes a BIT STRING, returning its bits packed into octets and its length in bits.
+    pub fn decode_bit_string(&mut self, constraint: &SizeConstraint) -> Result<(Vec<u8>, usize)> {
+        let mut bits = BitWriter::new();
+        let bit_len = self.decode_fragmented(constraint, 1, |d, count| {
+            bits.write_bits_from(&d.r.read_bits_to_vec(count)?, count);
+            Ok(())
+        })?;
+        Ok((bits.into_bytes(), bit_len))
+    }
+
+    pub fn decode_octet_string(&mut self, constraint: &SizeConstraint) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.decode_fragmented(constraint, 8, |d, count| {
+            data.extend_from_slice(&d.r.read_bytes(count)?);
+            Ok(())
+        })?;
+        Ok(data)
+    }
+
+    /// Decodes a SEQUENCE OF or SET OF, reading each component with `f`.
+    pub fn decode_sequence_of<T, F>(&mut self, constraint: &SizeConstraint, mut f: F) -> Result<Vec<T>>
+        where F: FnMut(&mut Decoder<'a>) -> Result<T>
+    {
+        let mut items = Vec::new();
+        self.decode_fragmented(constraint, 0, |d, count| {
+            for _ in 0..count {
+                items.push(f(d)?);
+            }
+            Ok(())
+        })?;
+        Ok(items)
+    }
+
+    /// Decodes a SEQUENCE or SET preamble, returning whether extension additions are present and which of the
+    /// `optional_count` OPTIONAL or DEFAULT components are.
+    pub fn decode_sequence_preamble(&mut self, extensible: bool, optional_count: usize) -> Result<(bool, Vec<bool>)> {
+        let extended = extensible && self.r.read_bit()?;
+        let mut present = Vec::with_capacity(std::cmp::min(optional_count, self.r.remaining()));
+        for _ in 0..optional_count {
+            present.push(self.r.read_bit()?);
+        }
+        Ok((extended, present))
+    }
+
+    /// Decodes the bitmap of present extension additions. It may be longer than the additions the decoder knows
+    /// about; the open types of unknown additions can be skipped with `decode_open_type`.
+    pub fn decode_extension_presence(&mut self) -> Result<Vec<bool>> {
+        let len = if !self.r.read_bit()? {
+            self.r.read_bits(6)? as usize + 1
+        } else {
+            self.decode_unconstrained_length()?
+        };
+        if len > self.r.remaining() {
+            return Err(Error::UnexpectedEndOfData);
+        }
+        let mut present = Vec::with_capacity(len);
+        for _ in 0..len {
+            present.push(self.r.read_bit()?);
+        }
+        Ok(present)
+    }
+
+    /// Decodes an open type, returning the complete encoding it carries for a separate `Decoder`.
+    pub fn decode_open_type(&mut self) -> Result<Vec<u8>> {
+        self.decode_octet_string(&SizeConstraint::unconstrained())
+    }
+}
+
+
+fn unsigned_value(octets: &[u8]) -> Result<u64> {
+    if octets.is_empty() || octets.len() > 8 {
+        return Err(Error::ValueOutOfRange);
+    }
+    Ok(octets.iter().fold(0u64, |v, b| (v << 8) | *b as u64))
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use per::Encoder;
+    use per::encoder::test::NG_SETUP_REQUEST;
+
+    #[test]
+    fn test_roundtrip() {
+        let constraints = [
+            IntegerConstraint::unconstrained(),
+            IntegerConstraint::range(-5, 1000),
+            IntegerConstraint::range(i64::MIN, i64::MAX),
+            IntegerConstraint::semi_constrained(-10),
+            IntegerConstraint::range(0, 15).extensible(),
+        ];
+        let values = [-10, -5, 0, 1, 15, 16, 127, 128, 1000, i64::MAX];
+
+        let mut e = Encoder::new();
+        for c in &constraints {
+            for v in values.iter().filter(|v| c.extensible || c.contains(**v)) {
+                e.encode_integer(*v, c).unwrap();
+            }
+        }
+        e.encode_enumerated(4, 3, true).unwrap();
+        e.encode_choice_index(1, 3, false).unwrap();
+        e.encode_normally_small_number(1000).unwrap();
+        e.encode_bit_string(&[0xde, 0xad, 0xbe, 0xef], 27, &SizeConstraint::unconstrained()).unwrap();
+        e.encode_bit_string(&[0xc0], 2, &SizeConstraint::fixed(2)).unwrap();
+        e.encode_octet_string(&[0x55; 300], &SizeConstraint::range(1, 1000)).unwrap();
+        e.encode_octet_string(b"extended", &SizeConstraint::range(1, 4).extensible()).unwrap();
+        e.encode_sequence_preamble(Some(true), &[false, true]).unwrap();
+        e.encode_extension_presence(&[true; 70]).unwrap();
+        e.encode_open_type(&[0x80]).unwrap();
+        let b = e.into_bytes();
+
+        let mut d = Decoder::new(&b);
+        for c in &constraints {
+            for v in values.iter().filter(|v| c.extensible || c.contains(**v)) {
+                assert_eq!(d.decode_integer(c).unwrap(), *v);
+            }
+        }
+        assert_eq!(d.decode_enumerated(3, true).unwrap(), 4);
+        assert_eq!(d.decode_choice_index(3, false).unwrap(), 1);
+        assert_eq!(d.decode_normally_small_number().unwrap(), 1000);
+        assert_eq!(d.decode_bit_string(&SizeConstraint::unconstrained()).unwrap(), (vec![0xde, 0xad, 0xbe, 0xe0], 27));
+        assert_eq!(d.decode_bit_string(&SizeConstraint::fixed(2)).unwrap(), (vec![0xc0], 2));
+        assert_eq!(d.decode_octet_string(&SizeConstraint::range(1, 1000)).unwrap(), vec![0x55; 300]);
+        assert_eq!(d.decode_octet_string(&SizeConstraint::range(1, 4).extensible()).unwrap(), b"extended");
+        assert_eq!(d.decode_sequence_preamble(true, 2).unwrap(), (true, vec![false, true]));
+        assert_eq!(d.decode_extension_presence().unwrap(), vec![true; 70]);
+        assert_eq!(d.decode_open_type().unwrap(), [0x80]);
+        assert!(d.bits().remaining() < 8);
+    }
+
+    #[test]
+    fn test_invalid() {
+        // 3 bits can hold 6 and 7, which lie outside INTEGER (0..5).
+        assert!(Decoder::new(&[0xe0]).decode_integer(&IntegerConstraint::range(0, 5)).is_err());
+        assert!(Decoder::new(&[0xc0]).decode_enumerated(3, false).is_err());
+        // A fragment of 16K octets with nothing after it.
+        assert!(Decoder::new(&[0xc1, 0x00]).decode_octet_string(&SizeConstraint::unconstrained()).is_err());
+        assert!(Decoder::new(&[0xc5]).decode_octet_string(&SizeConstraint::unconstrained()).is_err());
+        assert!(Decoder::new(&[0x05, 0x00]).decode_octet_string(&SizeConstraint::unconstrained()).is_err());
+        // Two bits can hold a length of 4, which lies outside SIZE (1..3).
+        match Decoder::new(&[0xff; 4]).decode_octet_string(&SizeConstraint::range(1, 3)) {
+            Err(Error::ValueOutOfRange) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+        // A value of INTEGER (0..16777215) has at most 3 octets, but two bits can count 4.
+        let constraint = IntegerConstraint::range(0, 16777215);
+        assert!(Decoder::aligned(&[0xc0, 0x01, 0x00, 0x00, 0x00]).decode_integer(&constraint).is_err());
+        // Unconstrained integers must be minimally encoded.
+        assert!(Decoder::new(&[0x02, 0x00, 0x01]).decode_integer(&IntegerConstraint::unconstrained()).is_err());
+    }
+
+    #[test]
+    fn test_aligned_roundtrip() {
+        let mut e = Encoder::aligned();
+        e.encode_boolean(true).unwrap();
+        e.encode_integer(70000, &IntegerConstraint::range(0, 1 << 40)).unwrap();
+        e.encode_integer(-3, &IntegerConstraint::range(-5, 300)).unwrap();
+        e.encode_normally_small_number(100).unwrap();
+        e.encode_bit_string(&[0xde, 0xad, 0xbe], 20, &SizeConstraint::fixed(20)).unwrap();
+        e.encode_bit_string(&[0xff; 2049], 16385, &SizeConstraint::unconstrained()).unwrap();
+        e.encode_octet_string(b"xy", &SizeConstraint::range(0, 300)).unwrap();
+        e.encode_octet_string(&[7; 50000], &SizeConstraint::unconstrained()).unwrap();
+        e.encode_extension_presence(&[true, false]).unwrap();
+        let b = e.into_bytes();
+
+        let mut d = Decoder::aligned(&b);
+        assert!(d.decode_boolean().unwrap());
+        assert_eq!(d.decode_integer(&IntegerConstraint::range(0, 1 << 40)).unwrap(), 70000);
+        assert_eq!(d.decode_integer(&IntegerConstraint::range(-5, 300)).unwrap(), -3);
+        assert_eq!(d.decode_normally_small_number().unwrap(), 100);
+        assert_eq!(d.decode_bit_string(&SizeConstraint::fixed(20)).unwrap(), (vec![0xde, 0xad, 0xb0], 20));
+        let mut bits = vec![0xff; 2049];
+        bits[2048] = 0x80;
+        assert_eq!(d.decode_bit_string(&SizeConstraint::unconstrained()).unwrap(), (bits, 16385));
+        assert_eq!(d.decode_octet_string(&SizeConstraint::range(0, 300)).unwrap(), b"xy");
+        assert_eq!(d.decode_octet_string(&SizeConstraint::unconstrained()).unwrap(), vec![7; 50000]);
+        assert_eq!(d.decode_extension_presence().unwrap(), [true, false]);
+        assert!(d.bits().remaining() < 8);
+    }
+
+    #[test]
+    fn test_ngap() {
+        let mut d = Decoder::aligned(NG_SETUP_REQUEST);
+        assert_eq!(d.decode_choice_index(3, true).unwrap(), 0);
+        assert_eq!(d.decode_constrained_whole_number(0, 255).unwrap(), 21);
+        assert_eq!(d.decode_enumerated(3, false).unwrap(), 0);
+        let value = d.decode_open_type().unwrap();
+        assert_eq!(d.bits().remaining(), 0);
+
+        let mut d = Decoder::aligned(&value);
+        assert_eq!(d.decode_sequence_preamble(true, 0).unwrap(), (false, vec![]));
+        let ies = d.decode_sequence_of(&SizeConstraint::range(0, 65535), |d| {
+            let id = d.decode_constrained_whole_number(0, 65535)?;
+            let criticality = d.decode_enumerated(3, false)?;
+            Ok((id, criticality, d.decode_open_type()?))
+        }).unwrap();
+        assert_eq!(ies.iter().map(|ie| (ie.0, ie.1)).collect::<Vec<_>>(), [(27, 0), (102, 0), (21, 1)]);
+
+        let mut d = Decoder::aligned(&ies[0].2);
+        assert_eq!(d.decode_choice_index(4, false).unwrap(), 0);
+        assert_eq!(d.decode_sequence_preamble(true, 1).unwrap(), (false, vec![false]));
+        assert_eq!(d.decode_octet_string(&SizeConstraint::fixed(3)).unwrap(), [0x02, 0xf8, 0x39]);
+        assert_eq!(d.decode_choice_index(2, false).unwrap(), 0);
+        assert_eq!(d.decode_bit_string(&SizeConstraint::range(22, 32)).unwrap(), (vec![0x00, 0x01, 0x02], 24));
+
+        let mut d = Decoder::aligned(&ies[1].2);
+        let tas = d.decode_sequence_of(&SizeConstraint::range(1, 256), |d| {
+            assert_eq!(d.decode_sequence_preamble(true, 1)?, (false, vec![false]));
+            let tac = d.decode_octet_string(&SizeConstraint::fixed(3))?;
+            let plmns = d.decode_sequence_of(&SizeConstraint::range(1, 12), |d| {
+                assert_eq!(d.decode_sequence_preamble(true, 1)?, (false, vec![false]));
+                let plmn = d.decode_octet_string(&SizeConstraint::fixed(3))?;
+                let slices = d.decode_sequence_of(&SizeConstraint::range(1, 1024), |d| {
+                    assert_eq!(d.decode_sequence_preamble(true, 1)?, (false, vec![false]));
+                    assert_eq!(d.decode_sequence_preamble(true, 2)?, (false, vec![true, false]));
+                    let sst = d.decode_octet_string(&SizeConstraint::fixed(1))?;
+                    Ok((sst, d.decode_octet_string(&SizeConstraint::fixed(3))?))
+                })?;
+                Ok((plmn, slices))
+            })?;
+            Ok((tac, plmns))
+        }).unwrap();
+        assert_eq!(tas, [(vec![0x00, 0x00, 0x01], vec![(vec![0x02, 0xf8, 0x39], vec![(vec![0x01], vec![0x01, 0x02, 0x03])])])]);
+        assert_eq!(d.bits().remaining(), 0);
+
+        let mut d = Decoder::aligned(&ies[2].2);
+        assert_eq!(d.decode_enumerated(4, true).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_fragmentation() {
+        let data: Vec<u8> = (0..70000u32).map(|i| i as u8).collect();
+        let mut e = Encoder::new();
+        e.encode_boolean(false).unwrap();
+        e.encode_octet_string(&data, &SizeConstraint::range(0, 100000)).unwrap();
+        e.encode_sequence_of(&[1u8; 32768], &SizeConstraint::unconstrained(), |e, v| {
+            e.encode_constrained_whole_number(*v as i64, 0, 1)
+        }).unwrap();
+        let b = e.into_bytes();
+
+        let mut d = Decoder::new(&b);
+        assert!(!d.decode_boolean().unwrap());
+        assert_eq!(d.decode_octet_string(&SizeConstraint::range(0, 100000)).unwrap(), data);
+        let items = d.decode_sequence_of(&SizeConstraint::unconstrained(), |d| d.decode_constrained_whole_number(0, 1))
+            .unwrap();
+        assert_eq!(items, vec![1; 32768]);
+
+        // The total length must satisfy the constraint.
+        let mut e = Encoder::new();
+        e.encode_octet_string(&data, &SizeConstraint::unconstrained()).unwrap();
+        let b = e.into_bytes();
+        assert!(Decoder::new(&b).decode_octet_string(&SizeConstraint::range(0, 65536)).is_err());
+    }
+}
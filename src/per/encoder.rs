@@ -0,0 +1,502 @@
+use integer;
+use per::{self, BitWriter, Error, IntegerConstraint, Result, SizeConstraint};
+use std;
+
+
+/// Writes the primitive PER encodings into a bit buffer, in either the unaligned or the aligned variant.
+#[derive(Clone,Debug,Default)]
+pub struct Encoder {
+    w: BitWriter,
+    aligned: bool,
+}
+
+impl Encoder {
+    /// An encoder for the unaligned variant (UPER).
+    pub fn new() -> Self {
+        Encoder::default()
+    }
+
+    /// An encoder for the aligned variant (APER).
+    pub fn aligned() -> Self {
+        Encoder {
+            w: BitWriter::new(),
+            aligned: true,
+        }
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        self.aligned
+    }
+
+    /// The underlying bits, for encodings not covered by the methods here.
+    pub fn bits(&mut self) -> &mut BitWriter {
+        &mut self.w
+    }
+
+    /// Returns the complete encoding, padded to a whole number of octets. An empty encoding becomes a single zero
+    /// octet (X.691 11.1.3).
+    pub fn into_bytes(self) -> Vec<u8> {
+        if self.w.bit_len() == 0 {
+            return vec![0];
+        }
+        self.w.into_bytes()
+    }
+
+    fn align(&mut self) {
+        if self.aligned {
+            self.w.align();
+        }
+    }
+
+    pub fn encode_boolean(&mut self, v: bool) -> Result<()> {
+        self.w.write_bit(v);
+        Ok(())
+    }
+
+    /// Encodes `v` as a constrained whole number in `lower..=upper` (X.691 11.5.7). The aligned variant puts ranges
+    /// above 255 in whole octets, and ranges above 64K in as few octets as `v` needs, preceded by their count.
+    pub fn encode_constrained_whole_number(&mut self, v: i64, lower: i64, upper: i64) -> Result<()> {
+        if v < lower || v > upper {
+            return Err(Error::ValueOutOfRange);
+        }
+        let range = (upper as i128 - lower as i128 + 1) as u128;
+        let offset = (v as i128 - lower as i128) as u64;
+        if !self.aligned || range <= 255 {
+            self.w.write_bits(offset, per::range_bits(range));
+        } else if range <= 65536 {
+            self.w.align();
+            self.w.write_bits(offset, if range == 256 { 8 } else { 16 });
+        } else {
+            let octets = per::unsigned_octets(offset);
+            let max_octets = per::unsigned_octets((range - 1) as u64).len();
+            self.encode_constrained_whole_number(octets.len() as i64, 1, max_octets as i64)?;
+            self.w.align();
+            self.w.write_bytes(&octets);
+        }
+        Ok(())
+    }
+
+    /// Encodes a normally small non-negative whole number (X.691 11.6), as used for extension indexes.
+    pub fn encode_normally_small_number(&mut self, v: u64) -> Result<()> {
+        if v < 64 {
+            self.w.write_bit(false);
+            self.w.write_bits(v, 6);
+            return Ok(());
+        }
+        self.w.write_bit(true);
+        let octets = per::unsigned_octets(v);
+        self.encode_unconstrained_length(octets.len())?;
+        self.w.write_bytes(&octets);
+        Ok(())
+    }
+
+    pub fn encode_integer(&mut self, v: i64, constraint: &IntegerConstraint) -> Result<()> {
+        if constraint.extensible {
+            let extended = !constraint.contains(v);
+            self.w.write_bit(extended);
+            if extended {
+                return self.encode_octets_with_length(&integer::from_i64(v));
+            }
+        } else if !constraint.contains(v) {
+            return Err(Error::ValueOutOfRange);
+        }
+        match (constraint.lower, constraint.upper) {
+            (Some(lower), Some(upper)) => self.encode_constrained_whole_number(v, lower, upper),
+            (Some(lower), None) => {
+                self.encode_octets_with_length(&per::unsigned_octets((v as i128 - lower as i128) as u64))
+            }
+            _ => self.encode_octets_with_length(&integer::from_i64(v)),
+        }
+    }
+
+    /// Encodes a length determinant for a count constrained by `constraint`, preceded by the extension bit if the
+    /// constraint is extensible (X.691 11.9). Lengths that need fragmentation are refused with `LengthTooLarge`;
+    /// the string, SEQUENCE OF and open type methods fragment their contents themselves.
+    pub fn encode_length(&mut self, len: usize, constraint: &SizeConstraint) -> Result<()> {
+        if constraint.extensible {
+            let extended = !constraint.contains(len);
+            self.w.write_bit(extended);
+            if extended {
+                return self.encode_unconstrained_length(len);
+            }
+        } else if !constraint.contains(len) {
+            return Err(Error::ValueOutOfRange);
+        }
+        match constraint.upper {
+            Some(upper) if constraint.is_small() => {
+                self.encode_constrained_whole_number(len as i64, constraint.lower as i64, upper as i64)
+            }
+            _ => self.encode_unconstrained_length(len),
+        }
+    }
+
+    fn encode_unconstrained_length(&mut self, len: usize) -> Result<()> {
+        self.align();
+        if len < 128 {
+            self.w.write_bits(len as u64, 8);
+        } else if len < per::MAX_UNFRAGMENTED_LENGTH {
+            self.w.write_bits(0x8000 | len as u64, 16);
+        } else {
+            return Err(Error::LengthTooLarge);
+        }
+        Ok(())
+    }
+
+    /// Encodes `len` units preceded by their length determinant, splitting them into fragments of up to 64K units
+    /// when the length is not constrained below 64K (X.691 11.9.3.8). `write_units` writes `count` units from
+    /// `start` on. `unit_bits` is the size of a unit for alignment in the aligned variant, or zero for the
+    /// components of a SEQUENCE OF, which are not aligned.
+    fn encode_fragmented<F>(&mut self, len: usize, constraint: &SizeConstraint, unit_bits: usize, mut write_units: F)
+        -> Result<()>
+        where F: FnMut(&mut Self, usize, usize) -> Result<()>
+    {
+        let extended = !constraint.contains(len);
+        if constraint.extensible {
+            self.w.write_bit(extended);
+        } else if extended {
+            return Err(Error::ValueOutOfRange);
+        }
+        if !extended && constraint.is_small() {
+            let upper = constraint.upper.unwrap_or(constraint.lower);
+            self.encode_constrained_whole_number(len as i64, constraint.lower as i64, upper as i64)?;
+            let fixed = constraint.lower == upper;
+            if unit_bits > 0 && if fixed { len * unit_bits > 16 } else { len > 0 } {
+                self.align();
+            }
+            return write_units(self, 0, len);
+        }
+        let mut start = 0;
+        loop {
+            let remaining = len - start;
+            if remaining < per::MAX_UNFRAGMENTED_LENGTH {
+                self.encode_unconstrained_length(remaining)?;
+                return write_units(self, start, remaining);
+            }
+            let multiplier = std::cmp::min(remaining / per::MAX_UNFRAGMENTED_LENGTH, 4);
+            self.align();
+            self.w.write_bits(0xc0 | multiplier as u64, 8);
+            write_units(self, start, multiplier * per::MAX_UNFRAGMENTED_LENGTH)?;
+            start += multiplier * per::MAX_UNFRAGMENTED_LENGTH;
+        }
+    }
+
+    fn encode_octets_with_length(&mut self, octets: &[u8]) -> Result<()> {
+        self.encode_unconstrained_length(octets.len())?;
+        self.w.write_bytes(octets);
+        Ok(())
+    }
+
+    /// Encodes the index of an ENUMERATED value. Indexes from `root_count` on are extension additions, which need
+    /// `extensible`.
+    pub fn encode_enumerated(&mut self, index: usize, root_count: usize, extensible: bool) -> Result<()> {
+        self.encode_index(index, root_count, extensible)
+    }
+
+    /// Encodes the index of the chosen CHOICE alternative, in the same way as `encode_enumerated`. The value of an
+    /// extension addition must follow as an open type.
+    pub fn encode_choice_index(&mut self, index: usize, root_count: usize, extensible: bool) -> Result<()> {
+        self.encode_index(index, root_count, extensible)
+    }
+
+    fn encode_index(&mut self, index: usize, root_count: usize, extensible: bool) -> Result<()> {
+        if extensible {
+            let extended = index >= root_count;
+            self.w.write_bit(extended);
+            if extended {
+                return self.encode_normally_small_number((index - root_count) as u64);
+            }
+        } else if index >= root_count {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.encode_constrained_whole_number(index as i64, 0, root_count as i64 - 1)
+    }
+
+    /// Encodes the first `bit_len` bits of `data` as a BIT STRING.
+    pub fn encode_bit_string(&mut self, data: &[u8], bit_len: usize, constraint: &SizeConstraint) -> Result<()> {
+        if bit_len > data.len() * 8 {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.encode_fragmented(bit_len, constraint, 1, |e, start, count| {
+            e.w.write_bits_from(&data[start / 8..], count);
+            Ok(())
+        })
+    }
+
+    pub fn encode_octet_string(&mut self, data: &[u8], constraint: &SizeConstraint) -> Result<()> {
+        self.encode_fragmented(data.len(), constraint, 8, |e, start, count| {
+            e.w.write_bytes(&data[start..start + count]);
+            Ok(())
+        })
+    }
+
+    /// Encodes a SEQUENCE OF or SET OF: the number of components, then each of them as written by `f`.
+    pub fn encode_sequence_of<T, F>(&mut self, items: &[T], constraint: &SizeConstraint, mut f: F) -> Result<()>
+        where F: FnMut(&mut Encoder, &T) -> Result<()>
+    {
+        self.encode_fragmented(items.len(), constraint, 0, |e, start, count| {
+            items[start..start + count].iter().try_for_each(|item| f(e, item))
+        })
+    }
+
+    /// Encodes the preamble of a SEQUENCE or SET: the extension bit, if the type is extensible, then one bit for
+    /// each OPTIONAL or DEFAULT component saying whether it is present.
+    pub fn encode_sequence_preamble(&mut self, extensions_present: Option<bool>, optional_present: &[bool]) -> Result<()> {
+        if let Some(extended) = extensions_present {
+            self.w.write_bit(extended);
+        }
+        for present in optional_present {
+            self.w.write_bit(*present);
+        }
+        Ok(())
+    }
+
+    /// Encodes the bitmap saying which extension additions of a SEQUENCE are present (X.691 19.7). Each present
+    /// addition then follows as an open type.
+    pub fn encode_extension_presence(&mut self, present: &[bool]) -> Result<()> {
+        if present.is_empty() {
+            return Err(Error::ValueOutOfRange);
+        }
+        self.encode_normally_small_length(present.len())?;
+        for p in present {
+            self.w.write_bit(*p);
+        }
+        Ok(())
+    }
+
+    fn encode_normally_small_length(&mut self, len: usize) -> Result<()> {
+        if len <= 64 {
+            self.w.write_bit(false);
+            self.w.write_bits(len as u64 - 1, 6);
+            return Ok(());
+        }
+        self.w.write_bit(true);
+        self.encode_unconstrained_length(len)
+    }
+
+    /// Encodes an open type: the complete encoding of a value, which can be produced by a separate `Encoder`,
+    /// preceded by its length in octets.
+    pub fn encode_open_type(&mut self, encoding: &[u8]) -> Result<()> {
+        self.encode_octet_string(encoding, &SizeConstraint::unconstrained())
+    }
+}
+
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    fn encode<F: FnOnce(&mut Encoder) -> Result<()>>(f: F) -> Vec<u8> {
+        let mut e = Encoder::new();
+        f(&mut e).unwrap();
+        e.into_bytes()
+    }
+
+    fn encode_aligned<F: FnOnce(&mut Encoder) -> Result<()>>(f: F) -> Vec<u8> {
+        let mut e = Encoder::aligned();
+        f(&mut e).unwrap();
+        e.into_bytes()
+    }
+
+    /// A ProtocolIE-Field of NGAP, S1AP or X2AP.
+    fn encode_ie(e: &mut Encoder, id: i64, criticality: usize, value: &[u8]) -> Result<()> {
+        e.encode_constrained_whole_number(id, 0, 65535)?;
+        e.encode_enumerated(criticality, 3, false)?;
+        e.encode_open_type(value)
+    }
+
+    fn ng_setup_request() -> Vec<u8> {
+        // GlobalRANNodeID: globalGNB-ID with PLMN 208/93 and a 24 bit gNB-ID.
+        let global_ran_node_id = encode_aligned(|e| {
+            e.encode_choice_index(0, 4, false)?;
+            e.encode_sequence_preamble(Some(false), &[false])?;
+            e.encode_octet_string(&[0x02, 0xf8, 0x39], &SizeConstraint::fixed(3))?;
+            e.encode_choice_index(0, 2, false)?;
+            e.encode_bit_string(&[0x00, 0x01, 0x02], 24, &SizeConstraint::range(22, 32))
+        });
+        // SupportedTAList: TAC 1, broadcasting the same PLMN with a single S-NSSAI of SST 1 and SD 010203.
+        let supported_ta_list = encode_aligned(|e| {
+            e.encode_sequence_of(&[()], &SizeConstraint::range(1, 256), |e, _| {
+                e.encode_sequence_preamble(Some(false), &[false])?;
+                e.encode_octet_string(&[0x00, 0x00, 0x01], &SizeConstraint::fixed(3))?;
+                e.encode_sequence_of(&[()], &SizeConstraint::range(1, 12), |e, _| {
+                    e.encode_sequence_preamble(Some(false), &[false])?;
+                    e.encode_octet_string(&[0x02, 0xf8, 0x39], &SizeConstraint::fixed(3))?;
+                    e.encode_sequence_of(&[()], &SizeConstraint::range(1, 1024), |e, _| {
+                        e.encode_sequence_preamble(Some(false), &[false])?;
+                        e.encode_sequence_preamble(Some(false), &[true, false])?;
+                        e.encode_octet_string(&[0x01], &SizeConstraint::fixed(1))?;
+                        e.encode_octet_string(&[0x01, 0x02, 0x03], &SizeConstraint::fixed(3))
+                    })
+                })
+            })
+        });
+        // PagingDRX v128.
+        let default_paging_drx = encode_aligned(|e| e.encode_enumerated(2, 4, true));
+        let ies = [(27, 0, global_ran_node_id), (102, 0, supported_ta_list), (21, 1, default_paging_drx)];
+        let ng_setup_request = encode_aligned(|e| {
+            e.encode_sequence_preamble(Some(false), &[])?;
+            e.encode_sequence_of(&ies, &SizeConstraint::range(0, 65535), |e, ie| encode_ie(e, ie.0, ie.1, &ie.2))
+        });
+        // NGAP-PDU: initiatingMessage for procedure 21 (NGSetup), criticality reject.
+        encode_aligned(|e| {
+            e.encode_choice_index(0, 3, true)?;
+            e.encode_constrained_whole_number(21, 0, 255)?;
+            e.encode_enumerated(0, 3, false)?;
+            e.encode_open_type(&ng_setup_request)
+        })
+    }
+
+    // An NGSetupRequest (3GPP TS 38.413 clause 9.2.6.1) in APER, derived by hand from the TS 38.413 ASN.1 module
+    // and X.691 rather than taken from a capture, so that it checks the encoder independently of itself.
+    pub(crate) const NG_SETUP_REQUEST: &[u8] = &[
+        // NGAP-PDU initiatingMessage, procedureCode id-NGSetup (21), criticality reject, open type of 40 octets.
+        0x00, 0x15, 0x00, 0x28,
+        // NGSetupRequest extension bit and padding, protocolIEs count 3.
+        0x00, 0x00, 0x03,
+        // id-GlobalRANNodeID (27), reject, 8 octets: globalGNB-ID, PLMN 208/93, 24 bit gNB-ID 000102.
+        0x00, 0x1b, 0x00, 0x08, 0x00, 0x02, 0xf8, 0x39, 0x10, 0x00, 0x01, 0x02,
+        // id-SupportedTAList (102), reject, 16 octets: TAC 000001, PLMN 208/93, S-NSSAI SST 1 and SD 010203.
+        0x00, 0x66, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02, 0xf8, 0x39, 0x00, 0x00, 0x10, 0x08,
+        0x01, 0x02, 0x03,
+        // id-DefaultPagingDRX (21), ignore, 1 octet: v128.
+        0x00, 0x15, 0x40, 0x01, 0x40,
+    ];
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(encode(|e| e.encode_integer(5, &IntegerConstraint::range(0, 7))), [0xa0]);
+        assert_eq!(encode(|e| e.encode_integer(200, &IntegerConstraint::range(0, 255))), [0xc8]);
+        assert_eq!(encode(|e| e.encode_integer(65538, &IntegerConstraint::range(1, 65538))), [0x80, 0x00, 0x80]);
+        assert_eq!(encode(|e| e.encode_integer(3, &IntegerConstraint::range(3, 3))), [0x00]);
+        assert_eq!(encode(|e| e.encode_integer(256, &IntegerConstraint::semi_constrained(0))), [0x02, 0x01, 0x00]);
+        assert_eq!(encode(|e| e.encode_integer(-1, &IntegerConstraint::semi_constrained(-1))), [0x01, 0x00]);
+        assert_eq!(encode(|e| e.encode_integer(-129, &IntegerConstraint::unconstrained())), [0x02, 0xff, 0x7f]);
+        assert_eq!(encode(|e| e.encode_integer(0, &IntegerConstraint::unconstrained())), [0x01, 0x00]);
+        assert_eq!(encode(|e| e.encode_integer(i64::MAX, &IntegerConstraint::range(i64::MIN, i64::MAX))),
+            [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+
+        let extensible = IntegerConstraint::range(0, 7).extensible();
+        assert_eq!(encode(|e| e.encode_integer(7, &extensible)), [0x70]);
+        assert_eq!(encode(|e| e.encode_integer(8, &extensible)), [0x80, 0x84, 0x00]);
+
+        assert!(Encoder::new().encode_integer(8, &IntegerConstraint::range(0, 7)).is_err());
+    }
+
+    #[test]
+    fn test_lengths_and_strings() {
+        assert_eq!(encode(|e| e.encode_length(200, &SizeConstraint::unconstrained())), [0x80, 0xc8]);
+        assert_eq!(encode(|e| e.encode_length(16383, &SizeConstraint::unconstrained())), [0xbf, 0xff]);
+        assert!(Encoder::new().encode_length(16384, &SizeConstraint::unconstrained()).is_err());
+        assert_eq!(encode(|e| e.encode_length(300, &SizeConstraint::range(0, 100000))), [0x81, 0x2c]);
+
+        assert_eq!(encode(|e| e.encode_octet_string(b"ab", &SizeConstraint::fixed(2))), b"ab");
+        assert_eq!(encode(|e| {
+            e.encode_boolean(true)?;
+            e.encode_octet_string(b"ab", &SizeConstraint::unconstrained())
+        }), [0x81, 0x30, 0xb1, 0x00]);
+        assert_eq!(encode(|e| e.encode_bit_string(&[0xa0], 3, &SizeConstraint::range(0, 7))), [0x74]);
+        assert_eq!(encode(|e| e.encode_bit_string(&[0xa0], 3, &SizeConstraint::range(1, 2).extensible())),
+            [0x81, 0xd0]);
+        assert!(Encoder::new().encode_octet_string(b"abc", &SizeConstraint::range(1, 2)).is_err());
+    }
+
+    #[test]
+    fn test_indexes_and_preambles() {
+        assert_eq!(encode(|e| e.encode_enumerated(2, 3, true)), [0x40]);
+        assert_eq!(encode(|e| e.encode_enumerated(3, 3, true)), [0x80]);
+        assert_eq!(encode(|e| e.encode_enumerated(1, 2, false)), [0x80]);
+        assert!(Encoder::new().encode_enumerated(2, 2, false).is_err());
+        assert_eq!(encode(|e| e.encode_choice_index(0, 1, false)), [0x00]);
+
+        assert_eq!(encode(|e| e.encode_normally_small_number(5)), [0x0a]);
+        assert_eq!(encode(|e| e.encode_normally_small_number(64)), [0x80, 0xa0, 0x00]);
+
+        assert_eq!(encode(|e| e.encode_sequence_preamble(Some(false), &[true, false])), [0x40]);
+        assert_eq!(encode(|e| e.encode_sequence_preamble(None, &[true, true, true])), [0xe0]);
+        assert_eq!(encode(|e| e.encode_extension_presence(&[true, false, true])), [0x05, 0x40]);
+
+        let inner = encode(|e| e.encode_integer(5, &IntegerConstraint::range(0, 7)));
+        assert_eq!(encode(|e| {
+            e.encode_choice_index(2, 2, true)?;
+            e.encode_open_type(&inner)
+        }), [0x80, 0x01, 0xa0]);
+    }
+
+    #[test]
+    fn test_aligned() {
+        assert_eq!(encode_aligned(|e| {
+            e.encode_boolean(true)?;
+            e.encode_integer(5, &IntegerConstraint::range(0, 7))
+        }), [0xd0]);
+        assert_eq!(encode_aligned(|e| {
+            e.encode_boolean(true)?;
+            e.encode_integer(200, &IntegerConstraint::range(0, 255))
+        }), [0x80, 0xc8]);
+        assert_eq!(encode_aligned(|e| {
+            e.encode_boolean(true)?;
+            e.encode_integer(1, &IntegerConstraint::range(0, 65535))
+        }), [0x80, 0x00, 0x01]);
+        assert_eq!(encode_aligned(|e| e.encode_integer(256, &IntegerConstraint::range(0, 4294967295))),
+            [0x40, 0x01, 0x00]);
+        assert_eq!(encode_aligned(|e| {
+            e.encode_boolean(true)?;
+            e.encode_integer(256, &IntegerConstraint::semi_constrained(0))
+        }), [0x80, 0x02, 0x01, 0x00]);
+
+        assert_eq!(encode_aligned(|e| {
+            e.encode_boolean(true)?;
+            e.encode_octet_string(b"ab", &SizeConstraint::fixed(2))
+        }), [0xb0, 0xb1, 0x00]);
+        assert_eq!(encode_aligned(|e| {
+            e.encode_boolean(true)?;
+            e.encode_octet_string(b"abc", &SizeConstraint::fixed(3))
+        }), [0x80, 0x61, 0x62, 0x63]);
+        assert_eq!(encode_aligned(|e| {
+            e.encode_boolean(true)?;
+            e.encode_octet_string(b"ab", &SizeConstraint::unconstrained())
+        }), [0x80, 0x02, 0x61, 0x62]);
+        assert_eq!(encode_aligned(|e| e.encode_bit_string(&[0xa0], 3, &SizeConstraint::range(0, 7))), [0x60, 0xa0]);
+        assert_eq!(encode_aligned(|e| e.encode_bit_string(&[0xa0], 3, &SizeConstraint::fixed(3))), [0xa0]);
+    }
+
+    #[test]
+    fn test_ngap() {
+        assert_eq!(ng_setup_request(), NG_SETUP_REQUEST);
+
+        // AMF-UE-NGAP-ID ::= INTEGER (0..1099511627775) and RAN-UE-NGAP-ID ::= INTEGER (0..4294967295).
+        let amf_ue_ngap_id = |v| encode_aligned(|e| e.encode_constrained_whole_number(v, 0, 1099511627775));
+        assert_eq!(encode_aligned(|e| encode_ie(e, 10, 0, &amf_ue_ngap_id(1))), [0x00, 0x0a, 0x00, 0x02, 0x00, 0x01]);
+        assert_eq!(amf_ue_ngap_id(0x0102030405), [0x80, 0x01, 0x02, 0x03, 0x04, 0x05]);
+        let ran_ue_ngap_id = encode_aligned(|e| e.encode_constrained_whole_number(1, 0, 4294967295));
+        assert_eq!(encode_aligned(|e| encode_ie(e, 85, 0, &ran_ue_ngap_id)), [0x00, 0x55, 0x00, 0x02, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn test_fragmentation() {
+        let data = vec![0x55; 40000];
+        let mut expected = vec![0xc2];
+        expected.extend_from_slice(&data[..32768]);
+        expected.extend_from_slice(&[0x9c, 0x40]);
+        expected.extend_from_slice(&data[32768..]);
+        assert_eq!(encode(|e| e.encode_octet_string(&data, &SizeConstraint::unconstrained())), expected);
+
+        let data = vec![0x55; 65536];
+        let mut expected = vec![0xc4];
+        expected.extend_from_slice(&data);
+        expected.push(0x00);
+        assert_eq!(encode(|e| e.encode_open_type(&data)), expected);
+
+        // Fragment headers are aligned in APER, the components of a SEQUENCE OF are not.
+        let mut expected = vec![0x80, 0xc1];
+        expected.extend_from_slice(&[0xff; 2048]);
+        expected.push(0x00);
+        assert_eq!(encode_aligned(|e| {
+            e.encode_boolean(true)?;
+            e.encode_sequence_of(&[true; 16384], &SizeConstraint::unconstrained(), |e, v| e.encode_boolean(*v))
+        }), expected);
+
+        // A size constraint reaching 64K or more does not prevent fragmentation.
+        assert_eq!(encode(|e| e.encode_octet_string(&[0; 70000], &SizeConstraint::range(0, 100000))).len(), 70003);
+        assert!(Encoder::new().encode_octet_string(&[0; 70000], &SizeConstraint::range(0, 65535)).is_err());
+    }
+}
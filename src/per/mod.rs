@@ -0,0 +1,178 @@
+//! Packed Encoding Rules (X.691), in the unaligned (UPER) and aligned (APER) variants.
+//!
+//! Unlike BER and DER, PER carries no tags and packs values at bit granularity, so encoding and decoding are
+//! driven by the type's constraints rather than by the data. `Encoder` and `Decoder` provide the primitive
+//! encodings, each taking the constraint metadata of the type it encodes; generated or hand-written code for a
+//! particular type calls them in the order the type's components appear.
+//!
+//! Both variants share the same constraints and methods. The aligned variant, used by S1AP, NGAP and X2AP, pads
+//! to an octet boundary before length determinants, larger constrained whole numbers and most string contents;
+//! `Encoder::aligned` and `Decoder::aligned` select it.
+
+use integer;
+use std;
+
+pub mod bits;
+pub mod decoder;
+pub mod encoder;
+
+pub use self::bits::{BitReader, BitWriter};
+pub use self::decoder::Decoder;
+pub use self::encoder::Encoder;
+
+
+/// Lengths from this value on are fragmented into chunks of 16K, 32K, 48K or 64K units (X.691 11.9.3.8).
+pub const MAX_UNFRAGMENTED_LENGTH: usize = 16384;
+
+
+#[derive(Debug)]
+pub enum Error {
+    UnexpectedEndOfData,
+    /// A value lies outside a constraint that has no extension marker, or decodes to one that does.
+    ValueOutOfRange,
+    /// A length needs fragmentation where only a single length determinant is allowed.
+    LengthTooLarge,
+    Integer(integer::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::UnexpectedEndOfData => write!(f, "unexpected end of data"),
+            Error::ValueOutOfRange => write!(f, "value out of range"),
+            Error::LengthTooLarge => write!(f, "length too large"),
+            Error::Integer(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Integer(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<integer::Error> for Error {
+    fn from(e: integer::Error) -> Error {
+        Error::Integer(e)
+    }
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// The value range of an INTEGER type, as used by PER-visible constraints.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct IntegerConstraint {
+    pub lower: Option<i64>,
+    pub upper: Option<i64>,
+    /// Whether the constraint has an extension marker (`INTEGER (0..7, ...)`).
+    pub extensible: bool,
+}
+
+impl IntegerConstraint {
+    pub fn unconstrained() -> Self {
+        IntegerConstraint {
+            lower: None,
+            upper: None,
+            extensible: false,
+        }
+    }
+
+    /// `INTEGER (lower..upper)`.
+    pub fn range(lower: i64, upper: i64) -> Self {
+        IntegerConstraint {
+            lower: Some(lower),
+            upper: Some(upper),
+            extensible: false,
+        }
+    }
+
+    /// `INTEGER (lower..MAX)`.
+    pub fn semi_constrained(lower: i64) -> Self {
+        IntegerConstraint {
+            lower: Some(lower),
+            upper: None,
+            extensible: false,
+        }
+    }
+
+    pub fn extensible(self) -> Self {
+        IntegerConstraint {
+            extensible: true,
+            ..self
+        }
+    }
+
+    pub fn contains(&self, v: i64) -> bool {
+        self.lower.is_none_or(|lower| v >= lower) && self.upper.is_none_or(|upper| v <= upper)
+    }
+}
+
+
+/// The permitted sizes of a string or SEQUENCE OF type.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct SizeConstraint {
+    pub lower: usize,
+    pub upper: Option<usize>,
+    /// Whether the constraint has an extension marker (`SIZE (1..4, ...)`).
+    pub extensible: bool,
+}
+
+impl SizeConstraint {
+    pub fn unconstrained() -> Self {
+        SizeConstraint {
+            lower: 0,
+            upper: None,
+            extensible: false,
+        }
+    }
+
+    /// `SIZE (size)`.
+    pub fn fixed(size: usize) -> Self {
+        SizeConstraint::range(size, size)
+    }
+
+    /// `SIZE (lower..upper)`.
+    pub fn range(lower: usize, upper: usize) -> Self {
+        SizeConstraint {
+            lower,
+            upper: Some(upper),
+            extensible: false,
+        }
+    }
+
+    pub fn extensible(self) -> Self {
+        SizeConstraint {
+            extensible: true,
+            ..self
+        }
+    }
+
+    pub fn contains(&self, size: usize) -> bool {
+        size >= self.lower && self.upper.is_none_or(|upper| size <= upper)
+    }
+
+    /// Whether the length is encoded as a constrained whole number rather than a general length determinant
+    /// (X.691 11.9.3.3).
+    fn is_small(&self) -> bool {
+        self.upper.is_some_and(|upper| upper < 65536)
+    }
+}
+
+
+/// The number of bits needed to encode a constrained whole number with `range` possible values.
+fn range_bits(range: u128) -> usize {
+    128 - (range - 1).leading_zeros() as usize
+}
+
+/// The minimal octets of a non-negative-binary-integer, at least one octet long.
+fn unsigned_octets(v: u64) -> Vec<u8> {
+    let bytes = v.to_be_bytes();
+    let skip = std::cmp::min(v.leading_zeros() as usize / 8, 7);
+    bytes[skip..].to_vec()
+}
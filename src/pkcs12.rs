@@ -0,0 +1,688 @@
+use cms::{self, ContentInfo};
+use identifier::{Identifier, UniversalTag};
+use object_identifier::ObjectIdentifier;
+use pkcs8::{self, EncryptedPrivateKeyInfo, PrivateKeyInfo};
+use reading::{self, Asn1Reader};
+use std;
+use std::borrow::Cow;
+use x509::{self, name::AttributeValue, AlgorithmIdentifier, Attribute, Certificate};
+
+
+pub mod oid {
+    pub const KEY_BAG: &[u64] = &[1, 2, 840, 113549, 1, 12, 10, 1, 1];
+    pub const PKCS8_SHROUDED_KEY_BAG: &[u64] = &[1, 2, 840, 113549, 1, 12, 10, 1, 2];
+    pub const CERT_BAG: &[u64] = &[1, 2, 840, 113549, 1, 12, 10, 1, 3];
+    pub const CRL_BAG: &[u64] = &[1, 2, 840, 113549, 1, 12, 10, 1, 4];
+    pub const SECRET_BAG: &[u64] = &[1, 2, 840, 113549, 1, 12, 10, 1, 5];
+    pub const SAFE_CONTENTS_BAG: &[u64] = &[1, 2, 840, 113549, 1, 12, 10, 1, 6];
+
+    pub const FRIENDLY_NAME: &[u64] = &[1, 2, 840, 113549, 1, 9, 20];
+    pub const LOCAL_KEY_ID: &[u64] = &[1, 2, 840, 113549, 1, 9, 21];
+
+    pub const X509_CERTIFICATE: &[u64] = &[1, 2, 840, 113549, 1, 9, 22, 1];
+    pub const SDSI_CERTIFICATE: &[u64] = &[1, 2, 840, 113549, 1, 9, 22, 2];
+    pub const X509_CRL: &[u64] = &[1, 2, 840, 113549, 1, 9, 23, 1];
+}
+
+
+/// The deepest nesting of safeContentsBags that will be decoded, which bounds the recursion of `SafeBag::read_list`.
+pub const MAX_SAFE_CONTENTS_DEPTH: usize = 64;
+
+
+#[derive(Debug)]
+pub enum Error {
+    Read(reading::Error),
+    InvalidVersion,
+    InvalidValue,
+    UnexpectedContentType(ObjectIdentifier),
+    MissingMac,
+    NestingTooDeep,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Read(ref e) => e.fmt(f),
+            Error::InvalidVersion => write!(f, "invalid version"),
+            Error::InvalidValue => write!(f, "invalid value"),
+            Error::UnexpectedContentType(ref oid) => write!(f, "unexpected content type: {}", oid),
+            Error::MissingMac => write!(f, "missing mac"),
+            Error::NestingTooDeep => write!(f, "safe contents nesting too deep"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Read(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reading::Error> for Error {
+    fn from(e: reading::Error) -> Error {
+        Error::Read(e)
+    }
+}
+
+impl From<x509::Error> for Error {
+    fn from(e: x509::Error) -> Error {
+        match e {
+            x509::Error::Read(e) => Error::Read(e),
+            _ => Error::InvalidValue,
+        }
+    }
+}
+
+impl From<pkcs8::Error> for Error {
+    fn from(e: pkcs8::Error) -> Error {
+        match e {
+            pkcs8::Error::Read(e) => Error::Read(e),
+            _ => Error::InvalidValue,
+        }
+    }
+}
+
+impl From<cms::Error> for Error {
+    fn from(e: cms::Error) -> Error {
+        match e {
+            cms::Error::Read(e) => Error::Read(e),
+            cms::Error::UnexpectedContentType(oid) => Error::UnexpectedContentType(oid),
+            _ => Error::InvalidValue,
+        }
+    }
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// An error from an operation that delegates decryption or MAC computation to a caller-supplied callback.
+#[derive(Debug)]
+pub enum HookError<E> {
+    Decode(Error),
+    Hook(E),
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for HookError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            HookError::Decode(ref e) => e.fmt(f),
+            HookError::Hook(ref e) => write!(f, "callback failed: {}", e),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for HookError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            HookError::Decode(ref e) => Some(e),
+            HookError::Hook(ref e) => Some(e),
+        }
+    }
+}
+
+impl<E, T: Into<Error>> From<T> for HookError<E> {
+    fn from(e: T) -> HookError<E> {
+        HookError::Decode(e.into())
+    }
+}
+
+
+/// Reads `[0] EXPLICIT` around a single value, returning the value's complete encoding.
+fn read_explicit_value<'a>(r: &mut Asn1Reader<'a>) -> Result<&'a [u8]> {
+    let mut r = Asn1Reader::new(r.read_expected(Identifier::context_specific(true, 0))?);
+    let v = r.read_raw()?;
+    r.expect_end()?;
+    Ok(v)
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct MacData<'a> {
+    pub digest_algorithm: AlgorithmIdentifier<'a>,
+    pub digest: &'a [u8],
+    pub mac_salt: &'a [u8],
+    pub iterations: u64,
+}
+
+impl<'a> MacData<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let (digest_algorithm, digest) = {
+            let mut r = r.read_sequence()?;
+            let digest_algorithm = AlgorithmIdentifier::read(&mut r)?;
+            let digest = r.read_octet_string()?;
+            r.expect_end()?;
+            (digest_algorithm, digest)
+        };
+        let mac_salt = r.read_octet_string()?;
+        let iterations = if r.is_at_end() { 1 } else { r.read_u64()? };
+        r.expect_end()?;
+        Ok(MacData {
+            digest_algorithm,
+            digest,
+            mac_salt,
+            iterations,
+        })
+    }
+}
+
+
+/// A PFX, the outermost PKCS#12 structure.
+///
+/// Only password integrity mode is supported, where `auth_safe` is `data` content protected by `mac_data`.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Pfx<'a> {
+    pub auth_safe: ContentInfo<'a>,
+    pub mac_data: Option<MacData<'a>>,
+}
+
+impl<'a> Pfx<'a> {
+    pub fn from_ber(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = Pfx::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        if r.read_u64()? != 3 {
+            return Err(Error::InvalidVersion);
+        }
+        let auth_safe = ContentInfo::read(&mut r)?;
+        let mac_data = if r.is_at_end() { None } else { Some(MacData::read(&mut r)?) };
+        r.expect_end()?;
+        Ok(Pfx {
+            auth_safe,
+            mac_data,
+        })
+    }
+
+    /// The encoded AuthenticatedSafe, which is also the input to the MAC.
+    pub fn auth_safe_data(&self) -> Result<Cow<'a, [u8]>> {
+        Ok(self.auth_safe.data()?)
+    }
+
+    /// Checks the MAC, using `mac` to compute it over the encoded AuthenticatedSafe with the parameters in
+    /// `mac_data`.
+    pub fn verify_mac<F, E>(&self, mac: F) -> std::result::Result<bool, HookError<E>>
+        where
+            F: FnOnce(&MacData<'a>, &[u8]) -> std::result::Result<Vec<u8>, E>,
+    {
+        let mac_data = match self.mac_data {
+            Some(ref mac_data) => mac_data,
+            None => return Err(HookError::Decode(Error::MissingMac)),
+        };
+        let data = self.auth_safe_data()?;
+        let expected = mac(mac_data, &data).map_err(HookError::Hook)?;
+        Ok(expected.len() == mac_data.digest.len() &&
+            expected.iter().zip(mac_data.digest).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0)
+    }
+}
+
+
+/// An AuthenticatedSafe, the sequence of ContentInfo values that each hold SafeContents.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct AuthenticatedSafe<'a> {
+    pub contents: Vec<ContentInfo<'a>>,
+}
+
+impl<'a> AuthenticatedSafe<'a> {
+    pub fn from_ber(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let mut seq = r.read_sequence()?;
+        r.expect_end()?;
+        let mut contents = Vec::new();
+        while !seq.is_at_end() {
+            contents.push(ContentInfo::read(&mut seq)?);
+        }
+        Ok(AuthenticatedSafe {
+            contents,
+        })
+    }
+}
+
+
+/// Returns the encoded SafeContents held by an AuthenticatedSafe element.
+///
+/// `data` content is returned as is. For `encryptedData`, `decrypt` is called with the content encryption
+/// algorithm and the ciphertext, and returns the plaintext. Public-key (`envelopedData`) protection is not
+/// supported.
+pub fn safe_contents<'a, F, E>(content_info: &ContentInfo<'a>, decrypt: F) -> std::result::Result<Cow<'a, [u8]>, HookError<E>>
+    where
+        F: FnOnce(&AlgorithmIdentifier<'a>, &[u8]) -> std::result::Result<Vec<u8>, E>,
+{
+    if content_info.content_type.components() != cms::oid::ENCRYPTED_DATA {
+        return Ok(content_info.data()?);
+    }
+    let encrypted_data = content_info.encrypted_data()?;
+    let info = &encrypted_data.encrypted_content_info;
+    let ciphertext = match info.encrypted_content {
+        Some(ref ciphertext) => ciphertext,
+        None => return Err(HookError::Decode(Error::InvalidValue)),
+    };
+    let plaintext = decrypt(&info.content_encryption_algorithm, ciphertext).map_err(HookError::Hook)?;
+    Ok(Cow::Owned(plaintext))
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Bag<'a> {
+    Key(PrivateKeyInfo<'a>),
+    Pkcs8ShroudedKey(EncryptedPrivateKeyInfo<'a>),
+    Cert {
+        cert_id: ObjectIdentifier,
+        /// The contents of the certValue OCTET STRING (a DER certificate for `oid::X509_CERTIFICATE`), joined if
+        /// BER constructed it from segments.
+        cert_value: Cow<'a, [u8]>,
+    },
+    Crl {
+        crl_id: ObjectIdentifier,
+        /// The contents of the crlValue OCTET STRING (a DER CRL for `oid::X509_CRL`), joined if BER constructed it
+        /// from segments.
+        crl_value: Cow<'a, [u8]>,
+    },
+    Secret {
+        secret_type_id: ObjectIdentifier,
+        /// The complete encoding of the secretValue.
+        secret_value: &'a [u8],
+    },
+    SafeContents(Vec<SafeBag<'a>>),
+    Other {
+        bag_id: ObjectIdentifier,
+        /// The complete encoding of the bagValue.
+        bag_value: &'a [u8],
+    },
+}
+
+impl<'a> Bag<'a> {
+    fn read(bag_id: ObjectIdentifier, bag_value: &'a [u8], depth: usize) -> Result<Self> {
+        let components = bag_id.components();
+        if components == oid::KEY_BAG {
+            Ok(Bag::Key(PrivateKeyInfo::from_der(bag_value)?))
+        } else if components == oid::PKCS8_SHROUDED_KEY_BAG {
+            Ok(Bag::Pkcs8ShroudedKey(EncryptedPrivateKeyInfo::from_der(bag_value)?))
+        } else if components == oid::CERT_BAG || components == oid::CRL_BAG {
+            let mut r = Asn1Reader::new(bag_value);
+            let mut seq = r.read_sequence()?;
+            r.expect_end()?;
+            let id = seq.read_object_identifier()?;
+            let value = Asn1Reader::new(read_explicit_value(&mut seq)?).read_ber_octet_string()?;
+            seq.expect_end()?;
+            if components == oid::CERT_BAG {
+                Ok(Bag::Cert { cert_id: id, cert_value: value })
+            } else {
+                Ok(Bag::Crl { crl_id: id, crl_value: value })
+            }
+        } else if components == oid::SECRET_BAG {
+            let mut r = Asn1Reader::new(bag_value);
+            let mut seq = r.read_sequence()?;
+            r.expect_end()?;
+            let secret_type_id = seq.read_object_identifier()?;
+            let secret_value = read_explicit_value(&mut seq)?;
+            seq.expect_end()?;
+            Ok(Bag::Secret { secret_type_id, secret_value })
+        } else if components == oid::SAFE_CONTENTS_BAG {
+            Ok(Bag::SafeContents(SafeBag::read_nested_list(bag_value, depth + 1)?))
+        } else {
+            Ok(Bag::Other { bag_id, bag_value })
+        }
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct SafeBag<'a> {
+    pub bag: Bag<'a>,
+    pub bag_attributes: Vec<Attribute<'a>>,
+}
+
+impl<'a> SafeBag<'a> {
+    fn read(r: &mut Asn1Reader<'a>, depth: usize) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let bag_id = r.read_object_identifier()?;
+        let bag_value = read_explicit_value(&mut r)?;
+        let mut bag_attributes = Vec::new();
+        if !r.is_at_end() {
+            let mut set = r.read_set()?;
+            while !set.is_at_end() {
+                bag_attributes.push(Attribute::read(&mut set)?);
+            }
+        }
+        r.expect_end()?;
+        Ok(SafeBag {
+            bag: Bag::read(bag_id, bag_value, depth)?,
+            bag_attributes,
+        })
+    }
+
+    /// Decodes an encoded SafeContents, as returned by `safe_contents`.
+    pub fn read_list(b: &'a [u8]) -> Result<Vec<Self>> {
+        SafeBag::read_nested_list(b, 0)
+    }
+
+    fn read_nested_list(b: &'a [u8], depth: usize) -> Result<Vec<Self>> {
+        if depth >= MAX_SAFE_CONTENTS_DEPTH {
+            return Err(Error::NestingTooDeep);
+        }
+        let mut r = Asn1Reader::new(b);
+        let mut seq = r.read_sequence()?;
+        r.expect_end()?;
+        let mut bags = Vec::new();
+        while !seq.is_at_end() {
+            bags.push(SafeBag::read(&mut seq, depth)?);
+        }
+        Ok(bags)
+    }
+
+    /// Returns the single value of the given attribute, if present.
+    fn attribute_value(&self, attr_type: &[u64]) -> Result<Option<&'a [u8]>> {
+        match self.bag_attributes.iter().find(|a| a.attr_type.components() == attr_type) {
+            Some(a) if a.values.len() == 1 => Ok(Some(a.values[0])),
+            Some(_) => Err(Error::InvalidValue),
+            None => Ok(None),
+        }
+    }
+
+    /// The friendlyName attribute, a BMPString.
+    pub fn friendly_name(&self) -> Result<Option<String>> {
+        let b = match self.attribute_value(oid::FRIENDLY_NAME)? {
+            Some(b) => b,
+            None => return Ok(None),
+        };
+        let mut r = Asn1Reader::new(b);
+        let contents = r.read_expected(Identifier::universal(UniversalTag::BmpString))?;
+        r.expect_end()?;
+        let value = AttributeValue {
+            identifier: Identifier::universal(UniversalTag::BmpString),
+            contents: Cow::Borrowed(contents),
+        };
+        value.to_str().map(|s| Some(s.into_owned())).ok_or(Error::InvalidValue)
+    }
+
+    /// The localKeyId attribute, which links a key to its certificate.
+    pub fn local_key_id(&self) -> Result<Option<&'a [u8]>> {
+        match self.attribute_value(oid::LOCAL_KEY_ID)? {
+            Some(b) => {
+                let mut r = Asn1Reader::new(b);
+                let v = r.read_octet_string()?;
+                r.expect_end()?;
+                Ok(Some(v))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Decodes the certificate held by an X.509 certBag.
+    pub fn certificate(&self) -> Result<Option<Certificate<'_>>> {
+        match self.bag {
+            Bag::Cert { ref cert_id, ref cert_value } if cert_id.components() == oid::X509_CERTIFICATE => {
+                Ok(Some(Certificate::from_der(cert_value)?))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pem;
+    use writing::der::Writer;
+    use x509::test::ca_der;
+
+    // `openssl pkcs12 -export -certpbe NONE -name "Example CA" -iter 2048` over the x509 test certificate and its
+    // key: the certificate is in plain `data` content and the key is in a PBES2 pkcs8ShroudedKeyBag.
+    const PFX: &str = "\
+MIIEmgIBAzCCBFAGCSqGSIb3DQEHAaCCBEEEggQ9MIIEOTCCAsMGCSqGSIb3DQEH
+AaCCArQEggKwMIICrDCCAqgGCyqGSIb3DQEMCgEDoIICSzCCAkcGCiqGSIb3DQEJ
+FgGgggI3BIICMzCCAi8wggHUoAMCAQICBQECAwQFMAoGCCqGSM49BAMCMDgxCzAJ
+BgNVBAYTAkdCMRQwEgYDVQQKDAtFeGFtcGxlIEx0ZDETMBEGA1UEAwwKRXhhbXBs
+ZSBDQTAeFw0yNDAxMDEwMDAwMDBaFw0zNDAxMDEwMDAwMDBaMDgxCzAJBgNVBAYT
+AkdCMRQwEgYDVQQKDAtFeGFtcGxlIEx0ZDETMBEGA1UEAwwKRXhhbXBsZSBDQTBZ
+MBMGByqGSM49AgEGCCqGSM49AwEHA0IABN5jWCOP8Q4kAg4o/gXYFBQAbCfKr5dJ
+oVRNLD+EijxglVbOc8zG4DchU4C9pDsLPA7Wmzc+KjerjPFPZvUdXHujgcowgccw
+HQYDVR0OBBYEFAfmo9JsAXUN7k6g3TofBFShw3LqMB8GA1UdIwQYMBaAFAfmo9Js
+AXUN7k6g3TofBFShw3LqMBIGA1UdEwEB/wQIMAYBAf8CAQEwDgYDVR0PAQH/BAQD
+AgGGMEIGA1UdEQQ7MDmCC2V4YW1wbGUuY29thwR/AAABgQ5jYUBleGFtcGxlLmNv
+bYYUaHR0cHM6Ly9leGFtcGxlLmNvbS8wHQYDVR0lBBYwFAYIKwYBBQUHAwEGCCsG
+AQUFBwMCMAoGCCqGSM49BAMCA0kAMEYCIQC+akiExCZx9OxNSt67xWiGrkaDpBXm
+PIz9NVLJWlCnrwIhAKsIJ3VMl/c2/S3SiFWDqAzTgPvaddyF/r2y62cYt19bMUow
+IwYJKoZIhvcNAQkUMRYeFABFAHgAYQBtAHAAbABlACAAQwBBMCMGCSqGSIb3DQEJ
+FTEWBBRzM+7E2vjNjhOZdStAqasmrgju3jCCAW4GCSqGSIb3DQEHAaCCAV8EggFb
+MIIBVzCCAVMGCyqGSIb3DQEMCgECoIH3MIH0MF8GCSqGSIb3DQEFDTBSMDEGCSqG
+SIb3DQEFDDAkBBDFa4QYgTBdixFGS0g43u0IAgIIADAMBggqhkiG9w0CCQUAMB0G
+CWCGSAFlAwQBKgQQftvw2lX7Z+D72lX5sruDrgSBkBr0D8CkyJCR8RP/kIexOX2F
+0KZLPtwn4aj6mpOBsqaY+l4cOiTJlgz379L7an2YaynHVipumBu+0c05MyB9m0B+
++nHc630nOqZOqo9SPsobX4sW0Xfzu6wYKCZB7IzqiBMbqmhWge299FqHJWNrRFMB
+di6QTXm+vFHQZKjyCxBX/tD9oyTLSaq357vvvXnhDTFKMCMGCSqGSIb3DQEJFDEW
+HhQARQB4AGEAbQBwAGwAZQAgAEMAQTAjBgkqhkiG9w0BCRUxFgQUczPuxNr4zY4T
+mXUrQKmrJq4I7t4wQTAxMA0GCWCGSAFlAwQCAQUABCD5mBVt1IEWfWdC2Qs49D6c
+rDXn1P+CSwTxea138zcwpwQIz6+Q8WNx4xgCAggA";
+
+    const MAC: [u8; 32] = [
+        0xf9, 0x98, 0x15, 0x6d, 0xd4, 0x81, 0x16, 0x7d, 0x67, 0x42, 0xd9, 0x0b, 0x38, 0xf4, 0x3e, 0x9c,
+        0xac, 0x35, 0xe7, 0xd4, 0xff, 0x82, 0x4b, 0x04, 0xf1, 0x79, 0xad, 0x77, 0xf3, 0x37, 0x30, 0xa7,
+    ];
+    const LOCAL_KEY_ID: [u8; 20] = [
+        0x73, 0x33, 0xee, 0xc4, 0xda, 0xf8, 0xcd, 0x8e, 0x13, 0x99, 0x75, 0x2b, 0x40, 0xa9, 0xab, 0x26, 0xae, 0x08, 0xee, 0xde,
+    ];
+
+    fn pfx_der() -> Vec<u8> {
+        let pem = format!("-----BEGIN PKCS12-----\n{}\n-----END PKCS12-----\n", PFX);
+        pem::parse(&pem, pem::Mode::Strict).unwrap().into_contents()
+    }
+
+    fn no_decrypt(_: &AlgorithmIdentifier, _: &[u8]) -> std::result::Result<Vec<u8>, ()> {
+        panic!("unexpected decryption")
+    }
+
+    #[test]
+    fn test_pfx() {
+        let der = pfx_der();
+        let pfx = Pfx::from_ber(&der).unwrap();
+
+        let mac_data = pfx.mac_data.as_ref().unwrap();
+        assert_eq!(mac_data.digest_algorithm.algorithm.components(), [2, 16, 840, 1, 101, 3, 4, 2, 1]);
+        assert_eq!(mac_data.digest, MAC);
+        assert_eq!(mac_data.mac_salt, [0xcfu8, 0xaf, 0x90, 0xf1, 0x63, 0x71, 0xe3, 0x18]);
+        assert_eq!(mac_data.iterations, 2048);
+
+        let data = pfx.auth_safe_data().unwrap();
+        assert_eq!(&data[..], &der[30..30 + 1085]);
+        assert!(pfx.verify_mac(|m, d| -> std::result::Result<_, ()> {
+            assert_eq!(m, mac_data);
+            assert_eq!(d, &data[..]);
+            Ok(MAC.to_vec())
+        }).unwrap());
+        assert!(!pfx.verify_mac(|_, _| -> std::result::Result<_, ()> { Ok(vec![0; 32]) }).unwrap());
+        match pfx.verify_mac(|_, _| Err("wrong password")) {
+            Err(HookError::Hook("wrong password")) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let safe = AuthenticatedSafe::from_ber(&data).unwrap();
+        assert_eq!(safe.contents.len(), 2);
+
+        let contents = safe_contents(&safe.contents[0], no_decrypt).unwrap();
+        let bags = SafeBag::read_list(&contents).unwrap();
+        assert_eq!(bags.len(), 1);
+        let cert = bags[0].certificate().unwrap().unwrap();
+        assert_eq!(cert.raw, &ca_der()[..]);
+        assert_eq!(bags[0].friendly_name().unwrap(), Some("Example CA".to_string()));
+        assert_eq!(bags[0].local_key_id().unwrap(), Some(&LOCAL_KEY_ID[..]));
+
+        let contents = safe_contents(&safe.contents[1], no_decrypt).unwrap();
+        let bags = SafeBag::read_list(&contents).unwrap();
+        assert_eq!(bags.len(), 1);
+        match bags[0].bag {
+            Bag::Pkcs8ShroudedKey(ref key) => {
+                assert_eq!(key.encryption_algorithm.algorithm.components(), [1, 2, 840, 113549, 1, 5, 13]);
+                assert_eq!(key.encrypted_data.len(), 144);
+            }
+            ref other => panic!("unexpected bag {:?}", other),
+        }
+        assert_eq!(bags[0].certificate().unwrap(), None);
+        assert_eq!(bags[0].friendly_name().unwrap(), Some("Example CA".to_string()));
+        assert_eq!(bags[0].local_key_id().unwrap(), Some(&LOCAL_KEY_ID[..]));
+    }
+
+    #[test]
+    fn test_encrypted_safe_contents() {
+        // A SafeContents holding a keyBag and a secretBag, "encrypted" by the identity function.
+        let mut plaintext = Vec::new();
+        Writer::new(&mut plaintext).write_sequence(|w| {
+            w.write_sequence(|w| {
+                w.write_object_identifier(&ObjectIdentifier::from_components(oid::KEY_BAG))?;
+                w.write_explicit(0, |w| {
+                    w.write_raw(&[
+                        0x30, 0x0e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70,
+                        0x04, 0x02, 0x04, 0x00,
+                    ])?;
+                    Ok(())
+                })?;
+                Ok(())
+            })?;
+            w.write_sequence(|w| {
+                w.write_object_identifier(&ObjectIdentifier::from_components(oid::SECRET_BAG))?;
+                w.write_explicit(0, |w| {
+                    w.write_sequence(|w| {
+                        w.write_object_identifier(&ObjectIdentifier::from_components(&[1, 2, 3]))?;
+                        w.write_explicit(0, |w| {
+                            w.write_octet_string(b"secret")?;
+                            Ok(())
+                        })?;
+                        Ok(())
+                    })?;
+                    Ok(())
+                })?;
+                Ok(())
+            })?;
+            Ok(())
+        }).unwrap();
+
+        let mut encrypted_data = Vec::new();
+        Writer::new(&mut encrypted_data).write_sequence(|w| {
+            w.write_object_identifier(&ObjectIdentifier::from_components(cms::oid::ENCRYPTED_DATA))?;
+            w.write_explicit(0, |w| {
+                w.write_sequence(|w| {
+                    w.write_u64(0)?;
+                    w.write_sequence(|w| {
+                        w.write_object_identifier(&ObjectIdentifier::from_components(cms::oid::DATA))?;
+                        w.write_raw(&[0x30, 0x03, 0x06, 0x01, 0x2a])?;
+                        w.write_tag_and_data(Identifier::context_specific(false, 0), &plaintext)?;
+                        Ok(())
+                    })?;
+                    Ok(())
+                })?;
+                Ok(())
+            })?;
+            Ok(())
+        }).unwrap();
+
+        let content_info = ContentInfo::from_ber(&encrypted_data).unwrap();
+        let contents = safe_contents(&content_info, |alg, ciphertext| -> std::result::Result<_, ()> {
+            assert_eq!(alg.algorithm.components(), [1, 2]);
+            Ok(ciphertext.to_vec())
+        }).unwrap();
+        assert_eq!(&contents[..], &plaintext[..]);
+
+        let bags = SafeBag::read_list(&contents).unwrap();
+        assert_eq!(bags.len(), 2);
+        match bags[0].bag {
+            Bag::Key(ref key) => assert_eq!(key.private_key_algorithm.algorithm.components(), [1, 3, 101, 112]),
+            ref other => panic!("unexpected bag {:?}", other),
+        }
+        assert_eq!(bags[1].bag, Bag::Secret {
+            secret_type_id: ObjectIdentifier::from_components(&[1, 2, 3]),
+            secret_value: &[0x04, 0x06, b's', b'e', b'c', b'r', b'e', b't'],
+        });
+        assert_eq!(bags[1].friendly_name().unwrap(), None);
+    }
+
+    #[test]
+    fn test_crl_bag() {
+        // Two crlBags, the second holding its crlValue as a BER constructed OCTET STRING.
+        let crl_bag = |crl_value: &[u8]| {
+            let mut output = Vec::new();
+            Writer::new(&mut output).write_sequence(|w| {
+                w.write_object_identifier(&ObjectIdentifier::from_components(oid::CRL_BAG))?;
+                w.write_explicit(0, |w| {
+                    w.write_sequence(|w| {
+                        w.write_object_identifier(&ObjectIdentifier::from_components(oid::X509_CRL))?;
+                        w.write_explicit(0, |w| {
+                            w.write_raw(crl_value)?;
+                            Ok(())
+                        })?;
+                        Ok(())
+                    })?;
+                    Ok(())
+                })?;
+                Ok(())
+            }).unwrap();
+            output
+        };
+        let mut contents = Vec::new();
+        Writer::new(&mut contents).write_sequence(|w| {
+            w.write_raw(&crl_bag(&[0x04, 0x02, 0x30, 0x00]))?;
+            w.write_raw(&crl_bag(&[0x24, 0x80, 0x04, 0x01, 0x30, 0x04, 0x01, 0x00, 0x00, 0x00]))?;
+            Ok(())
+        }).unwrap();
+
+        let bags = SafeBag::read_list(&contents).unwrap();
+        assert_eq!(bags.len(), 2);
+        for bag in &bags {
+            match bag.bag {
+                Bag::Crl { ref crl_id, ref crl_value } => {
+                    assert_eq!(crl_id.components(), oid::X509_CRL);
+                    assert_eq!(&crl_value[..], [0x30, 0x00]);
+                }
+                ref other => panic!("unexpected bag {:?}", other),
+            }
+            assert_eq!(bag.certificate().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_safe_contents_depth() {
+        let nested = |depth: usize| {
+            let mut contents = vec![0x30, 0x00];
+            for _ in 0..depth {
+                let mut outer = Vec::new();
+                Writer::new(&mut outer).write_sequence(|w| {
+                    w.write_sequence(|w| {
+                        w.write_object_identifier(&ObjectIdentifier::from_components(oid::SAFE_CONTENTS_BAG))?;
+                        w.write_explicit(0, |w| {
+                            w.write_raw(&contents)?;
+                            Ok(())
+                        })?;
+                        Ok(())
+                    })?;
+                    Ok(())
+                }).unwrap();
+                contents = outer;
+            }
+            contents
+        };
+
+        let contents = nested(MAX_SAFE_CONTENTS_DEPTH - 1);
+        let mut bags = SafeBag::read_list(&contents).unwrap();
+        for _ in 1..MAX_SAFE_CONTENTS_DEPTH - 1 {
+            bags = match bags.pop().unwrap().bag {
+                Bag::SafeContents(bags) => bags,
+                other => panic!("unexpected bag {:?}", other),
+            };
+        }
+        assert_eq!(bags[0].bag, Bag::SafeContents(Vec::new()));
+
+        match SafeBag::read_list(&nested(1000)) {
+            Err(Error::NestingTooDeep) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+    }
+}
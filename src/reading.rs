@@ -1,5 +1,12 @@
-use identifier::{self, Identifier};
+use bit_string::{self, BitString};
+use enumerated::Enumerated;
+use identifier::{self, Class, Identifier, UniversalTag};
+use integer;
+use object_identifier::{self, ObjectIdentifier};
+use real;
 use std;
+use std::borrow::Cow;
+use time::{self, DateTime};
 
 
 #[derive(Debug)]
@@ -19,29 +26,108 @@ pub enum Error {
     UnexpectedEndOfData,
     InvalidTag,
     LengthOverflow,
+    IndefiniteLengthPrimitive,
+    NestingTooDeep,
+    UnexpectedTag(Identifier),
+    TrailingData,
+    InvalidBoolean,
+    InvalidNull,
+    UnknownEnumerated(i64),
+    Integer(integer::Error),
+    Real(real::Error),
+    ObjectIdentifier(object_identifier::Error),
+    BitString(bit_string::Error),
+    Time(time::Error),
     Io(std::io::Error),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            &Error::UnexpectedEndOfData => write!(f, "unexpected endofdata"),
-            &Error::InvalidTag => write!(f, "invalid tag"),
-            &Error::LengthOverflow => write!(f, "length overflow"),
-            &Error::Io(ref e) => e.fmt(f)
+        match *self {
+            Error::UnexpectedEndOfData => write!(f, "unexpected endofdata"),
+            Error::InvalidTag => write!(f, "invalid tag"),
+            Error::LengthOverflow => write!(f, "length overflow"),
+            Error::IndefiniteLengthPrimitive => write!(f, "indefinite length on a primitive value"),
+            Error::NestingTooDeep => write!(f, "nesting too deep"),
+            Error::UnexpectedTag(ref i) => write!(f, "unexpected tag: {:?}", i),
+            Error::TrailingData => write!(f, "trailing data"),
+            Error::InvalidBoolean => write!(f, "invalid boolean"),
+            Error::InvalidNull => write!(f, "invalid null"),
+            Error::UnknownEnumerated(v) => write!(f, "unknown enumerated value {}", v),
+            Error::Integer(ref e) => e.fmt(f),
+            Error::Real(ref e) => e.fmt(f),
+            Error::ObjectIdentifier(ref e) => e.fmt(f),
+            Error::BitString(ref e) => e.fmt(f),
+            Error::Time(ref e) => e.fmt(f),
+            Error::Io(ref e) => e.fmt(f)
         }
     }
 }
 
 impl std::error::Error for Error {
+    #[allow(deprecated)]
     fn description(&self) -> &str {
-        match self {
-            &Error::UnexpectedEndOfData => "unexpected endofdata",
-            &Error::InvalidTag => "invalid tag",
-            &Error::LengthOverflow => "length overflow",
-            &Error::Io(ref e) => e.description()
+        match *self {
+            Error::UnexpectedEndOfData => "unexpected endofdata",
+            Error::InvalidTag => "invalid tag",
+            Error::LengthOverflow => "length overflow",
+            Error::IndefiniteLengthPrimitive => "indefinite length on a primitive value",
+            Error::NestingTooDeep => "nesting too deep",
+            Error::UnexpectedTag(_) => "unexpected tag",
+            Error::TrailingData => "trailing data",
+            Error::InvalidBoolean => "invalid boolean",
+            Error::InvalidNull => "invalid null",
+            Error::UnknownEnumerated(_) => "unknown enumerated value",
+            Error::Integer(_) => "invalid integer",
+            Error::Real(_) => "invalid real",
+            Error::ObjectIdentifier(ref e) => e.description(),
+            Error::BitString(_) => "invalid bit string",
+            Error::Time(_) => "invalid time",
+            Error::Io(ref e) => e.description()
         }
     }
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Integer(ref e) => Some(e),
+            Error::Real(ref e) => Some(e),
+            Error::ObjectIdentifier(ref e) => Some(e),
+            Error::BitString(ref e) => Some(e),
+            Error::Time(ref e) => Some(e),
+            Error::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<integer::Error> for Error {
+    fn from(e: integer::Error) -> Error {
+        Error::Integer(e)
+    }
+}
+
+impl From<real::Error> for Error {
+    fn from(e: real::Error) -> Error {
+        Error::Real(e)
+    }
+}
+
+impl From<object_identifier::Error> for Error {
+    fn from(e: object_identifier::Error) -> Error {
+        Error::ObjectIdentifier(e)
+    }
+}
+
+impl From<bit_string::Error> for Error {
+    fn from(e: bit_string::Error) -> Error {
+        Error::BitString(e)
+    }
+}
+
+impl From<time::Error> for Error {
+    fn from(e: time::Error) -> Error {
+        Error::Time(e)
+    }
 }
 
 impl From<std::io::Error> for Error {
@@ -53,12 +139,18 @@ impl From<std::io::Error> for Error {
 pub type Result<T> = std::result::Result<T, Error>;
 
 
+/// The deepest nesting of indefinite-length values or constructed string segments that is parsed, so that hostile
+/// input cannot exhaust the stack.
+pub const MAX_DEPTH: usize = 64;
+
+
 enum Asn1Length {
     Indefinite,
     Definite(usize),
 }
 
 
+#[derive(Clone)]
 pub struct Asn1Reader<'b> {
     buf: &'b [u8],
     pos: usize,
@@ -113,7 +205,7 @@ impl<'b> Asn1Reader<'b> {
             };
             let has_continuation = b & 0x80 != 0;
             let b = b & 0x7f;
-            v = match v.checked_mul(127) {
+            v = match v.checked_mul(128) {
                 Some(v) => v,
                 None => return Err(InternalReadMultibyteError::MultibyteOverflow),
             };
@@ -154,15 +246,18 @@ impl<'b> Asn1Reader<'b> {
         Ok(Asn1Length::Definite(len))
     }
 
-    fn read_contents(&mut self, len: Asn1Length) -> Result<&'b [u8]> {
+    /// Reads the contents of a value at `depth` levels of indefinite-length nesting. X.690 8.1.3.2 only permits the
+    /// indefinite form for constructed values.
+    fn read_contents(&mut self, identifier: Identifier, len: Asn1Length, depth: usize) -> Result<&'b [u8]> {
         match len {
-            Asn1Length::Indefinite => self.read_indefinite_length_contents(),
+            Asn1Length::Indefinite if identifier.is_constructed() => self.read_indefinite_length_contents(depth),
+            Asn1Length::Indefinite => Err(Error::IndefiniteLengthPrimitive),
             Asn1Length::Definite(len) => self.read_definite_length_contents(len),
         }
     }
 
     fn read_definite_length_contents(&mut self, len: usize) -> Result<&'b [u8]> {
-        if (self.pos + len) > self.buf.len() {
+        if len > self.buf.len() - self.pos {
             return Err(Error::UnexpectedEndOfData);
         }
         let contents = &self.buf[self.pos..self.pos+len];
@@ -170,55 +265,211 @@ impl<'b> Asn1Reader<'b> {
         Ok(contents)
     }
 
-    fn read_indefinite_length_contents(&mut self) -> Result<&'b [u8]> {
+    /// Reads the contents of an indefinite-length value, which are the nested values up to the end-of-contents
+    /// octets at this level. Nested values are parsed so that zero octets inside them are not mistaken for the end.
+    fn read_indefinite_length_contents(&mut self, depth: usize) -> Result<&'b [u8]> {
+        if depth >= MAX_DEPTH {
+            return Err(Error::NestingTooDeep);
+        }
         let pos = self.pos;
+        loop {
+            if self.buf[self.pos..].starts_with(&[0, 0]) {
+                let contents = &self.buf[pos..self.pos];
+                self.pos += 2;
+                return Ok(contents);
+            }
+            let identifier = self.read_identifier()?;
+            let len = self.read_length()?;
+            self.read_contents(identifier, len, depth + 1)?;
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
 
-        #[derive(Copy,Clone,Debug,PartialEq,Eq)]
-        enum State {
-            Scanning,
-            FoundOneNul,
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Option<(Identifier, &'b [u8])>> {
+        match self.next_with_header_len()? {
+            Some((identifier, _, value)) => Ok(Some((identifier, value))),
+            None => Ok(None),
         }
+    }
 
-        let mut s = State::Scanning;
-        let mut len = 0usize;
-        loop {
-            let b = match self.read_u8() {
-                Ok(b) => b,
-                Err(InternalReadU8Error::UnexpectedEndOfData) => return Err(Error::UnexpectedEndOfData),
-            };
+    /// Reads the next value and returns its complete encoding: identifier, length, contents and any end-of-contents
+    /// octets.
+    pub fn read_raw(&mut self) -> Result<&'b [u8]> {
+        let start = self.pos;
+        match self.next()? {
+            Some(_) => Ok(&self.buf[start..self.pos]),
+            None => Err(Error::UnexpectedEndOfData),
+        }
+    }
 
-            len = match len.checked_add(1) {
-                Some(len) => len,
-                None => return Err(Error::LengthOverflow),
-            };
+    /// Returns the identifier of the next value without consuming it.
+    pub fn peek(&self) -> Result<Option<Identifier>> {
+        if self.is_at_end() {
+            return Ok(None);
+        }
+        self.clone().read_identifier().map(Some)
+    }
+
+    /// Reads the next value, failing unless its identifier is `identifier`.
+    pub fn read_expected(&mut self, identifier: Identifier) -> Result<&'b [u8]> {
+        match self.next()? {
+            Some((i, value)) if i == identifier => Ok(value),
+            Some((i, _)) => Err(Error::UnexpectedTag(i)),
+            None => Err(Error::UnexpectedEndOfData),
+        }
+    }
 
-            let b_is_nul = b == 0u8;
-            match s {
-                State::Scanning => {
-                    if b_is_nul {
-                        s = State::FoundOneNul;
-                    }
-                }
-                State::FoundOneNul => {
-                    if b_is_nul {
-                        break;
-                    }
-                    s = State::Scanning;
-                }
+    /// Reads the next value if its identifier is `identifier`, leaving the reader untouched otherwise.
+    pub fn read_optional(&mut self, identifier: Identifier) -> Result<Option<&'b [u8]>> {
+        if self.peek()? != Some(identifier) {
+            return Ok(None);
+        }
+        self.read_expected(identifier).map(Some)
+    }
+
+    /// Reads a universal SEQUENCE and returns a reader over its contents.
+    pub fn read_sequence(&mut self) -> Result<Asn1Reader<'b>> {
+        self.read_expected(Identifier::universal(UniversalTag::Sequence)).map(Asn1Reader::new)
+    }
+
+    /// Reads a universal SET and returns a reader over its contents.
+    pub fn read_set(&mut self) -> Result<Asn1Reader<'b>> {
+        self.read_expected(Identifier::universal(UniversalTag::Set)).map(Asn1Reader::new)
+    }
+
+    pub fn read_boolean(&mut self) -> Result<bool> {
+        match self.read_expected(Identifier::universal(UniversalTag::Boolean))? {
+            [0x00] => Ok(false),
+            [_] => Ok(true),
+            _ => Err(Error::InvalidBoolean),
+        }
+    }
+
+    pub fn read_null(&mut self) -> Result<()> {
+        match self.read_expected(Identifier::universal(UniversalTag::Null))? {
+            [] => Ok(()),
+            _ => Err(Error::InvalidNull),
+        }
+    }
+
+    /// Reads an ENUMERATED value, whose contents are encoded as for INTEGER, as the Rust type for its values.
+    pub fn read_enumerated<E: Enumerated>(&mut self) -> Result<E> {
+        let b = self.read_expected(Identifier::universal(UniversalTag::Enumerated))?;
+        let v = integer::to_i64(b)?;
+        E::from_i64(v).ok_or(Error::UnknownEnumerated(v))
+    }
+
+    /// Reads an INTEGER and returns its validated two's complement contents.
+    pub fn read_integer_bytes(&mut self) -> Result<&'b [u8]> {
+        let b = self.read_expected(Identifier::universal(UniversalTag::Integer))?;
+        integer::validate(b)?;
+        Ok(b)
+    }
+
+    /// Reads a non-negative INTEGER and returns its magnitude without any sign octet.
+    pub fn read_unsigned_integer(&mut self) -> Result<&'b [u8]> {
+        let b = self.read_expected(Identifier::universal(UniversalTag::Integer))?;
+        Ok(integer::to_unsigned_bytes(b)?)
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64> {
+        let b = self.read_expected(Identifier::universal(UniversalTag::Integer))?;
+        Ok(integer::to_i64(b)?)
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64> {
+        let b = self.read_expected(Identifier::universal(UniversalTag::Integer))?;
+        Ok(integer::to_u64(b)?)
+    }
+
+    pub fn read_real(&mut self) -> Result<f64> {
+        let b = self.read_expected(Identifier::universal(UniversalTag::Real))?;
+        Ok(real::to_f64(b)?)
+    }
+
+    pub fn read_object_identifier(&mut self) -> Result<ObjectIdentifier> {
+        let b = self.read_expected(Identifier::universal(UniversalTag::ObjectIdentifier))?;
+        Ok(ObjectIdentifier::from_bytes(b)?)
+    }
+
+    pub fn read_bit_string(&mut self) -> Result<BitString<'b>> {
+        let b = self.read_expected(Identifier::universal(UniversalTag::BitString))?;
+        Ok(BitString::from_bytes(b)?)
+    }
+
+    pub fn read_octet_string(&mut self) -> Result<&'b [u8]> {
+        self.read_expected(Identifier::universal(UniversalTag::OctetString))
+    }
+
+    /// Reads an OCTET STRING that BER may have constructed from nested segments, which are concatenated.
+    pub fn read_ber_octet_string(&mut self) -> Result<Cow<'b, [u8]>> {
+        match self.next()? {
+            Some((identifier, contents)) if identifier.class() == Class::Universal &&
+                identifier.tag() == UniversalTag::OctetString.into() => {
+                join_segments(identifier, contents, UniversalTag::OctetString.into())
             }
+            Some((identifier, _)) => Err(Error::UnexpectedTag(identifier)),
+            None => Err(Error::UnexpectedEndOfData),
         }
-        let contents = &self.buf[pos..self.pos - 2];
-        Ok(contents)
     }
 
-    pub fn next(&mut self) -> Result<Option<(Identifier, &'b [u8])>> {
+    /// Reads an OCTET STRING holding an encoding, such as an X.509 extension value, and passes a reader over it to
+    /// `f`, which must consume all of it. Values read through `f` borrow from the input, so the OCTET STRING must be
+    /// primitive as DER requires; a BER constructed one fails with `UnexpectedTag`. For BER input, join the segments
+    /// with `read_ber_octet_string` and read them with a new `Asn1Reader`.
+    pub fn read_der_encapsulated<T, E, F>(&mut self, f: F) -> std::result::Result<T, E>
+        where
+            F: FnOnce(&mut Asn1Reader<'b>) -> std::result::Result<T, E>,
+            E: From<Error>,
+    {
+        let mut r = Asn1Reader::new(self.read_octet_string()?);
+        let v = f(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub fn read_utc_time(&mut self) -> Result<DateTime> {
+        let b = self.read_expected(Identifier::universal(UniversalTag::UtcTime))?;
+        Ok(DateTime::from_utc_time_bytes(b)?)
+    }
+
+    pub fn read_generalized_time(&mut self) -> Result<DateTime> {
+        let b = self.read_expected(Identifier::universal(UniversalTag::GeneralizedTime))?;
+        Ok(DateTime::from_generalized_time_bytes(b)?)
+    }
+
+    /// Reads either a UTCTime or a GeneralizedTime, as in the X.509 `Time` CHOICE.
+    pub fn read_time(&mut self) -> Result<DateTime> {
+        match self.peek()? {
+            Some(i) if i == Identifier::universal(UniversalTag::UtcTime) => self.read_utc_time(),
+            _ => self.read_generalized_time(),
+        }
+    }
+
+    /// Fails with `TrailingData` unless every value has been read.
+    pub fn expect_end(&self) -> Result<()> {
+        if !self.is_at_end() {
+            return Err(Error::TrailingData);
+        }
+        Ok(())
+    }
+
+    /// As `next`, but also returns the number of bytes taken up by the identifier and length octets.
+    #[allow(deprecated)]
+    pub fn next_with_header_len(&mut self) -> Result<Option<(Identifier, usize, &'b [u8])>> {
         if self.is_at_end() {
             return Ok(None);
         }
+        let start = self.pos;
         let identifier = try!(self.read_identifier());
         let len = try!(self.read_length());
-        let value = try!(self.read_contents(len));
-        Ok(Some((identifier, value)))
+        let header_len = self.pos - start;
+        let value = try!(self.read_contents(identifier, len, 0));
+        Ok(Some((identifier, header_len, value)))
     }
 
     pub fn iter(&'b mut self) -> Asn1ReaderRefIter<'b> {
@@ -229,6 +480,58 @@ impl<'b> Asn1Reader<'b> {
     }
 }
 
+/// Returns the contents of a string value given its identifier and contents. BER allows the value to be constructed
+/// from nested segments of the universal type `segment_tag`, in which case they are concatenated; the identifier may
+/// be an implicit tag.
+pub fn join_segments(identifier: Identifier, contents: &[u8], segment_tag: u64) -> Result<Cow<'_, [u8]>> {
+    if !identifier.is_constructed() {
+        return Ok(Cow::Borrowed(contents));
+    }
+    let mut segments = Vec::new();
+    collect_segments(contents, segment_tag, 0, &mut segments)?;
+    Ok(Cow::Owned(segments.concat()))
+}
+
+/// Returns the contents of a BIT STRING value given its identifier and contents, starting with the unused-bits
+/// octet as for a primitive value. BER allows the value to be constructed from BIT STRING segments, each with its own
+/// unused-bits octet, of which only the last may have unused bits (X.690 8.6.4); the identifier may be an implicit
+/// tag.
+pub fn join_bit_string_segments(identifier: Identifier, contents: &[u8]) -> Result<Cow<'_, [u8]>> {
+    if !identifier.is_constructed() {
+        return Ok(Cow::Borrowed(contents));
+    }
+    let mut segments = Vec::new();
+    collect_segments(contents, UniversalTag::BitString.into(), 0, &mut segments)?;
+    let mut output = vec![0u8];
+    for (i, segment) in segments.iter().enumerate() {
+        let bits = BitString::from_bytes(segment)?;
+        if bits.unused_bits() != 0 && i + 1 != segments.len() {
+            return Err(Error::BitString(bit_string::Error::InvalidUnusedBits));
+        }
+        output[0] = bits.unused_bits();
+        output.extend_from_slice(bits.data());
+    }
+    Ok(Cow::Owned(output))
+}
+
+fn collect_segments<'c>(contents: &'c [u8], segment_tag: u64, depth: usize, segments: &mut Vec<&'c [u8]>) -> Result<()> {
+    if depth >= MAX_DEPTH {
+        return Err(Error::NestingTooDeep);
+    }
+    let mut r = Asn1Reader::new(contents);
+    while let Some((identifier, contents)) = r.next()? {
+        if identifier.class() != Class::Universal || identifier.tag() != segment_tag {
+            return Err(Error::UnexpectedTag(identifier));
+        }
+        if identifier.is_constructed() {
+            collect_segments(contents, segment_tag, depth + 1, segments)?;
+        } else {
+            segments.push(contents);
+        }
+    }
+    Ok(())
+}
+
 impl<'b> IntoIterator for Asn1Reader<'b> {
     type Item = <Asn1ReaderIter<'b> as Iterator>::Item;
     type IntoIter = Asn1ReaderIter<'b>;
@@ -329,6 +632,7 @@ mod test {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_long_form_tag() {
         {
             let input = [0x1fu8, 0x01, 1, 0];
@@ -341,6 +645,16 @@ mod test {
             assert_eq!(v, [0u8]);
         }
 
+        {
+            let input = [0x7fu8, 0x81, 0x00, 0x01, 0xff];
+
+            let mut r = Asn1Reader::new(&input);
+            let (t, header_len, v) = r.next_with_header_len().unwrap().unwrap();
+            assert_eq!(t, Identifier::new(Class::Application, true, 128));
+            assert_eq!(header_len, 4);
+            assert_eq!(v, [0xffu8]);
+        }
+
         {
             let input = [0x1fu8, 0x81, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80];
 
@@ -352,6 +666,7 @@ mod test {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_long_form_length() {
         {
             let input = [0x01u8, 0x81, 1, 0];
@@ -390,14 +705,209 @@ mod test {
         {
             let input = [0x01u8, 0x80, 1, 0, 0];
 
+            let mut r = Asn1Reader::new(&input);
+            match r.next() {
+                Err(Error::IndefiniteLengthPrimitive) => (),
+                v => panic!("unexpected result {:?}", v),
+            }
+        }
+        {
+            let input = [0x30u8, 0x80, 0x01, 0x01, 0x01, 0, 0];
+
             let (t, v) = {
                 let mut r = Asn1Reader::new(&input);
                 let tv = r.next().unwrap();
                 assert!(r.is_at_end());
                 tv
             }.unwrap();
-            assert_eq!(t, Identifier::new(Class::Universal, false, UniversalTag::Boolean.into()));
-            assert_eq!(v, [1u8]);
+            assert_eq!(t, Identifier::new(Class::Universal, true, UniversalTag::Sequence.into()));
+            assert_eq!(v, [0x01u8, 0x01, 0x01]);
+        }
+    }
+
+    #[test]
+    fn test_indefinite_length_nested() {
+        {
+            let input = [
+                0x30u8, 0x80,
+                0x24, 0x80, 0x04, 0x02, 0x00, 0x00, 0x04, 0x01, 0x00, 0x00, 0x00,
+                0x02, 0x01, 0x00,
+                0x00, 0x00,
+                0x05, 0x00,
+            ];
+
+            let mut r = Asn1Reader::new(&input);
+            let mut seq = r.read_sequence().unwrap();
+            assert_eq!(r.position(), 18);
+            let (t, v) = seq.next().unwrap().unwrap();
+            assert_eq!(t, Identifier::new(Class::Universal, true, UniversalTag::OctetString.into()));
+            assert_eq!(v, [0x04u8, 0x02, 0x00, 0x00, 0x04, 0x01, 0x00]);
+            assert_eq!(seq.read_i64().unwrap(), 0);
+            assert!(seq.expect_end().is_ok());
+        }
+        {
+            let input = [0x30u8, 0x80, 0x05, 0x00, 0x00];
+
+            let mut r = Asn1Reader::new(&input);
+            assert!(r.next().is_err());
+        }
+        {
+            let mut input = Vec::new();
+            for _ in 0..100000 {
+                input.extend_from_slice(&[0x30, 0x80]);
+            }
+
+            let mut r = Asn1Reader::new(&input);
+            match r.next() {
+                Err(Error::NestingTooDeep) => (),
+                v => panic!("unexpected result {:?}", v),
+            }
+
+            let mut input = Vec::new();
+            for _ in 0..MAX_DEPTH {
+                input.extend_from_slice(&[0x30, 0x80]);
+            }
+            input.resize(input.len() * 2, 0);
+
+            let mut r = Asn1Reader::new(&input);
+            assert_eq!(r.read_raw().unwrap().len(), input.len());
+        }
+    }
+
+    #[test]
+    fn test_header_len() {
+        {
+            let input = [0x30u8, 0x81, 0x03, 0x01, 0x01, 0xff, 0x31, 0x80, 0x05, 0x00, 0, 0];
+
+            let mut r = Asn1Reader::new(&input);
+            let (t, header_len, v) = r.next_with_header_len().unwrap().unwrap();
+            assert_eq!(t, Identifier::new(Class::Universal, true, UniversalTag::Sequence.into()));
+            assert_eq!(header_len, 3);
+            assert_eq!(v, [0x01u8, 0x01, 0xff]);
+            assert_eq!(r.position(), 6);
+
+            let (t, header_len, v) = r.next_with_header_len().unwrap().unwrap();
+            assert_eq!(t, Identifier::new(Class::Universal, true, UniversalTag::Set.into()));
+            assert_eq!(header_len, 2);
+            assert_eq!(v, [0x05u8, 0x00]);
+            assert_eq!(r.position(), 12);
+            assert!(r.next_with_header_len().unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn test_structured_reads() {
+        {
+            let input = [0x30u8, 0x08, 0x01, 0x01, 0x00, 0x80, 0x00, 0x02, 0x01, 0x05, 0x05, 0x00];
+
+            let mut r = Asn1Reader::new(&input);
+            assert_eq!(r.clone().read_raw().unwrap(), &input[..10]);
+
+            let mut seq = r.read_sequence().unwrap();
+            assert_eq!(seq.peek().unwrap(), Some(Identifier::universal(UniversalTag::Boolean)));
+            assert_eq!(seq.read_optional(Identifier::context_specific(false, 0)).unwrap(), None);
+            assert_eq!(seq.read_expected(Identifier::universal(UniversalTag::Boolean)).unwrap(), [0u8]);
+            assert_eq!(seq.read_optional(Identifier::context_specific(false, 0)).unwrap(), Some(&[][..]));
+            assert!(seq.expect_end().is_err());
+            match seq.read_expected(Identifier::universal(UniversalTag::Boolean)).unwrap_err() {
+                Error::UnexpectedTag(i) => assert_eq!(i, Identifier::universal(UniversalTag::Integer)),
+                e => panic!("unexpected error: {}", e),
+            }
+            assert!(seq.expect_end().is_ok());
+
+            r.read_null().unwrap();
+            assert!(r.expect_end().is_ok());
+            assert_eq!(r.peek().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_typed_reads() {
+        {
+            let input = [
+                0x01u8, 0x01, 0xff,
+                0x02, 0x02, 0x00, 0x80,
+                0x02, 0x01, 0xfe,
+                0x06, 0x03, 0x55, 0x1d, 0x13,
+                0x03, 0x02, 0x07, 0x80,
+                0x04, 0x01, 0xaa,
+                0x09, 0x03, 0xc0, 0xff, 0x03,
+                0x17, 0x0d, b'1', b'9', b'0', b'1', b'0', b'2', b'0', b'3', b'0', b'4', b'0', b'5', b'Z',
+                0x18, 0x0f, b'2', b'0', b'5', b'0', b'0', b'1', b'0', b'1', b'0', b'0', b'0', b'0', b'0', b'0', b'Z',
+            ];
+
+            let mut r = Asn1Reader::new(&input);
+            assert!(r.read_boolean().unwrap());
+            assert_eq!(r.read_u64().unwrap(), 128);
+            assert_eq!(r.read_i64().unwrap(), -2);
+            assert_eq!(r.read_object_identifier().unwrap().components(), [2, 5, 29, 19]);
+            assert_eq!(r.read_bit_string().unwrap().bit_len(), 1);
+            assert_eq!(r.read_octet_string().unwrap(), [0xaau8]);
+            assert_eq!(r.read_real().unwrap(), -1.5);
+            assert_eq!(r.read_time().unwrap(), DateTime::new(2019, 1, 2, 3, 4, 5).unwrap());
+            assert_eq!(r.read_time().unwrap(), DateTime::new(2050, 1, 1, 0, 0, 0).unwrap());
+            assert!(r.expect_end().is_ok());
+        }
+        {
+            let input = [0x02u8, 0x02, 0x00, 0x01];
+
+            let mut r = Asn1Reader::new(&input);
+            assert_eq!(r.read_integer_bytes().unwrap_err().to_string(), "non-minimal integer encoding");
+        }
+        {
+            let input = [0x02u8, 0x02, 0x00, 0x80, 0x02, 0x01, 0xfe];
+
+            let mut r = Asn1Reader::new(&input);
+            assert_eq!(r.read_unsigned_integer().unwrap(), [0x80u8]);
+            assert!(r.read_unsigned_integer().is_err());
+        }
+    }
+
+    #[test]
+    fn test_octet_string_helpers() {
+        {
+            // A constructed OCTET STRING with a nested constructed segment, in indefinite length form.
+            let input = [0x24u8, 0x80, 0x04, 0x01, 0x01, 0x24, 0x03, 0x04, 0x01, 0x02, 0x00, 0x00, 0x04, 0x01, 0x03];
+
+            let mut r = Asn1Reader::new(&input);
+            assert_eq!(r.read_ber_octet_string().unwrap(), Cow::Owned::<[u8]>(vec![0x01, 0x02]));
+            assert_eq!(r.read_ber_octet_string().unwrap(), Cow::Borrowed(&[0x03u8][..]));
+            assert!(r.expect_end().is_ok());
+
+            let input = [0x24u8, 0x03, 0x02, 0x01, 0x01];
+            match Asn1Reader::new(&input).read_ber_octet_string().unwrap_err() {
+                Error::UnexpectedTag(i) => assert_eq!(i, Identifier::universal(UniversalTag::Integer)),
+                e => panic!("unexpected error: {}", e),
+            }
+
+            let mut input = vec![0x04, 0x00];
+            for _ in 0..1000 {
+                let mut outer = vec![0x24, 0x84];
+                outer.extend_from_slice(&(input.len() as u32).to_be_bytes());
+                outer.extend_from_slice(&input);
+                input = outer;
+            }
+            match Asn1Reader::new(&input).read_ber_octet_string().unwrap_err() {
+                Error::NestingTooDeep => (),
+                e => panic!("unexpected error: {}", e),
+            }
+        }
+        {
+            let input = [0x04u8, 0x03, 0x02, 0x01, 0x05, 0x04, 0x04, 0x02, 0x01, 0x05, 0x00];
+
+            let mut r = Asn1Reader::new(&input);
+            assert_eq!(r.read_der_encapsulated(|r| r.read_i64()).unwrap(), 5);
+            match r.read_der_encapsulated(|r| r.read_i64()).unwrap_err() {
+                Error::TrailingData => (),
+                e => panic!("unexpected error: {}", e),
+            }
+            assert!(r.expect_end().is_ok());
+
+            let input = [0x24u8, 0x80, 0x04, 0x03, 0x02, 0x01, 0x05, 0x00, 0x00];
+            match Asn1Reader::new(&input).read_der_encapsulated(|r| r.read_i64()).unwrap_err() {
+                Error::UnexpectedTag(_) => (),
+                e => panic!("unexpected error: {}", e),
+            }
         }
     }
 
@@ -411,7 +921,7 @@ mod test {
 
             assert_eq!(seq_i, Identifier::new(Class::Universal, true, UniversalTag::Sequence.into()));
 
-            let mut r = Asn1Reader::new(&seq_d);
+            let mut r = Asn1Reader::new(seq_d);
             let seq_vals: Vec<_> = r.iter().map(Result::unwrap).collect();
 
             assert_eq!(seq_vals, &[
@@ -0,0 +1,279 @@
+//! Conversion between the contents of a REAL (X.690 8.5) and `f64`.
+//!
+//! Decoding accepts every form BER allows: binary encodings with base 2, 8 or 16, any scaling factor and any
+//! exponent length, ISO 6093 decimal encodings in the NR1, NR2 and NR3 forms, and the special values. Encoding
+//! always produces the canonical form of DER and CER, which is base 2 with an odd mantissa and no scaling.
+
+use integer;
+use std;
+
+
+#[derive(Debug)]
+pub enum Error {
+    UnexpectedEndOfData,
+    /// The contents use an encoding that X.690 reserves, such as base 11 or an unknown special value.
+    Reserved,
+    /// The characters of a decimal encoding do not form a number of the ISO 6093 form it declares.
+    InvalidDecimal,
+    /// The value is too large or too small in magnitude for an `f64`.
+    OutOfRange,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::UnexpectedEndOfData => write!(f, "unexpected end of real"),
+            Error::Reserved => write!(f, "reserved real encoding"),
+            Error::InvalidDecimal => write!(f, "invalid decimal real"),
+            Error::OutOfRange => write!(f, "real out of range"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+const PLUS_INFINITY: u8 = 0x40;
+const MINUS_INFINITY: u8 = 0x41;
+const NOT_A_NUMBER: u8 = 0x42;
+const MINUS_ZERO: u8 = 0x43;
+
+
+pub fn to_f64(b: &[u8]) -> Result<f64> {
+    let first = match b.first() {
+        None => return Ok(0.0),
+        Some(&first) => first,
+    };
+    if first & 0x80 != 0 {
+        return binary_to_f64(b);
+    }
+    if first & 0x40 != 0 {
+        return match b {
+            [PLUS_INFINITY] => Ok(f64::INFINITY),
+            [MINUS_INFINITY] => Ok(f64::NEG_INFINITY),
+            [NOT_A_NUMBER] => Ok(f64::NAN),
+            [MINUS_ZERO] => Ok(-0.0),
+            _ => Err(Error::Reserved),
+        };
+    }
+    let s = std::str::from_utf8(&b[1..]).map_err(|_| Error::InvalidDecimal)?;
+    decimal_to_f64(first, s)
+}
+
+fn binary_to_f64(b: &[u8]) -> Result<f64> {
+    let first = b[0];
+    let log2_base = match (first >> 4) & 0x03 {
+        0 => 1,
+        1 => 3,
+        2 => 4,
+        _ => return Err(Error::Reserved),
+    };
+    let scale = ((first >> 2) & 0x03) as i64;
+    let (exponent_len, rest) = match first & 0x03 {
+        3 => (*b.get(1).ok_or(Error::UnexpectedEndOfData)? as usize, &b[2..]),
+        n => (n as usize + 1, &b[1..]),
+    };
+    if exponent_len == 0 || rest.len() <= exponent_len {
+        return Err(Error::UnexpectedEndOfData);
+    }
+    let (exponent, mantissa) = rest.split_at(exponent_len);
+
+    let negative_exponent = integer::is_negative(exponent);
+    let sign_octet = if negative_exponent { 0xff } else { 0x00 };
+    let redundant = exponent.iter().take_while(|&&byte| byte == sign_octet).count();
+    if exponent.len() - redundant >= 8 {
+        return Err(Error::OutOfRange);
+    }
+    let exponent = exponent[redundant..].iter()
+        .fold(if negative_exponent { -1i64 } else { 0 }, |v, &byte| (v << 8) | byte as i64);
+
+    // Trailing zero octets of the mantissa only scale it, and beyond 64 bits the low bits only matter for rounding,
+    // so they fold into the lowest bit.
+    let start = mantissa.iter().position(|&byte| byte != 0).unwrap_or(mantissa.len());
+    let end = mantissa.iter().rposition(|&byte| byte != 0).map_or(start, |i| i + 1);
+    if start == end {
+        return Ok(if first & 0x40 != 0 { -0.0 } else { 0.0 });
+    }
+    let kept = std::cmp::min(end - start, 8);
+    let mut n = mantissa[start..start + kept].iter().fold(0u64, |v, &byte| (v << 8) | byte as u64);
+    if mantissa[start + kept..end].iter().any(|&byte| byte != 0) {
+        n |= 1;
+    }
+    let shift = 8 * (mantissa.len() - start - kept) as i64;
+
+    let exponent = exponent.checked_mul(log2_base)
+        .and_then(|e| e.checked_add(scale))
+        .and_then(|e| e.checked_add(shift))
+        .ok_or(Error::OutOfRange)?;
+    let v = scale_by_power_of_two(n as f64, exponent);
+    if v == 0.0 || v.is_infinite() {
+        return Err(Error::OutOfRange);
+    }
+    Ok(if first & 0x40 != 0 { -v } else { v })
+}
+
+/// `v × 2^exponent`, without overflowing or underflowing in the intermediate steps.
+fn scale_by_power_of_two(mut v: f64, mut exponent: i64) -> f64 {
+    while exponent > 1023 && v.is_finite() {
+        v *= 2f64.powi(1023);
+        exponent -= 1023;
+    }
+    while exponent < -1022 && v != 0.0 {
+        v *= 2f64.powi(-1022);
+        exponent += 1022;
+    }
+    v * 2f64.powi(exponent.clamp(-1074, 1023) as i32)
+}
+
+fn decimal_to_f64(first: u8, s: &str) -> Result<f64> {
+    let number = s.trim_start_matches(' ');
+    let unsigned = number.strip_prefix(['+', '-']).unwrap_or(number);
+    let (significand, exponent) = match unsigned.find(['E', 'e']) {
+        Some(i) => (&unsigned[..i], Some(&unsigned[i + 1..])),
+        None => (unsigned, None),
+    };
+    let mark = significand.find(['.', ',']);
+    let digits = significand.bytes().filter(u8::is_ascii_digit).count();
+    let valid_significand = digits > 0 && digits + mark.map_or(0, |_| 1) == significand.len();
+    let valid_exponent = exponent.is_some_and(|e| {
+        let e = e.strip_prefix(['+', '-']).unwrap_or(e);
+        !e.is_empty() && e.bytes().all(|b| b.is_ascii_digit())
+    });
+    let valid = match first {
+        0x01 => valid_significand && mark.is_none() && exponent.is_none(),
+        0x02 => valid_significand && mark.is_some() && exponent.is_none(),
+        0x03 => valid_significand && valid_exponent,
+        _ => return Err(Error::Reserved),
+    };
+    if !valid {
+        return Err(Error::InvalidDecimal);
+    }
+    let v: f64 = number.replace(',', ".").parse().map_err(|_| Error::InvalidDecimal)?;
+    if v.is_infinite() {
+        return Err(Error::OutOfRange);
+    }
+    Ok(v)
+}
+
+/// Encodes `v` in the canonical form of DER and CER.
+pub fn from_f64(v: f64) -> Vec<u8> {
+    if v.is_nan() {
+        return vec![NOT_A_NUMBER];
+    }
+    if v.is_infinite() {
+        return vec![if v > 0.0 { PLUS_INFINITY } else { MINUS_INFINITY }];
+    }
+    if v == 0.0 {
+        return if v.is_sign_negative() { vec![MINUS_ZERO] } else { vec![] };
+    }
+
+    let bits = v.to_bits();
+    let biased_exponent = ((bits >> 52) & 0x7ff) as i64;
+    let fraction = bits & ((1 << 52) - 1);
+    let (mut mantissa, mut exponent) = if biased_exponent == 0 {
+        (fraction, -1074)
+    } else {
+        (fraction | (1 << 52), biased_exponent - 1075)
+    };
+    let zeros = mantissa.trailing_zeros();
+    mantissa >>= zeros;
+    exponent += zeros as i64;
+
+    let exponent = integer::from_i64(exponent);
+    let mantissa = mantissa.to_be_bytes();
+    let mantissa = &mantissa[mantissa.iter().position(|&byte| byte != 0).unwrap()..];
+    let mut b = Vec::with_capacity(1 + exponent.len() + mantissa.len());
+    b.push(0x80 | if v < 0.0 { 0x40 } else { 0 } | (exponent.len() as u8 - 1));
+    b.extend_from_slice(&exponent);
+    b.extend_from_slice(mantissa);
+    b
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let cases: &[(f64, &[u8])] = &[
+            (0.0, &[]),
+            (-0.0, &[0x43]),
+            (f64::INFINITY, &[0x40]),
+            (f64::NEG_INFINITY, &[0x41]),
+            (f64::NAN, &[0x42]),
+            (1.0, &[0x80, 0x00, 0x01]),
+            (0.5, &[0x80, 0xff, 0x01]),
+            (-1.5, &[0xc0, 0xff, 0x03]),
+            (10.0, &[0x80, 0x01, 0x05]),
+            (0.15625, &[0x80, 0xfb, 0x05]),
+            (f64::MAX, &[0x81, 0x03, 0xcb, 0x1f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+            (5e-324, &[0x81, 0xfb, 0xce, 0x01]),
+        ];
+        for &(v, b) in cases {
+            assert_eq!(from_f64(v), b, "{}", v);
+            let decoded = to_f64(b).unwrap();
+            assert!(decoded.to_bits() == v.to_bits() || (v.is_nan() && decoded.is_nan()), "{}", v);
+        }
+        for &v in &[std::f64::consts::PI, -1e300, 1.2345e-310, 123456789.0, f64::MIN_POSITIVE] {
+            assert_eq!(to_f64(&from_f64(v)).unwrap(), v);
+        }
+    }
+
+    #[test]
+    fn test_decode_binary() {
+        let cases: &[(&[u8], f64)] = &[
+            // Base 8 and base 16.
+            (&[0x90, 0x01, 0x01], 8.0),
+            (&[0xa0, 0x01, 0x03], 48.0),
+            (&[0xe0, 0xff, 0x08], -0.5),
+            // Scaling factors of 1 and 3.
+            (&[0x84, 0x00, 0x01], 2.0),
+            (&[0x8c, 0xfe, 0x03], 6.0),
+            // Two octet, three octet and long form exponents.
+            (&[0x81, 0x00, 0x02, 0x01], 4.0),
+            (&[0x82, 0xff, 0xff, 0xfd, 0x04], 0.5),
+            (&[0x83, 0x01, 0x05, 0x01], 32.0),
+            (&[0x83, 0x09, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01], 0.5),
+            // Mantissas with leading and trailing zeros, and one longer than 64 bits that rounds.
+            (&[0x80, 0x00, 0x00, 0x01, 0x00], 256.0),
+            (&[0x80, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01], 2f64.powi(72)),
+            (&[0x80, 0x00, 0x00], 0.0),
+        ];
+        for &(b, v) in cases {
+            assert_eq!(to_f64(b).unwrap(), v, "{:02x?}", b);
+        }
+
+        let cases: &[&[u8]] = &[&[0x80], &[0x80, 0x00], &[0x83, 0x00, 0x01], &[0x83, 0x02, 0x01], &[0xb0, 0x00, 0x01],
+            &[0x44], &[0x40, 0x00], &[0x81, 0x04, 0x00, 0x01], &[0x81, 0xf8, 0x00, 0x01]];
+        for b in cases {
+            assert!(to_f64(b).is_err(), "{:02x?}", b);
+        }
+    }
+
+    #[test]
+    fn test_decode_decimal() {
+        let cases: &[(&[u8], f64)] = &[
+            (b"\x01  -12", -12.0),
+            (b"\x01+7", 7.0),
+            (b"\x023,25", 3.25),
+            (b"\x02-.5", -0.5),
+            (b"\x024.", 4.0),
+            (b"\x031.5E1", 15.0),
+            (b"\x0325e-1", 2.5),
+            (b"\x03-0,1E+2", -10.0),
+        ];
+        for &(b, v) in cases {
+            assert_eq!(to_f64(b).unwrap(), v, "{:02x?}", b);
+        }
+
+        let cases: &[&[u8]] = &[b"\x011.5", b"\x0215", b"\x031.5", b"\x031E", b"\x01", b"\x02.",
+            b"\x031..5E1", b"\x04-1", b"\x031E999", b"\x01 1 "];
+        for b in cases {
+            assert!(to_f64(b).is_err(), "{:02x?}", b);
+        }
+    }
+}
@@ -0,0 +1,987 @@
+//! SNMP messages (RFC 3416 PDUs, RFC 1157 traps and RFC 3412 version 3 messages).
+//!
+//! Decoding accepts BER, including indefinite lengths; encoding always uses definite lengths.
+
+use identifier::{Class, Identifier, UniversalTag};
+use integer;
+use object_identifier::ObjectIdentifier;
+use reading::{self, Asn1Reader};
+use std;
+use std::borrow::Cow;
+use writing::der::{self, Writer};
+
+
+pub mod tag {
+    pub const IP_ADDRESS: u64 = 0;
+    pub const COUNTER32: u64 = 1;
+    pub const GAUGE32: u64 = 2;
+    pub const TIME_TICKS: u64 = 3;
+    pub const OPAQUE: u64 = 4;
+    pub const COUNTER64: u64 = 6;
+
+    pub const GET_REQUEST: u64 = 0;
+    pub const GET_NEXT_REQUEST: u64 = 1;
+    pub const RESPONSE: u64 = 2;
+    pub const SET_REQUEST: u64 = 3;
+    pub const TRAP: u64 = 4;
+    pub const GET_BULK_REQUEST: u64 = 5;
+    pub const INFORM_REQUEST: u64 = 6;
+    pub const SNMPV2_TRAP: u64 = 7;
+    pub const REPORT: u64 = 8;
+
+    pub const NO_SUCH_OBJECT: u64 = 0;
+    pub const NO_SUCH_INSTANCE: u64 = 1;
+    pub const END_OF_MIB_VIEW: u64 = 2;
+}
+
+
+#[derive(Debug)]
+pub enum Error {
+    Read(reading::Error),
+    InvalidVersion,
+    InvalidValue,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Read(ref e) => e.fmt(f),
+            Error::InvalidVersion => write!(f, "invalid version"),
+            Error::InvalidValue => write!(f, "invalid value"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Read(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reading::Error> for Error {
+    fn from(e: reading::Error) -> Error {
+        Error::Read(e)
+    }
+}
+
+impl From<integer::Error> for Error {
+    fn from(e: integer::Error) -> Error {
+        Error::Read(reading::Error::from(e))
+    }
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+fn read_i32(r: &mut Asn1Reader) -> Result<i32> {
+    let v = r.read_i64()?;
+    if v < i32::MIN as i64 || v > i32::MAX as i64 {
+        return Err(Error::InvalidValue);
+    }
+    Ok(v as i32)
+}
+
+fn read_u32(r: &mut Asn1Reader) -> Result<u32> {
+    let v = r.read_u64()?;
+    if v > u32::MAX as u64 {
+        return Err(Error::InvalidValue);
+    }
+    Ok(v as u32)
+}
+
+fn to_u32(b: &[u8]) -> Result<u32> {
+    let v = integer::to_u64(b)?;
+    if v > u32::MAX as u64 {
+        return Err(Error::InvalidValue);
+    }
+    Ok(v as u32)
+}
+
+fn to_ip_address(b: &[u8]) -> Result<[u8; 4]> {
+    match *b {
+        [a, b, c, d] => Ok([a, b, c, d]),
+        _ => Err(Error::InvalidValue),
+    }
+}
+
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum Version {
+    V1,
+    V2c,
+    V3,
+}
+
+impl Version {
+    pub fn from_i64(v: i64) -> Option<Self> {
+        match v {
+            0 => Some(Version::V1),
+            1 => Some(Version::V2c),
+            3 => Some(Version::V3),
+            _ => None,
+        }
+    }
+
+    pub fn into_i64(self) -> i64 {
+        match self {
+            Version::V1 => 0,
+            Version::V2c => 1,
+            Version::V3 => 3,
+        }
+    }
+}
+
+
+/// The value of a variable binding: an ObjectSyntax value or one of the exception markers of RFC 3416.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Value<'a> {
+    Integer(i32),
+    OctetString(Cow<'a, [u8]>),
+    ObjectIdentifier(ObjectIdentifier),
+    IpAddress([u8; 4]),
+    Counter32(u32),
+    Gauge32(u32),
+    TimeTicks(u32),
+    Opaque(Cow<'a, [u8]>),
+    Counter64(u64),
+    /// The `unSpecified` NULL used in requests.
+    Null,
+    NoSuchObject,
+    NoSuchInstance,
+    EndOfMibView,
+}
+
+impl<'a> Value<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let (identifier, contents) = match r.next()? {
+            Some(v) => v,
+            None => return Err(Error::Read(reading::Error::UnexpectedEndOfData)),
+        };
+        // BER lets OCTET STRING and Opaque values be split into segments; every other type is primitive.
+        let v = match (identifier.class(), identifier.tag()) {
+            (Class::Universal, t) if t == UniversalTag::OctetString.into() => {
+                Value::OctetString(reading::join_segments(identifier, contents, UniversalTag::OctetString.into())?)
+            }
+            (Class::Application, tag::OPAQUE) => {
+                Value::Opaque(reading::join_segments(identifier, contents, UniversalTag::OctetString.into())?)
+            }
+            _ if identifier.is_constructed() => return Err(Error::Read(reading::Error::UnexpectedTag(identifier))),
+            (Class::Universal, t) if t == UniversalTag::Integer.into() => {
+                let v = integer::to_i64(contents)?;
+                if v < i32::MIN as i64 || v > i32::MAX as i64 {
+                    return Err(Error::InvalidValue);
+                }
+                Value::Integer(v as i32)
+            }
+            (Class::Universal, t) if t == UniversalTag::ObjectIdentifier.into() => {
+                Value::ObjectIdentifier(ObjectIdentifier::from_bytes(contents).map_err(reading::Error::from)?)
+            }
+            (Class::Universal, t) if t == UniversalTag::Null.into() && contents.is_empty() => Value::Null,
+            (Class::Application, tag::IP_ADDRESS) => Value::IpAddress(to_ip_address(contents)?),
+            (Class::Application, tag::COUNTER32) => Value::Counter32(to_u32(contents)?),
+            (Class::Application, tag::GAUGE32) => Value::Gauge32(to_u32(contents)?),
+            (Class::Application, tag::TIME_TICKS) => Value::TimeTicks(to_u32(contents)?),
+            (Class::Application, tag::COUNTER64) => Value::Counter64(integer::to_u64(contents)?),
+            (Class::ContextSpecific, tag::NO_SUCH_OBJECT) if contents.is_empty() => Value::NoSuchObject,
+            (Class::ContextSpecific, tag::NO_SUCH_INSTANCE) if contents.is_empty() => Value::NoSuchInstance,
+            (Class::ContextSpecific, tag::END_OF_MIB_VIEW) if contents.is_empty() => Value::EndOfMibView,
+            _ => return Err(Error::Read(reading::Error::UnexpectedTag(identifier))),
+        };
+        Ok(v)
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        match *self {
+            Value::Integer(v) => w.write_i64(v as i64),
+            Value::OctetString(ref v) => w.write_octet_string(v),
+            Value::ObjectIdentifier(ref v) => w.write_object_identifier(v),
+            Value::IpAddress(ref v) => w.write_tag_and_data(Identifier::application(false, tag::IP_ADDRESS), v),
+            Value::Counter32(v) => w.write_tag_and_data(Identifier::application(false, tag::COUNTER32), &integer::from_u64(v as u64)),
+            Value::Gauge32(v) => w.write_tag_and_data(Identifier::application(false, tag::GAUGE32), &integer::from_u64(v as u64)),
+            Value::TimeTicks(v) => w.write_tag_and_data(Identifier::application(false, tag::TIME_TICKS), &integer::from_u64(v as u64)),
+            Value::Opaque(ref v) => w.write_tag_and_data(Identifier::application(false, tag::OPAQUE), v),
+            Value::Counter64(v) => w.write_tag_and_data(Identifier::application(false, tag::COUNTER64), &integer::from_u64(v)),
+            Value::Null => w.write_null(),
+            Value::NoSuchObject => w.write_tag_and_data(Identifier::context_specific(false, tag::NO_SUCH_OBJECT), &[]),
+            Value::NoSuchInstance => w.write_tag_and_data(Identifier::context_specific(false, tag::NO_SUCH_INSTANCE), &[]),
+            Value::EndOfMibView => w.write_tag_and_data(Identifier::context_specific(false, tag::END_OF_MIB_VIEW), &[]),
+        }
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct VarBind<'a> {
+    pub name: ObjectIdentifier,
+    pub value: Value<'a>,
+}
+
+impl<'a> VarBind<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let name = r.read_object_identifier()?;
+        let value = Value::read(&mut r)?;
+        r.expect_end()?;
+        Ok(VarBind {
+            name,
+            value,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_object_identifier(&self.name)?;
+            self.value.write(w)?;
+            Ok(())
+        })
+    }
+
+    pub(crate) fn read_list(r: &mut Asn1Reader<'a>) -> Result<Vec<Self>> {
+        let mut r = r.read_sequence()?;
+        let mut list = Vec::new();
+        while !r.is_at_end() {
+            list.push(VarBind::read(&mut r)?);
+        }
+        Ok(list)
+    }
+
+    pub fn write_list<W: std::io::Write>(w: &mut Writer<W>, list: &[VarBind]) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            for var_bind in list {
+                var_bind.write(w)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum ErrorStatus {
+    NoError,
+    TooBig,
+    NoSuchName,
+    BadValue,
+    ReadOnly,
+    GenErr,
+    NoAccess,
+    WrongType,
+    WrongLength,
+    WrongEncoding,
+    WrongValue,
+    NoCreation,
+    InconsistentValue,
+    ResourceUnavailable,
+    CommitFailed,
+    UndoFailed,
+    AuthorizationError,
+    NotWritable,
+    InconsistentName,
+}
+
+impl ErrorStatus {
+    const ALL: [ErrorStatus; 19] = [
+        ErrorStatus::NoError,
+        ErrorStatus::TooBig,
+        ErrorStatus::NoSuchName,
+        ErrorStatus::BadValue,
+        ErrorStatus::ReadOnly,
+        ErrorStatus::GenErr,
+        ErrorStatus::NoAccess,
+        ErrorStatus::WrongType,
+        ErrorStatus::WrongLength,
+        ErrorStatus::WrongEncoding,
+        ErrorStatus::WrongValue,
+        ErrorStatus::NoCreation,
+        ErrorStatus::InconsistentValue,
+        ErrorStatus::ResourceUnavailable,
+        ErrorStatus::CommitFailed,
+        ErrorStatus::UndoFailed,
+        ErrorStatus::AuthorizationError,
+        ErrorStatus::NotWritable,
+        ErrorStatus::InconsistentName,
+    ];
+
+    pub fn from_i64(v: i64) -> Option<Self> {
+        if v < 0 {
+            return None;
+        }
+        ErrorStatus::ALL.get(v as usize).cloned()
+    }
+
+    pub fn into_i64(self) -> i64 {
+        self as i64
+    }
+}
+
+
+/// The PDU shape shared by every request and response type other than GetBulkRequest and the SNMPv1 Trap.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Pdu<'a> {
+    pub request_id: i32,
+    pub error_status: ErrorStatus,
+    pub error_index: u32,
+    pub variable_bindings: Vec<VarBind<'a>>,
+}
+
+impl<'a> Pdu<'a> {
+    fn read_contents(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let request_id = read_i32(r)?;
+        let error_status = ErrorStatus::from_i64(r.read_i64()?).ok_or(Error::InvalidValue)?;
+        let error_index = read_u32(r)?;
+        let variable_bindings = VarBind::read_list(r)?;
+        r.expect_end()?;
+        Ok(Pdu {
+            request_id,
+            error_status,
+            error_index,
+            variable_bindings,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_i64(self.request_id as i64)?;
+        w.write_i64(self.error_status.into_i64())?;
+        w.write_u64(self.error_index as u64)?;
+        VarBind::write_list(w, &self.variable_bindings)?;
+        Ok(())
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct BulkPdu<'a> {
+    pub request_id: i32,
+    pub non_repeaters: u32,
+    pub max_repetitions: u32,
+    pub variable_bindings: Vec<VarBind<'a>>,
+}
+
+impl<'a> BulkPdu<'a> {
+    fn read_contents(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let request_id = read_i32(r)?;
+        let non_repeaters = read_u32(r)?;
+        let max_repetitions = read_u32(r)?;
+        let variable_bindings = VarBind::read_list(r)?;
+        r.expect_end()?;
+        Ok(BulkPdu {
+            request_id,
+            non_repeaters,
+            max_repetitions,
+            variable_bindings,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_i64(self.request_id as i64)?;
+        w.write_u64(self.non_repeaters as u64)?;
+        w.write_u64(self.max_repetitions as u64)?;
+        VarBind::write_list(w, &self.variable_bindings)?;
+        Ok(())
+    }
+}
+
+
+/// The SNMPv1 Trap-PDU.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct TrapPdu<'a> {
+    pub enterprise: ObjectIdentifier,
+    pub agent_addr: [u8; 4],
+    pub generic_trap: i64,
+    pub specific_trap: i64,
+    pub time_stamp: u32,
+    pub variable_bindings: Vec<VarBind<'a>>,
+}
+
+impl<'a> TrapPdu<'a> {
+    fn read_contents(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let enterprise = r.read_object_identifier()?;
+        let agent_addr = to_ip_address(r.read_expected(Identifier::application(false, tag::IP_ADDRESS))?)?;
+        let generic_trap = r.read_i64()?;
+        let specific_trap = r.read_i64()?;
+        let time_stamp = to_u32(r.read_expected(Identifier::application(false, tag::TIME_TICKS))?)?;
+        let variable_bindings = VarBind::read_list(r)?;
+        r.expect_end()?;
+        Ok(TrapPdu {
+            enterprise,
+            agent_addr,
+            generic_trap,
+            specific_trap,
+            time_stamp,
+            variable_bindings,
+        })
+    }
+
+    fn write_contents<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<()> {
+        w.write_object_identifier(&self.enterprise)?;
+        w.write_tag_and_data(Identifier::application(false, tag::IP_ADDRESS), &self.agent_addr)?;
+        w.write_i64(self.generic_trap)?;
+        w.write_i64(self.specific_trap)?;
+        w.write_tag_and_data(Identifier::application(false, tag::TIME_TICKS), &integer::from_u64(self.time_stamp as u64))?;
+        VarBind::write_list(w, &self.variable_bindings)?;
+        Ok(())
+    }
+}
+
+
+/// The PDUs CHOICE, each alternative carried under its context-specific tag.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Pdus<'a> {
+    GetRequest(Pdu<'a>),
+    GetNextRequest(Pdu<'a>),
+    Response(Pdu<'a>),
+    SetRequest(Pdu<'a>),
+    Trap(TrapPdu<'a>),
+    GetBulkRequest(BulkPdu<'a>),
+    InformRequest(Pdu<'a>),
+    SnmpV2Trap(Pdu<'a>),
+    Report(Pdu<'a>),
+}
+
+impl<'a> Pdus<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let (identifier, contents) = match r.next()? {
+            Some(v) => v,
+            None => return Err(Error::Read(reading::Error::UnexpectedEndOfData)),
+        };
+        if identifier.class() != Class::ContextSpecific || !identifier.is_constructed() {
+            return Err(Error::Read(reading::Error::UnexpectedTag(identifier)));
+        }
+        let mut r = Asn1Reader::new(contents);
+        let v = match identifier.tag() {
+            tag::GET_REQUEST => Pdus::GetRequest(Pdu::read_contents(&mut r)?),
+            tag::GET_NEXT_REQUEST => Pdus::GetNextRequest(Pdu::read_contents(&mut r)?),
+            tag::RESPONSE => Pdus::Response(Pdu::read_contents(&mut r)?),
+            tag::SET_REQUEST => Pdus::SetRequest(Pdu::read_contents(&mut r)?),
+            tag::TRAP => Pdus::Trap(TrapPdu::read_contents(&mut r)?),
+            tag::GET_BULK_REQUEST => Pdus::GetBulkRequest(BulkPdu::read_contents(&mut r)?),
+            tag::INFORM_REQUEST => Pdus::InformRequest(Pdu::read_contents(&mut r)?),
+            tag::SNMPV2_TRAP => Pdus::SnmpV2Trap(Pdu::read_contents(&mut r)?),
+            tag::REPORT => Pdus::Report(Pdu::read_contents(&mut r)?),
+            _ => return Err(Error::Read(reading::Error::UnexpectedTag(identifier))),
+        };
+        Ok(v)
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        let (t, pdu) = match *self {
+            Pdus::GetRequest(ref pdu) => (tag::GET_REQUEST, pdu),
+            Pdus::GetNextRequest(ref pdu) => (tag::GET_NEXT_REQUEST, pdu),
+            Pdus::Response(ref pdu) => (tag::RESPONSE, pdu),
+            Pdus::SetRequest(ref pdu) => (tag::SET_REQUEST, pdu),
+            Pdus::InformRequest(ref pdu) => (tag::INFORM_REQUEST, pdu),
+            Pdus::SnmpV2Trap(ref pdu) => (tag::SNMPV2_TRAP, pdu),
+            Pdus::Report(ref pdu) => (tag::REPORT, pdu),
+            Pdus::Trap(ref trap) => {
+                return w.write_constructed(Identifier::context_specific(true, tag::TRAP), |w| trap.write_contents(w));
+            }
+            Pdus::GetBulkRequest(ref bulk) => {
+                return w.write_constructed(Identifier::context_specific(true, tag::GET_BULK_REQUEST), |w| bulk.write_contents(w));
+            }
+        };
+        w.write_constructed(Identifier::context_specific(true, t), |w| pdu.write_contents(w))
+    }
+
+    /// The variable bindings carried by any PDU type.
+    pub fn variable_bindings(&self) -> &[VarBind<'a>] {
+        match *self {
+            Pdus::GetRequest(ref pdu) | Pdus::GetNextRequest(ref pdu) | Pdus::Response(ref pdu) |
+            Pdus::SetRequest(ref pdu) | Pdus::InformRequest(ref pdu) | Pdus::SnmpV2Trap(ref pdu) |
+            Pdus::Report(ref pdu) => &pdu.variable_bindings,
+            Pdus::Trap(ref trap) => &trap.variable_bindings,
+            Pdus::GetBulkRequest(ref bulk) => &bulk.variable_bindings,
+        }
+    }
+}
+
+
+/// A community-based (SNMPv1 or SNMPv2c) message.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct CommunityMessage<'a> {
+    /// Either `Version::V1` or `Version::V2c`.
+    pub version: Version,
+    pub community: Cow<'a, [u8]>,
+    pub data: Pdus<'a>,
+}
+
+impl<'a> CommunityMessage<'a> {
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        if self.version == Version::V3 {
+            return Err(der::Error::InvalidValue);
+        }
+        w.write_sequence(|w| {
+            w.write_i64(self.version.into_i64())?;
+            w.write_octet_string(&self.community)?;
+            self.data.write(w)?;
+            Ok(())
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct HeaderData {
+    pub msg_id: i32,
+    pub msg_max_size: i32,
+    /// The single msgFlags octet, a combination of the `FLAG_` constants.
+    pub msg_flags: u8,
+    pub msg_security_model: i32,
+}
+
+impl HeaderData {
+    pub const FLAG_AUTH: u8 = 0x01;
+    pub const FLAG_PRIV: u8 = 0x02;
+    pub const FLAG_REPORTABLE: u8 = 0x04;
+
+    /// The User-based Security Model.
+    pub const SECURITY_MODEL_USM: i32 = 3;
+
+    pub(crate) fn read(r: &mut Asn1Reader) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let msg_id = read_i32(&mut r)?;
+        let msg_max_size = read_i32(&mut r)?;
+        let msg_flags = match *r.read_ber_octet_string()? {
+            [flags] => flags,
+            _ => return Err(Error::InvalidValue),
+        };
+        let msg_security_model = read_i32(&mut r)?;
+        r.expect_end()?;
+        Ok(HeaderData {
+            msg_id,
+            msg_max_size,
+            msg_flags,
+            msg_security_model,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_i64(self.msg_id as i64)?;
+            w.write_i64(self.msg_max_size as i64)?;
+            w.write_octet_string(&[self.msg_flags])?;
+            w.write_i64(self.msg_security_model as i64)?;
+            Ok(())
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct ScopedPdu<'a> {
+    pub context_engine_id: Cow<'a, [u8]>,
+    pub context_name: Cow<'a, [u8]>,
+    pub data: Pdus<'a>,
+}
+
+impl<'a> ScopedPdu<'a> {
+    pub fn from_ber(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = ScopedPdu::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let context_engine_id = r.read_ber_octet_string()?;
+        let context_name = r.read_ber_octet_string()?;
+        let data = Pdus::read(&mut r)?;
+        r.expect_end()?;
+        Ok(ScopedPdu {
+            context_engine_id,
+            context_name,
+            data,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_octet_string(&self.context_engine_id)?;
+            w.write_octet_string(&self.context_name)?;
+            self.data.write(w)?;
+            Ok(())
+        })
+    }
+
+    /// The encoding that is encrypted to produce an `encryptedPDU`.
+    pub fn to_ber(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum ScopedPduData<'a> {
+    Plaintext(ScopedPdu<'a>),
+    /// The encrypted encoding of a ScopedPdu, which can be decoded with `ScopedPdu::from_ber` once decrypted.
+    EncryptedPdu(Cow<'a, [u8]>),
+}
+
+
+/// The User-based Security Model parameters carried in msgSecurityParameters (RFC 3414).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct UsmSecurityParameters<'a> {
+    pub authoritative_engine_id: Cow<'a, [u8]>,
+    pub authoritative_engine_boots: u32,
+    pub authoritative_engine_time: u32,
+    pub user_name: Cow<'a, [u8]>,
+    pub authentication_parameters: Cow<'a, [u8]>,
+    pub privacy_parameters: Cow<'a, [u8]>,
+}
+
+impl<'a> UsmSecurityParameters<'a> {
+    pub fn from_ber(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let mut seq = r.read_sequence()?;
+        r.expect_end()?;
+        let authoritative_engine_id = seq.read_ber_octet_string()?;
+        let authoritative_engine_boots = read_u32(&mut seq)?;
+        let authoritative_engine_time = read_u32(&mut seq)?;
+        let user_name = seq.read_ber_octet_string()?;
+        let authentication_parameters = seq.read_ber_octet_string()?;
+        let privacy_parameters = seq.read_ber_octet_string()?;
+        seq.expect_end()?;
+        Ok(UsmSecurityParameters {
+            authoritative_engine_id,
+            authoritative_engine_boots,
+            authoritative_engine_time,
+            user_name,
+            authentication_parameters,
+            privacy_parameters,
+        })
+    }
+
+    pub fn to_ber(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        Writer::new(&mut output).write_sequence(|w| {
+            w.write_octet_string(&self.authoritative_engine_id)?;
+            w.write_u64(self.authoritative_engine_boots as u64)?;
+            w.write_u64(self.authoritative_engine_time as u64)?;
+            w.write_octet_string(&self.user_name)?;
+            w.write_octet_string(&self.authentication_parameters)?;
+            w.write_octet_string(&self.privacy_parameters)?;
+            Ok(())
+        })?;
+        Ok(output)
+    }
+}
+
+
+/// An SNMPv3 message.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct V3Message<'a> {
+    pub global_data: HeaderData,
+    /// The contents of msgSecurityParameters, whose format depends on the security model.
+    pub security_parameters: Cow<'a, [u8]>,
+    pub data: ScopedPduData<'a>,
+}
+
+impl<'a> V3Message<'a> {
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_i64(Version::V3.into_i64())?;
+            self.global_data.write(w)?;
+            w.write_octet_string(&self.security_parameters)?;
+            match self.data {
+                ScopedPduData::Plaintext(ref scoped_pdu) => scoped_pdu.write(w)?,
+                ScopedPduData::EncryptedPdu(ref encrypted) => w.write_octet_string(encrypted)?,
+            };
+            Ok(())
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Message<'a> {
+    Community(CommunityMessage<'a>),
+    V3(V3Message<'a>),
+}
+
+impl<'a> Message<'a> {
+    pub fn from_ber(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = Message::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let version = Version::from_i64(r.read_i64()?).ok_or(Error::InvalidVersion)?;
+        let v = match version {
+            Version::V1 | Version::V2c => {
+                let community = r.read_ber_octet_string()?;
+                let data = Pdus::read(&mut r)?;
+                Message::Community(CommunityMessage {
+                    version,
+                    community,
+                    data,
+                })
+            }
+            Version::V3 => {
+                let global_data = HeaderData::read(&mut r)?;
+                let security_parameters = r.read_ber_octet_string()?;
+                let data = match r.peek()? {
+                    Some(i) if i.class() == Class::Universal && i.tag() == UniversalTag::OctetString.into() => {
+                        ScopedPduData::EncryptedPdu(r.read_ber_octet_string()?)
+                    }
+                    _ => ScopedPduData::Plaintext(ScopedPdu::read(&mut r)?),
+                };
+                Message::V3(V3Message {
+                    global_data,
+                    security_parameters,
+                    data,
+                })
+            }
+        };
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        match *self {
+            Message::Community(ref m) => m.write(w),
+            Message::V3(ref m) => m.write(w),
+        }
+    }
+
+    pub fn to_ber(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn oid(components: &[u64]) -> ObjectIdentifier {
+        ObjectIdentifier::from_components(components)
+    }
+
+    // SNMPv1 GetRequest for sysDescr.0 with community "public".
+    const GET_SYS_DESCR: [u8; 40] = [
+        0x30, 0x26, 0x02, 0x01, 0x00, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c',
+        0xa0, 0x19, 0x02, 0x01, 0x26, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00,
+        0x30, 0x0e, 0x30, 0x0c, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00, 0x05, 0x00,
+    ];
+
+    #[test]
+    fn test_get_request() {
+        let message = Message::from_ber(&GET_SYS_DESCR).unwrap();
+        let expected = Message::Community(CommunityMessage {
+            version: Version::V1,
+            community: Cow::Borrowed(b"public"),
+            data: Pdus::GetRequest(Pdu {
+                request_id: 0x26,
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+                variable_bindings: vec![VarBind {
+                    name: oid(&[1, 3, 6, 1, 2, 1, 1, 1, 0]),
+                    value: Value::Null,
+                }],
+            }),
+        });
+        assert_eq!(message, expected);
+        assert_eq!(message.to_ber().unwrap(), GET_SYS_DESCR);
+
+        // The same message with indefinite lengths and a long-form length, as BER permits.
+        let mut ber = vec![0x30, 0x80];
+        ber.extend_from_slice(&GET_SYS_DESCR[2..13]);
+        ber.extend_from_slice(&[0xa0, 0x81, 0x19]);
+        ber.extend_from_slice(&GET_SYS_DESCR[15..]);
+        ber.extend_from_slice(&[0x00, 0x00]);
+        assert_eq!(Message::from_ber(&ber).unwrap(), expected);
+
+        // A BER constructed community string, split into segments.
+        let mut ber = vec![0x30, 0x80, 0x02, 0x01, 0x00, 0x24, 0x80, 0x04, 0x03, b'p', b'u', b'b', 0x04, 0x03, b'l', b'i',
+            b'c', 0x00, 0x00];
+        ber.extend_from_slice(&GET_SYS_DESCR[13..]);
+        ber.extend_from_slice(&[0x00, 0x00]);
+        match Message::from_ber(&ber).unwrap() {
+            Message::Community(CommunityMessage { community: Cow::Owned(ref c), .. }) => assert_eq!(c, b"public"),
+            m => panic!("unexpected message {:?}", m),
+        }
+        assert_eq!(Message::from_ber(&ber).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_response_values() {
+        let message = Message::Community(CommunityMessage {
+            version: Version::V2c,
+            community: Cow::Borrowed(b"public"),
+            data: Pdus::Response(Pdu {
+                request_id: -1,
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+                variable_bindings: vec![
+                    VarBind { name: oid(&[1, 3, 6, 1, 2, 1, 1, 3, 0]), value: Value::TimeTicks(0x8000_0000) },
+                    VarBind { name: oid(&[1, 3, 6, 1, 2, 1, 4, 20, 1, 1]), value: Value::IpAddress([192, 0, 2, 1]) },
+                    VarBind { name: oid(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 10, 1]), value: Value::Counter32(u32::MAX) },
+                    VarBind { name: oid(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 5, 1]), value: Value::Gauge32(1_000_000_000) },
+                    VarBind { name: oid(&[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 6, 1]), value: Value::Counter64(u64::MAX) },
+                    VarBind { name: oid(&[1, 3, 6, 1, 2, 1, 1, 1, 0]), value: Value::OctetString(Cow::Borrowed(b"router")) },
+                    VarBind { name: oid(&[1, 3, 6, 1, 2, 1, 1, 2, 0]), value: Value::ObjectIdentifier(oid(&[1, 3, 6, 1, 4, 1, 9])) },
+                    VarBind { name: oid(&[1, 3, 6, 1, 2, 1, 1, 7, 0]), value: Value::Integer(-72) },
+                    VarBind { name: oid(&[1, 3, 6, 1, 4, 1, 1]), value: Value::Opaque(Cow::Borrowed(&[0x9f, 0x78, 0x04, 0x3f, 0x80, 0x00, 0x00])) },
+                    VarBind { name: oid(&[1, 3, 6, 1, 4, 1, 2]), value: Value::NoSuchObject },
+                    VarBind { name: oid(&[1, 3, 6, 1, 4, 1, 3]), value: Value::NoSuchInstance },
+                    VarBind { name: oid(&[1, 3, 6, 1, 4, 1, 4]), value: Value::EndOfMibView },
+                ],
+            }),
+        });
+        let ber = message.to_ber().unwrap();
+        assert_eq!(Message::from_ber(&ber).unwrap(), message);
+
+        let find = |needle: &[u8]| ber.windows(needle.len()).any(|w| w == needle);
+        assert!(find(&[0x43, 0x05, 0x00, 0x80, 0x00, 0x00, 0x00]));
+        assert!(find(&[0x40, 0x04, 192, 0, 2, 1]));
+        assert!(find(&[0x41, 0x05, 0x00, 0xff, 0xff, 0xff, 0xff]));
+        assert!(find(&[0x42, 0x04, 0x3b, 0x9a, 0xca, 0x00]));
+        assert!(find(&[0x46, 0x09, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]));
+        assert!(find(&[0x44, 0x07, 0x9f, 0x78]));
+        assert!(find(&[0x80, 0x00, 0x30]));
+        assert!(ber.ends_with(&[0x82, 0x00]));
+
+        // Application-class values that are out of range for their type.
+        let mut bad = Asn1Reader::new(&[0x41, 0x05, 0x01, 0x00, 0x00, 0x00, 0x00]);
+        assert!(Value::read(&mut bad).is_err());
+        let mut bad = Asn1Reader::new(&[0x40, 0x03, 0x01, 0x02, 0x03]);
+        assert!(Value::read(&mut bad).is_err());
+
+        // BER constructed OCTET STRING and Opaque values are joined; other constructed values are rejected.
+        let mut r = Asn1Reader::new(&[0x24, 0x80, 0x04, 0x02, b'r', b'o', 0x04, 0x04, b'u', b't', b'e', b'r', 0x00, 0x00]);
+        assert_eq!(Value::read(&mut r).unwrap(), Value::OctetString(Cow::Owned(b"router".to_vec())));
+        let mut r = Asn1Reader::new(&[0x64, 0x06, 0x04, 0x01, 0x9f, 0x04, 0x01, 0x78]);
+        assert_eq!(Value::read(&mut r).unwrap(), Value::Opaque(Cow::Owned(vec![0x9f, 0x78])));
+        let mut bad = Asn1Reader::new(&[0x62, 0x03, 0x02, 0x01, 0x00]);
+        assert!(Value::read(&mut bad).is_err());
+    }
+
+    #[test]
+    fn test_trap_and_bulk() {
+        let trap = Message::Community(CommunityMessage {
+            version: Version::V1,
+            community: Cow::Borrowed(b"public"),
+            data: Pdus::Trap(TrapPdu {
+                enterprise: oid(&[1, 3, 6, 1, 4, 1, 8072]),
+                agent_addr: [10, 0, 0, 1],
+                generic_trap: 6,
+                specific_trap: 1,
+                time_stamp: 1234,
+                variable_bindings: Vec::new(),
+            }),
+        });
+        let ber = trap.to_ber().unwrap();
+        assert_eq!(&ber[13..], [
+            0xa4, 0x1b,
+                0x06, 0x07, 0x2b, 0x06, 0x01, 0x04, 0x01, 0xbf, 0x08,
+                0x40, 0x04, 0x0a, 0x00, 0x00, 0x01,
+                0x02, 0x01, 0x06,
+                0x02, 0x01, 0x01,
+                0x43, 0x02, 0x04, 0xd2,
+                0x30, 0x00,
+        ]);
+        assert_eq!(Message::from_ber(&ber).unwrap(), trap);
+
+        let bulk = Message::Community(CommunityMessage {
+            version: Version::V2c,
+            community: Cow::Borrowed(b"public"),
+            data: Pdus::GetBulkRequest(BulkPdu {
+                request_id: 7,
+                non_repeaters: 1,
+                max_repetitions: 10,
+                variable_bindings: vec![VarBind { name: oid(&[1, 3, 6, 1, 2, 1, 2, 2]), value: Value::Null }],
+            }),
+        });
+        let ber = bulk.to_ber().unwrap();
+        assert_eq!(&ber[13..24], [0xa5, 0x18, 0x02, 0x01, 0x07, 0x02, 0x01, 0x01, 0x02, 0x01, 0x0a]);
+        let decoded = Message::from_ber(&ber).unwrap();
+        assert_eq!(decoded, bulk);
+        match decoded {
+            Message::Community(ref m) => assert_eq!(m.data.variable_bindings().len(), 1),
+            _ => panic!("unexpected message"),
+        }
+    }
+
+    #[test]
+    fn test_v3() {
+        let usm = UsmSecurityParameters {
+            authoritative_engine_id: Cow::Borrowed(&[0x80, 0x00, 0x1f, 0x88, 0x04]),
+            authoritative_engine_boots: 3,
+            authoritative_engine_time: 600,
+            user_name: Cow::Borrowed(b"monitor"),
+            authentication_parameters: Cow::Borrowed(&[]),
+            privacy_parameters: Cow::Borrowed(&[]),
+        };
+        let security_parameters = usm.to_ber().unwrap();
+        assert_eq!(UsmSecurityParameters::from_ber(&security_parameters).unwrap(), usm);
+
+        let message = Message::V3(V3Message {
+            global_data: HeaderData {
+                msg_id: 91,
+                msg_max_size: 65507,
+                msg_flags: HeaderData::FLAG_REPORTABLE,
+                msg_security_model: HeaderData::SECURITY_MODEL_USM,
+            },
+            security_parameters: Cow::Borrowed(&security_parameters),
+            data: ScopedPduData::Plaintext(ScopedPdu {
+                context_engine_id: Cow::Borrowed(&[0x80, 0x00, 0x1f, 0x88, 0x04]),
+                context_name: Cow::Borrowed(b""),
+                data: Pdus::GetNextRequest(Pdu {
+                    request_id: 1,
+                    error_status: ErrorStatus::NoError,
+                    error_index: 0,
+                    variable_bindings: vec![VarBind { name: oid(&[1, 3, 6, 1, 2, 1, 1]), value: Value::Null }],
+                }),
+            }),
+        });
+        let ber = message.to_ber().unwrap();
+        assert_eq!(&ber[2..22], [
+            0x02, 0x01, 0x03,
+            0x30, 0x0e, 0x02, 0x01, 0x5b, 0x02, 0x03, 0x00, 0xff, 0xe3, 0x04, 0x01, 0x04, 0x02, 0x01, 0x03,
+            0x04,
+        ]);
+        assert_eq!(Message::from_ber(&ber).unwrap(), message);
+
+        let encrypted = Message::V3(V3Message {
+            global_data: HeaderData {
+                msg_flags: HeaderData::FLAG_AUTH | HeaderData::FLAG_PRIV,
+                ..HeaderData {
+                    msg_id: 92,
+                    msg_max_size: 1500,
+                    msg_flags: 0,
+                    msg_security_model: HeaderData::SECURITY_MODEL_USM,
+                }
+            },
+            security_parameters: Cow::Borrowed(&security_parameters),
+            data: ScopedPduData::EncryptedPdu(Cow::Borrowed(&[0xde, 0xad, 0xbe, 0xef])),
+        });
+        let ber = encrypted.to_ber().unwrap();
+        assert!(ber.ends_with(&[0x04, 0x04, 0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(Message::from_ber(&ber).unwrap(), encrypted);
+
+        // The same message with a BER constructed msgFlags, msgSecurityParameters and encryptedPDU.
+        let mut segmented = vec![0x30, 0x80, 0x02, 0x01, 0x03];
+        segmented.extend_from_slice(&[0x30, 0x80, 0x02, 0x01, 0x5c, 0x02, 0x02, 0x05, 0xdc, 0x24, 0x80, 0x04, 0x01, 0x03,
+            0x00, 0x00, 0x02, 0x01, 0x03, 0x00, 0x00]);
+        segmented.extend_from_slice(&[0x24, 0x80, 0x04, 0x01]);
+        segmented.push(security_parameters[0]);
+        segmented.extend_from_slice(&[0x04, security_parameters.len() as u8 - 1]);
+        segmented.extend_from_slice(&security_parameters[1..]);
+        segmented.extend_from_slice(&[0x00, 0x00]);
+        segmented.extend_from_slice(&[0x24, 0x08, 0x04, 0x02, 0xde, 0xad, 0x04, 0x02, 0xbe, 0xef, 0x00, 0x00]);
+        assert_eq!(Message::from_ber(&segmented).unwrap(), encrypted);
+
+        assert!(Message::from_ber(&[0x30, 0x03, 0x02, 0x01, 0x02]).is_err());
+    }
+}
@@ -0,0 +1,286 @@
+use std;
+
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidFormat,
+    InvalidValue,
+    OutOfRange,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::InvalidFormat => write!(f, "invalid time format"),
+            Error::InvalidValue => write!(f, "invalid time value"),
+            Error::OutOfRange => write!(f, "time out of range"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// A UTC date and time as carried by UTCTime and GeneralizedTime values.
+///
+/// Only the `Z` (UTC) form is accepted, as DER requires.
+#[derive(Copy,Clone,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub struct DateTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    nanosecond: u32,
+}
+
+impl DateTime {
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Result<Self> {
+        if year > 9999 || !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) ||
+            hour > 23 || minute > 59 || second > 59 {
+            return Err(Error::InvalidValue);
+        }
+        Ok(DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            nanosecond: 0,
+        })
+    }
+
+    pub fn with_nanosecond(self, nanosecond: u32) -> Result<Self> {
+        if nanosecond >= 1_000_000_000 {
+            return Err(Error::InvalidValue);
+        }
+        Ok(DateTime {
+            nanosecond,
+            ..self
+        })
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    pub fn nanosecond(&self) -> u32 {
+        self.nanosecond
+    }
+
+    /// Parses UTCTime contents of the form `YYMMDDHHMM[SS]Z`, with two-digit years interpreted as 1950 to 2049 per
+    /// RFC 5280.
+    pub fn from_utc_time_bytes(b: &[u8]) -> Result<Self> {
+        let b = match b.split_last() {
+            Some((&b'Z', b)) => b,
+            _ => return Err(Error::InvalidFormat),
+        };
+        if b.len() != 10 && b.len() != 12 {
+            return Err(Error::InvalidFormat);
+        }
+        let yy = parse_digits(&b[0..2])? as u16;
+        let year = if yy >= 50 { 1900 + yy } else { 2000 + yy };
+        let second = if b.len() == 12 { parse_digits(&b[10..12])? as u8 } else { 0 };
+        DateTime::new(year, parse_digits(&b[2..4])? as u8, parse_digits(&b[4..6])? as u8,
+                      parse_digits(&b[6..8])? as u8, parse_digits(&b[8..10])? as u8, second)
+    }
+
+    /// Parses GeneralizedTime contents of the form `YYYYMMDDHHMMSS[.f+]Z`.
+    pub fn from_generalized_time_bytes(b: &[u8]) -> Result<Self> {
+        let b = match b.split_last() {
+            Some((&b'Z', b)) => b,
+            _ => return Err(Error::InvalidFormat),
+        };
+        if b.len() < 14 {
+            return Err(Error::InvalidFormat);
+        }
+        let mut nanosecond = 0u32;
+        match &b[14..] {
+            [] => (),
+            [b'.', fraction @ ..] | [b',', fraction @ ..] => {
+                if fraction.is_empty() || fraction.len() > 9 {
+                    return Err(Error::InvalidFormat);
+                }
+                nanosecond = parse_digits(fraction)?;
+                for _ in fraction.len()..9 {
+                    nanosecond *= 10;
+                }
+            }
+            _ => return Err(Error::InvalidFormat),
+        }
+        let dt = DateTime::new(parse_digits(&b[0..4])? as u16, parse_digits(&b[4..6])? as u8,
+                               parse_digits(&b[6..8])? as u8, parse_digits(&b[8..10])? as u8,
+                               parse_digits(&b[10..12])? as u8, parse_digits(&b[12..14])? as u8)?;
+        dt.with_nanosecond(nanosecond)
+    }
+
+    /// Encodes as UTCTime contents; fails for years outside 1950 to 2049 or if there are fractional seconds.
+    pub fn to_utc_time_bytes(&self) -> Result<Vec<u8>> {
+        if self.year < 1950 || self.year > 2049 || self.nanosecond != 0 {
+            return Err(Error::OutOfRange);
+        }
+        Ok(format!("{:02}{:02}{:02}{:02}{:02}{:02}Z", self.year % 100, self.month, self.day, self.hour,
+                   self.minute, self.second).into_bytes())
+    }
+
+    /// Encodes as GeneralizedTime contents, with any fractional seconds written without trailing zeros as DER
+    /// requires.
+    pub fn to_generalized_time_bytes(&self) -> Vec<u8> {
+        let mut s = format!("{:04}{:02}{:02}{:02}{:02}{:02}", self.year, self.month, self.day, self.hour,
+                            self.minute, self.second);
+        if self.nanosecond != 0 {
+            let fraction = format!("{:09}", self.nanosecond);
+            s.push('.');
+            s.push_str(fraction.trim_end_matches('0'));
+        }
+        s.push('Z');
+        s.into_bytes()
+    }
+
+    /// Seconds since 1970-01-01T00:00:00Z, ignoring any fractional part.
+    pub fn unix_timestamp(&self) -> i64 {
+        let days = days_from_civil(self.year as i64, self.month as i64, self.day as i64);
+        days * 86400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64
+    }
+
+    pub fn from_unix_timestamp(timestamp: i64) -> Result<Self> {
+        let days = timestamp.div_euclid(86400);
+        let seconds = timestamp.rem_euclid(86400);
+        let (year, month, day) = civil_from_days(days);
+        if !(0..=9999).contains(&year) {
+            return Err(Error::OutOfRange);
+        }
+        DateTime::new(year as u16, month as u8, day as u8, (seconds / 3600) as u8, (seconds / 60 % 60) as u8,
+                      (seconds % 60) as u8)
+    }
+}
+
+impl std::fmt::Display for DateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", self.year, self.month, self.day, self.hour, self.minute,
+               self.second)?;
+        if self.nanosecond != 0 {
+            let fraction = format!("{:09}", self.nanosecond);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        write!(f, "Z")
+    }
+}
+
+
+fn parse_digits(b: &[u8]) -> Result<u32> {
+    let mut v = 0u32;
+    for &d in b {
+        if !d.is_ascii_digit() {
+            return Err(Error::InvalidFormat);
+        }
+        v = v * 10 + (d - b'0') as u32;
+    }
+    Ok(v)
+}
+
+fn is_leap_year(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Howard Hinnant's days_from_civil and civil_from_days algorithms.
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_utc_time() {
+        let dt = DateTime::from_utc_time_bytes(b"190102030405Z").unwrap();
+        assert_eq!(dt, DateTime::new(2019, 1, 2, 3, 4, 5).unwrap());
+        assert_eq!(dt.to_utc_time_bytes().unwrap(), b"190102030405Z");
+        assert_eq!(DateTime::from_utc_time_bytes(b"9912312359Z").unwrap(), DateTime::new(1999, 12, 31, 23, 59, 0).unwrap());
+
+        assert!(DateTime::from_utc_time_bytes(b"190102030405").is_err());
+        assert!(DateTime::from_utc_time_bytes(b"190102030405+0100").is_err());
+        assert!(DateTime::from_utc_time_bytes(b"190230030405Z").is_err());
+        assert!(DateTime::new(2050, 1, 1, 0, 0, 0).unwrap().to_utc_time_bytes().is_err());
+    }
+
+    #[test]
+    fn test_generalized_time() {
+        let dt = DateTime::from_generalized_time_bytes(b"20190102030405Z").unwrap();
+        assert_eq!(dt, DateTime::new(2019, 1, 2, 3, 4, 5).unwrap());
+        assert_eq!(dt.to_generalized_time_bytes(), b"20190102030405Z");
+
+        let dt = DateTime::from_generalized_time_bytes(b"20190102030405.120Z").unwrap();
+        assert_eq!(dt.nanosecond(), 120_000_000);
+        assert_eq!(dt.to_generalized_time_bytes(), b"20190102030405.12Z");
+        assert_eq!(dt.to_string(), "2019-01-02T03:04:05.12Z");
+
+        assert!(DateTime::from_generalized_time_bytes(b"20190102030405.Z").is_err());
+        assert!(DateTime::from_generalized_time_bytes(b"201901020304Z").is_err());
+        assert!(DateTime::from_generalized_time_bytes(b"20000229000000Z").is_ok());
+        assert!(DateTime::from_generalized_time_bytes(b"19000229000000Z").is_err());
+    }
+
+    #[test]
+    fn test_unix_timestamp() {
+        assert_eq!(DateTime::new(1970, 1, 1, 0, 0, 0).unwrap().unix_timestamp(), 0);
+        assert_eq!(DateTime::new(2019, 1, 2, 3, 4, 5).unwrap().unix_timestamp(), 1546398245);
+        assert_eq!(DateTime::from_unix_timestamp(1546398245).unwrap(), DateTime::new(2019, 1, 2, 3, 4, 5).unwrap());
+        assert_eq!(DateTime::from_unix_timestamp(-1).unwrap(), DateTime::new(1969, 12, 31, 23, 59, 59).unwrap());
+    }
+}
@@ -0,0 +1,682 @@
+use bit_string::{self, BitString};
+use cms::{self, ContentInfo, SignedData};
+use identifier::{Identifier, UniversalTag};
+use integer;
+use object_identifier::ObjectIdentifier;
+use reading::{self, Asn1Reader};
+use std;
+use time::DateTime;
+use writing::der::{self, Writer};
+use x509::{self, extensions::GeneralName, AlgorithmIdentifier, Extension};
+
+
+pub mod oid {
+    pub const CT_TST_INFO: &[u64] = &[1, 2, 840, 113549, 1, 9, 16, 1, 4];
+}
+
+
+#[derive(Debug)]
+pub enum Error {
+    Read(reading::Error),
+    InvalidVersion,
+    InvalidValue,
+    UnexpectedContentType(ObjectIdentifier),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Read(ref e) => e.fmt(f),
+            Error::InvalidVersion => write!(f, "invalid version"),
+            Error::InvalidValue => write!(f, "invalid value"),
+            Error::UnexpectedContentType(ref oid) => write!(f, "unexpected content type: {}", oid),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Read(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reading::Error> for Error {
+    fn from(e: reading::Error) -> Error {
+        Error::Read(e)
+    }
+}
+
+impl From<x509::Error> for Error {
+    fn from(e: x509::Error) -> Error {
+        match e {
+            x509::Error::Read(e) => Error::Read(e),
+            _ => Error::InvalidValue,
+        }
+    }
+}
+
+impl From<cms::Error> for Error {
+    fn from(e: cms::Error) -> Error {
+        match e {
+            cms::Error::Read(e) => Error::Read(e),
+            cms::Error::UnexpectedContentType(oid) => Error::UnexpectedContentType(oid),
+            _ => Error::InvalidValue,
+        }
+    }
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// Reads `version INTEGER { v1(1) }`, the only version RFC 3161 defines.
+fn read_version(r: &mut Asn1Reader) -> Result<()> {
+    if r.read_i64()? != 1 {
+        return Err(Error::InvalidVersion);
+    }
+    Ok(())
+}
+
+/// Reads `BOOLEAN DEFAULT FALSE`.
+fn read_default_false(r: &mut Asn1Reader) -> Result<bool> {
+    match r.peek()? {
+        Some(i) if i == Identifier::universal(UniversalTag::Boolean) => Ok(r.read_boolean()?),
+        _ => Ok(false),
+    }
+}
+
+fn read_optional_integer<'a>(r: &mut Asn1Reader<'a>) -> Result<Option<&'a [u8]>> {
+    match r.peek()? {
+        Some(i) if i == Identifier::universal(UniversalTag::Integer) => Ok(Some(r.read_integer_bytes()?)),
+        _ => Ok(None),
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct MessageImprint<'a> {
+    pub hash_algorithm: AlgorithmIdentifier<'a>,
+    pub hashed_message: &'a [u8],
+}
+
+impl<'a> MessageImprint<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let hash_algorithm = AlgorithmIdentifier::read(&mut r)?;
+        let hashed_message = r.read_octet_string()?;
+        r.expect_end()?;
+        Ok(MessageImprint {
+            hash_algorithm,
+            hashed_message,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            self.hash_algorithm.write(w)?;
+            w.write_octet_string(self.hashed_message)?;
+            Ok(())
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct TimeStampReq<'a> {
+    pub message_imprint: MessageImprint<'a>,
+    pub req_policy: Option<ObjectIdentifier>,
+    /// The validated contents of the nonce INTEGER.
+    pub nonce: Option<&'a [u8]>,
+    pub cert_req: bool,
+    pub extensions: Vec<Extension<'a>>,
+}
+
+impl<'a> TimeStampReq<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = TimeStampReq::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        read_version(&mut r)?;
+        let message_imprint = MessageImprint::read(&mut r)?;
+        let req_policy = match r.peek()? {
+            Some(i) if i == Identifier::universal(UniversalTag::ObjectIdentifier) => Some(r.read_object_identifier()?),
+            _ => None,
+        };
+        let nonce = read_optional_integer(&mut r)?;
+        let cert_req = read_default_false(&mut r)?;
+        let extensions = match r.read_optional(Identifier::context_specific(true, 0))? {
+            Some(b) => Extension::read_list_contents(b)?,
+            None => Vec::new(),
+        };
+        r.expect_end()?;
+        Ok(TimeStampReq {
+            message_imprint,
+            req_policy,
+            nonce,
+            cert_req,
+            extensions,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_u64(1)?;
+            self.message_imprint.write(w)?;
+            if let Some(ref req_policy) = self.req_policy {
+                w.write_object_identifier(req_policy)?;
+            }
+            if let Some(nonce) = self.nonce {
+                w.write_integer_bytes(nonce)?;
+            }
+            if self.cert_req {
+                w.write_boolean(true)?;
+            }
+            if !self.extensions.is_empty() {
+                Extension::write_implicit_list(w, Identifier::context_specific(true, 0), &self.extensions)?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum PkiStatus {
+    Granted,
+    GrantedWithMods,
+    Rejection,
+    Waiting,
+    RevocationWarning,
+    RevocationNotification,
+}
+
+impl PkiStatus {
+    pub fn from_i64(v: i64) -> Option<Self> {
+        Some(match v {
+            0 => PkiStatus::Granted,
+            1 => PkiStatus::GrantedWithMods,
+            2 => PkiStatus::Rejection,
+            3 => PkiStatus::Waiting,
+            4 => PkiStatus::RevocationWarning,
+            5 => PkiStatus::RevocationNotification,
+            _ => return None,
+        })
+    }
+
+    pub fn into_i64(self) -> i64 {
+        match self {
+            PkiStatus::Granted => 0,
+            PkiStatus::GrantedWithMods => 1,
+            PkiStatus::Rejection => 2,
+            PkiStatus::Waiting => 3,
+            PkiStatus::RevocationWarning => 4,
+            PkiStatus::RevocationNotification => 5,
+        }
+    }
+}
+
+
+/// The PKIFailureInfo named bit list, with bit `n` of the ASN.1 definition stored as `1 << n`.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct PkiFailureInfo(pub u32);
+
+impl PkiFailureInfo {
+    pub const BAD_ALG: u32 = 1 << 0;
+    pub const BAD_REQUEST: u32 = 1 << 2;
+    pub const BAD_DATA_FORMAT: u32 = 1 << 5;
+    pub const TIME_NOT_AVAILABLE: u32 = 1 << 14;
+    pub const UNACCEPTED_POLICY: u32 = 1 << 15;
+    pub const UNACCEPTED_EXTENSION: u32 = 1 << 16;
+    pub const ADD_INFO_NOT_AVAILABLE: u32 = 1 << 17;
+    pub const SYSTEM_FAILURE: u32 = 1 << 25;
+
+    pub const BIT_COUNT: usize = 26;
+
+    fn from_bit_string(bits: BitString) -> Self {
+        let mut v = 0u32;
+        for i in 0..PkiFailureInfo::BIT_COUNT {
+            if bits.bit(i) {
+                v |= 1 << i;
+            }
+        }
+        PkiFailureInfo(v)
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        let (unused_bits, data) = bit_string::named_bits_to_bytes(self.0 as u64, PkiFailureInfo::BIT_COUNT);
+        let bits = BitString::new(unused_bits, &data).map_err(|_| der::Error::InvalidValue)?;
+        w.write_bit_string(bits)
+    }
+
+    pub fn contains(&self, failure: u32) -> bool {
+        self.0 & failure == failure
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct PkiStatusInfo<'a> {
+    pub status: PkiStatus,
+    /// The UTF8String elements of the statusString PKIFreeText.
+    pub status_string: Vec<&'a str>,
+    pub fail_info: Option<PkiFailureInfo>,
+}
+
+impl<'a> PkiStatusInfo<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let status = PkiStatus::from_i64(r.read_i64()?).ok_or(Error::InvalidValue)?;
+        let mut status_string = Vec::new();
+        if let Some(b) = r.read_optional(Identifier::universal(UniversalTag::Sequence))? {
+            let mut r = Asn1Reader::new(b);
+            while !r.is_at_end() {
+                let s = r.read_expected(Identifier::universal(UniversalTag::Utf8String))?;
+                status_string.push(std::str::from_utf8(s).map_err(|_| Error::InvalidValue)?);
+            }
+            if status_string.is_empty() {
+                return Err(Error::InvalidValue);
+            }
+        }
+        let fail_info = match r.peek()? {
+            Some(i) if i == Identifier::universal(UniversalTag::BitString) => Some(PkiFailureInfo::from_bit_string(r.read_bit_string()?)),
+            _ => None,
+        };
+        r.expect_end()?;
+        Ok(PkiStatusInfo {
+            status,
+            status_string,
+            fail_info,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_i64(self.status.into_i64())?;
+            if !self.status_string.is_empty() {
+                w.write_sequence(|w| {
+                    for s in &self.status_string {
+                        w.write_utf8_string(s)?;
+                    }
+                    Ok(())
+                })?;
+            }
+            if let Some(ref fail_info) = self.fail_info {
+                fail_info.write(w)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct TimeStampResp<'a> {
+    pub status: PkiStatusInfo<'a>,
+    /// The complete encoding of the timeStampToken ContentInfo, if present.
+    pub time_stamp_token: Option<&'a [u8]>,
+}
+
+impl<'a> TimeStampResp<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = TimeStampResp::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let status = PkiStatusInfo::read(&mut r)?;
+        let time_stamp_token = if r.is_at_end() { None } else { Some(r.read_raw()?) };
+        r.expect_end()?;
+        Ok(TimeStampResp {
+            status,
+            time_stamp_token,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            self.status.write(w)?;
+            if let Some(token) = self.time_stamp_token {
+                w.write_raw(token)?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+
+    /// Decodes the token as SignedData, checking that it encapsulates a TSTInfo.
+    ///
+    /// The TSTInfo itself is the `e_content` of the returned value's `encap_content_info`.
+    pub fn signed_data(&self) -> Result<Option<SignedData<'a>>> {
+        let token = match self.time_stamp_token {
+            Some(token) => token,
+            None => return Ok(None),
+        };
+        let signed_data = ContentInfo::from_ber(token)?.signed_data()?;
+        let e_content_type = &signed_data.encap_content_info.e_content_type;
+        if e_content_type.components() != oid::CT_TST_INFO {
+            return Err(Error::UnexpectedContentType(e_content_type.clone()));
+        }
+        Ok(Some(signed_data))
+    }
+}
+
+
+#[derive(Copy,Clone,Debug,Default,PartialEq,Eq)]
+pub struct Accuracy {
+    pub seconds: Option<u64>,
+    /// Between 1 and 999 when present.
+    pub millis: Option<u16>,
+    /// Between 1 and 999 when present.
+    pub micros: Option<u16>,
+}
+
+impl Accuracy {
+    pub(crate) fn read(r: &mut Asn1Reader) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let seconds = match r.peek()? {
+            Some(i) if i == Identifier::universal(UniversalTag::Integer) => Some(r.read_u64()?),
+            _ => None,
+        };
+        let millis = Accuracy::read_fraction(&mut r, 0)?;
+        let micros = Accuracy::read_fraction(&mut r, 1)?;
+        r.expect_end()?;
+        Ok(Accuracy {
+            seconds,
+            millis,
+            micros,
+        })
+    }
+
+    fn read_fraction(r: &mut Asn1Reader, tag: u64) -> Result<Option<u16>> {
+        match r.read_optional(Identifier::context_specific(false, tag))? {
+            Some(b) => {
+                let v = integer::to_i64(b).map_err(reading::Error::from)?;
+                if !(1..=999).contains(&v) {
+                    return Err(Error::InvalidValue);
+                }
+                Ok(Some(v as u16))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            if let Some(seconds) = self.seconds {
+                w.write_u64(seconds)?;
+            }
+            if let Some(millis) = self.millis {
+                w.write_tag_and_data(Identifier::context_specific(false, 0), &integer::from_i64(millis as i64))?;
+            }
+            if let Some(micros) = self.micros {
+                w.write_tag_and_data(Identifier::context_specific(false, 1), &integer::from_i64(micros as i64))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct TstInfo<'a> {
+    pub policy: ObjectIdentifier,
+    pub message_imprint: MessageImprint<'a>,
+    /// The validated contents of the serialNumber INTEGER.
+    pub serial_number: &'a [u8],
+    pub gen_time: DateTime,
+    pub accuracy: Option<Accuracy>,
+    pub ordering: bool,
+    /// The validated contents of the nonce INTEGER.
+    pub nonce: Option<&'a [u8]>,
+    pub tsa: Option<GeneralName<'a>>,
+    pub extensions: Vec<Extension<'a>>,
+}
+
+impl<'a> TstInfo<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = TstInfo::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        read_version(&mut r)?;
+        let policy = r.read_object_identifier()?;
+        let message_imprint = MessageImprint::read(&mut r)?;
+        let serial_number = r.read_integer_bytes()?;
+        let gen_time = r.read_generalized_time()?;
+        let accuracy = match r.peek()? {
+            Some(i) if i == Identifier::universal(UniversalTag::Sequence) => Some(Accuracy::read(&mut r)?),
+            _ => None,
+        };
+        let ordering = read_default_false(&mut r)?;
+        let nonce = read_optional_integer(&mut r)?;
+        let tsa = match r.read_optional(Identifier::context_specific(true, 0))? {
+            Some(b) => {
+                let mut r = Asn1Reader::new(b);
+                let v = GeneralName::read(&mut r)?;
+                r.expect_end()?;
+                Some(v)
+            }
+            None => None,
+        };
+        let extensions = match r.read_optional(Identifier::context_specific(true, 1))? {
+            Some(b) => Extension::read_list_contents(b)?,
+            None => Vec::new(),
+        };
+        r.expect_end()?;
+        Ok(TstInfo {
+            policy,
+            message_imprint,
+            serial_number,
+            gen_time,
+            accuracy,
+            ordering,
+            nonce,
+            tsa,
+            extensions,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_u64(1)?;
+            w.write_object_identifier(&self.policy)?;
+            self.message_imprint.write(w)?;
+            w.write_integer_bytes(self.serial_number)?;
+            w.write_generalized_time(self.gen_time)?;
+            if let Some(ref accuracy) = self.accuracy {
+                accuracy.write(w)?;
+            }
+            if self.ordering {
+                w.write_boolean(true)?;
+            }
+            if let Some(nonce) = self.nonce {
+                w.write_integer_bytes(nonce)?;
+            }
+            if let Some(ref tsa) = self.tsa {
+                w.write_explicit(0, |w| {
+                    tsa.write(w)?;
+                    Ok(())
+                })?;
+            }
+            if !self.extensions.is_empty() {
+                Extension::write_implicit_list(w, Identifier::context_specific(true, 1), &self.extensions)?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pem;
+
+    const REQUEST: &str = "\
+MEQCAQEwMTANBglghkgBZQMEAgEFAAQgCcp+TqpuiunH0mEWcSkYSINkTQffuny/
+vEyKLgg2DVsCCQDZTOP/5vxLFQEB/w==";
+
+    const RESPONSE: &str = "\
+MIID7DADAgEAMIID4wYJKoZIhvcNAQcCoIID1DCCA9ACAQMxDzANBglghkgBZQME
+AgEFADCBwwYLKoZIhvcNAQkQAQSggbMEgbAwga0CAQEGBCoDBAEwMTANBglghkgB
+ZQMEAgEFAAQgCcp+TqpuiunH0mEWcSkYSINkTQffuny/vEyKLgg2DVsCASsYEzIw
+MjYxMDE4MjEzMjE4LjA1M1owCgIBAYACAfSBAWQBAf8CCQDZTOP/5vxLFaA9pDsw
+OTELMAkGA1UEBhMCR0IxFDASBgNVBAoMC0V4YW1wbGUgTHRkMRQwEgYDVQQDDAtF
+eGFtcGxlIFRTQaCCAZ4wggGaMIIBP6ADAgECAgEJMAoGCCqGSM49BAMCMDgxCzAJ
+BgNVBAYTAkdCMRQwEgYDVQQKDAtFeGFtcGxlIEx0ZDETMBEGA1UEAwwKRXhhbXBs
+ZSBDQTAeFw0yNjEwMTgyMTMyMThaFw0zNjEwMTUyMTMyMThaMDkxCzAJBgNVBAYT
+AkdCMRQwEgYDVQQKDAtFeGFtcGxlIEx0ZDEUMBIGA1UEAwwLRXhhbXBsZSBUU0Ew
+WTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAATeY1gjj/EOJAIOKP4F2BQUAGwnyq+X
+SaFUTSw/hIo8YJVWznPMxuA3IVOAvaQ7CzwO1ps3Pio3q4zxT2b1HVx7ozkwNzAW
+BgNVHSUBAf8EDDAKBggrBgEFBQcDCDAdBgNVHQ4EFgQUB+aj0mwBdQ3uTqDdOh8E
+VKHDcuowCgYIKoZIzj0EAwIDSQAwRgIhAJnxBXANEqFfq7v/F+3l6IUP7gXUKg/T
+HQjq1cu79AlgAiEAktXWPg3B6z67EIjMx3T8o5EJz3BichHLp1Q9T6AdeXYxggFQ
+MIIBTAIBATA9MDgxCzAJBgNVBAYTAkdCMRQwEgYDVQQKDAtFeGFtcGxlIEx0ZDET
+MBEGA1UEAwwKRXhhbXBsZSBDQQIBCTANBglghkgBZQMEAgEFAKCBpDAaBgkqhkiG
+9w0BCQMxDQYLKoZIhvcNAQkQAQQwHAYJKoZIhvcNAQkFMQ8XDTI2MTAxODIxMzIx
+OFowLwYJKoZIhvcNAQkEMSIEIHbfjF1nTIf8vM/JCK2kITceJ7XvI48oEMAW6def
+DqLFMDcGCyqGSIb3DQEJEAIvMSgwJjAkMCIEIGCwTU9jM0fa1IZCbHFdOUfQr3B1
+n/I+Nhm6AeXImox2MAoGCCqGSM49BAMCBEYwRAIgUMeMDa2JOw8/eIjqIgrxhLk1
+BnzhVr6jXHkaC+C/n14CIFm0aWslD23MNVuVBymZH+zUWB6uKAIpqq/IpVIh0wNu";
+
+    // SHA-256 of "hello, world".
+    const HASHED_MESSAGE: [u8; 32] = [
+        0x09, 0xca, 0x7e, 0x4e, 0xaa, 0x6e, 0x8a, 0xe9, 0xc7, 0xd2, 0x61, 0x16, 0x71, 0x29, 0x18, 0x48,
+        0x83, 0x64, 0x4d, 0x07, 0xdf, 0xba, 0x7c, 0xbf, 0xbc, 0x4c, 0x8a, 0x2e, 0x08, 0x36, 0x0d, 0x5b,
+    ];
+    const NONCE: [u8; 9] = [0x00, 0xd9, 0x4c, 0xe3, 0xff, 0xe6, 0xfc, 0x4b, 0x15];
+    const SHA256: [u8; 15] = [0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05, 0x00];
+
+    fn decode(b64: &str) -> Vec<u8> {
+        let pem = format!("-----BEGIN X-----\n{}\n-----END X-----\n", b64);
+        pem::parse(&pem, pem::Mode::Strict).unwrap().into_contents()
+    }
+
+    fn message_imprint() -> MessageImprint<'static> {
+        MessageImprint {
+            hash_algorithm: AlgorithmIdentifier::from_der(&SHA256).unwrap(),
+            hashed_message: &HASHED_MESSAGE,
+        }
+    }
+
+    #[test]
+    fn test_request() {
+        let der = decode(REQUEST);
+        let request = TimeStampReq::from_der(&der).unwrap();
+        assert_eq!(request, TimeStampReq {
+            message_imprint: message_imprint(),
+            req_policy: None,
+            nonce: Some(&NONCE),
+            cert_req: true,
+            extensions: Vec::new(),
+        });
+        assert_eq!(request.to_der().unwrap(), der);
+
+        let request = TimeStampReq {
+            req_policy: Some(ObjectIdentifier::from_components(&[1, 2, 3, 4, 1])),
+            nonce: None,
+            cert_req: false,
+            extensions: vec![Extension {
+                extn_id: ObjectIdentifier::from_components(&[1, 2, 3]),
+                critical: true,
+                extn_value: &[0x05, 0x00],
+            }],
+            ..request
+        };
+        let der = request.to_der().unwrap();
+        assert_eq!(der[der.len() - 21..], [
+            0x06, 0x04, 0x2a, 0x03, 0x04, 0x01,
+            0xa0, 0x0d, 0x30, 0x0b, 0x06, 0x02, 0x2a, 0x03, 0x01, 0x01, 0xff, 0x04, 0x02, 0x05, 0x00,
+        ]);
+        assert_eq!(TimeStampReq::from_der(&der).unwrap(), request);
+
+        let mut bad_version = decode(REQUEST);
+        bad_version[4] = 2;
+        assert!(TimeStampReq::from_der(&bad_version).is_err());
+    }
+
+    #[test]
+    fn test_response() {
+        let der = decode(RESPONSE);
+        let response = TimeStampResp::from_der(&der).unwrap();
+        assert_eq!(response.status, PkiStatusInfo {
+            status: PkiStatus::Granted,
+            status_string: Vec::new(),
+            fail_info: None,
+        });
+        assert_eq!(response.time_stamp_token, Some(&der[9..]));
+        assert_eq!(response.to_der().unwrap(), der);
+
+        let signed_data = response.signed_data().unwrap().unwrap();
+        assert_eq!(signed_data.certificates.len(), 1);
+        let e_content = signed_data.encap_content_info.e_content.unwrap();
+        let tst_info = TstInfo::from_der(&e_content).unwrap();
+        assert_eq!(tst_info.policy.components(), [1, 2, 3, 4, 1]);
+        assert_eq!(tst_info.message_imprint, message_imprint());
+        assert_eq!(tst_info.serial_number, [0x2bu8]);
+        assert_eq!(tst_info.gen_time, DateTime::new(2026, 10, 18, 21, 32, 18).unwrap().with_nanosecond(53_000_000).unwrap());
+        assert_eq!(tst_info.accuracy, Some(Accuracy {
+            seconds: Some(1),
+            millis: Some(500),
+            micros: Some(100),
+        }));
+        assert!(tst_info.ordering);
+        assert_eq!(tst_info.nonce, Some(&NONCE[..]));
+        match tst_info.tsa {
+            Some(GeneralName::DirectoryName(ref name)) => assert_eq!(name.to_string(), "CN=Example TSA,O=Example Ltd,C=GB"),
+            ref other => panic!("unexpected tsa {:?}", other),
+        }
+        assert!(tst_info.extensions.is_empty());
+        assert_eq!(tst_info.to_der().unwrap(), &e_content[..]);
+    }
+
+    #[test]
+    fn test_rejection() {
+        let response = TimeStampResp {
+            status: PkiStatusInfo {
+                status: PkiStatus::Rejection,
+                status_string: vec!["unsupported algorithm"],
+                fail_info: Some(PkiFailureInfo(PkiFailureInfo::BAD_ALG | PkiFailureInfo::UNACCEPTED_POLICY)),
+            },
+            time_stamp_token: None,
+        };
+        let der = response.to_der().unwrap();
+        assert_eq!(&der[der.len() - 5..], [0x03, 0x03, 0x00, 0x80, 0x01]);
+        let decoded = TimeStampResp::from_der(&der).unwrap();
+        assert_eq!(decoded, response);
+        assert!(decoded.status.fail_info.unwrap().contains(PkiFailureInfo::UNACCEPTED_POLICY));
+        assert_eq!(decoded.signed_data().unwrap(), None);
+    }
+}
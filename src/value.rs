@@ -0,0 +1,364 @@
+//! A generic tree of ASN.1 values, for working with encodings whose schema is not known.
+//!
+//! `Value::from_der` follows the TLV structure of an encoding, decoding the contents of the universal types it
+//! recognizes and keeping anything else as octets, so that `to_der` reproduces the original encoding. The tree is
+//! the common form for the textual encodings in `xer`, `jer` and `notation`.
+
+use identifier::{Class, Identifier, UniversalTag};
+use integer;
+use object_identifier::ObjectIdentifier;
+use reading::{self, Asn1Reader};
+use std;
+use writing::der::{self, Writer};
+
+
+/// The deepest nesting of constructed values that `Value::read` accepts.
+pub const MAX_DEPTH: usize = 64;
+
+
+#[derive(Debug)]
+pub enum Error {
+    Read(reading::Error),
+    NestingTooDeep,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Read(ref e) => e.fmt(f),
+            Error::NestingTooDeep => write!(f, "nesting too deep"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Read(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reading::Error> for Error {
+    fn from(e: reading::Error) -> Error {
+        Error::Read(e)
+    }
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Value {
+    Boolean(bool),
+    /// The minimal two's complement octets of an INTEGER of any size.
+    Integer(Vec<u8>),
+    /// The bits packed into octets, with any unused bits of the last octet zero, and the length in bits.
+    BitString(Vec<u8>, usize),
+    OctetString(Vec<u8>),
+    Null,
+    ObjectIdentifier(ObjectIdentifier),
+    /// The minimal two's complement octets of an ENUMERATED value.
+    Enumerated(Vec<u8>),
+    /// A character string or time of the given universal type, as text.
+    String(UniversalTag, String),
+    Sequence(Vec<Value>),
+    Set(Vec<Value>),
+    /// A constructed value of any other type, such as an explicitly tagged component.
+    Constructed(Identifier, Vec<Value>),
+    /// A primitive value of any other type, or one whose contents do not decode, as its contents octets.
+    Primitive(Identifier, Vec<u8>),
+    /// A value labelled with the name of the component it is, as given by its schema. The textual encodings show
+    /// the name; DER ignores it.
+    Named(String, Box<Value>),
+}
+
+impl Value {
+    pub fn integer(v: i64) -> Self {
+        Value::Integer(integer::from_i64(v))
+    }
+
+    pub fn named<S: Into<String>>(name: S, v: Value) -> Self {
+        Value::Named(name.into(), Box::new(v))
+    }
+
+    /// The identifier the value is encoded with.
+    pub fn identifier(&self) -> Identifier {
+        match *self {
+            Value::Boolean(_) => Identifier::universal(UniversalTag::Boolean),
+            Value::Integer(_) => Identifier::universal(UniversalTag::Integer),
+            Value::BitString(..) => Identifier::universal(UniversalTag::BitString),
+            Value::OctetString(_) => Identifier::universal(UniversalTag::OctetString),
+            Value::Null => Identifier::universal(UniversalTag::Null),
+            Value::ObjectIdentifier(_) => Identifier::universal(UniversalTag::ObjectIdentifier),
+            Value::Enumerated(_) => Identifier::universal(UniversalTag::Enumerated),
+            Value::String(tag, _) => Identifier::universal(tag),
+            Value::Sequence(_) => Identifier::universal(UniversalTag::Sequence),
+            Value::Set(_) => Identifier::universal(UniversalTag::Set),
+            Value::Constructed(identifier, _) | Value::Primitive(identifier, _) => identifier,
+            Value::Named(_, ref v) => v.identifier(),
+        }
+    }
+
+    /// The value without any `Named` labels around it.
+    pub fn unnamed(&self) -> &Value {
+        match *self {
+            Value::Named(_, ref v) => v.unnamed(),
+            ref v => v,
+        }
+    }
+
+    pub fn from_der(b: &[u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = Value::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub fn read(r: &mut Asn1Reader) -> Result<Self> {
+        Value::read_nested(r, 0)
+    }
+
+    fn read_nested(r: &mut Asn1Reader, depth: usize) -> Result<Self> {
+        let (identifier, contents) = match r.next()? {
+            Some(tlv) => tlv,
+            None => return Err(Error::Read(reading::Error::UnexpectedEndOfData)),
+        };
+        if identifier.is_constructed() {
+            if depth >= MAX_DEPTH {
+                return Err(Error::NestingTooDeep);
+            }
+            let mut children = Vec::new();
+            let mut r = Asn1Reader::new(contents);
+            while !r.is_at_end() {
+                children.push(Value::read_nested(&mut r, depth + 1)?);
+            }
+            return Ok(if identifier == Identifier::universal(UniversalTag::Sequence) {
+                Value::Sequence(children)
+            } else if identifier == Identifier::universal(UniversalTag::Set) {
+                Value::Set(children)
+            } else {
+                Value::Constructed(identifier, children)
+            });
+        }
+        Ok(Value::from_primitive(identifier, contents)
+            .unwrap_or_else(|| Value::Primitive(identifier, contents.to_vec())))
+    }
+
+    fn from_primitive(identifier: Identifier, contents: &[u8]) -> Option<Self> {
+        if identifier.class() != Class::Universal || identifier.tag() > 30 {
+            return None;
+        }
+        let tag = UniversalTag::from_u64(identifier.tag())?;
+        if identifier != Identifier::universal(tag) {
+            return None;
+        }
+        let v = match tag {
+            UniversalTag::Boolean => match contents {
+                [0x00] => Value::Boolean(false),
+                [0xff] => Value::Boolean(true),
+                _ => return None,
+            },
+            UniversalTag::Integer => {
+                integer::validate(contents).ok()?;
+                Value::Integer(contents.to_vec())
+            }
+            UniversalTag::Enumerated => {
+                integer::validate(contents).ok()?;
+                Value::Enumerated(contents.to_vec())
+            }
+            UniversalTag::BitString => {
+                let (&unused, data) = contents.split_first()?;
+                if unused > 7 || (data.is_empty() && unused != 0) || data.last().is_some_and(|b| b & !(0xff << unused) != 0) {
+                    return None;
+                }
+                Value::BitString(data.to_vec(), data.len() * 8 - unused as usize)
+            }
+            UniversalTag::OctetString => Value::OctetString(contents.to_vec()),
+            UniversalTag::Null if contents.is_empty() => Value::Null,
+            UniversalTag::ObjectIdentifier => {
+                let oid = ObjectIdentifier::from_bytes(contents).ok()?;
+                if oid.to_bytes() != contents {
+                    return None;
+                }
+                Value::ObjectIdentifier(oid)
+            }
+            _ => Value::String(tag, decode_string(tag, contents)?),
+        };
+        Some(v)
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        match *self {
+            Value::Boolean(v) => w.write_boolean(v),
+            Value::Integer(ref v) | Value::Enumerated(ref v) => w.write_tag_and_data(self.identifier(), v),
+            Value::BitString(ref data, bit_len) => {
+                if bit_len > data.len() * 8 || data.len() * 8 - bit_len > 7 {
+                    return Err(der::Error::InvalidValue);
+                }
+                let unused = data.len() * 8 - bit_len;
+                let mut contents = Vec::with_capacity(data.len() + 1);
+                contents.push(unused as u8);
+                contents.extend_from_slice(data);
+                if let Some(last) = contents.last_mut().filter(|_| unused > 0) {
+                    *last &= 0xff << unused;
+                }
+                w.write_tag_and_data(self.identifier(), &contents)
+            }
+            Value::OctetString(ref v) | Value::Primitive(_, ref v) => w.write_tag_and_data(self.identifier(), v),
+            Value::Null => w.write_tag_and_data(self.identifier(), &[]),
+            Value::ObjectIdentifier(ref v) => w.write_object_identifier(v),
+            Value::String(tag, ref s) => w.write_tag_and_data(self.identifier(), &encode_string(tag, s)?),
+            Value::Sequence(ref children) | Value::Set(ref children) | Value::Constructed(_, ref children) => {
+                w.write_constructed(self.identifier(), |w| {
+                    for child in children {
+                        child.write(w)?;
+                    }
+                    Ok(())
+                })
+            }
+            Value::Named(_, ref v) => v.write(w),
+        }
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.write(&mut Writer::new(&mut buf))?;
+        Ok(buf)
+    }
+}
+
+
+/// The universal string and time types whose contents `Value` holds as text.
+pub fn is_string_type(tag: UniversalTag) -> bool {
+    matches!(tag,
+        UniversalTag::Utf8String | UniversalTag::NumericString | UniversalTag::PrintableString |
+        UniversalTag::Ia5String | UniversalTag::VisibleString | UniversalTag::GraphicString |
+        UniversalTag::ObjectDescriptor | UniversalTag::UtcTime | UniversalTag::GeneralizedTime |
+        UniversalTag::BmpString | UniversalTag::UniversalString)
+}
+
+fn decode_string(tag: UniversalTag, contents: &[u8]) -> Option<String> {
+    match tag {
+        UniversalTag::Utf8String => std::str::from_utf8(contents).ok().map(str::to_string),
+        UniversalTag::BmpString => {
+            if !contents.len().is_multiple_of(2) {
+                return None;
+            }
+            let units: Vec<u16> = contents.chunks(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+            String::from_utf16(&units).ok()
+        }
+        UniversalTag::UniversalString => {
+            if !contents.len().is_multiple_of(4) {
+                return None;
+            }
+            contents.chunks(4).map(|c| std::char::from_u32(u32::from_be_bytes([c[0], c[1], c[2], c[3]]))).collect()
+        }
+        _ if is_string_type(tag) && contents.is_ascii() => Some(String::from_utf8(contents.to_vec()).unwrap()),
+        _ => None,
+    }
+}
+
+fn encode_string(tag: UniversalTag, s: &str) -> der::Result<Vec<u8>> {
+    match tag {
+        UniversalTag::Utf8String => Ok(s.as_bytes().to_vec()),
+        UniversalTag::BmpString => Ok(s.encode_utf16().flat_map(|u| u.to_be_bytes()).collect()),
+        UniversalTag::UniversalString => Ok(s.chars().flat_map(|c| (c as u32).to_be_bytes()).collect()),
+        _ if is_string_type(tag) && s.is_ascii() => Ok(s.as_bytes().to_vec()),
+        _ => Err(der::Error::InvalidValue),
+    }
+}
+
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// A SEQUENCE exercising each kind of `Value`.
+    pub(crate) fn sample() -> Vec<u8> {
+        let mut b = vec![0x30, 0x81, 0x8f];
+        b.extend_from_slice(&[0x01, 0x01, 0xff]);
+        b.extend_from_slice(&[0x02, 0x09, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        b.extend_from_slice(&[0x02, 0x01, 0x80]);
+        b.extend_from_slice(&[0x03, 0x02, 0x05, 0xa0]);
+        b.extend_from_slice(&[0x04, 0x03, 0x01, 0x02, 0xfe]);
+        b.extend_from_slice(&[0x05, 0x00]);
+        b.extend_from_slice(&[0x06, 0x03, 0x55, 0x04, 0x03]);
+        b.extend_from_slice(&[0x0a, 0x01, 0x02]);
+        b.extend_from_slice(b"\x0c\x0a<caf\xc3\xa9 & >");
+        b.extend_from_slice(b"\x13\x03abc");
+        b.extend_from_slice(b"\x16\x03a\nb");
+        b.extend_from_slice(b"\x17\x0d250101120000Z");
+        b.extend_from_slice(b"\x18\x0f20250101120000Z");
+        b.extend_from_slice(&[0x1e, 0x04, 0x00, 0x41, 0x04, 0x10]);
+        b.extend_from_slice(&[0x1c, 0x04, 0x00, 0x01, 0xf6, 0x00]);
+        b.extend_from_slice(&[0x31, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02]);
+        b.extend_from_slice(&[0xa0, 0x05, 0x30, 0x03, 0x02, 0x01, 0x05]);
+        b.extend_from_slice(&[0x80, 0x02, 0xca, 0xfe]);
+        b.extend_from_slice(&[0x5f, 0x81, 0x00, 0x00]);
+        b.extend_from_slice(&[0x14, 0x02, 0x41, 0xc1]);
+        b.extend_from_slice(&[0x01, 0x01, 0x01]);
+        b.extend_from_slice(&[0x30, 0x00]);
+        b.extend_from_slice(&[0xe2, 0x00]);
+        b.extend_from_slice(&[0x0c, 0x00]);
+        b.extend_from_slice(&[0x09, 0x03, 0x80, 0xfb, 0x05]);
+        assert_eq!(b.len(), 0x8f + 3);
+        b
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let b = sample();
+        let v = Value::from_der(&b).unwrap();
+        let children = match v {
+            Value::Sequence(ref children) => children,
+            ref v => panic!("unexpected value {:?}", v),
+        };
+        assert_eq!(children[0], Value::Boolean(true));
+        assert_eq!(children[2], Value::integer(-128));
+        assert_eq!(children[3], Value::BitString(vec![0xa0], 3));
+        assert_eq!(children[8], Value::String(UniversalTag::Utf8String, "<café & >".to_string()));
+        assert_eq!(children[13], Value::String(UniversalTag::BmpString, "A\u{410}".to_string()));
+        assert_eq!(children[14], Value::String(UniversalTag::UniversalString, "\u{1f600}".to_string()));
+        assert_eq!(children[16], Value::Constructed(Identifier::context_specific(true, 0),
+            vec![Value::Sequence(vec![Value::integer(5)])]));
+        assert_eq!(children[17], Value::Primitive(Identifier::context_specific(false, 0), vec![0xca, 0xfe]));
+        assert_eq!(children[18], Value::Primitive(Identifier::new(Class::Application, false, 128), vec![]));
+        // T61String contents are kept as octets, as is a BER boolean that is not DER.
+        assert_eq!(children[19], Value::Primitive(Identifier::universal(UniversalTag::T61String), vec![0x41, 0xc1]));
+        assert_eq!(children[20], Value::Primitive(Identifier::universal(UniversalTag::Boolean), vec![0x01]));
+        assert_eq!(v.to_der().unwrap(), b);
+
+        let named = Value::Sequence(vec![Value::named("version", Value::integer(1))]);
+        assert_eq!(named.to_der().unwrap(), [0x30, 0x03, 0x02, 0x01, 0x01]);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Value::from_der(&[0x30, 0x03, 0x02, 0x01]).is_err());
+        assert!(Value::from_der(&[0x05, 0x00, 0x05, 0x00]).is_err());
+        let mut deep = vec![0x05, 0x00];
+        for _ in 0..100 {
+            let mut outer = vec![0x30, 0x82, (deep.len() >> 8) as u8, deep.len() as u8];
+            outer.extend_from_slice(&deep);
+            deep = outer;
+        }
+        match Value::from_der(&deep) {
+            Err(Error::NestingTooDeep) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+        let mut deep = Vec::new();
+        for _ in 0..100 {
+            deep.extend_from_slice(&[0x30, 0x80]);
+        }
+        deep.extend_from_slice(&[0x00; 200]);
+        match Value::from_der(&deep) {
+            Err(Error::Read(reading::Error::NestingTooDeep)) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+        assert!(Value::String(UniversalTag::PrintableString, "é".to_string()).to_der().is_err());
+        assert!(Value::BitString(vec![0xff], 9).to_der().is_err());
+    }
+}
@@ -1,27 +1,45 @@
+use bit_string::BitString;
+use enumerated::Enumerated;
 use identifier::{Identifier, Class, UniversalTag};
+use integer;
+use object_identifier::ObjectIdentifier;
+use reading::Asn1Reader;
+use real;
 use std;
+use time::DateTime;
 // use core::array::FixedSizeArray;
 
 
 #[derive(Debug)]
 pub enum Error {
     // InsufficientSpace,
+    InvalidValue,
     Io(std::io::Error),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
+        match *self {
             // Error::InsufficientSpace => write!(f, "Insufficient")
-            &Error::Io(ref e) => e.fmt(f)
+            Error::InvalidValue => write!(f, "invalid value"),
+            Error::Io(ref e) => e.fmt(f)
         }
     }
 }
 
 impl std::error::Error for Error {
+    #[allow(deprecated)]
     fn description(&self) -> &str {
-        match self {
-            &Error::Io(ref e) => e.description()
+        match *self {
+            Error::InvalidValue => "invalid value",
+            Error::Io(ref e) => e.description()
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::InvalidValue => None,
+            Error::Io(ref e) => Some(e),
         }
     }
 }
@@ -44,36 +62,35 @@ pub struct Writer<'w, W: std::io::Write + 'w> {
 impl<'w, W: std::io::Write> Writer<'w, W> {
     pub fn new(w: &'w mut W) -> Self {
         Writer {
-            w: w,
+            w,
             // pos: 0,
         }
     }
 
+    #[allow(deprecated)]
     pub fn write_tag_and_data(&mut self, tag: Identifier, data: &[u8]) -> Result<usize> {
-        let len = data.len();
-
-        let len: Vec<u8> = if len < 128 {
-            let mut len_buf = Vec::with_capacity(1);
-            len_buf.push(len as u8);
-            len_buf
-        } else {
-            unimplemented!();
-        };
+        let len = length_bytes(data.len());
 
         let tag_bytes = tag.to_bytes();
-        try!(self.w.write(&tag_bytes));
-        try!(self.w.write(&len));
-        try!(self.w.write(data));
+        try!(self.w.write_all(&tag_bytes));
+        try!(self.w.write_all(&len));
+        try!(self.w.write_all(data));
         Ok(tag_bytes.len() + len.len() + data.len())
     }
 
+    /// Writes a constructed value with the indefinite length form, which X.690 8.1.3.2 does not allow for primitive
+    /// values; a primitive `tag` fails with `InvalidValue`.
+    #[allow(deprecated)]
     pub fn write_indefinite_tag<F>(&mut self, tag: Identifier, f: F) -> Result<usize>
         where
             F: FnOnce(&mut IndeterminateLengthContentWriter<W>) -> Result<()>,
     {
+        if !tag.is_constructed() {
+            return Err(Error::InvalidValue);
+        }
         let tag_bytes = tag.to_bytes();
-        let tag_size = try!(self.w.write(&tag_bytes));
-        let indefinite_len_size = try!(self.w.write(&[0x80u8]));
+        try!(self.w.write_all(&tag_bytes));
+        try!(self.w.write_all(&[0x80u8]));
 
         let x = |s: &mut Self| -> Result<usize> {
             let mut ilcw = IndeterminateLengthContentWriter {
@@ -83,10 +100,7 @@ impl<'w, W: std::io::Write> Writer<'w, W> {
             try!(f(&mut ilcw));
             Ok(ilcw.bytes_written)
         };
-        let content_len = match x(self) {
-            Ok(len) => len,
-            Err(e) => return Err(e.into()),
-        };
+        let content_len = try!(x(self));
 
         // let content_len = {
         //     let mut ilcw = IndeterminateLengthContentWriter {
@@ -97,14 +111,223 @@ impl<'w, W: std::io::Write> Writer<'w, W> {
         //     ilcw.bytes_written
         // };
 
-        let eoc_size = try!(self.w.write(&[0u8, 0]));
-        Ok(tag_size + indefinite_len_size + content_len + eoc_size)
+        try!(self.w.write_all(&[0u8, 0]));
+        Ok(tag_bytes.len() + 1 + content_len + 2)
+    }
+
+    /// Writes a definite-length constructed value whose contents are produced by `f`.
+    ///
+    /// The contents are buffered so that their length can be written first.
+    pub fn write_constructed<F>(&mut self, tag: Identifier, f: F) -> Result<usize>
+        where
+            F: FnOnce(&mut Writer<Vec<u8>>) -> Result<()>,
+    {
+        let mut contents = Vec::new();
+        f(&mut Writer::new(&mut contents))?;
+        self.write_tag_and_data(tag, &contents)
+    }
+
+    pub fn write_sequence<F>(&mut self, f: F) -> Result<usize>
+        where
+            F: FnOnce(&mut Writer<Vec<u8>>) -> Result<()>,
+    {
+        self.write_constructed(Identifier::universal(UniversalTag::Sequence), f)
+    }
+
+    /// Writes a SET OF whose elements are produced by `f`, sorting their encodings as DER requires.
+    pub fn write_set_of<F>(&mut self, f: F) -> Result<usize>
+        where
+            F: FnOnce(&mut Writer<Vec<u8>>) -> Result<()>,
+    {
+        self.write_sorted(Identifier::universal(UniversalTag::Set), f)
+    }
+
+    /// Writes `[tag] IMPLICIT SET OF`, sorting the element encodings as `write_set_of` does.
+    pub fn write_implicit_set_of<F>(&mut self, tag: u64, f: F) -> Result<usize>
+        where
+            F: FnOnce(&mut Writer<Vec<u8>>) -> Result<()>,
+    {
+        self.write_sorted(Identifier::context_specific(true, tag), f)
+    }
+
+    fn write_sorted<F>(&mut self, tag: Identifier, f: F) -> Result<usize>
+        where
+            F: FnOnce(&mut Writer<Vec<u8>>) -> Result<()>,
+    {
+        let mut contents = Vec::new();
+        f(&mut Writer::new(&mut contents))?;
+
+        let mut elements = Vec::new();
+        let mut r = Asn1Reader::new(&contents);
+        while !r.is_at_end() {
+            elements.push(r.read_raw().map_err(|_| Error::InvalidValue)?);
+        }
+        elements.sort();
+        self.write_tag_and_data(tag, &elements.concat())
+    }
+
+    /// Writes `[tag] EXPLICIT`, a constructed context-specific value wrapping whatever `f` writes.
+    pub fn write_explicit<F>(&mut self, tag: u64, f: F) -> Result<usize>
+        where
+            F: FnOnce(&mut Writer<Vec<u8>>) -> Result<()>,
+    {
+        self.write_constructed(Identifier::context_specific(true, tag), f)
+    }
+
+    /// Writes bytes that are already a complete encoding.
+    pub fn write_raw(&mut self, encoded: &[u8]) -> Result<usize> {
+        self.w.write_all(encoded)?;
+        Ok(encoded.len())
     }
 
     pub fn write_boolean(&mut self, v: bool) -> Result<usize> {
         let v: u8 = if v { 0xff } else { 0 };
         self.write_tag_and_data(Identifier::new(Class::Universal, false, UniversalTag::Boolean.into()), &[v])
     }
+
+    pub fn write_null(&mut self) -> Result<usize> {
+        self.write_tag_and_data(Identifier::universal(UniversalTag::Null), &[])
+    }
+
+    /// Writes an INTEGER from its two's complement contents octets, which must be minimally encoded.
+    pub fn write_integer_bytes(&mut self, v: &[u8]) -> Result<usize> {
+        integer::validate(v).map_err(|_| Error::InvalidValue)?;
+        self.write_tag_and_data(Identifier::universal(UniversalTag::Integer), v)
+    }
+
+    pub fn write_i64(&mut self, v: i64) -> Result<usize> {
+        self.write_tag_and_data(Identifier::universal(UniversalTag::Integer), &integer::from_i64(v))
+    }
+
+    pub fn write_u64(&mut self, v: u64) -> Result<usize> {
+        self.write_tag_and_data(Identifier::universal(UniversalTag::Integer), &integer::from_u64(v))
+    }
+
+    /// Writes a REAL in the canonical base 2 form.
+    pub fn write_real(&mut self, v: f64) -> Result<usize> {
+        self.write_tag_and_data(Identifier::universal(UniversalTag::Real), &real::from_f64(v))
+    }
+
+    /// Writes a non-negative INTEGER from a big-endian magnitude, such as an RSA modulus.
+    pub fn write_unsigned_integer(&mut self, magnitude: &[u8]) -> Result<usize> {
+        self.write_tag_and_data(Identifier::universal(UniversalTag::Integer), &integer::from_unsigned_bytes(magnitude))
+    }
+
+    /// Writes an ENUMERATED value, whose contents are encoded as for INTEGER.
+    pub fn write_enumerated<E: Enumerated>(&mut self, v: E) -> Result<usize> {
+        self.write_tag_and_data(Identifier::universal(UniversalTag::Enumerated), &integer::from_i64(v.into_i64()))
+    }
+
+    /// Writes an OBJECT IDENTIFIER, failing with `InvalidValue` if its arcs cannot be encoded.
+    pub fn write_object_identifier(&mut self, v: &ObjectIdentifier) -> Result<usize> {
+        self.write_tagged_object_identifier(Identifier::universal(UniversalTag::ObjectIdentifier), v)
+    }
+
+    /// Writes `[tag] IMPLICIT OBJECT IDENTIFIER`.
+    pub fn write_implicit_object_identifier(&mut self, tag: u64, v: &ObjectIdentifier) -> Result<usize> {
+        self.write_tagged_object_identifier(Identifier::context_specific(false, tag), v)
+    }
+
+    fn write_tagged_object_identifier(&mut self, tag: Identifier, v: &ObjectIdentifier) -> Result<usize> {
+        if !v.is_valid() {
+            return Err(Error::InvalidValue);
+        }
+        self.write_tag_and_data(tag, &v.to_bytes())
+    }
+
+    pub fn write_bit_string(&mut self, v: BitString) -> Result<usize> {
+        let mut contents = Vec::with_capacity(v.bytes_len());
+        v.write_to(&mut contents)?;
+        self.write_tag_and_data(Identifier::universal(UniversalTag::BitString), &contents)
+    }
+
+    pub fn write_octet_string(&mut self, v: &[u8]) -> Result<usize> {
+        self.write_tag_and_data(Identifier::universal(UniversalTag::OctetString), v)
+    }
+
+    /// Writes an OCTET STRING holding the encoding that `f` writes, such as an X.509 extension value, without
+    /// buffering it.
+    ///
+    /// `f` is called twice: first to count the length of the encoding, then to write it after the OCTET STRING
+    /// header. It must write the same bytes both times; if the second call writes a different number of bytes, or
+    /// fails, the output is left incomplete and an error is returned.
+    #[allow(deprecated)]
+    pub fn write_encapsulated<F>(&mut self, f: F) -> Result<usize>
+        where
+            F: Fn(&mut Writer<&mut dyn std::io::Write>) -> Result<()>,
+    {
+        let mut counter = ByteCounter {
+            w: std::io::sink(),
+            count: 0,
+        };
+        try!(f(&mut Writer::new(&mut (&mut counter as &mut dyn std::io::Write))));
+        let len = counter.count;
+
+        let tag_bytes = Identifier::universal(UniversalTag::OctetString).to_bytes();
+        let len_bytes = length_bytes(len);
+        try!(self.w.write_all(&tag_bytes));
+        try!(self.w.write_all(&len_bytes));
+        let mut counter = ByteCounter {
+            w: &mut *self.w,
+            count: 0,
+        };
+        try!(f(&mut Writer::new(&mut (&mut counter as &mut dyn std::io::Write))));
+        if counter.count != len {
+            return Err(Error::InvalidValue);
+        }
+        Ok(tag_bytes.len() + len_bytes.len() + len)
+    }
+
+    pub fn write_utf8_string(&mut self, v: &str) -> Result<usize> {
+        self.write_tag_and_data(Identifier::universal(UniversalTag::Utf8String), v.as_bytes())
+    }
+
+    pub fn write_utc_time(&mut self, v: DateTime) -> Result<usize> {
+        let contents = v.to_utc_time_bytes().map_err(|_| Error::InvalidValue)?;
+        self.write_tag_and_data(Identifier::universal(UniversalTag::UtcTime), &contents)
+    }
+
+    pub fn write_generalized_time(&mut self, v: DateTime) -> Result<usize> {
+        self.write_tag_and_data(Identifier::universal(UniversalTag::GeneralizedTime), &v.to_generalized_time_bytes())
+    }
+
+    /// Writes the X.509 `Time` CHOICE: UTCTime for years up to 2049 and GeneralizedTime after, per RFC 5280.
+    pub fn write_time(&mut self, v: DateTime) -> Result<usize> {
+        if v.year() >= 1950 && v.year() <= 2049 && v.nanosecond() == 0 {
+            return self.write_utc_time(v);
+        }
+        self.write_generalized_time(v)
+    }
+}
+
+fn length_bytes(len: usize) -> Vec<u8> {
+    if len < 128 {
+        return vec![len as u8];
+    }
+    let be = len.to_be_bytes();
+    let skip = be.iter().take_while(|&&b| b == 0).count();
+    let mut v = Vec::with_capacity(1 + be.len() - skip);
+    v.push(0x80 | (be.len() - skip) as u8);
+    v.extend_from_slice(&be[skip..]);
+    v
+}
+
+/// Counts the bytes written through it, for `Writer::write_encapsulated`.
+struct ByteCounter<W: std::io::Write> {
+    w: W,
+    count: usize,
+}
+
+impl<W: std::io::Write> std::io::Write for ByteCounter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.w.write(buf)?;
+        self.count += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.w.flush()
+    }
 }
 
 pub struct IndeterminateLengthContentWriter<'w, W: std::io::Write + 'w> {
@@ -114,10 +337,9 @@ pub struct IndeterminateLengthContentWriter<'w, W: std::io::Write + 'w> {
 
 impl<'w, W: std::io::Write + 'w> IndeterminateLengthContentWriter<'w, W> {
     pub fn write(&mut self, buf: &[u8]) -> Result<()> {
-        match self.w.write(buf) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
-        }
+        self.w.write_all(buf)?;
+        self.bytes_written += buf.len();
+        Ok(())
     }
 }
 
@@ -135,6 +357,8 @@ impl<'w, W: std::io::Write + 'w> IndeterminateLengthContentWriter<'w, W> {
 #[cfg(test)]
 mod test {
     use identifier::{Identifier, Class, UniversalTag};
+    use object_identifier::ObjectIdentifier;
+    use time::DateTime;
     use super::*;
 
     #[test]
@@ -157,17 +381,174 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_long_form_length() {
+        {
+            let data = [0xaau8; 200];
+
+            let mut output = Vec::new();
+            {
+                let mut w = Writer::new(&mut output);
+                assert_eq!(w.write_octet_string(&data).unwrap(), 203);
+            }
+            assert_eq!(output[..3], [0x04u8, 0x81, 200]);
+            assert_eq!(output[3..], data[..]);
+        }
+        {
+            assert_eq!(length_bytes(0x7f), [0x7fu8]);
+            assert_eq!(length_bytes(0x80), [0x81u8, 0x80]);
+            assert_eq!(length_bytes(0x1234), [0x82u8, 0x12, 0x34]);
+        }
+    }
+
+    #[test]
+    fn test_write_nested() {
+        {
+            let mut output = Vec::new();
+            {
+                let mut w = Writer::new(&mut output);
+                w.write_sequence(|w| {
+                    w.write_i64(-129)?;
+                    w.write_explicit(0, |w| {
+                        w.write_boolean(true)?;
+                        Ok(())
+                    })?;
+                    w.write_object_identifier(&ObjectIdentifier::from_components(&[2, 5, 29, 19]))?;
+                    w.write_utc_time(DateTime::new(2019, 1, 2, 3, 4, 5).unwrap())?;
+                    Ok(())
+                }).unwrap();
+            }
+            assert_eq!(output, [
+                0x30u8, 0x1d,
+                0x02, 0x02, 0xff, 0x7f,
+                0xa0, 0x03, 0x01, 0x01, 0xff,
+                0x06, 0x03, 0x55, 0x1d, 0x13,
+                0x17, 0x0d, b'1', b'9', b'0', b'1', b'0', b'2', b'0', b'3', b'0', b'4', b'0', b'5', b'Z',
+            ][..]);
+        }
+    }
+
+    #[test]
+    fn test_write_set_of_sorts() {
+        {
+            let mut output = Vec::new();
+            {
+                let mut w = Writer::new(&mut output);
+                w.write_set_of(|w| {
+                    w.write_octet_string(&[0x02])?;
+                    w.write_boolean(false)?;
+                    w.write_octet_string(&[0x01])?;
+                    Ok(())
+                }).unwrap();
+            }
+            assert_eq!(output, [0x31u8, 0x09, 0x01, 0x01, 0x00, 0x04, 0x01, 0x01, 0x04, 0x01, 0x02]);
+        }
+    }
+
+    #[test]
+    fn test_write_time_choice() {
+        {
+            let mut output = Vec::new();
+            {
+                let mut w = Writer::new(&mut output);
+                w.write_time(DateTime::new(2050, 1, 1, 0, 0, 0).unwrap()).unwrap();
+            }
+            assert_eq!(output[..2], [0x18u8, 0x0f]);
+        }
+    }
+
     #[test]
     fn test_write_indefinite_length() {
         {
             let mut output = Vec::with_capacity(4);
             {
                 let mut w = Writer::new(&mut output);
-                w.write_indefinite_tag(Identifier::new(Class::Universal, false, UniversalTag::Boolean.into()), |w| {
+                assert!(w.write_indefinite_tag(Identifier::new(Class::Universal, false, UniversalTag::Boolean.into()), |w| {
                     w.write(&[0xffu8])
+                }).is_err());
+            }
+            assert!(output.is_empty());
+        }
+        {
+            let mut output = Vec::with_capacity(7);
+            {
+                let mut w = Writer::new(&mut output);
+                w.write_indefinite_tag(Identifier::new(Class::Universal, true, UniversalTag::Sequence.into()), |w| {
+                    w.write(&[0x01u8, 0x01, 0xff])
                 }).unwrap();
             }
-            assert_eq!(output, [0x01u8, 0x80, 0xff, 0, 0]);
+            assert_eq!(output, [0x30u8, 0x80, 0x01, 0x01, 0xff, 0, 0]);
+        }
+    }
+
+    #[test]
+    fn test_write_object_identifier() {
+        let mut output = Vec::new();
+        {
+            let mut w = Writer::new(&mut output);
+            w.write_object_identifier(&ObjectIdentifier::from_components(&[2, 999, 1])).unwrap();
+            w.write_implicit_object_identifier(8, &ObjectIdentifier::from_components(&[1, 2])).unwrap();
+            assert!(w.write_object_identifier(&ObjectIdentifier::from_components(&[1])).is_err());
+            assert!(w.write_object_identifier(&ObjectIdentifier::from_components(&[0, 40])).is_err());
+            assert!(w.write_implicit_object_identifier(8, &ObjectIdentifier::from_components(&[3, 1])).is_err());
+        }
+        assert_eq!(output, [0x06u8, 0x03, 0x88, 0x37, 0x01, 0x88, 0x01, 0x2a]);
+    }
+
+    #[test]
+    fn test_write_encapsulated() {
+        {
+            let mut output = Vec::new();
+            {
+                let mut w = Writer::new(&mut output);
+                let len = w.write_encapsulated(|w| {
+                    w.write_sequence(|w| {
+                        w.write_boolean(true)?;
+                        Ok(())
+                    })?;
+                    Ok(())
+                }).unwrap();
+                assert_eq!(len, 7);
+            }
+            assert_eq!(output, [0x04u8, 0x05, 0x30, 0x03, 0x01, 0x01, 0xff]);
+        }
+        {
+            let data = [0xaau8; 200];
+
+            let mut output = Vec::new();
+            {
+                let mut w = Writer::new(&mut output);
+                assert_eq!(w.write_encapsulated(|w| {
+                    w.write_octet_string(&data)?;
+                    Ok(())
+                }).unwrap(), 206);
+            }
+            assert_eq!(output[..7], [0x04u8, 0x81, 203, 0x04, 0x81, 200, 0xaa]);
+        }
+        {
+            let mut output = Vec::new();
+            {
+                let mut w = Writer::new(&mut output);
+                let result = w.write_encapsulated(|w| {
+                    w.write_boolean(true)?;
+                    Err(Error::InvalidValue)
+                });
+                assert!(result.is_err());
+            }
+            assert!(output.is_empty());
+        }
+        {
+            let calls = std::cell::Cell::new(0);
+
+            let mut output = Vec::new();
+            let mut w = Writer::new(&mut output);
+            let result = w.write_encapsulated(|w| {
+                calls.set(calls.get() + 1);
+                w.write_u64(calls.get() * 100)?;
+                Ok(())
+            });
+            assert!(result.is_err());
+            assert_eq!(calls.get(), 2);
         }
     }
 }
@@ -0,0 +1,265 @@
+use bit_string::BitString;
+use object_identifier::ObjectIdentifier;
+use std;
+use super::{oid, AlgorithmIdentifier, Attribute, Extension, Name, SubjectPublicKeyInfo, Validity, Version};
+use writing::der::{self, Writer};
+
+
+#[derive(Debug)]
+pub enum SignError<E> {
+    Encode(der::Error),
+    Signer(E),
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for SignError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            SignError::Encode(ref e) => e.fmt(f),
+            SignError::Signer(ref e) => write!(f, "signer failed: {}", e),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for SignError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            SignError::Encode(ref e) => Some(e),
+            SignError::Signer(ref e) => Some(e),
+        }
+    }
+}
+
+impl<E> From<der::Error> for SignError<E> {
+    fn from(e: der::Error) -> SignError<E> {
+        SignError::Encode(e)
+    }
+}
+
+
+/// Writes `SEQUENCE { tbs, signatureAlgorithm, signature BIT STRING }`, the shape shared by certificates,
+/// certification requests and CRLs.
+fn sign_structure<F, E>(tbs: &[u8], signature_algorithm: &AlgorithmIdentifier, signer: F) -> std::result::Result<Vec<u8>, SignError<E>>
+    where
+        F: FnOnce(&[u8]) -> std::result::Result<Vec<u8>, E>,
+{
+    let signature = signer(tbs).map_err(SignError::Signer)?;
+    let mut output = Vec::with_capacity(tbs.len() + signature.len() + 32);
+    Writer::new(&mut output).write_sequence(|w| {
+        w.write_raw(tbs)?;
+        signature_algorithm.write(w)?;
+        w.write_bit_string(BitString::from_octets(&signature))?;
+        Ok(())
+    })?;
+    Ok(output)
+}
+
+
+/// The fields of a TBSCertificate, from which a signed Certificate is produced.
+///
+/// The signature itself is delegated to a caller-supplied callback, which is handed the DER encoding of the
+/// TBSCertificate and returns the raw signature value.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct CertificateBuilder<'a> {
+    pub version: Version,
+    /// The two's complement contents of the serialNumber INTEGER.
+    pub serial_number: &'a [u8],
+    pub signature_algorithm: AlgorithmIdentifier<'a>,
+    pub issuer: Name<'a>,
+    pub validity: Validity,
+    pub subject: Name<'a>,
+    pub subject_public_key_info: SubjectPublicKeyInfo<'a>,
+    pub extensions: Vec<Extension<'a>>,
+}
+
+impl<'a> CertificateBuilder<'a> {
+    pub fn write_tbs_certificate<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        if !self.extensions.is_empty() && self.version != Version::V3 {
+            return Err(der::Error::InvalidValue);
+        }
+        w.write_sequence(|w| {
+            if self.version != Version::V1 {
+                w.write_explicit(0, |w| {
+                    w.write_i64(self.version.into_i64())?;
+                    Ok(())
+                })?;
+            }
+            w.write_integer_bytes(self.serial_number)?;
+            self.signature_algorithm.write(w)?;
+            self.issuer.write(w)?;
+            self.validity.write(w)?;
+            self.subject.write(w)?;
+            self.subject_public_key_info.write(w)?;
+            if !self.extensions.is_empty() {
+                w.write_explicit(3, |w| {
+                    Extension::write_list(w, &self.extensions)?;
+                    Ok(())
+                })?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn tbs_certificate_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write_tbs_certificate(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+
+    /// Encodes the TBSCertificate, passes it to `signer` and returns the DER encoding of the signed Certificate.
+    pub fn sign<F, E>(&self, signer: F) -> std::result::Result<Vec<u8>, SignError<E>>
+        where
+            F: FnOnce(&[u8]) -> std::result::Result<Vec<u8>, E>,
+    {
+        let tbs = self.tbs_certificate_der()?;
+        sign_structure(&tbs, &self.signature_algorithm, signer)
+    }
+}
+
+
+/// The fields of a PKCS#10 CertificationRequestInfo, from which a signed CertificationRequest is produced.
+///
+/// Any `extensions` are carried in a PKCS#9 extensionRequest attribute, written after the other `attributes`.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct CertificationRequestBuilder<'a> {
+    pub subject: Name<'a>,
+    pub subject_public_key_info: SubjectPublicKeyInfo<'a>,
+    pub attributes: Vec<Attribute<'a>>,
+    pub extensions: Vec<Extension<'a>>,
+    pub signature_algorithm: AlgorithmIdentifier<'a>,
+}
+
+impl<'a> CertificationRequestBuilder<'a> {
+    pub fn write_certification_request_info<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        let mut extension_request = Vec::new();
+        if !self.extensions.is_empty() {
+            Extension::write_list(&mut Writer::new(&mut extension_request), &self.extensions)?;
+        }
+        w.write_sequence(|w| {
+            w.write_i64(0)?;
+            self.subject.write(w)?;
+            self.subject_public_key_info.write(w)?;
+            w.write_implicit_set_of(0, |w| {
+                for attribute in &self.attributes {
+                    attribute.write(w)?;
+                }
+                if !extension_request.is_empty() {
+                    Attribute {
+                        attr_type: ObjectIdentifier::from_components(oid::PKCS9_EXTENSION_REQUEST),
+                        values: vec![&extension_request],
+                    }.write(w)?;
+                }
+                Ok(())
+            })?;
+            Ok(())
+        })
+    }
+
+    pub fn certification_request_info_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write_certification_request_info(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+
+    /// Encodes the CertificationRequestInfo, passes it to `signer` and returns the DER encoding of the signed
+    /// CertificationRequest.
+    pub fn sign<F, E>(&self, signer: F) -> std::result::Result<Vec<u8>, SignError<E>>
+        where
+            F: FnOnce(&[u8]) -> std::result::Result<Vec<u8>, E>,
+    {
+        let info = self.certification_request_info_der()?;
+        sign_structure(&info, &self.signature_algorithm, signer)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pem;
+    use time::DateTime;
+    use x509::{Certificate, TbsCertificate};
+    use x509::test::ca_der;
+
+    const CA_CSR_PEM: &str = "\
+-----BEGIN CERTIFICATE REQUEST-----
+MIIBSDCB7wIBADA4MQswCQYDVQQGEwJHQjEUMBIGA1UECgwLRXhhbXBsZSBMdGQx
+EzARBgNVBAMMCkV4YW1wbGUgQ0EwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAATe
+Y1gjj/EOJAIOKP4F2BQUAGwnyq+XSaFUTSw/hIo8YJVWznPMxuA3IVOAvaQ7CzwO
+1ps3Pio3q4zxT2b1HVx7oFUwUwYJKoZIhvcNAQkOMUYwRDBCBgNVHREEOzA5ggtl
+eGFtcGxlLmNvbYcEfwAAAYEOY2FAZXhhbXBsZS5jb22GFGh0dHBzOi8vZXhhbXBs
+ZS5jb20vMAoGCCqGSM49BAMCA0gAMEUCIQC8r2ZIaEzbB0OiNwkQSV2ueavCwnrY
+pcZvvRdnH6AJNQIgYX4fuIWzP8FLVyZq8veWyIQA6nCq0XjVhtfetjEscyk=
+-----END CERTIFICATE REQUEST-----
+";
+
+    fn builder_from<'a>(tbs: &TbsCertificate<'a>) -> CertificateBuilder<'a> {
+        CertificateBuilder {
+            version: tbs.version,
+            serial_number: tbs.serial_number,
+            signature_algorithm: tbs.signature.clone(),
+            issuer: tbs.issuer.clone(),
+            validity: tbs.validity,
+            subject: tbs.subject.clone(),
+            subject_public_key_info: tbs.subject_public_key_info.clone(),
+            extensions: tbs.extensions.clone(),
+        }
+    }
+
+    #[test]
+    fn test_certificate_roundtrip() {
+        let der = ca_der();
+        let cert = Certificate::from_der(&der).unwrap();
+
+        let builder = builder_from(&cert.tbs_certificate);
+        assert_eq!(builder.tbs_certificate_der().unwrap(), cert.tbs_certificate.raw);
+
+        let signature = cert.signature_value.as_octets().unwrap();
+        let signed = builder.sign(|tbs| -> Result<Vec<u8>, ()> {
+            assert_eq!(tbs, cert.signed_data());
+            Ok(signature.to_vec())
+        }).unwrap();
+        assert_eq!(signed, der);
+    }
+
+    #[test]
+    fn test_certificate_v1_and_signer_error() {
+        let der = ca_der();
+        let cert = Certificate::from_der(&der).unwrap();
+
+        let mut builder = builder_from(&cert.tbs_certificate);
+        builder.version = Version::V1;
+        assert!(builder.tbs_certificate_der().is_err());
+
+        builder.extensions.clear();
+        builder.validity.not_after = DateTime::new(2050, 1, 1, 0, 0, 0).unwrap();
+        let signed = builder.sign(|_| -> Result<Vec<u8>, ()> { Ok(vec![0x30, 0x00]) }).unwrap();
+        let reparsed = Certificate::from_der(&signed).unwrap();
+        assert_eq!(reparsed.tbs_certificate.version, Version::V1);
+        assert_eq!(reparsed.tbs_certificate.validity, builder.validity);
+        assert_eq!(reparsed.signature_value.as_octets().unwrap(), [0x30u8, 0x00]);
+
+        match builder.sign(|_| Err("no key")).unwrap_err() {
+            SignError::Signer(e) => assert_eq!(e, "no key"),
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_certification_request() {
+        let der = ca_der();
+        let cert = Certificate::from_der(&der).unwrap();
+        let tbs = &cert.tbs_certificate;
+        let expected = pem::parse(CA_CSR_PEM, pem::Mode::Strict).unwrap().into_contents();
+
+        let builder = CertificationRequestBuilder {
+            subject: tbs.subject.clone(),
+            subject_public_key_info: tbs.subject_public_key_info.clone(),
+            attributes: Vec::new(),
+            extensions: vec![tbs.extension(oid::SUBJECT_ALT_NAME).unwrap().clone()],
+            signature_algorithm: cert.signature_algorithm.clone(),
+        };
+        let signature = &expected[expected.len() - 71..];
+        let signed = builder.sign(|_| -> Result<Vec<u8>, ()> { Ok(signature.to_vec()) }).unwrap();
+        assert_eq!(signed, expected);
+    }
+}
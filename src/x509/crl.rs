@@ -0,0 +1,330 @@
+use bit_string::BitString;
+use identifier::{Identifier, UniversalTag};
+use reading::Asn1Reader;
+use time::DateTime;
+
+use super::{extensions, oid, AlgorithmIdentifier, Error, Extension, Name, Result, Version};
+
+
+/// A single entry of the revokedCertificates list.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct RevokedCertificate<'a> {
+    /// The validated contents of the userCertificate serial number INTEGER.
+    pub user_certificate: &'a [u8],
+    pub revocation_date: DateTime,
+    pub crl_entry_extensions: Vec<Extension<'a>>,
+}
+
+impl<'a> RevokedCertificate<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = RevokedCertificate::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let user_certificate = r.read_integer_bytes()?;
+        let revocation_date = r.read_time()?;
+        let crl_entry_extensions = if r.is_at_end() { Vec::new() } else { Extension::read_list(&mut r)? };
+        r.expect_end()?;
+        Ok(RevokedCertificate {
+            user_certificate,
+            revocation_date,
+            crl_entry_extensions,
+        })
+    }
+
+    pub fn extension(&self, extn_id: &[u64]) -> Option<&Extension<'a>> {
+        self.crl_entry_extensions.iter().find(|e| e.extn_id.components() == extn_id)
+    }
+
+    pub fn reason_code(&self) -> Result<Option<extensions::CrlReason>> {
+        self.extension(oid::REASON_CODE).map(|e| extensions::CrlReason::from_der(e.extn_value)).transpose()
+    }
+
+    pub fn invalidity_date(&self) -> Result<Option<DateTime>> {
+        self.extension(oid::INVALIDITY_DATE).map(|e| extensions::invalidity_date(e.extn_value)).transpose()
+    }
+}
+
+
+/// The revokedCertificates list, decoded one entry at a time so that large CRLs can be scanned without
+/// materializing every entry.
+///
+/// Entries are only validated as they are iterated.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct RevokedCertificates<'a> {
+    contents: &'a [u8],
+}
+
+impl<'a> RevokedCertificates<'a> {
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
+    }
+
+    pub fn iter(&self) -> RevokedCertificatesIter<'a> {
+        RevokedCertificatesIter {
+            r: Asn1Reader::new(self.contents),
+            failed: false,
+        }
+    }
+
+    /// Finds the entry for the given serial number, given as the contents of its INTEGER.
+    pub fn find(&self, serial_number: &[u8]) -> Result<Option<RevokedCertificate<'a>>> {
+        for entry in self.iter() {
+            let entry = entry?;
+            if entry.user_certificate == serial_number {
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'a> IntoIterator for RevokedCertificates<'a> {
+    type Item = Result<RevokedCertificate<'a>>;
+    type IntoIter = RevokedCertificatesIter<'a>;
+
+    fn into_iter(self) -> RevokedCertificatesIter<'a> {
+        self.iter()
+    }
+}
+
+
+/// Iterator over the entries of a CRL. After yielding an error, the iterator is exhausted.
+#[derive(Clone)]
+pub struct RevokedCertificatesIter<'a> {
+    r: Asn1Reader<'a>,
+    failed: bool,
+}
+
+impl<'a> Iterator for RevokedCertificatesIter<'a> {
+    type Item = Result<RevokedCertificate<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.r.is_at_end() {
+            return None;
+        }
+        let v = RevokedCertificate::read(&mut self.r);
+        self.failed = v.is_err();
+        Some(v)
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct TbsCertList<'a> {
+    /// The complete DER encoding of the TBSCertList, over which the signature is computed.
+    pub raw: &'a [u8],
+    pub version: Version,
+    pub signature: AlgorithmIdentifier<'a>,
+    pub issuer: Name<'a>,
+    pub this_update: DateTime,
+    pub next_update: Option<DateTime>,
+    pub revoked_certificates: RevokedCertificates<'a>,
+    pub crl_extensions: Vec<Extension<'a>>,
+}
+
+impl<'a> TbsCertList<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = TbsCertList::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let raw = r.clone().read_raw()?;
+        let mut r = r.read_sequence()?;
+
+        let version = match r.peek()? {
+            Some(i) if i == Identifier::universal(UniversalTag::Integer) => match r.read_i64()? {
+                1 => Version::V2,
+                _ => return Err(Error::InvalidVersion),
+            },
+            _ => Version::V1,
+        };
+        let signature = AlgorithmIdentifier::read(&mut r)?;
+        let issuer = Name::read(&mut r)?;
+        let this_update = r.read_time()?;
+        let next_update = match r.peek()? {
+            Some(i) if i == Identifier::universal(UniversalTag::UtcTime) || i == Identifier::universal(UniversalTag::GeneralizedTime) => Some(r.read_time()?),
+            _ => None,
+        };
+        let revoked_certificates = match r.peek()? {
+            Some(i) if i == Identifier::universal(UniversalTag::Sequence) => RevokedCertificates {
+                contents: r.read_expected(i)?,
+            },
+            _ => RevokedCertificates { contents: &[] },
+        };
+        let crl_extensions = match r.read_optional(Identifier::context_specific(true, 0))? {
+            Some(b) => {
+                let mut r = Asn1Reader::new(b);
+                let extensions = Extension::read_list(&mut r)?;
+                r.expect_end()?;
+                extensions
+            }
+            None => Vec::new(),
+        };
+        r.expect_end()?;
+
+        if !crl_extensions.is_empty() && version != Version::V2 {
+            return Err(Error::InvalidVersion);
+        }
+
+        Ok(TbsCertList {
+            raw,
+            version,
+            signature,
+            issuer,
+            this_update,
+            next_update,
+            revoked_certificates,
+            crl_extensions,
+        })
+    }
+
+    pub fn extension(&self, extn_id: &[u64]) -> Option<&Extension<'a>> {
+        self.crl_extensions.iter().find(|e| e.extn_id.components() == extn_id)
+    }
+
+    pub fn crl_number(&self) -> Result<Option<&'a [u8]>> {
+        self.extension(oid::CRL_NUMBER).map(|e| extensions::crl_number(e.extn_value)).transpose()
+    }
+
+    pub fn delta_crl_indicator(&self) -> Result<Option<&'a [u8]>> {
+        self.extension(oid::DELTA_CRL_INDICATOR).map(|e| extensions::crl_number(e.extn_value)).transpose()
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct CertificateList<'a> {
+    /// The complete DER encoding of the CertificateList.
+    pub raw: &'a [u8],
+    pub tbs_cert_list: TbsCertList<'a>,
+    pub signature_algorithm: AlgorithmIdentifier<'a>,
+    pub signature_value: BitString<'a>,
+}
+
+impl<'a> CertificateList<'a> {
+    pub const PEM_LABEL: &'static str = "X509 CRL";
+
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = CertificateList::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let raw = r.clone().read_raw()?;
+        let mut r = r.read_sequence()?;
+        let tbs_cert_list = TbsCertList::read(&mut r)?;
+        let signature_algorithm = AlgorithmIdentifier::read(&mut r)?;
+        let signature_value = r.read_bit_string()?;
+        r.expect_end()?;
+        Ok(CertificateList {
+            raw,
+            tbs_cert_list,
+            signature_algorithm,
+            signature_value,
+        })
+    }
+
+    /// The bytes covered by the signature, i.e. the DER encoding of the TBSCertList.
+    pub fn signed_data(&self) -> &'a [u8] {
+        self.tbs_cert_list.raw
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pem;
+    use x509::extensions::CrlReason;
+
+    const CRL_PEM: &str = "\
+-----BEGIN X509 CRL-----
+MIIBTjCB9QIBATAKBggqhkjOPQQDAjA4MQswCQYDVQQGEwJHQjEUMBIGA1UECgwL
+RXhhbXBsZSBMdGQxEzARBgNVBAMMCkV4YW1wbGUgQ0EXDTI2MTAxODIxMjc1MFoX
+DTI2MTExNzIxMjc1MFowWzAhAgIA/xcNMjUwMzAzMTIwMDAwWjAMMAoGA1UdFQQD
+CgEEMCECAhABFw0yNTAzMDExMjAwMDBaMAwwCgYDVR0VBAMKAQEwEwICEAIXDTI1
+MDMwMjEyMDAwMFqgLzAtMB8GA1UdIwQYMBaAFAfmo9JsAXUN7k6g3TofBFShw3Lq
+MAoGA1UdFAQDAgEFMAoGCCqGSM49BAMCA0gAMEUCIQDc4XzuODPs/daQ5hL8nECX
+FsytMqy//GGPjlFuFU/ckQIgEtQU0MUs7JH+Y7dtNFQUNwfxnSp0j1iK31FU79l7
+clw=
+-----END X509 CRL-----
+";
+
+    fn noon(year: u16, month: u8, day: u8) -> DateTime {
+        DateTime::new(year, month, day, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_crl() {
+        let der = pem::parse(CRL_PEM, pem::Mode::Strict).unwrap().into_contents();
+        let crl = CertificateList::from_der(&der).unwrap();
+        assert_eq!(crl.raw, &der[..]);
+        assert_eq!(crl.signed_data(), &der[4..4 + 3 + 245]);
+        assert_eq!(crl.signature_algorithm.algorithm.components(), [1, 2, 840, 10045, 4, 3, 2]);
+
+        let tbs = &crl.tbs_cert_list;
+        assert_eq!(tbs.version, Version::V2);
+        assert_eq!(tbs.signature, crl.signature_algorithm);
+        assert_eq!(tbs.issuer.to_string(), "CN=Example CA,O=Example Ltd,C=GB");
+        assert_eq!(tbs.this_update, DateTime::new(2026, 10, 18, 21, 27, 50).unwrap());
+        assert_eq!(tbs.next_update, Some(DateTime::new(2026, 11, 17, 21, 27, 50).unwrap()));
+        assert_eq!(tbs.crl_extensions.len(), 2);
+        assert_eq!(tbs.crl_number().unwrap(), Some(&[5u8][..]));
+        assert_eq!(tbs.delta_crl_indicator().unwrap(), None);
+
+        let entries = tbs.revoked_certificates.iter().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].user_certificate, [0x00u8, 0xff]);
+        assert_eq!(entries[0].revocation_date, noon(2025, 3, 3));
+        assert_eq!(entries[0].reason_code().unwrap(), Some(CrlReason::Superseded));
+        assert_eq!(CrlReason::Superseded.to_der().unwrap(), entries[0].crl_entry_extensions[0].extn_value);
+        assert_eq!(entries[1].user_certificate, [0x10u8, 0x01]);
+        assert_eq!(entries[1].revocation_date, noon(2025, 3, 1));
+        assert_eq!(entries[1].reason_code().unwrap(), Some(CrlReason::KeyCompromise));
+        assert_eq!(entries[2].user_certificate, [0x10u8, 0x02]);
+        assert_eq!(entries[2].revocation_date, noon(2025, 3, 2));
+        assert!(entries[2].crl_entry_extensions.is_empty());
+        assert_eq!(entries[2].reason_code().unwrap(), None);
+        assert_eq!(entries[2].invalidity_date().unwrap(), None);
+
+        assert_eq!(tbs.revoked_certificates.find(&[0x10, 0x02]).unwrap(), Some(entries[2].clone()));
+        assert_eq!(tbs.revoked_certificates.find(&[0x10, 0x03]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_crl_entries_lazy() {
+        // v1 CRL with no nextUpdate and a malformed second entry, which is only reported when iterated.
+        let der = [
+            0x30, 0x35,
+                0x30, 0x03, 0x06, 0x01, 0x2a,
+                0x30, 0x00,
+                0x17, 0x0d, 0x32, 0x35, 0x30, 0x31, 0x30, 0x31, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x5a,
+                0x30, 0x1d,
+                    0x30, 0x12, 0x02, 0x01, 0x01,
+                        0x17, 0x0d, 0x32, 0x35, 0x30, 0x31, 0x30, 0x31, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x5a,
+                    0x30, 0x07, 0x02, 0x02, 0x00, 0x01, 0x04, 0x01, 0x00,
+        ];
+        let tbs = TbsCertList::from_der(&der).unwrap();
+        assert_eq!(tbs.version, Version::V1);
+        assert_eq!(tbs.next_update, None);
+        assert!(!tbs.revoked_certificates.is_empty());
+
+        let mut iter = tbs.revoked_certificates.iter();
+        assert_eq!(iter.next().unwrap().unwrap().user_certificate, [0x01u8]);
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+
+        assert_eq!(tbs.revoked_certificates.find(&[0x01]).unwrap().unwrap().revocation_date, DateTime::new(2025, 1, 1, 0, 0, 0).unwrap());
+        assert!(tbs.revoked_certificates.find(&[0x02]).is_err());
+    }
+}
@@ -0,0 +1,453 @@
+use bit_string::{self, BitString};
+use enumerated::Enumerated;
+use identifier::{Class, Identifier, UniversalTag};
+use object_identifier::ObjectIdentifier;
+use reading::{self, Asn1Reader};
+use std;
+use super::{Error, Name, Result};
+use time::DateTime;
+use writing::der::{self, Writer};
+
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct BasicConstraints {
+    pub ca: bool,
+    pub path_len_constraint: Option<u64>,
+}
+
+impl BasicConstraints {
+    pub fn from_der(b: &[u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let mut seq = r.read_sequence()?;
+        r.expect_end()?;
+        let ca = match seq.peek()? {
+            Some(i) if i == Identifier::universal(UniversalTag::Boolean) => seq.read_boolean()?,
+            _ => false,
+        };
+        let path_len_constraint = if seq.is_at_end() { None } else { Some(seq.read_u64()?) };
+        seq.expect_end()?;
+        Ok(BasicConstraints {
+            ca,
+            path_len_constraint,
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        Writer::new(&mut output).write_sequence(|w| {
+            if self.ca {
+                w.write_boolean(true)?;
+            }
+            if let Some(path_len_constraint) = self.path_len_constraint {
+                w.write_u64(path_len_constraint)?;
+            }
+            Ok(())
+        })?;
+        Ok(output)
+    }
+}
+
+
+/// The KeyUsage named bit list, with bit `n` of the ASN.1 definition stored as `1 << n`.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct KeyUsage(pub u16);
+
+impl KeyUsage {
+    pub const DIGITAL_SIGNATURE: u16 = 1 << 0;
+    pub const NON_REPUDIATION: u16 = 1 << 1;
+    pub const KEY_ENCIPHERMENT: u16 = 1 << 2;
+    pub const DATA_ENCIPHERMENT: u16 = 1 << 3;
+    pub const KEY_AGREEMENT: u16 = 1 << 4;
+    pub const KEY_CERT_SIGN: u16 = 1 << 5;
+    pub const CRL_SIGN: u16 = 1 << 6;
+    pub const ENCIPHER_ONLY: u16 = 1 << 7;
+    pub const DECIPHER_ONLY: u16 = 1 << 8;
+
+    pub const BIT_COUNT: usize = 9;
+
+    pub fn from_der(b: &[u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let bits = r.read_bit_string()?;
+        r.expect_end()?;
+        let mut v = 0u16;
+        for i in 0..KeyUsage::BIT_COUNT {
+            if bits.bit(i) {
+                v |= 1 << i;
+            }
+        }
+        Ok(KeyUsage(v))
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let (unused_bits, data) = bit_string::named_bits_to_bytes(self.0 as u64, KeyUsage::BIT_COUNT);
+        let bits = BitString::new(unused_bits, &data).map_err(|_| der::Error::InvalidValue)?;
+        let mut output = Vec::new();
+        Writer::new(&mut output).write_bit_string(bits)?;
+        Ok(output)
+    }
+
+    pub fn contains(&self, usage: u16) -> bool {
+        self.0 & usage == usage
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum GeneralName<'a> {
+    OtherName {
+        type_id: ObjectIdentifier,
+        /// The complete encoding of the `[0] EXPLICIT` value's inner type.
+        value: &'a [u8],
+    },
+    Rfc822Name(&'a str),
+    DnsName(&'a str),
+    /// The contents of the x400Address ORAddress.
+    X400Address(&'a [u8]),
+    DirectoryName(Name<'a>),
+    /// The contents of the ediPartyName EDIPartyName.
+    EdiPartyName(&'a [u8]),
+    UniformResourceIdentifier(&'a str),
+    IpAddress(&'a [u8]),
+    RegisteredId(ObjectIdentifier),
+}
+
+impl<'a> GeneralName<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let (identifier, contents) = match r.next()? {
+            Some(v) => v,
+            None => return Err(reading::Error::UnexpectedEndOfData.into()),
+        };
+        if identifier.class() != Class::ContextSpecific {
+            return Err(reading::Error::UnexpectedTag(identifier).into());
+        }
+        let expected_constructed = match identifier.tag() {
+            0 | 3 | 4 | 5 => true,
+            1 | 2 | 6 | 7 | 8 => false,
+            _ => return Err(reading::Error::UnexpectedTag(identifier).into()),
+        };
+        if identifier.is_constructed() != expected_constructed {
+            return Err(reading::Error::UnexpectedTag(identifier).into());
+        }
+        Ok(match identifier.tag() {
+            0 => {
+                let mut r = Asn1Reader::new(contents);
+                let type_id = r.read_object_identifier()?;
+                let mut explicit = Asn1Reader::new(r.read_expected(Identifier::context_specific(true, 0))?);
+                r.expect_end()?;
+                let value = explicit.read_raw()?;
+                explicit.expect_end()?;
+                GeneralName::OtherName {
+                    type_id,
+                    value,
+                }
+            }
+            1 => GeneralName::Rfc822Name(ia5_str(contents)?),
+            2 => GeneralName::DnsName(ia5_str(contents)?),
+            3 => GeneralName::X400Address(contents),
+            4 => GeneralName::DirectoryName(Name::from_der(contents)?),
+            5 => GeneralName::EdiPartyName(contents),
+            6 => GeneralName::UniformResourceIdentifier(ia5_str(contents)?),
+            7 => {
+                if contents.len() != 4 && contents.len() != 16 {
+                    return Err(Error::InvalidValue);
+                }
+                GeneralName::IpAddress(contents)
+            }
+            8 => GeneralName::RegisteredId(ObjectIdentifier::from_bytes(contents).map_err(reading::Error::from)?),
+            _ => unreachable!(),
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        match *self {
+            GeneralName::OtherName { ref type_id, value } => {
+                w.write_constructed(Identifier::context_specific(true, 0), |w| {
+                    w.write_object_identifier(type_id)?;
+                    w.write_explicit(0, |w| {
+                        w.write_raw(value)?;
+                        Ok(())
+                    })?;
+                    Ok(())
+                })
+            }
+            GeneralName::Rfc822Name(v) => w.write_tag_and_data(Identifier::context_specific(false, 1), v.as_bytes()),
+            GeneralName::DnsName(v) => w.write_tag_and_data(Identifier::context_specific(false, 2), v.as_bytes()),
+            GeneralName::X400Address(v) => w.write_tag_and_data(Identifier::context_specific(true, 3), v),
+            GeneralName::DirectoryName(ref v) => {
+                w.write_explicit(4, |w| {
+                    v.write(w)?;
+                    Ok(())
+                })
+            }
+            GeneralName::EdiPartyName(v) => w.write_tag_and_data(Identifier::context_specific(true, 5), v),
+            GeneralName::UniformResourceIdentifier(v) => {
+                w.write_tag_and_data(Identifier::context_specific(false, 6), v.as_bytes())
+            }
+            GeneralName::IpAddress(v) => w.write_tag_and_data(Identifier::context_specific(false, 7), v),
+            GeneralName::RegisteredId(ref v) => w.write_implicit_object_identifier(8, v),
+        }
+    }
+
+    pub fn ip_address(&self) -> Option<std::net::IpAddr> {
+        match *self {
+            GeneralName::IpAddress(b) if b.len() == 4 => {
+                Some(std::net::IpAddr::from([b[0], b[1], b[2], b[3]]))
+            }
+            GeneralName::IpAddress(b) if b.len() == 16 => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(b);
+                Some(std::net::IpAddr::from(octets))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn ia5_str(b: &[u8]) -> Result<&str> {
+    if !b.is_ascii() {
+        return Err(Error::InvalidValue);
+    }
+    std::str::from_utf8(b).map_err(|_| Error::InvalidValue)
+}
+
+
+/// A GeneralNames SEQUENCE, as carried by the subjectAltName and issuerAltName extensions.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct GeneralNames<'a> {
+    pub names: Vec<GeneralName<'a>>,
+}
+
+impl<'a> GeneralNames<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let mut seq = r.read_sequence()?;
+        r.expect_end()?;
+        let mut names = Vec::new();
+        while !seq.is_at_end() {
+            names.push(GeneralName::read(&mut seq)?);
+        }
+        if names.is_empty() {
+            return Err(Error::InvalidValue);
+        }
+        Ok(GeneralNames {
+            names,
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        Writer::new(&mut output).write_sequence(|w| {
+            for name in &self.names {
+                name.write(w)?;
+            }
+            Ok(())
+        })?;
+        Ok(output)
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct ExtendedKeyUsage {
+    pub key_purposes: Vec<ObjectIdentifier>,
+}
+
+impl ExtendedKeyUsage {
+    pub fn from_der(b: &[u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let mut seq = r.read_sequence()?;
+        r.expect_end()?;
+        let mut key_purposes = Vec::new();
+        while !seq.is_at_end() {
+            key_purposes.push(seq.read_object_identifier()?);
+        }
+        if key_purposes.is_empty() {
+            return Err(Error::InvalidValue);
+        }
+        Ok(ExtendedKeyUsage {
+            key_purposes,
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        Writer::new(&mut output).write_sequence(|w| {
+            for key_purpose in &self.key_purposes {
+                w.write_object_identifier(key_purpose)?;
+            }
+            Ok(())
+        })?;
+        Ok(output)
+    }
+
+    pub fn contains(&self, key_purpose: &[u64]) -> bool {
+        self.key_purposes.iter().any(|kp| kp.components() == key_purpose)
+    }
+}
+
+
+/// Reads a KeyIdentifier-style OCTET STRING, as used by subjectKeyIdentifier.
+pub fn subject_key_identifier(b: &[u8]) -> Result<&[u8]> {
+    let mut r = Asn1Reader::new(b);
+    let v = r.read_octet_string()?;
+    r.expect_end()?;
+    Ok(v)
+}
+
+
+/// Reads a CRL number (or delta CRL indicator), returning the validated INTEGER contents.
+pub fn crl_number(b: &[u8]) -> Result<&[u8]> {
+    let mut r = Asn1Reader::new(b);
+    let v = r.read_integer_bytes()?;
+    r.expect_end()?;
+    Ok(v)
+}
+
+pub fn invalidity_date(b: &[u8]) -> Result<DateTime> {
+    let mut r = Asn1Reader::new(b);
+    let v = r.read_generalized_time()?;
+    r.expect_end()?;
+    Ok(v)
+}
+
+
+/// The CRLReason ENUMERATED, used by the reasonCode CRL entry extension and by OCSP.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum CrlReason {
+    Unspecified,
+    KeyCompromise,
+    CaCompromise,
+    AffiliationChanged,
+    Superseded,
+    CessationOfOperation,
+    CertificateHold,
+    RemoveFromCrl,
+    PrivilegeWithdrawn,
+    AaCompromise,
+}
+
+impl Enumerated for CrlReason {
+    fn from_i64(v: i64) -> Option<Self> {
+        Some(match v {
+            0 => CrlReason::Unspecified,
+            1 => CrlReason::KeyCompromise,
+            2 => CrlReason::CaCompromise,
+            3 => CrlReason::AffiliationChanged,
+            4 => CrlReason::Superseded,
+            5 => CrlReason::CessationOfOperation,
+            6 => CrlReason::CertificateHold,
+            8 => CrlReason::RemoveFromCrl,
+            9 => CrlReason::PrivilegeWithdrawn,
+            10 => CrlReason::AaCompromise,
+            _ => return None,
+        })
+    }
+
+    fn into_i64(self) -> i64 {
+        match self {
+            CrlReason::Unspecified => 0,
+            CrlReason::KeyCompromise => 1,
+            CrlReason::CaCompromise => 2,
+            CrlReason::AffiliationChanged => 3,
+            CrlReason::Superseded => 4,
+            CrlReason::CessationOfOperation => 5,
+            CrlReason::CertificateHold => 6,
+            CrlReason::RemoveFromCrl => 8,
+            CrlReason::PrivilegeWithdrawn => 9,
+            CrlReason::AaCompromise => 10,
+        }
+    }
+}
+
+impl CrlReason {
+    pub fn from_der(b: &[u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = CrlReason::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader) -> Result<Self> {
+        Ok(r.read_enumerated()?)
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_enumerated(*self)
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use x509::{oid, Certificate};
+    use x509::test::ca_der;
+
+    #[test]
+    fn test_certificate_extensions() {
+        let der = ca_der();
+        let cert = Certificate::from_der(&der).unwrap();
+        let tbs = &cert.tbs_certificate;
+
+        assert_eq!(tbs.basic_constraints().unwrap(), Some(BasicConstraints { ca: true, path_len_constraint: Some(1) }));
+
+        let key_usage = tbs.key_usage().unwrap().unwrap();
+        assert_eq!(key_usage, KeyUsage(KeyUsage::DIGITAL_SIGNATURE | KeyUsage::KEY_CERT_SIGN | KeyUsage::CRL_SIGN));
+        assert!(key_usage.contains(KeyUsage::KEY_CERT_SIGN));
+        assert!(!key_usage.contains(KeyUsage::KEY_ENCIPHERMENT));
+
+        let san = tbs.subject_alt_name().unwrap().unwrap();
+        assert_eq!(san.names.len(), 4);
+        assert_eq!(san.names[0], GeneralName::DnsName("example.com"));
+        assert_eq!(san.names[1].ip_address(), Some("127.0.0.1".parse().unwrap()));
+        assert_eq!(san.names[2], GeneralName::Rfc822Name("ca@example.com"));
+        assert_eq!(san.names[3], GeneralName::UniformResourceIdentifier("https://example.com/"));
+
+        let eku = tbs.extended_key_usage().unwrap().unwrap();
+        assert!(eku.contains(oid::KP_SERVER_AUTH));
+        assert!(eku.contains(oid::KP_CLIENT_AUTH));
+        assert!(!eku.contains(oid::KP_CODE_SIGNING));
+
+        let e = |oid| tbs.extension(oid).unwrap().extn_value;
+        assert_eq!(tbs.basic_constraints().unwrap().unwrap().to_der().unwrap(), e(oid::BASIC_CONSTRAINTS));
+        assert_eq!(key_usage.to_der().unwrap(), e(oid::KEY_USAGE));
+        assert_eq!(san.to_der().unwrap(), e(oid::SUBJECT_ALT_NAME));
+        assert_eq!(eku.to_der().unwrap(), e(oid::EXT_KEY_USAGE));
+
+        let ski = subject_key_identifier(tbs.extension(oid::SUBJECT_KEY_IDENTIFIER).unwrap().extn_value).unwrap();
+        assert_eq!(ski.len(), 20);
+    }
+
+    #[test]
+    fn test_basic_constraints_defaults() {
+        assert_eq!(BasicConstraints::from_der(&[0x30, 0x00]).unwrap(), BasicConstraints { ca: false, path_len_constraint: None });
+        assert!(BasicConstraints::from_der(&[0x30, 0x03, 0x02, 0x01, 0xff]).is_err());
+    }
+
+    #[test]
+    fn test_general_name_other_and_directory() {
+        let input = [
+            0x30u8, 0x15,
+            0xa0, 0x0b, 0x06, 0x03, 0x2a, 0x03, 0x04, 0xa0, 0x04, 0x0c, 0x02, b'h', b'i',
+            0xa4, 0x02, 0x30, 0x00,
+            0x88, 0x02, 0x2a, 0x03,
+        ];
+
+        let names = GeneralNames::from_der(&input).unwrap();
+        assert_eq!(names.names, [
+            GeneralName::OtherName {
+                type_id: ObjectIdentifier::from_components(&[1, 2, 3, 4]),
+                value: &[0x0c, 0x02, b'h', b'i'],
+            },
+            GeneralName::DirectoryName(Name::default()),
+            GeneralName::RegisteredId(ObjectIdentifier::from_components(&[1, 2, 3])),
+        ]);
+        assert_eq!(names.to_der().unwrap(), &input[..]);
+    }
+}
@@ -0,0 +1,562 @@
+use bit_string::BitString;
+use identifier::{Identifier, UniversalTag};
+use object_identifier::ObjectIdentifier;
+use reading::{self, Asn1Reader};
+use std;
+use time::DateTime;
+use writing::der::{self, Writer};
+
+pub mod builder;
+pub mod crl;
+pub mod extensions;
+pub mod name;
+
+pub use self::builder::{CertificateBuilder, CertificationRequestBuilder};
+pub use self::crl::CertificateList;
+pub use self::name::Name;
+
+
+pub mod oid {
+    pub const AT_COMMON_NAME: &[u64] = &[2, 5, 4, 3];
+    pub const AT_COUNTRY_NAME: &[u64] = &[2, 5, 4, 6];
+    pub const AT_LOCALITY_NAME: &[u64] = &[2, 5, 4, 7];
+    pub const AT_STATE_OR_PROVINCE_NAME: &[u64] = &[2, 5, 4, 8];
+    pub const AT_STREET_ADDRESS: &[u64] = &[2, 5, 4, 9];
+    pub const AT_ORGANIZATION_NAME: &[u64] = &[2, 5, 4, 10];
+    pub const AT_ORGANIZATIONAL_UNIT_NAME: &[u64] = &[2, 5, 4, 11];
+    pub const AT_DOMAIN_COMPONENT: &[u64] = &[0, 9, 2342, 19200300, 100, 1, 25];
+    pub const AT_USER_ID: &[u64] = &[0, 9, 2342, 19200300, 100, 1, 1];
+
+    pub const SUBJECT_KEY_IDENTIFIER: &[u64] = &[2, 5, 29, 14];
+    pub const KEY_USAGE: &[u64] = &[2, 5, 29, 15];
+    pub const SUBJECT_ALT_NAME: &[u64] = &[2, 5, 29, 17];
+    pub const ISSUER_ALT_NAME: &[u64] = &[2, 5, 29, 18];
+    pub const BASIC_CONSTRAINTS: &[u64] = &[2, 5, 29, 19];
+    pub const CRL_NUMBER: &[u64] = &[2, 5, 29, 20];
+    pub const REASON_CODE: &[u64] = &[2, 5, 29, 21];
+    pub const INVALIDITY_DATE: &[u64] = &[2, 5, 29, 24];
+    pub const DELTA_CRL_INDICATOR: &[u64] = &[2, 5, 29, 27];
+    pub const ISSUING_DISTRIBUTION_POINT: &[u64] = &[2, 5, 29, 28];
+    pub const AUTHORITY_KEY_IDENTIFIER: &[u64] = &[2, 5, 29, 35];
+    pub const EXT_KEY_USAGE: &[u64] = &[2, 5, 29, 37];
+
+    pub const KP_SERVER_AUTH: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 3, 1];
+    pub const KP_CLIENT_AUTH: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 3, 2];
+    pub const KP_CODE_SIGNING: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 3, 3];
+    pub const KP_EMAIL_PROTECTION: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 3, 4];
+    pub const KP_TIME_STAMPING: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 3, 8];
+    pub const KP_OCSP_SIGNING: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 3, 9];
+
+    pub const PKCS9_EXTENSION_REQUEST: &[u64] = &[1, 2, 840, 113549, 1, 9, 14];
+}
+
+
+#[derive(Debug)]
+pub enum Error {
+    Read(reading::Error),
+    InvalidVersion,
+    InvalidValue,
+    DuplicateExtension,
+    InvalidNameString,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Read(ref e) => e.fmt(f),
+            Error::InvalidVersion => write!(f, "invalid version"),
+            Error::InvalidValue => write!(f, "invalid value"),
+            Error::DuplicateExtension => write!(f, "duplicate extension"),
+            Error::InvalidNameString => write!(f, "invalid distinguished name string"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Read(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reading::Error> for Error {
+    fn from(e: reading::Error) -> Error {
+        Error::Read(e)
+    }
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum Version {
+    V1,
+    V2,
+    V3,
+}
+
+impl Version {
+    pub fn from_i64(v: i64) -> Option<Self> {
+        match v {
+            0 => Some(Version::V1),
+            1 => Some(Version::V2),
+            2 => Some(Version::V3),
+            _ => None,
+        }
+    }
+
+    pub fn into_i64(self) -> i64 {
+        match self {
+            Version::V1 => 0,
+            Version::V2 => 1,
+            Version::V3 => 2,
+        }
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct AlgorithmIdentifier<'a> {
+    pub algorithm: ObjectIdentifier,
+    /// The complete encoding of the parameters, if present.
+    pub parameters: Option<&'a [u8]>,
+}
+
+impl<'a> AlgorithmIdentifier<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = AlgorithmIdentifier::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let algorithm = r.read_object_identifier()?;
+        let parameters = if r.is_at_end() { None } else { Some(r.read_raw()?) };
+        r.expect_end()?;
+        Ok(AlgorithmIdentifier {
+            algorithm,
+            parameters,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_object_identifier(&self.algorithm)?;
+            if let Some(parameters) = self.parameters {
+                w.write_raw(parameters)?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct SubjectPublicKeyInfo<'a> {
+    pub algorithm: AlgorithmIdentifier<'a>,
+    pub subject_public_key: BitString<'a>,
+}
+
+impl<'a> SubjectPublicKeyInfo<'a> {
+    pub const PEM_LABEL: &'static str = "PUBLIC KEY";
+
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = SubjectPublicKeyInfo::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let algorithm = AlgorithmIdentifier::read(&mut r)?;
+        let subject_public_key = r.read_bit_string()?;
+        r.expect_end()?;
+        Ok(SubjectPublicKeyInfo {
+            algorithm,
+            subject_public_key,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            self.algorithm.write(w)?;
+            w.write_bit_string(self.subject_public_key)?;
+            Ok(())
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+}
+
+
+/// A PKCS#9 Attribute as used in certification requests and PKCS#8, with each value given as a complete encoding.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Attribute<'a> {
+    pub attr_type: ObjectIdentifier,
+    pub values: Vec<&'a [u8]>,
+}
+
+impl<'a> Attribute<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let attr_type = r.read_object_identifier()?;
+        let mut set = r.read_set()?;
+        let mut values = Vec::new();
+        while !set.is_at_end() {
+            values.push(set.read_raw()?);
+        }
+        r.expect_end()?;
+        Ok(Attribute {
+            attr_type,
+            values,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_object_identifier(&self.attr_type)?;
+            w.write_set_of(|w| {
+                for value in &self.values {
+                    w.write_raw(value)?;
+                }
+                Ok(())
+            })?;
+            Ok(())
+        })
+    }
+}
+
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct Validity {
+    pub not_before: DateTime,
+    pub not_after: DateTime,
+}
+
+impl Validity {
+    pub(crate) fn read(r: &mut Asn1Reader) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let not_before = r.read_time()?;
+        let not_after = r.read_time()?;
+        r.expect_end()?;
+        Ok(Validity {
+            not_before,
+            not_after,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_time(self.not_before)?;
+            w.write_time(self.not_after)?;
+            Ok(())
+        })
+    }
+
+    pub fn contains(&self, t: DateTime) -> bool {
+        self.not_before <= t && t <= self.not_after
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Extension<'a> {
+    pub extn_id: ObjectIdentifier,
+    pub critical: bool,
+    /// The contents of the extnValue OCTET STRING, i.e. the DER encoding of the extension value.
+    pub extn_value: &'a [u8],
+}
+
+impl<'a> Extension<'a> {
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let extn_id = r.read_object_identifier()?;
+        let critical = match r.peek()? {
+            Some(i) if i == Identifier::universal(UniversalTag::Boolean) => r.read_boolean()?,
+            _ => false,
+        };
+        let extn_value = r.read_octet_string()?;
+        r.expect_end()?;
+        Ok(Extension {
+            extn_id,
+            critical,
+            extn_value,
+        })
+    }
+
+    /// Reads an `Extensions` SEQUENCE, rejecting any extension that appears more than once.
+    pub(crate) fn read_list(r: &mut Asn1Reader<'a>) -> Result<Vec<Self>> {
+        let contents = r.read_expected(Identifier::universal(UniversalTag::Sequence))?;
+        Extension::read_list_contents(contents)
+    }
+
+    /// Reads the elements of an `Extensions` list from its contents, as needed when the list is implicitly tagged.
+    pub(crate) fn read_list_contents(b: &'a [u8]) -> Result<Vec<Self>> {
+        let mut r = Asn1Reader::new(b);
+        let mut extensions: Vec<Extension> = Vec::new();
+        while !r.is_at_end() {
+            let extension = Extension::read(&mut r)?;
+            if extensions.iter().any(|e| e.extn_id == extension.extn_id) {
+                return Err(Error::DuplicateExtension);
+            }
+            extensions.push(extension);
+        }
+        Ok(extensions)
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_object_identifier(&self.extn_id)?;
+            if self.critical {
+                w.write_boolean(true)?;
+            }
+            w.write_octet_string(self.extn_value)?;
+            Ok(())
+        })
+    }
+
+    /// Writes an `Extensions` SEQUENCE.
+    pub fn write_list<W: std::io::Write>(w: &mut Writer<W>, extensions: &[Extension]) -> der::Result<usize> {
+        Extension::write_implicit_list(w, Identifier::universal(UniversalTag::Sequence), extensions)
+    }
+
+    /// Writes an `Extensions` list under an implicit tag, which must be constructed.
+    pub fn write_implicit_list<W: std::io::Write>(w: &mut Writer<W>, tag: Identifier, extensions: &[Extension]) -> der::Result<usize> {
+        w.write_constructed(tag, |w| {
+            for extension in extensions {
+                extension.write(w)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct TbsCertificate<'a> {
+    /// The complete DER encoding of the TBSCertificate, over which the signature is computed.
+    pub raw: &'a [u8],
+    pub version: Version,
+    /// The validated contents of the serialNumber INTEGER.
+    pub serial_number: &'a [u8],
+    pub signature: AlgorithmIdentifier<'a>,
+    pub issuer: Name<'a>,
+    pub validity: Validity,
+    pub subject: Name<'a>,
+    pub subject_public_key_info: SubjectPublicKeyInfo<'a>,
+    pub issuer_unique_id: Option<BitString<'a>>,
+    pub subject_unique_id: Option<BitString<'a>>,
+    pub extensions: Vec<Extension<'a>>,
+}
+
+impl<'a> TbsCertificate<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = TbsCertificate::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let raw = r.clone().read_raw()?;
+        let mut r = r.read_sequence()?;
+
+        let version = match r.read_optional(Identifier::context_specific(true, 0))? {
+            Some(b) => {
+                let mut r = Asn1Reader::new(b);
+                let v = r.read_i64()?;
+                r.expect_end()?;
+                Version::from_i64(v).ok_or(Error::InvalidVersion)?
+            }
+            None => Version::V1,
+        };
+        let serial_number = r.read_integer_bytes()?;
+        let signature = AlgorithmIdentifier::read(&mut r)?;
+        let issuer = Name::read(&mut r)?;
+        let validity = Validity::read(&mut r)?;
+        let subject = Name::read(&mut r)?;
+        let subject_public_key_info = SubjectPublicKeyInfo::read(&mut r)?;
+
+        let issuer_unique_id = match r.read_optional(Identifier::context_specific(false, 1))? {
+            Some(b) => Some(BitString::from_bytes(b).map_err(reading::Error::from)?),
+            None => None,
+        };
+        let subject_unique_id = match r.read_optional(Identifier::context_specific(false, 2))? {
+            Some(b) => Some(BitString::from_bytes(b).map_err(reading::Error::from)?),
+            None => None,
+        };
+        let extensions = match r.read_optional(Identifier::context_specific(true, 3))? {
+            Some(b) => {
+                let mut r = Asn1Reader::new(b);
+                let extensions = Extension::read_list(&mut r)?;
+                r.expect_end()?;
+                extensions
+            }
+            None => Vec::new(),
+        };
+        r.expect_end()?;
+
+        if (issuer_unique_id.is_some() || subject_unique_id.is_some()) && version == Version::V1 {
+            return Err(Error::InvalidVersion);
+        }
+        if !extensions.is_empty() && version != Version::V3 {
+            return Err(Error::InvalidVersion);
+        }
+
+        Ok(TbsCertificate {
+            raw,
+            version,
+            serial_number,
+            signature,
+            issuer,
+            validity,
+            subject,
+            subject_public_key_info,
+            issuer_unique_id,
+            subject_unique_id,
+            extensions,
+        })
+    }
+
+    pub fn extension(&self, extn_id: &[u64]) -> Option<&Extension<'a>> {
+        self.extensions.iter().find(|e| e.extn_id.components() == extn_id)
+    }
+
+    pub fn basic_constraints(&self) -> Result<Option<extensions::BasicConstraints>> {
+        self.extension(oid::BASIC_CONSTRAINTS).map(|e| extensions::BasicConstraints::from_der(e.extn_value)).transpose()
+    }
+
+    pub fn key_usage(&self) -> Result<Option<extensions::KeyUsage>> {
+        self.extension(oid::KEY_USAGE).map(|e| extensions::KeyUsage::from_der(e.extn_value)).transpose()
+    }
+
+    pub fn subject_alt_name(&self) -> Result<Option<extensions::GeneralNames<'a>>> {
+        self.extension(oid::SUBJECT_ALT_NAME).map(|e| extensions::GeneralNames::from_der(e.extn_value)).transpose()
+    }
+
+    pub fn extended_key_usage(&self) -> Result<Option<extensions::ExtendedKeyUsage>> {
+        self.extension(oid::EXT_KEY_USAGE).map(|e| extensions::ExtendedKeyUsage::from_der(e.extn_value)).transpose()
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Certificate<'a> {
+    /// The complete DER encoding of the Certificate.
+    pub raw: &'a [u8],
+    pub tbs_certificate: TbsCertificate<'a>,
+    pub signature_algorithm: AlgorithmIdentifier<'a>,
+    pub signature_value: BitString<'a>,
+}
+
+impl<'a> Certificate<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = Certificate::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let raw = r.clone().read_raw()?;
+        let mut r = r.read_sequence()?;
+        let tbs_certificate = TbsCertificate::read(&mut r)?;
+        let signature_algorithm = AlgorithmIdentifier::read(&mut r)?;
+        let signature_value = r.read_bit_string()?;
+        r.expect_end()?;
+        Ok(Certificate {
+            raw,
+            tbs_certificate,
+            signature_algorithm,
+            signature_value,
+        })
+    }
+
+    /// The bytes covered by the signature, i.e. the DER encoding of the TBSCertificate.
+    pub fn signed_data(&self) -> &'a [u8] {
+        self.tbs_certificate.raw
+    }
+}
+
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use pem;
+
+    pub const CA_PEM: &str = "\
+-----BEGIN CERTIFICATE-----
+MIICLzCCAdSgAwIBAgIFAQIDBAUwCgYIKoZIzj0EAwIwODELMAkGA1UEBhMCR0Ix
+FDASBgNVBAoMC0V4YW1wbGUgTHRkMRMwEQYDVQQDDApFeGFtcGxlIENBMB4XDTI0
+MDEwMTAwMDAwMFoXDTM0MDEwMTAwMDAwMFowODELMAkGA1UEBhMCR0IxFDASBgNV
+BAoMC0V4YW1wbGUgTHRkMRMwEQYDVQQDDApFeGFtcGxlIENBMFkwEwYHKoZIzj0C
+AQYIKoZIzj0DAQcDQgAE3mNYI4/xDiQCDij+BdgUFABsJ8qvl0mhVE0sP4SKPGCV
+Vs5zzMbgNyFTgL2kOws8DtabNz4qN6uM8U9m9R1ce6OByjCBxzAdBgNVHQ4EFgQU
+B+aj0mwBdQ3uTqDdOh8EVKHDcuowHwYDVR0jBBgwFoAUB+aj0mwBdQ3uTqDdOh8E
+VKHDcuowEgYDVR0TAQH/BAgwBgEB/wIBATAOBgNVHQ8BAf8EBAMCAYYwQgYDVR0R
+BDswOYILZXhhbXBsZS5jb22HBH8AAAGBDmNhQGV4YW1wbGUuY29thhRodHRwczov
+L2V4YW1wbGUuY29tLzAdBgNVHSUEFjAUBggrBgEFBQcDAQYIKwYBBQUHAwIwCgYI
+KoZIzj0EAwIDSQAwRgIhAL5qSITEJnH07E1K3rvFaIauRoOkFeY8jP01UslaUKev
+AiEAqwgndUyX9zb9LdKIVYOoDNOA+9p13IX+vbLrZxi3X1s=
+-----END CERTIFICATE-----
+";
+
+    pub fn ca_der() -> Vec<u8> {
+        pem::parse(CA_PEM, pem::Mode::Strict).unwrap().into_contents()
+    }
+
+    #[test]
+    fn test_certificate() {
+        let der = ca_der();
+
+        let cert = Certificate::from_der(&der).unwrap();
+        assert_eq!(cert.raw, &der[..]);
+        assert_eq!(cert.signature_algorithm.algorithm.components(), [1, 2, 840, 10045, 4, 3, 2]);
+        assert_eq!(cert.signature_algorithm.parameters, None);
+        assert_eq!(cert.signature_value.as_octets().unwrap().len(), 72);
+
+        let tbs = &cert.tbs_certificate;
+        assert_eq!(cert.signed_data(), &der[4..4 + 4 + 0x01d4]);
+        assert_eq!(tbs.version, Version::V3);
+        assert_eq!(tbs.serial_number, [0x01u8, 0x02, 0x03, 0x04, 0x05]);
+        assert_eq!(tbs.signature, cert.signature_algorithm);
+        assert_eq!(tbs.issuer, tbs.subject);
+        assert_eq!(tbs.validity.not_before, DateTime::new(2024, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(tbs.validity.not_after, DateTime::new(2034, 1, 1, 0, 0, 0).unwrap());
+        assert!(tbs.validity.contains(DateTime::new(2030, 6, 1, 0, 0, 0).unwrap()));
+
+        let spki = &tbs.subject_public_key_info;
+        assert_eq!(spki.algorithm.algorithm.components(), [1, 2, 840, 10045, 2, 1]);
+        assert_eq!(spki.algorithm.parameters, Some(&[0x06u8, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07][..]));
+        assert_eq!(spki.subject_public_key.as_octets().unwrap().len(), 65);
+
+        assert_eq!(tbs.extensions.len(), 6);
+        assert!(tbs.extension(oid::BASIC_CONSTRAINTS).unwrap().critical);
+        assert!(!tbs.extension(oid::SUBJECT_ALT_NAME).unwrap().critical);
+        assert!(tbs.extension(oid::ISSUER_ALT_NAME).is_none());
+    }
+
+    #[test]
+    fn test_certificate_rejects_trailing_data() {
+        let mut der = ca_der();
+        der.push(0);
+
+        assert_eq!(Certificate::from_der(&der).unwrap_err().to_string(), "trailing data");
+    }
+}
@@ -0,0 +1,586 @@
+use identifier::{Class, Identifier, UniversalTag};
+use object_identifier::ObjectIdentifier;
+use reading::Asn1Reader;
+use std;
+use std::borrow::Cow;
+use super::{oid, Error, Result};
+use writing::der::{self, Writer};
+
+
+/// The attribute type short names of RFC 4514 section 3.
+const SHORT_NAMES: &[(&str, &[u64])] = &[
+    ("CN", oid::AT_COMMON_NAME),
+    ("L", oid::AT_LOCALITY_NAME),
+    ("ST", oid::AT_STATE_OR_PROVINCE_NAME),
+    ("O", oid::AT_ORGANIZATION_NAME),
+    ("OU", oid::AT_ORGANIZATIONAL_UNIT_NAME),
+    ("C", oid::AT_COUNTRY_NAME),
+    ("STREET", oid::AT_STREET_ADDRESS),
+    ("DC", oid::AT_DOMAIN_COMPONENT),
+    ("UID", oid::AT_USER_ID),
+];
+
+fn short_name(attr_type: &ObjectIdentifier) -> Option<&'static str> {
+    SHORT_NAMES.iter().find(|&&(_, o)| attr_type.components() == o).map(|&(name, _)| name)
+}
+
+
+/// An attribute value as it appears in a Name: its identifier and contents octets.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct AttributeValue<'a> {
+    pub identifier: Identifier,
+    pub contents: Cow<'a, [u8]>,
+}
+
+impl<'a> AttributeValue<'a> {
+    /// Decodes the value as text according to its universal string tag.
+    ///
+    /// Returns `None` for non-string types and for contents that are invalid for their tag. TeletexString is
+    /// treated as ISO 8859-1, as is common practice.
+    pub fn to_str(&self) -> Option<Cow<'_, str>> {
+        if self.identifier.class() != Class::Universal || self.identifier.is_constructed() {
+            return None;
+        }
+        let b = &self.contents[..];
+        let ascii = |valid: fn(u8) -> bool| if b.iter().all(|&c| valid(c)) {
+            std::str::from_utf8(b).ok().map(Cow::Borrowed)
+        } else {
+            None
+        };
+        match UniversalTag::from_u64(self.identifier.tag())? {
+            UniversalTag::Utf8String => std::str::from_utf8(b).ok().map(Cow::Borrowed),
+            UniversalTag::PrintableString => ascii(is_printable),
+            UniversalTag::NumericString => ascii(|c| c == b' ' || c.is_ascii_digit()),
+            UniversalTag::Ia5String => ascii(|c| c.is_ascii()),
+            UniversalTag::VisibleString => ascii(|c| c == b' ' || c.is_ascii_graphic()),
+            UniversalTag::T61String => Some(Cow::Owned(b.iter().map(|&c| c as char).collect())),
+            UniversalTag::BmpString => {
+                if !b.len().is_multiple_of(2) {
+                    return None;
+                }
+                let units = b.chunks(2).map(|c| u16::from_be_bytes([c[0], c[1]]));
+                std::char::decode_utf16(units).collect::<std::result::Result<String, _>>().ok().map(Cow::Owned)
+            }
+            UniversalTag::UniversalString => {
+                if !b.len().is_multiple_of(4) {
+                    return None;
+                }
+                b.chunks(4)
+                    .map(|c| std::char::from_u32(u32::from_be_bytes([c[0], c[1], c[2], c[3]])))
+                    .collect::<Option<String>>()
+                    .map(Cow::Owned)
+            }
+            _ => None,
+        }
+    }
+
+    /// The complete encoding of the value.
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        Writer::new(&mut output).write_tag_and_data(self.identifier, &self.contents)?;
+        Ok(output)
+    }
+
+    /// Compares two values using the caseIgnoreMatch rule as RFC 5280 section 7.1 requires: string values match
+    /// if they are equal after case folding and whitespace compression, irrespective of their string type. Other
+    /// values must be identical.
+    pub fn matches(&self, other: &AttributeValue) -> bool {
+        match (self.to_str(), other.to_str()) {
+            (Some(a), Some(b)) => prepare_string(&a) == prepare_string(&b),
+            _ => self == other,
+        }
+    }
+}
+
+fn is_printable(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b" '()+,-./:=?".contains(&c)
+}
+
+fn prepare_string(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct AttributeTypeAndValue<'a> {
+    pub attr_type: ObjectIdentifier,
+    pub value: AttributeValue<'a>,
+}
+
+impl<'a> AttributeTypeAndValue<'a> {
+    fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let attr_type = r.read_object_identifier()?;
+        let (identifier, contents) = match r.next()? {
+            Some(v) => v,
+            None => return Err(Error::InvalidValue),
+        };
+        r.expect_end()?;
+        Ok(AttributeTypeAndValue {
+            attr_type,
+            value: AttributeValue {
+                identifier,
+                contents: Cow::Borrowed(contents),
+            },
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            w.write_object_identifier(&self.attr_type)?;
+            w.write_tag_and_data(self.value.identifier, &self.value.contents)?;
+            Ok(())
+        })
+    }
+
+    pub fn matches(&self, other: &AttributeTypeAndValue) -> bool {
+        self.attr_type == other.attr_type && self.value.matches(&other.value)
+    }
+
+    fn parse(p: &mut Parser) -> Result<AttributeTypeAndValue<'static>> {
+        let attr_type = p.take_while(|c| c != b'=');
+        if !p.eat(b'=') {
+            return Err(Error::InvalidNameString);
+        }
+        let attr_type = match SHORT_NAMES.iter().find(|&&(name, _)| name.eq_ignore_ascii_case(attr_type)) {
+            Some(&(_, o)) => ObjectIdentifier::from_components(o),
+            None if attr_type.starts_with(|c: char| c.is_ascii_digit()) => {
+                attr_type.parse().map_err(|_| Error::InvalidNameString)?
+            }
+            None => return Err(Error::InvalidNameString),
+        };
+
+        let value = if p.eat(b'#') {
+            let hex = p.take_while(|c| c != b',' && c != b'+');
+            let encoding = decode_hex(hex).ok_or(Error::InvalidNameString)?;
+            let mut r = Asn1Reader::new(&encoding);
+            let (identifier, contents) = r.next()?.ok_or(Error::InvalidNameString)?;
+            r.expect_end()?;
+            AttributeValue {
+                identifier,
+                contents: Cow::Owned(contents.to_vec()),
+            }
+        } else {
+            let s = p.unescape_value()?;
+            let tag = match attr_type.components() {
+                c if c == oid::AT_COUNTRY_NAME => UniversalTag::PrintableString,
+                c if c == oid::AT_DOMAIN_COMPONENT => UniversalTag::Ia5String,
+                _ => UniversalTag::Utf8String,
+            };
+            let value = AttributeValue {
+                identifier: Identifier::universal(tag),
+                contents: Cow::Owned(s.into_bytes()),
+            };
+            if value.to_str().is_none() {
+                return Err(Error::InvalidNameString);
+            }
+            value
+        };
+
+        Ok(AttributeTypeAndValue {
+            attr_type,
+            value,
+        })
+    }
+}
+
+/// Formats as `type=value` per RFC 4514, using `#` and the hex encoding of the value for types without a short
+/// name and for values that are not strings.
+impl<'a> std::fmt::Display for AttributeTypeAndValue<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = short_name(&self.attr_type);
+        match name {
+            Some(name) => write!(f, "{}=", name)?,
+            None => write!(f, "{}=", self.attr_type)?,
+        }
+        match self.value.to_str() {
+            Some(ref s) if name.is_some() => write_escaped(f, s),
+            _ => {
+                write!(f, "#")?;
+                for b in self.value.to_der().map_err(|_| std::fmt::Error)? {
+                    write!(f, "{:02X}", b)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn write_escaped(f: &mut std::fmt::Formatter, s: &str) -> std::fmt::Result {
+    let last = s.chars().count().saturating_sub(1);
+    for (i, c) in s.chars().enumerate() {
+        match c {
+            '"' | '+' | ',' | ';' | '<' | '>' | '\\' => write!(f, "\\{}", c)?,
+            '#' if i == 0 => write!(f, "\\#")?,
+            ' ' if i == 0 || i == last => write!(f, "\\ ")?,
+            '\0' => write!(f, "\\00")?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    Ok(())
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if s.is_empty() || !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok()).collect()
+}
+
+
+/// A RelativeDistinguishedName: a non-empty SET OF AttributeTypeAndValue.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct RelativeDistinguishedName<'a> {
+    pub attributes: Vec<AttributeTypeAndValue<'a>>,
+}
+
+impl<'a> RelativeDistinguishedName<'a> {
+    fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_set()?;
+        let mut attributes = Vec::new();
+        while !r.is_at_end() {
+            attributes.push(AttributeTypeAndValue::read(&mut r)?);
+        }
+        if attributes.is_empty() {
+            return Err(Error::InvalidValue);
+        }
+        Ok(RelativeDistinguishedName {
+            attributes,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_set_of(|w| {
+            for attribute in &self.attributes {
+                attribute.write(w)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Matches if every attribute has a matching counterpart in `other`, in any order.
+    pub fn matches(&self, other: &RelativeDistinguishedName) -> bool {
+        if self.attributes.len() != other.attributes.len() {
+            return false;
+        }
+        let mut used = vec![false; other.attributes.len()];
+        self.attributes.iter().all(|a| {
+            match other.attributes.iter().enumerate().position(|(i, b)| !used[i] && a.matches(b)) {
+                Some(i) => {
+                    used[i] = true;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+}
+
+impl<'a> std::fmt::Display for RelativeDistinguishedName<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, attribute) in self.attributes.iter().enumerate() {
+            if i > 0 {
+                write!(f, "+")?;
+            }
+            write!(f, "{}", attribute)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// A Name in its only defined form, an RDNSequence.
+#[derive(Clone,Debug,PartialEq,Eq,Default)]
+pub struct Name<'a> {
+    pub rdn_sequence: Vec<RelativeDistinguishedName<'a>>,
+}
+
+impl<'a> Name<'a> {
+    pub fn from_der(b: &'a [u8]) -> Result<Self> {
+        let mut r = Asn1Reader::new(b);
+        let v = Name::read(&mut r)?;
+        r.expect_end()?;
+        Ok(v)
+    }
+
+    pub(crate) fn read(r: &mut Asn1Reader<'a>) -> Result<Self> {
+        let mut r = r.read_sequence()?;
+        let mut rdn_sequence = Vec::new();
+        while !r.is_at_end() {
+            rdn_sequence.push(RelativeDistinguishedName::read(&mut r)?);
+        }
+        Ok(Name {
+            rdn_sequence,
+        })
+    }
+
+    pub fn write<W: std::io::Write>(&self, w: &mut Writer<W>) -> der::Result<usize> {
+        w.write_sequence(|w| {
+            for rdn in &self.rdn_sequence {
+                rdn.write(w)?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn to_der(&self) -> der::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.write(&mut Writer::new(&mut output))?;
+        Ok(output)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rdn_sequence.is_empty()
+    }
+
+    /// Iterates over every attribute in every RDN, in encoded order.
+    pub fn attributes(&self) -> impl Iterator<Item=&AttributeTypeAndValue<'a>> {
+        self.rdn_sequence.iter().flat_map(|rdn| rdn.attributes.iter())
+    }
+
+    /// Compares two names following RFC 5280 section 7.1: they match if they have the same number of RDNs and
+    /// each pair of RDNs match.
+    pub fn matches(&self, other: &Name) -> bool {
+        self.rdn_sequence.len() == other.rdn_sequence.len() &&
+            self.rdn_sequence.iter().zip(&other.rdn_sequence).all(|(a, b)| a.matches(b))
+    }
+}
+
+/// Formats as an RFC 4514 string, with the RDNs in reverse order, e.g. `CN=Example CA,O=Example Ltd,C=GB`.
+impl<'a> std::fmt::Display for Name<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, rdn) in self.rdn_sequence.iter().rev().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", rdn)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses an RFC 4514 string. String values are encoded as UTF8String, except for countryName and
+/// domainComponent which use PrintableString and IA5String respectively.
+impl std::str::FromStr for Name<'static> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Name<'static>> {
+        let mut p = Parser {
+            s,
+            pos: 0,
+        };
+        let mut rdn_sequence = Vec::new();
+        while !s.is_empty() {
+            let mut attributes = vec![AttributeTypeAndValue::parse(&mut p)?];
+            while p.eat(b'+') {
+                attributes.push(AttributeTypeAndValue::parse(&mut p)?);
+            }
+            rdn_sequence.push(RelativeDistinguishedName {
+                attributes,
+            });
+            if p.pos == s.len() {
+                break;
+            }
+            if !p.eat(b',') {
+                return Err(Error::InvalidNameString);
+            }
+        }
+        rdn_sequence.reverse();
+        Ok(Name {
+            rdn_sequence,
+        })
+    }
+}
+
+
+struct Parser<'s> {
+    s: &'s str,
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn peek(&self) -> Option<u8> {
+        self.s.as_bytes().get(self.pos).cloned()
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes ASCII bytes matching `f`.
+    fn take_while<F: Fn(u8) -> bool>(&mut self, f: F) -> &'s str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !f(c) {
+                break;
+            }
+            self.pos += 1;
+        }
+        &self.s[start..self.pos]
+    }
+
+    /// Consumes a string value up to the next unescaped `,` or `+`, resolving `\c` and `\XX` escapes.
+    fn unescape_value(&mut self) -> Result<String> {
+        let mut bytes = Vec::new();
+        while let Some(c) = self.peek() {
+            match c {
+                b',' | b'+' => break,
+                b'\\' => {
+                    let rest = &self.s.as_bytes()[self.pos + 1..];
+                    match rest.first() {
+                        Some(&e) if b" \"#+,;<=>\\".contains(&e) => {
+                            bytes.push(e);
+                            self.pos += 2;
+                        }
+                        Some(_) => {
+                            let hex = self.s.get(self.pos + 1..self.pos + 3).ok_or(Error::InvalidNameString)?;
+                            bytes.extend(decode_hex(hex).ok_or(Error::InvalidNameString)?);
+                            self.pos += 3;
+                        }
+                        None => return Err(Error::InvalidNameString),
+                    }
+                }
+                c => {
+                    bytes.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| Error::InvalidNameString)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use identifier::UniversalTag;
+
+    #[test]
+    fn test_name_decode() {
+        let input = [
+            0x30u8, 0x22,
+            0x31, 0x0b, 0x30, 0x09, 0x06, 0x03, 0x55, 0x04, 0x06, 0x13, 0x02, b'G', b'B',
+            0x31, 0x13,
+            0x30, 0x07, 0x06, 0x03, 0x55, 0x04, 0x03, 0x0c, 0x00,
+            0x30, 0x08, 0x06, 0x03, 0x55, 0x04, 0x0a, 0x0c, 0x01, b'O',
+        ];
+
+        let name = Name::from_der(&input).unwrap();
+        assert_eq!(name.rdn_sequence.len(), 2);
+        assert_eq!(name.rdn_sequence[1].attributes.len(), 2);
+
+        let attributes: Vec<_> = name.attributes().collect();
+        assert_eq!(attributes.len(), 3);
+        assert_eq!(attributes[0].attr_type.components(), [2, 5, 4, 6]);
+        assert_eq!(attributes[0].value.identifier, Identifier::universal(UniversalTag::PrintableString));
+        assert_eq!(&attributes[0].value.contents[..], b"GB");
+        assert_eq!(attributes[1].attr_type.components(), [2, 5, 4, 3]);
+        assert_eq!(&attributes[1].value.contents[..], b"");
+        assert_eq!(attributes[2].attr_type.components(), [2, 5, 4, 10]);
+        assert_eq!(&attributes[2].value.contents[..], b"O");
+
+        assert!(Name::from_der(&[0x30, 0x02, 0x31, 0x00]).is_err());
+        assert!(Name::from_der(&[0x30, 0x00]).unwrap().is_empty());
+
+        assert_eq!(name.to_der().unwrap(), &input[..]);
+    }
+
+    #[test]
+    fn test_rfc4514_format() {
+        let der = ::x509::test::ca_der();
+        let cert = ::x509::Certificate::from_der(&der).unwrap();
+        assert_eq!(cert.tbs_certificate.subject.to_string(), "CN=Example CA,O=Example Ltd,C=GB");
+
+        let hi = AttributeTypeAndValue {
+            attr_type: "1.3.6.1.4.1.1466.0".parse().unwrap(),
+            value: AttributeValue {
+                identifier: Identifier::universal(UniversalTag::OctetString),
+                contents: Cow::Borrowed(b"Hi"),
+            },
+        };
+        assert_eq!(hi.to_string(), "1.3.6.1.4.1.1466.0=#04024869");
+
+        let bmp = AttributeValue {
+            identifier: Identifier::universal(UniversalTag::BmpString),
+            contents: Cow::Borrowed(&[0x00, b'#', 0x01, 0x0d, 0x00, b',', 0x00, b' ']),
+        };
+        assert_eq!(bmp.to_str().unwrap(), "#č, ");
+        let name = Name {
+            rdn_sequence: vec![RelativeDistinguishedName {
+                attributes: vec![AttributeTypeAndValue {
+                    attr_type: ObjectIdentifier::from_components(oid::AT_COMMON_NAME),
+                    value: bmp,
+                }],
+            }],
+        };
+        assert_eq!(name.to_string(), "CN=\\#č\\,\\ ");
+    }
+
+    #[test]
+    fn test_rfc4514_parse() {
+        for s in &[
+            "CN=Steve Kille,O=Isode Limited,C=GB",
+            "OU=Sales+CN=J. Smith,DC=example,DC=net",
+            "CN=James \\\"Jim\\\" Smith\\, III,DC=example,DC=net",
+            "1.3.6.1.4.1.1466.0=#04024869,O=Test,C=GB",
+            "",
+        ] {
+            let name: Name = s.parse().unwrap();
+            assert_eq!(name.to_string(), *s);
+        }
+
+        let name: Name = "CN=Lu\\C4\\8Di\\C4\\87,cn=Before\\0dAfter,dc=example".parse().unwrap();
+        let values: Vec<_> = name.attributes().map(|a| a.value.to_str().unwrap().into_owned()).collect();
+        assert_eq!(values, ["example", "Before\rAfter", "Lučić"]);
+        assert_eq!(name.rdn_sequence[0].attributes[0].value.identifier, Identifier::universal(UniversalTag::Ia5String));
+        assert_eq!(name.to_string(), "CN=Lučić,CN=Before\rAfter,DC=example");
+
+        let name: Name = "CN=Example CA,O=Example Ltd,C=GB".parse().unwrap();
+        let der = ::x509::test::ca_der();
+        let cert = ::x509::Certificate::from_der(&der).unwrap();
+        assert_eq!(name, cert.tbs_certificate.subject);
+
+        for s in &["CN", "=x", "CN=x,", "CN=x,,O=y", "XX=y", "CN=#zz", "CN=#0402486", "CN=#040248", "C=G_",
+                   "CN=a\\", "CN=a\\zz", "CN=\\ff"] {
+            assert!(s.parse::<Name>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_name_matching() {
+        let der = ::x509::test::ca_der();
+        let cert = ::x509::Certificate::from_der(&der).unwrap();
+        let subject = &cert.tbs_certificate.subject;
+
+        let name: Name = "CN=  example   ca ,O=EXAMPLE LTD,C=gb".parse().unwrap();
+        assert!(name.matches(subject));
+        assert!(subject.matches(&name));
+        assert!(!"CN=Example CA,O=Example Ltd".parse::<Name>().unwrap().matches(subject));
+        assert!(!"CN=Example CA2,O=Example Ltd,C=GB".parse::<Name>().unwrap().matches(subject));
+        assert!(!"O=Example Ltd,CN=Example CA,C=GB".parse::<Name>().unwrap().matches(subject));
+
+        let a: Name = "OU=Sales+CN=J. Smith,DC=example".parse().unwrap();
+        let b: Name = "CN=j. smith+OU=sales,DC=Example".parse().unwrap();
+        let c: Name = "CN=j. smith+CN=j. smith,DC=Example".parse().unwrap();
+        assert!(a.matches(&b));
+        assert!(!a.matches(&c));
+
+        let bmp = AttributeValue {
+            identifier: Identifier::universal(UniversalTag::BmpString),
+            contents: Cow::Borrowed(&[0x00, b'G', 0x00, b'B']),
+        };
+        assert!(bmp.matches(&subject.rdn_sequence[0].attributes[0].value));
+        let octets = AttributeValue {
+            identifier: Identifier::universal(UniversalTag::OctetString),
+            contents: Cow::Borrowed(b"GB"),
+        };
+        assert!(!octets.matches(&subject.rdn_sequence[0].attributes[0].value));
+        assert!(octets.matches(&octets.clone()));
+    }
+}
@@ -0,0 +1,603 @@
+//! XML Encoding Rules (X.693), in the basic variant (Basic-XER), for `value::Value` trees.
+//!
+//! Each value becomes an element named after its universal type, such as `<INTEGER>` or `<BIT_STRING>`, or after
+//! its component name when it is `Value::Named`. BOOLEAN values are `<true/>` or `<false/>`, BIT STRING values
+//! are written as `0` and `1` characters, OCTET STRING values in hexadecimal and OBJECT IDENTIFIER values in dotted
+//! decimal. Control characters in strings are written as the empty elements of X.693 table 3, such as `<nul/>`.
+//!
+//! XER has no form for tags, which a value tree without a schema needs in order to reproduce DER. Values with any
+//! other identifier are written as `<CONTEXT-0>` elements holding their components, or as `<CONTEXT-0-PRIMITIVE>`
+//! elements holding their contents in hexadecimal, with `UNIVERSAL`, `APPLICATION` and `PRIVATE` for the other
+//! classes. `from_str` parses this output back into the same tree. Elements named after components need
+//! `from_str_with_names`, which is given the identifier of each component's type, since only the schema knows them.
+
+use identifier::{Class, Identifier, UniversalTag};
+use integer;
+use object_identifier::ObjectIdentifier;
+use std;
+use std::fmt::Write;
+use value::{self, Value};
+
+
+#[derive(Debug)]
+pub enum Error {
+    /// The input is not well-formed XML, or uses XML features that XER does not, at the given byte offset.
+    Syntax(usize),
+    UnknownElement(String),
+    /// The contents of the named element are not a valid value of its type.
+    InvalidValue(String),
+    NestingTooDeep,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::Syntax(pos) => write!(f, "XML syntax error at offset {}", pos),
+            Error::UnknownElement(ref name) => write!(f, "unknown element <{}>", name),
+            Error::InvalidValue(ref name) => write!(f, "invalid value in <{}>", name),
+            Error::NestingTooDeep => write!(f, "nesting too deep"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// The names X.693 gives the control characters other than TAB, LF and CR, indexed by character.
+const CONTROL_NAMES: [&str; 32] = [
+    "nul", "soh", "stx", "etx", "eot", "enq", "ack", "bel", "bs", "", "", "vt", "ff", "", "so", "si",
+    "dle", "dc1", "dc2", "dc3", "dc4", "nak", "syn", "etb", "can", "em", "sub", "esc", "is4", "is3", "is2", "is1",
+];
+
+const UNIVERSAL_TAGS: [UniversalTag; 29] = [
+    UniversalTag::Boolean, UniversalTag::Integer, UniversalTag::BitString, UniversalTag::OctetString,
+    UniversalTag::Null, UniversalTag::ObjectIdentifier, UniversalTag::ObjectDescriptor, UniversalTag::External,
+    UniversalTag::Real, UniversalTag::Enumerated, UniversalTag::EmbeddedPdv, UniversalTag::Utf8String,
+    UniversalTag::RelativeOid, UniversalTag::Sequence, UniversalTag::Set, UniversalTag::NumericString,
+    UniversalTag::PrintableString, UniversalTag::T61String, UniversalTag::VideotexString, UniversalTag::Ia5String,
+    UniversalTag::UtcTime, UniversalTag::GeneralizedTime, UniversalTag::GraphicString, UniversalTag::VisibleString,
+    UniversalTag::GeneralString, UniversalTag::UniversalString, UniversalTag::CharacterString,
+    UniversalTag::BmpString, UniversalTag::EndOfContent,
+];
+
+
+/// The XER element name of a universal type.
+pub fn type_name(tag: UniversalTag) -> &'static str {
+    match tag {
+        UniversalTag::EndOfContent => "END_OF_CONTENT",
+        UniversalTag::Boolean => "BOOLEAN",
+        UniversalTag::Integer => "INTEGER",
+        UniversalTag::BitString => "BIT_STRING",
+        UniversalTag::OctetString => "OCTET_STRING",
+        UniversalTag::Null => "NULL",
+        UniversalTag::ObjectIdentifier => "OBJECT_IDENTIFIER",
+        UniversalTag::ObjectDescriptor => "ObjectDescriptor",
+        UniversalTag::External => "EXTERNAL",
+        UniversalTag::Real => "REAL",
+        UniversalTag::Enumerated => "ENUMERATED",
+        UniversalTag::EmbeddedPdv => "EMBEDDED_PDV",
+        UniversalTag::Utf8String => "UTF8String",
+        UniversalTag::RelativeOid => "RELATIVE_OID",
+        UniversalTag::Sequence => "SEQUENCE",
+        UniversalTag::Set => "SET",
+        UniversalTag::NumericString => "NumericString",
+        UniversalTag::PrintableString => "PrintableString",
+        UniversalTag::T61String => "TeletexString",
+        UniversalTag::VideotexString => "VideotexString",
+        UniversalTag::Ia5String => "IA5String",
+        UniversalTag::UtcTime => "UTCTime",
+        UniversalTag::GeneralizedTime => "GeneralizedTime",
+        UniversalTag::GraphicString => "GraphicString",
+        UniversalTag::VisibleString => "VisibleString",
+        UniversalTag::GeneralString => "GeneralString",
+        UniversalTag::UniversalString => "UniversalString",
+        UniversalTag::CharacterString => "CHARACTER_STRING",
+        UniversalTag::BmpString => "BMPString",
+    }
+}
+
+fn class_name(class: Class) -> &'static str {
+    match class {
+        Class::Universal => "UNIVERSAL",
+        Class::Application => "APPLICATION",
+        Class::ContextSpecific => "CONTEXT",
+        Class::Private => "PRIVATE",
+    }
+}
+
+fn element_name(v: &Value) -> String {
+    match *v {
+        Value::Named(ref name, _) => name.clone(),
+        Value::Constructed(identifier, _) => format!("{}-{}", class_name(identifier.class()), identifier.tag()),
+        Value::Primitive(identifier, _) => {
+            format!("{}-{}-PRIMITIVE", class_name(identifier.class()), identifier.tag())
+        }
+        ref v => {
+            let tag = UniversalTag::from_u64(v.identifier().tag()).unwrap();
+            type_name(tag).to_string()
+        }
+    }
+}
+
+
+/// Renders `v` as Basic-XER, with each element on its own line and components indented.
+pub fn to_string(v: &Value) -> String {
+    let mut out = String::new();
+    write_element(&mut out, v, 0).unwrap();
+    out
+}
+
+fn write_element(out: &mut String, v: &Value, depth: usize) -> std::fmt::Result {
+    let name = element_name(v);
+    let v = v.unnamed();
+    let indent = "  ".repeat(depth);
+    let text = match *v {
+        Value::Boolean(b) => return writeln!(out, "{}<{}><{}/></{}>", indent, name, b, name),
+        Value::Sequence(ref children) | Value::Set(ref children) | Value::Constructed(_, ref children) => {
+            if children.is_empty() {
+                return writeln!(out, "{}<{}/>", indent, name);
+            }
+            writeln!(out, "{}<{}>", indent, name)?;
+            for child in children {
+                write_element(out, child, depth + 1)?;
+            }
+            return writeln!(out, "{}</{}>", indent, name);
+        }
+        Value::Integer(ref b) | Value::Enumerated(ref b) => integer::to_decimal(b).unwrap_or_default(),
+        Value::BitString(ref data, bit_len) => {
+            (0..bit_len).map(|i| if data.get(i / 8).is_some_and(|b| b & (0x80 >> (i % 8)) != 0) { '1' } else { '0' })
+                .collect()
+        }
+        Value::OctetString(ref b) | Value::Primitive(_, ref b) => b.iter().map(|b| format!("{:02X}", b)).collect(),
+        Value::Null => String::new(),
+        Value::ObjectIdentifier(ref oid) => oid.to_string(),
+        Value::String(_, ref s) => escape(s),
+        Value::Named(..) => unreachable!(),
+    };
+    if text.is_empty() {
+        return writeln!(out, "{}<{}/>", indent, name);
+    }
+    writeln!(out, "{}<{}>{}</{}>", indent, name, text, name)
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if (c as u32) < 32 => {
+                out.push('<');
+                out.push_str(CONTROL_NAMES[c as usize]);
+                out.push_str("/>");
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+
+/// An XML element, as far as XER needs one.
+#[derive(Debug)]
+struct Element {
+    name: String,
+    children: Vec<Node>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    /// The child elements, provided that any text between them is whitespace.
+    fn elements(&self) -> Result<Vec<&Element>> {
+        let mut elements = Vec::new();
+        for child in &self.children {
+            match *child {
+                Node::Element(ref e) => elements.push(e),
+                Node::Text(ref t) if t.chars().all(char::is_whitespace) => (),
+                Node::Text(_) => return Err(self.invalid()),
+            }
+        }
+        Ok(elements)
+    }
+
+    /// The text contents, provided that there are no child elements.
+    fn text(&self) -> Result<String> {
+        let mut text = String::new();
+        for child in &self.children {
+            match *child {
+                Node::Text(ref t) => text.push_str(t),
+                Node::Element(_) => return Err(self.invalid()),
+            }
+        }
+        Ok(text)
+    }
+
+    /// The text contents of a character string, in which control characters are empty elements.
+    fn string(&self) -> Result<String> {
+        let mut text = String::new();
+        for child in &self.children {
+            match *child {
+                Node::Text(ref t) => text.push_str(t),
+                Node::Element(ref e) => {
+                    let c = CONTROL_NAMES.iter().position(|name| !name.is_empty() && *name == e.name)
+                        .filter(|_| e.children.is_empty())
+                        .ok_or_else(|| self.invalid())?;
+                    text.push(c as u8 as char);
+                }
+            }
+        }
+        Ok(text)
+    }
+
+    fn invalid(&self) -> Error {
+        Error::InvalidValue(self.name.clone())
+    }
+}
+
+
+/// Parses Basic-XER as written by `to_string` into a value tree. Elements named after components are rejected with
+/// `UnknownElement`.
+pub fn from_str(s: &str) -> Result<Value> {
+    from_str_with_names(s, &[])
+}
+
+/// As `from_str`, but an element named after a component in `names` becomes a `Value::Named` whose value has the
+/// given identifier: a universal type is parsed as its element would be, and any other identifier as the matching
+/// `CONTEXT-0` or `CONTEXT-0-PRIMITIVE` element would be.
+pub fn from_str_with_names(s: &str, names: &[(&str, Identifier)]) -> Result<Value> {
+    let mut p = Parser {
+        s,
+        pos: 0,
+    };
+    p.skip_misc();
+    if p.rest().starts_with("<?xml") {
+        p.pos = p.s.find("?>").ok_or(Error::Syntax(p.pos))? + 2;
+        p.skip_misc();
+    }
+    let root = p.parse_element(0)?;
+    p.skip_misc();
+    if p.pos != s.len() {
+        return Err(Error::Syntax(p.pos));
+    }
+    to_value(&root, names)
+}
+
+fn to_value(e: &Element, names: &[(&str, Identifier)]) -> Result<Value> {
+    if let Some(&(name, identifier)) = names.iter().find(|&&(name, _)| name == e.name) {
+        let v = match UniversalTag::from_u64(identifier.tag()).filter(|_| identifier.class() == Class::Universal) {
+            Some(tag) => to_universal_value(e, tag, names)?,
+            None => to_tagged_value(e, identifier, names)?,
+        };
+        return Ok(Value::named(name, v));
+    }
+    match UNIVERSAL_TAGS.iter().cloned().find(|tag| type_name(*tag) == e.name) {
+        Some(tag) => to_universal_value(e, tag, names),
+        None => {
+            let identifier = parse_tag_name(&e.name).ok_or_else(|| Error::UnknownElement(e.name.clone()))?;
+            to_tagged_value(e, identifier, names)
+        }
+    }
+}
+
+fn to_universal_value(e: &Element, tag: UniversalTag, names: &[(&str, Identifier)]) -> Result<Value> {
+    let v = match tag {
+        UniversalTag::Boolean => match e.elements()?.as_slice() {
+            [b] if b.children.is_empty() && b.name == "true" => Value::Boolean(true),
+            [b] if b.children.is_empty() && b.name == "false" => Value::Boolean(false),
+            _ => return Err(e.invalid()),
+        },
+        UniversalTag::Integer => Value::Integer(integer::from_decimal(e.text()?.trim()).ok_or_else(|| e.invalid())?),
+        UniversalTag::Enumerated => {
+            Value::Enumerated(integer::from_decimal(e.text()?.trim()).ok_or_else(|| e.invalid())?)
+        }
+        UniversalTag::BitString => {
+            let bits: Vec<char> = e.text()?.chars().filter(|c| !c.is_whitespace()).collect();
+            let mut data = vec![0u8; bits.len().div_ceil(8)];
+            for (i, bit) in bits.iter().enumerate() {
+                match *bit {
+                    '0' => (),
+                    '1' => data[i / 8] |= 0x80 >> (i % 8),
+                    _ => return Err(e.invalid()),
+                }
+            }
+            Value::BitString(data, bits.len())
+        }
+        UniversalTag::OctetString => Value::OctetString(parse_hex(&e.text()?).ok_or_else(|| e.invalid())?),
+        UniversalTag::Null if e.children.is_empty() => Value::Null,
+        UniversalTag::ObjectIdentifier => {
+            Value::ObjectIdentifier(e.text()?.trim().parse::<ObjectIdentifier>().map_err(|_| e.invalid())?)
+        }
+        UniversalTag::Sequence => Value::Sequence(to_values(e, names)?),
+        UniversalTag::Set => Value::Set(to_values(e, names)?),
+        tag if value::is_string_type(tag) => Value::String(tag, e.string()?),
+        _ => return Err(e.invalid()),
+    };
+    Ok(v)
+}
+
+fn to_tagged_value(e: &Element, identifier: Identifier, names: &[(&str, Identifier)]) -> Result<Value> {
+    if identifier.is_constructed() {
+        Ok(Value::Constructed(identifier, to_values(e, names)?))
+    } else {
+        Ok(Value::Primitive(identifier, parse_hex(&e.text()?).ok_or_else(|| e.invalid())?))
+    }
+}
+
+fn to_values(e: &Element, names: &[(&str, Identifier)]) -> Result<Vec<Value>> {
+    e.elements()?.into_iter().map(|e| to_value(e, names)).collect()
+}
+
+/// Parses a name such as `CONTEXT-0` or `CONTEXT-0-PRIMITIVE` into an identifier.
+fn parse_tag_name(name: &str) -> Option<Identifier> {
+    let (name, primitive) = match name.strip_suffix("-PRIMITIVE") {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+    let (class, number) = name.split_at(name.find('-')?);
+    let class = [Class::Universal, Class::Application, Class::ContextSpecific, Class::Private].iter().cloned()
+        .find(|c| class_name(*c) == class)?;
+    let number = &number[1..];
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) || (number.len() > 1 && number.starts_with('0')) {
+        return None;
+    }
+    Some(Identifier::new(class, !primitive, number.parse().ok()?))
+}
+
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = s.chars().filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<_>>()?;
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    Some(digits.chunks(2).map(|d| (d[0] << 4) | d[1]).collect())
+}
+
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn syntax_error(&self) -> Error {
+        Error::Syntax(self.pos)
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_misc(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if !trimmed.starts_with("<!--") {
+                return;
+            }
+            match trimmed.find("-->") {
+                Some(end) => self.pos += end + 3,
+                None => return,
+            }
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<String> {
+        let rest = self.rest();
+        let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '.' || c == ':'))
+            .unwrap_or(rest.len());
+        if len == 0 || rest.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') {
+            return Err(self.syntax_error());
+        }
+        self.pos += len;
+        Ok(rest[..len].to_string())
+    }
+
+    fn expect(&mut self, s: &str) -> Result<()> {
+        if !self.rest().starts_with(s) {
+            return Err(self.syntax_error());
+        }
+        self.pos += s.len();
+        Ok(())
+    }
+
+    fn parse_element(&mut self, depth: usize) -> Result<Element> {
+        if depth >= value::MAX_DEPTH {
+            return Err(Error::NestingTooDeep);
+        }
+        self.expect("<")?;
+        let name = self.parse_name()?;
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+        let mut children = Vec::new();
+        if self.rest().starts_with("/>") {
+            self.pos += 2;
+            return Ok(Element {
+                name,
+                children,
+            });
+        }
+        self.expect(">")?;
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.pos += 2;
+                if self.parse_name()? != name {
+                    return Err(self.syntax_error());
+                }
+                let rest = self.rest();
+                self.pos += rest.len() - rest.trim_start().len();
+                self.expect(">")?;
+                return Ok(Element {
+                    name,
+                    children,
+                });
+            } else if rest.starts_with("<!--") {
+                let end = rest.find("-->").ok_or_else(|| self.syntax_error())?;
+                self.pos += end + 3;
+            } else if rest.starts_with('<') {
+                children.push(Node::Element(self.parse_element(depth + 1)?));
+            } else if rest.is_empty() {
+                return Err(self.syntax_error());
+            } else {
+                let text = self.parse_text()?;
+                match children.last_mut() {
+                    Some(&mut Node::Text(ref mut t)) => t.push_str(&text),
+                    _ => children.push(Node::Text(text)),
+                }
+            }
+        }
+    }
+
+    fn parse_text(&mut self) -> Result<String> {
+        let rest = self.rest();
+        let len = rest.find('<').unwrap_or(rest.len());
+        let raw = &rest[..len];
+        let mut text = String::with_capacity(len);
+        let mut parts = raw.split('&');
+        text.push_str(parts.next().unwrap());
+        for part in parts {
+            let end = part.find(';').ok_or_else(|| self.syntax_error())?;
+            let c = match &part[..end] {
+                "lt" => '<',
+                "gt" => '>',
+                "amp" => '&',
+                "quot" => '"',
+                "apos" => '\'',
+                reference => {
+                    let code = if let Some(hex) = reference.strip_prefix("#x") {
+                        u32::from_str_radix(hex, 16).ok()
+                    } else if let Some(decimal) = reference.strip_prefix('#') {
+                        decimal.parse().ok()
+                    } else {
+                        None
+                    };
+                    code.and_then(std::char::from_u32).ok_or_else(|| self.syntax_error())?
+                }
+            };
+            text.push(c);
+            text.push_str(&part[end + 1..]);
+        }
+        self.pos += len;
+        Ok(text)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use value::test::sample;
+
+    #[test]
+    fn test_roundtrip() {
+        let der = sample();
+        let v = Value::from_der(&der).unwrap();
+        let xer = to_string(&v);
+        assert!(xer.starts_with("<SEQUENCE>\n  <BOOLEAN><true/></BOOLEAN>\n  <INTEGER>18446744073709551615</INTEGER>\n"));
+        assert!(xer.contains("\n  <BIT_STRING>101</BIT_STRING>\n  <OCTET_STRING>0102FE</OCTET_STRING>\n  <NULL/>\n"));
+        assert!(xer.contains("\n  <OBJECT_IDENTIFIER>2.5.4.3</OBJECT_IDENTIFIER>\n  <ENUMERATED>2</ENUMERATED>\n"));
+        assert!(xer.contains("\n  <UTF8String>&lt;café &amp; &gt;</UTF8String>\n"));
+        assert!(xer.contains("\n  <CONTEXT-0>\n    <SEQUENCE>\n      <INTEGER>5</INTEGER>\n    </SEQUENCE>\n  </CONTEXT-0>\n"));
+        assert!(xer.contains("\n  <CONTEXT-0-PRIMITIVE>CAFE</CONTEXT-0-PRIMITIVE>\n  <APPLICATION-128-PRIMITIVE/>\n"));
+        assert!(xer.contains("\n  <UNIVERSAL-20-PRIMITIVE>41C1</UNIVERSAL-20-PRIMITIVE>\n"));
+        assert!(xer.contains("\n  <SEQUENCE/>\n  <PRIVATE-2/>\n  <UTF8String/>\n"));
+        assert!(xer.ends_with("</SEQUENCE>\n"));
+
+        let parsed = from_str(&xer).unwrap();
+        assert_eq!(parsed, v);
+        assert_eq!(parsed.to_der().unwrap(), der);
+    }
+
+    #[test]
+    fn test_names_and_escapes() {
+        let v = Value::Sequence(vec![
+            Value::named("version", Value::integer(2)),
+            Value::named("critical", Value::Boolean(false)),
+            Value::named("subject", Value::Sequence(vec![Value::String(UniversalTag::Ia5String, "a\u{0}b\tc".to_string())])),
+        ]);
+        assert_eq!(to_string(&v), "<SEQUENCE>\n  <version>2</version>\n  <critical><false/></critical>\n  \
+            <subject>\n    <IA5String>a<nul/>b\tc</IA5String>\n  </subject>\n</SEQUENCE>\n");
+
+        let xer = "<?xml version=\"1.0\"?>\n<!-- dump -->\n<SEQUENCE>\n  <IA5String>x<bel/>&#x41;&#66;&quot;</IA5String>\
+            <BIT_STRING>1 0\n1</BIT_STRING><OCTET_STRING> ab CD </OCTET_STRING><INTEGER>-5</INTEGER ></SEQUENCE>";
+        assert_eq!(from_str(xer).unwrap(), Value::Sequence(vec![
+            Value::String(UniversalTag::Ia5String, "x\u{7}AB\"".to_string()),
+            Value::BitString(vec![0xa0], 3),
+            Value::OctetString(vec![0xab, 0xcd]),
+            Value::integer(-5),
+        ]));
+    }
+
+    #[test]
+    fn test_named_roundtrip() {
+        let v = Value::Sequence(vec![
+            Value::named("version", Value::Constructed(Identifier::context_specific(true, 0), vec![Value::integer(2)])),
+            Value::named("serialNumber", Value::integer(5)),
+            Value::named("critical", Value::Boolean(false)),
+            Value::named("subject", Value::Sequence(vec![Value::String(UniversalTag::Ia5String, "x".to_string())])),
+            Value::named("keyIdentifier", Value::Primitive(Identifier::context_specific(false, 0), vec![0xab])),
+        ]);
+        let names = [
+            ("version", Identifier::context_specific(true, 0)),
+            ("serialNumber", Identifier::universal(UniversalTag::Integer)),
+            ("critical", Identifier::universal(UniversalTag::Boolean)),
+            ("subject", Identifier::universal(UniversalTag::Sequence)),
+            ("keyIdentifier", Identifier::context_specific(false, 0)),
+        ];
+        let xer = to_string(&v);
+        assert_eq!(xer, "<SEQUENCE>\n  <version>\n    <INTEGER>2</INTEGER>\n  </version>\n  \
+            <serialNumber>5</serialNumber>\n  <critical><false/></critical>\n  <subject>\n    \
+            <IA5String>x</IA5String>\n  </subject>\n  <keyIdentifier>AB</keyIdentifier>\n</SEQUENCE>\n");
+
+        let parsed = from_str_with_names(&xer, &names).unwrap();
+        assert_eq!(parsed, v);
+        assert_eq!(parsed.to_der().unwrap(), v.to_der().unwrap());
+
+        match from_str(&xer) {
+            Err(Error::UnknownElement(ref name)) if name == "version" => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+        assert!(from_str_with_names("<critical>1</critical>", &names).is_err());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let cases = [
+            "<SEQUENCE>",
+            "<SEQUENCE></SET>",
+            "<INTEGER>1</INTEGER><INTEGER>2</INTEGER>",
+            "<INTEGER a=\"1\">1</INTEGER>",
+            "<IA5String>&bogus;</IA5String>",
+            "<version>1</version>",
+            "<INTEGER>1.5</INTEGER>",
+            "<BOOLEAN>true</BOOLEAN>",
+            "<BIT_STRING>102</BIT_STRING>",
+            "<OCTET_STRING>ABC</OCTET_STRING>",
+            "<SEQUENCE>text</SEQUENCE>",
+            "<IA5String><INTEGER>1</INTEGER></IA5String>",
+            "<CONTEXT-01/>",
+            "<NULL>x</NULL>",
+        ];
+        for xer in &cases {
+            assert!(from_str(xer).is_err(), "{}", xer);
+        }
+        let deep = "<SEQUENCE>".repeat(100);
+        match from_str(&deep) {
+            Err(Error::NestingTooDeep) => (),
+            v => panic!("unexpected result {:?}", v),
+        }
+    }
+}